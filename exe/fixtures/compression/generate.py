@@ -0,0 +1,63 @@
+#!/usr/bin/env python3
+# SPDX-License-Identifier: GPL-2.0 OR MIT
+"""Generates the gzip and zstd fixtures for the rzbench compression benchmark.
+
+The decompressed data matches the rzbench text and zeros patterns. gzip data
+is a zlib stream at level 6, as written by ZFS gzip-6. zstd data is a level 3
+frame from the zstd command, without a checksum, and without the magic number
+like ZFS, behind the ZFS zstd header.
+
+Usage: ./generate.py (from this directory, with zstd in PATH)
+"""
+
+import subprocess
+import zlib
+
+SIZE = 128 * 1024
+
+TEXT_WORDS = [
+    "the", "pool", "block", "pointer", "checksum", "dataset", "snapshot", "of", "and", "vdev",
+    "label", "object", "to", "a", "record", "is",
+]
+
+ZSTD_LEVEL = 3
+ZSTD_VERSION = 10507
+ZSTD_MAGIC = bytes([0x28, 0xB5, 0x2F, 0xFD])
+
+MASK = (1 << 64) - 1
+
+
+def text(size):
+    state = 0x9E3779B97F4A7C15
+    data = bytearray()
+    while len(data) < size:
+        state ^= (state << 13) & MASK
+        state ^= state >> 7
+        state ^= (state << 17) & MASK
+        word = TEXT_WORDS[state % len(TEXT_WORDS)]
+        data += word.encode() + b" "
+    return bytes(data[:size])
+
+
+def main():
+    for pattern, data in [("text", text(SIZE)), ("zeros", bytes(SIZE))]:
+        with open(f"{pattern}-{SIZE}.gzip", "wb") as f:
+            f.write(zlib.compress(data, 6))
+
+        frame = subprocess.run(
+            ["zstd", f"-{ZSTD_LEVEL}", "--no-check", "-c"],
+            input=data,
+            stdout=subprocess.PIPE,
+            check=True,
+        ).stdout
+        assert frame[0:4] == ZSTD_MAGIC
+        frame = frame[4:]
+
+        header = len(frame).to_bytes(4, "big")
+        header += ((ZSTD_LEVEL << 24) | ZSTD_VERSION).to_bytes(4, "big")
+        with open(f"{pattern}-{SIZE}.zstd", "wb") as f:
+            f.write(header + frame)
+
+
+if __name__ == "__main__":
+    main()
@@ -1,29 +1,218 @@
 // SPDX-License-Identifier: GPL-2.0 OR MIT
 
 use std::env;
+use std::error::Error;
 use std::process::ExitCode;
 use std::time::Instant;
 
-use rzfs::checksum::{Checksum, ChecksumError};
 use rzfs::checksum::{
-    Fletcher2, Fletcher2Implementation, Fletcher4, Fletcher4Implementation, Sha256,
-    Sha256Implementation,
+    Blake3, Blake3Implementation, Edonr, Fletcher2, Fletcher2Implementation, Fletcher4,
+    Fletcher4Implementation, Sha256, Sha256Implementation, Sha512_256, Sha512_256Implementation,
+    Skein,
+};
+use rzfs::checksum::{Checksum, ChecksumError};
+use rzfs::compression::{
+    Compression, CompressionError, Decompression, GzipDecoder, Lz4Decoder, Lz4Encoder, LzjbDecoder,
+    LzjbEncoder, ZleDecoder, ZleEncoder, ZstdDecoder, ZLE_LEVEL,
+};
+use rzfs::phys::{
+    BigLittleEndianDecoder, BigLittleEndianEncoder, BlockPointer, BlockPointerRegular,
+    ChecksumType, ChecksumValue, CompressionType, DmuType, Dva, EndianOrder, ENDIAN_ORDER_NATIVE,
+    ENDIAN_ORDER_SWAP, SECTOR_SHIFT,
 };
-use rzfs::phys::{EndianOrder, ENDIAN_ORDER_NATIVE, ENDIAN_ORDER_SWAP, SECTOR_SHIFT};
 
 const MICROSECONDS_PER_SECOND: u64 = 1_000_000;
 
-fn benchmark_checksum(
-    checksum: &mut dyn Checksum,
-    order: EndianOrder,
-    data: &[u8],
-    iterations: usize,
+/// Default duration of each measurement.
+const DEFAULT_DURATION_MS: u64 = 1000;
+
+/// Data size for checksum benchmarks (128 KiB).
+const CHECKSUM_SIZE: usize = 1 << (SECTOR_SHIFT + 8);
+
+/// Block sizes for compression benchmarks, 512 B to 16 MiB.
+const COMPRESSION_SIZES: [usize; 7] = [
+    512,
+    4 * 1024,
+    32 * 1024,
+    128 * 1024,
+    1024 * 1024,
+    4 * 1024 * 1024,
+    16 * 1024 * 1024,
+];
+
+/// Compressed data for codecs without an encoder, of the codec, the data
+/// pattern, and the decompressed size. Generated by
+/// `exe/fixtures/compression/generate.py`.
+const DECOMPRESSION_FIXTURES: [(&str, Pattern, usize, &[u8]); 4] = [
+    (
+        "gzip",
+        Pattern::Text,
+        128 * 1024,
+        include_bytes!("../../fixtures/compression/text-131072.gzip"),
+    ),
+    (
+        "gzip",
+        Pattern::Zeros,
+        128 * 1024,
+        include_bytes!("../../fixtures/compression/zeros-131072.gzip"),
+    ),
+    (
+        "zstd",
+        Pattern::Text,
+        128 * 1024,
+        include_bytes!("../../fixtures/compression/text-131072.zstd"),
+    ),
+    (
+        "zstd",
+        Pattern::Zeros,
+        128 * 1024,
+        include_bytes!("../../fixtures/compression/zeros-131072.zstd"),
+    ),
+];
+
+/// Block sizes for block pointer decode benchmarks, 512 B to 128 KiB.
+const DECODE_SIZES: [usize; 4] = [512, 4 * 1024, 16 * 1024, 128 * 1024];
+
+/// Words for the text data pattern.
+const TEXT_WORDS: [&str; 16] = [
+    "the", "pool", "block", "pointer", "checksum", "dataset", "snapshot", "of", "and", "vdev",
+    "label", "object", "to", "a", "record", "is",
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Output format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+    Table,
+}
+
+/// Data pattern.
+#[derive(Clone, Copy, Debug)]
+enum Pattern {
+    Random,
+    Text,
+    Zeros,
+}
+
+impl Pattern {
+    fn all() -> [Pattern; 3] {
+        [Pattern::Zeros, Pattern::Random, Pattern::Text]
+    }
+
+    fn to_str(self) -> &'static str {
+        match self {
+            Pattern::Random => "random",
+            Pattern::Text => "text",
+            Pattern::Zeros => "zeros",
+        }
+    }
+
+    /// Fill `data` with the pattern.
+    fn fill(self, data: &mut [u8]) {
+        // xorshift64, so runs are reproducible.
+        let mut state: u64 = 0x9e3779b97f4a7c15;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        match self {
+            Pattern::Random => {
+                for chunk in data.chunks_mut(8) {
+                    let bytes = next().to_le_bytes();
+                    chunk.copy_from_slice(&bytes[0..chunk.len()]);
+                }
+            }
+            Pattern::Text => {
+                let mut offset = 0;
+                while offset < data.len() {
+                    let word = TEXT_WORDS[(next() % TEXT_WORDS.len() as u64) as usize];
+                    for b in word.bytes().chain([b' ']) {
+                        if offset == data.len() {
+                            break;
+                        }
+                        data[offset] = b;
+                        offset += 1;
+                    }
+                }
+            }
+            Pattern::Zeros => data.fill(0),
+        }
+    }
+}
+
+/// Benchmark measurement.
+struct Measurement {
+    /// Benchmark name, such as the checksum or compression.
+    benchmark: &'static str,
+
+    /// Implementation name.
+    implementation: String,
+
+    /// Operation, such as native, byteswap, compress, or decompress.
+    operation: &'static str,
+
+    /// Data pattern.
+    pattern: &'static str,
+
+    /// Input size in bytes.
+    size: usize,
+
+    /// Throughput, or [`None`] if not supported.
+    bytes_per_second: Option<u64>,
+
+    /// Compression ratio, or [`None`] if not applicable.
+    ratio: Option<f64>,
+}
+
+/// Benchmark options.
+struct Options {
+    /// Duration of each measurement.
     duration_us: u64,
-) -> Result<u64, ChecksumError> {
+
+    /// Output format.
+    format: Format,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Allocate a 4096 byte aligned buffer.
+ *
+ * Returns the allocation, and the offset of the aligned buffer.
+ */
+fn allocate_aligned(size: usize) -> (Vec<u8>, usize) {
+    // TODO(cybojanek): Is there an API for this?
+    let alignment = 4096;
+    let data: Vec<u8> = vec![0; size + alignment];
+
+    let mut offset = 0;
+    let addr = data.as_ptr();
+    let remainder = (addr as usize) % alignment;
+    if remainder != 0 {
+        offset = alignment - remainder;
+    }
+
+    (data, offset)
+}
+
+/** Run `f` repeatedly for `duration_us`, and return the number of bytes per
+ * second, where each call processes `size` bytes.
+ */
+fn measure<E>(
+    size: usize,
+    duration_us: u64,
+    mut f: impl FnMut() -> Result<(), E>,
+) -> Result<u64, E> {
     // Warm up.
-    checksum.reset(order)?;
-    checksum.update(data)?;
-    checksum.finalize()?;
+    f()?;
+
+    // Check elapsed time about every 4 MiB of work.
+    let iterations = (4 * 1024 * 1024 / size).max(1);
 
     // Keep track of elapsed time and work.
     let mut microseconds = 0;
@@ -33,215 +222,528 @@ fn benchmark_checksum(
 
     while microseconds < duration_us {
         for _ in 0..iterations {
-            checksum.reset(order)?;
-            checksum.update(data)?;
-            checksum.finalize()?;
+            f()?;
             total_iterations += 1;
         }
 
         microseconds = start.elapsed().as_micros() as u64;
     }
 
-    // Total number of bytes hashed.
-    let total_size = (data.len() as u64) * total_iterations;
+    // Total number of bytes processed.
+    let total_size = (size as u64) * total_iterations;
 
     // Bytes per second.
-    let bytes_per_second = (MICROSECONDS_PER_SECOND * total_size) / microseconds;
-
-    Ok(bytes_per_second)
+    Ok((MICROSECONDS_PER_SECOND * total_size) / microseconds)
 }
 
-fn benchmark_fletcher2(
+////////////////////////////////////////////////////////////////////////////////
+
+/// Name and instance of each implementation of a checksum.
+type ChecksumInstances = Vec<(String, Result<Box<dyn Checksum>, ChecksumError>)>;
+
+fn benchmark_checksum(
+    checksum: &mut dyn Checksum,
+    order: EndianOrder,
     data: &[u8],
-    iterations: usize,
     duration_us: u64,
-    display_units: u64,
-) -> Result<(), ChecksumError> {
-    println!(
-        "{:>16} {:>11} {:>11}",
-        "implementation", "native", "byteswap"
-    );
+) -> Result<u64, ChecksumError> {
+    measure(data.len(), duration_us, || {
+        checksum.reset(order)?;
+        checksum.update(data)?;
+        checksum.finalize()?;
+        Ok(())
+    })
+}
 
-    // Loop through each implementation.
-    for implementation in Fletcher2Implementation::all() {
-        print!("{implementation:>16}");
+/** Benchmark each implementation of a checksum.
+ *
+ * - `benchmark` name of the checksum
+ * - `implementations` name and instance of each implementation
+ * - `byteswap` if the checksum has a byteswap variant
+ */
+fn benchmark_checksums(
+    benchmark: &'static str,
+    implementations: ChecksumInstances,
+    byteswap: bool,
+    options: &Options,
+) -> Result<Vec<Measurement>, Box<dyn Error>> {
+    let (mut data, offset) = allocate_aligned(CHECKSUM_SIZE);
+    let data = &mut data[offset..offset + CHECKSUM_SIZE];
+
+    // Fill data buffer.
+    for (i, x) in data.iter_mut().enumerate() {
+        *x = i as u8;
+    }
 
-        let mut checksum = match Fletcher2::new(*implementation) {
-            Ok(v) => v,
+    let mut orders = vec![("native", ENDIAN_ORDER_NATIVE)];
+    if byteswap {
+        orders.push(("byteswap", ENDIAN_ORDER_SWAP));
+    }
+
+    let mut measurements = Vec::new();
+
+    // Loop through each implementation.
+    for (implementation, checksum) in implementations {
+        let mut checksum = match checksum {
+            Ok(v) => Some(v),
             Err(
                 _err @ ChecksumError::Unsupported {
                     checksum: _,
                     implementation: _,
                 },
-            ) => {
-                // Skip if not supported.
-                println!(" {:>11} {:>11}", "n/a", "n/a");
-                continue;
-            }
+            ) => None,
+            Err(e) => return Err(e.into()),
         };
 
         // Loop through native and swap order.
-        for order in [ENDIAN_ORDER_NATIVE, ENDIAN_ORDER_SWAP] {
-            let bytes_per_second =
-                benchmark_checksum(&mut checksum, order, data, iterations, duration_us)?;
-
-            // Display units.
-            print!(" {:11}", bytes_per_second / display_units)
+        for (operation, order) in &orders {
+            let bytes_per_second = match &mut checksum {
+                Some(checksum) => Some(benchmark_checksum(
+                    checksum.as_mut(),
+                    *order,
+                    data,
+                    options.duration_us,
+                )?),
+                // Skip if not supported.
+                None => None,
+            };
+
+            measurements.push(Measurement {
+                benchmark,
+                implementation: implementation.clone(),
+                operation,
+                pattern: "sequential",
+                size: data.len(),
+                bytes_per_second,
+                ratio: None,
+            });
         }
-        println!();
     }
 
-    Ok(())
+    Ok(measurements)
 }
 
-fn benchmark_fletcher4(
-    data: &[u8],
-    iterations: usize,
-    duration_us: u64,
-    display_units: u64,
-) -> Result<(), ChecksumError> {
-    println!(
-        "{:>16} {:>11} {:>11}",
-        "implementation", "native", "byteswap"
-    );
+/// Box each implementation of a checksum, for [`benchmark_checksums`].
+fn checksum_implementations<I: ToString, C: Checksum + 'static>(
+    all: &[I],
+    new: impl Fn(&I) -> Result<C, ChecksumError>,
+) -> ChecksumInstances {
+    all.iter()
+        .map(|i| {
+            let checksum = new(i).map(|c| Box::new(c) as Box<dyn Checksum>);
+            (i.to_string(), checksum)
+        })
+        .collect()
+}
 
-    // Loop through each implementation.
-    for implementation in Fletcher4Implementation::all() {
-        print!("{implementation:>16}");
+////////////////////////////////////////////////////////////////////////////////
+
+/** Benchmark compression and decompression of each codec, for each data
+ * pattern and block size.
+ */
+fn benchmark_compression(options: &Options) -> Result<Vec<Measurement>, Box<dyn Error>> {
+    type Codec = (
+        &'static str,
+        Box<dyn Compression>,
+        Box<dyn Decompression>,
+        u32,
+    );
 
-        let mut checksum = match Fletcher4::new(*implementation) {
-            Ok(v) => v,
-            Err(
-                _err @ ChecksumError::Unsupported {
-                    checksum: _,
-                    implementation: _,
-                },
-            ) => {
-                // Skip if not supported.
-                println!(" {:>11} {:>11}", "n/a", "n/a");
-                continue;
+    let mut codecs: Vec<Codec> = vec![
+        (
+            "lz4",
+            Box::new(Lz4Encoder::new()),
+            Box::new(Lz4Decoder {}),
+            0,
+        ),
+        (
+            "lzjb",
+            Box::new(LzjbEncoder::new()),
+            Box::new(LzjbDecoder {}),
+            0,
+        ),
+        (
+            "zle",
+            Box::new(ZleEncoder {}),
+            Box::new(ZleDecoder {}),
+            ZLE_LEVEL,
+        ),
+    ];
+
+    let max_size = COMPRESSION_SIZES[COMPRESSION_SIZES.len() - 1];
+    let (mut src, src_offset) = allocate_aligned(max_size);
+    let (mut dst, dst_offset) = allocate_aligned(max_size);
+    let (mut out, out_offset) = allocate_aligned(max_size);
+
+    let mut measurements = Vec::new();
+
+    for pattern in Pattern::all() {
+        pattern.fill(&mut src[src_offset..src_offset + max_size]);
+
+        for size in COMPRESSION_SIZES {
+            let src = &src[src_offset..src_offset + size];
+            let dst = &mut dst[dst_offset..dst_offset + size];
+            let out = &mut out[out_offset..out_offset + size];
+
+            for (benchmark, encoder, decoder, level) in codecs.iter_mut() {
+                // Compression of incompressible data is still measured.
+                let compressed_size = match encoder.compress(dst, src, *level) {
+                    Ok(v) => Some(v),
+                    Err(CompressionError::NotCompressable {}) => None,
+                    Err(e) => return Err(e.into()),
+                };
+
+                let bytes_per_second = measure(size, options.duration_us, || {
+                    match encoder.compress(dst, src, *level) {
+                        Ok(_) | Err(CompressionError::NotCompressable {}) => Ok(()),
+                        Err(e) => Err(e),
+                    }
+                })?;
+
+                measurements.push(Measurement {
+                    benchmark,
+                    implementation: String::from("generic"),
+                    operation: "compress",
+                    pattern: pattern.to_str(),
+                    size,
+                    bytes_per_second: Some(bytes_per_second),
+                    ratio: compressed_size.map(|v| size as f64 / v as f64),
+                });
+
+                // Decompress, if the data was compressed.
+                let bytes_per_second = match compressed_size {
+                    Some(compressed_size) => {
+                        // Re-compress, because the benchmark clobbered dst.
+                        encoder.compress(dst, src, *level)?;
+                        let compressed = &dst[0..compressed_size];
+
+                        decoder.decompress(out, compressed, *level)?;
+                        if out != src {
+                            return Err(format!("{benchmark} round trip mismatch").into());
+                        }
+
+                        Some(measure(size, options.duration_us, || {
+                            decoder.decompress(out, compressed, *level)
+                        })?)
+                    }
+                    None => None,
+                };
+
+                measurements.push(Measurement {
+                    benchmark,
+                    implementation: String::from("generic"),
+                    operation: "decompress",
+                    pattern: pattern.to_str(),
+                    size,
+                    bytes_per_second,
+                    ratio: compressed_size.map(|v| size as f64 / v as f64),
+                });
             }
+        }
+    }
+
+    ////////////////////////////////////
+    // Decompression only, from fixtures.
+    let mut gzip = GzipDecoder {};
+    let mut zstd = ZstdDecoder::new();
+
+    for (benchmark, pattern, size, compressed) in DECOMPRESSION_FIXTURES {
+        let decoder: &mut dyn Decompression = match benchmark {
+            "gzip" => &mut gzip,
+            _ => &mut zstd,
         };
 
-        // Loop through native and swap order.
-        for order in [ENDIAN_ORDER_NATIVE, ENDIAN_ORDER_SWAP] {
-            let bytes_per_second =
-                benchmark_checksum(&mut checksum, order, data, iterations, duration_us)?;
+        pattern.fill(&mut src[src_offset..src_offset + size]);
+        let src = &src[src_offset..src_offset + size];
+        let out = &mut out[out_offset..out_offset + size];
 
-            // Display units.
-            print!(" {:11}", bytes_per_second / display_units)
+        decoder.decompress(out, compressed, 0)?;
+        if out != src {
+            return Err(format!("{benchmark} fixture mismatch").into());
         }
-        println!();
+
+        let bytes_per_second = measure(size, options.duration_us, || {
+            decoder.decompress(out, compressed, 0)
+        })?;
+
+        measurements.push(Measurement {
+            benchmark,
+            implementation: String::from("generic"),
+            operation: "decompress",
+            pattern: pattern.to_str(),
+            size,
+            bytes_per_second: Some(bytes_per_second),
+            ratio: Some(size as f64 / compressed.len() as f64),
+        });
     }
 
-    Ok(())
+    Ok(measurements)
 }
 
-fn benchmark_sha256(
-    data: &[u8],
-    iterations: usize,
-    duration_us: u64,
-    display_units: u64,
-) -> Result<(), ChecksumError> {
-    println!(
-        "{:>16} {:>11} {:>11}",
-        "implementation", "native", "byteswap"
-    );
+////////////////////////////////////////////////////////////////////////////////
 
-    // Loop through each implementation.
-    for implementation in Sha256Implementation::all() {
-        print!("{implementation:>16}");
+/** Benchmark decoding indirect blocks of [`BlockPointer`], for each block
+ * size.
+ */
+fn benchmark_decode(options: &Options) -> Result<Vec<Measurement>, Box<dyn Error>> {
+    let mut measurements = Vec::new();
 
-        let mut checksum = match Sha256::new(*implementation) {
-            Ok(v) => v,
-            Err(
-                _err @ ChecksumError::Unsupported {
-                    checksum: _,
-                    implementation: _,
+    for size in DECODE_SIZES {
+        let mut block = vec![0; size];
+
+        // Encode an indirect block, full of block pointers.
+        let mut bl_encoder = BigLittleEndianEncoder::to_bytes(&mut block, ENDIAN_ORDER_NATIVE);
+        let encoder = bl_encoder.encoder();
+
+        for i in 0..(size / BlockPointer::SIZE) as u64 {
+            let ptr = BlockPointer::Regular(BlockPointerRegular {
+                checksum_type: ChecksumType::Fletcher4,
+                checksum_value: ChecksumValue {
+                    words: [i, i + 1, i + 2, i + 3],
                 },
-            ) => {
-                // Skip if not supported.
-                println!(" {:>11} {:>11}", "n/a", "n/a");
-                continue;
+                compression: CompressionType::Lz4,
+                dedup: false,
+                dmu: DmuType::PlainFileContents,
+                dvas: [
+                    Some(Dva {
+                        allocated: 256,
+                        offset: 256 * i,
+                        is_gang: false,
+                        vdev: 0,
+                    }),
+                    None,
+                    None,
+                ],
+                order: ENDIAN_ORDER_NATIVE,
+                fill_count: 1,
+                level: 0,
+                logical_birth_txg: 100 + i,
+                logical_sectors: 256,
+                physical_birth_txg: 0,
+                physical_sectors: 128,
+            });
+            ptr.to_encoder(encoder)?;
+        }
+
+        let bytes_per_second = measure(size, options.duration_us, || {
+            let mut bl_decoder = BigLittleEndianDecoder::from_bytes(&block, ENDIAN_ORDER_NATIVE);
+            let decoder = bl_decoder.decoder_as_mut();
+
+            while !decoder.is_empty() {
+                BlockPointer::from_decoder(decoder)?;
             }
-        };
 
-        let bytes_per_second = benchmark_checksum(
-            &mut checksum,
-            ENDIAN_ORDER_NATIVE,
-            data,
-            iterations,
-            duration_us,
-        )?;
+            Ok::<(), Box<dyn Error>>(())
+        })?;
+
+        measurements.push(Measurement {
+            benchmark: "blkptr",
+            implementation: String::from("generic"),
+            operation: "decode",
+            pattern: "indirect",
+            size,
+            bytes_per_second: Some(bytes_per_second),
+            ratio: None,
+        });
+    }
 
-        // Display units.
-        print!(" {:11}", bytes_per_second / display_units);
+    Ok(measurements)
+}
 
-        // There is no byte swap.
-        print!(" {:>11}", "n/a");
+////////////////////////////////////////////////////////////////////////////////
 
-        println!();
+fn print_measurements(measurements: &[Measurement], format: Format) {
+    let display_units = 1024 * 1024;
+
+    match format {
+        Format::Csv => {
+            println!("benchmark,implementation,operation,pattern,size,bytes_per_second,ratio");
+            for m in measurements {
+                println!(
+                    "{},{},{},{},{},{},{}",
+                    m.benchmark,
+                    m.implementation,
+                    m.operation,
+                    m.pattern,
+                    m.size,
+                    m.bytes_per_second
+                        .map(|v| v.to_string())
+                        .unwrap_or_default(),
+                    m.ratio.map(|v| format!("{v:.3}")).unwrap_or_default(),
+                );
+            }
+        }
+        Format::Json => {
+            println!("[");
+            for (i, m) in measurements.iter().enumerate() {
+                let separator = if i + 1 < measurements.len() { "," } else { "" };
+                println!(
+                    "  {{\"benchmark\": \"{}\", \"implementation\": \"{}\", \"operation\": \"{}\", \"pattern\": \"{}\", \"size\": {}, \"bytes_per_second\": {}, \"ratio\": {}}}{separator}",
+                    m.benchmark,
+                    m.implementation,
+                    m.operation,
+                    m.pattern,
+                    m.size,
+                    m.bytes_per_second
+                        .map(|v| v.to_string())
+                        .unwrap_or(String::from("null")),
+                    m.ratio
+                        .map(|v| format!("{v:.3}"))
+                        .unwrap_or(String::from("null")),
+                );
+            }
+            println!("]");
+        }
+        Format::Table => {
+            println!(
+                "{:>10} {:>16} {:>10} {:>10} {:>10} {:>11} {:>7}",
+                "benchmark", "implementation", "operation", "pattern", "size", "MiB/s", "ratio"
+            );
+            for m in measurements {
+                println!(
+                    "{:>10} {:>16} {:>10} {:>10} {:>10} {:>11} {:>7}",
+                    m.benchmark,
+                    m.implementation,
+                    m.operation,
+                    m.pattern,
+                    m.size,
+                    m.bytes_per_second
+                        .map(|v| (v / display_units).to_string())
+                        .unwrap_or(String::from("n/a")),
+                    m.ratio
+                        .map(|v| format!("{v:.2}"))
+                        .unwrap_or(String::from("n/a")),
+                );
+            }
+        }
     }
+}
 
-    Ok(())
+fn run(benchmark: &str, options: &Options) -> Result<Vec<Measurement>, Box<dyn Error>> {
+    let salt = [0; 32];
+
+    match benchmark {
+        "blake3" => benchmark_checksums(
+            "blake3",
+            checksum_implementations(Blake3Implementation::all(), |i| Blake3::new(*i, None)),
+            true,
+            options,
+        ),
+        "compression" => benchmark_compression(options),
+        "decode" => benchmark_decode(options),
+        "edonr" => benchmark_checksums(
+            "edonr",
+            vec![(String::from("generic"), Ok(Box::new(Edonr::new(&salt))))],
+            true,
+            options,
+        ),
+        "fletcher2" => benchmark_checksums(
+            "fletcher2",
+            checksum_implementations(Fletcher2Implementation::all(), |i| Fletcher2::new(*i)),
+            true,
+            options,
+        ),
+        "fletcher4" => benchmark_checksums(
+            "fletcher4",
+            checksum_implementations(Fletcher4Implementation::all(), |i| Fletcher4::new(*i)),
+            true,
+            options,
+        ),
+        // There is no byte swap.
+        "sha256" => benchmark_checksums(
+            "sha256",
+            checksum_implementations(Sha256Implementation::all(), |i| Sha256::new(*i)),
+            false,
+            options,
+        ),
+        // There is no byte swap.
+        "sha512_256" => benchmark_checksums(
+            "sha512_256",
+            checksum_implementations(Sha512_256Implementation::all(), |i| Sha512_256::new(*i)),
+            false,
+            options,
+        ),
+        "skein" => benchmark_checksums(
+            "skein",
+            vec![(String::from("generic"), Ok(Box::new(Skein::new(&salt))))],
+            true,
+            options,
+        ),
+        _ => Err(format!("unknown benchmark {benchmark}").into()),
+    }
 }
 
 fn print_usage(arg0: &str) {
-    eprintln!("usage: {arg0} fletcher2|fletcher4|sha256");
+    eprintln!(
+        "usage: {arg0} [--format table|csv|json] [--duration MS] blake3|compression|decode|edonr|fletcher2|fletcher4|sha256|sha512_256|skein"
+    );
 }
 
 fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
 
-    let duration_us = 3 * MICROSECONDS_PER_SECOND;
-
-    if args.len() != 2 {
-        print_usage(&args[0]);
-        return ExitCode::FAILURE;
-    }
-
-    // 128 KiB.
-    let size: usize = 1 << (SECTOR_SHIFT + 8);
-    let iterations = 32;
-    let display_units = 1024 * 1024;
-
-    // Allocate and align data.
-    // TODO(cybojanek): Is there an API for this?
-    let alignment = 4096;
-    let mut data: Vec<u8> = vec![0; size + alignment];
-
-    let mut offset = 0;
-    let addr = data.as_ptr();
-    let remainder = (addr as usize) % alignment;
-    if remainder != 0 {
-        offset = alignment - remainder;
-    }
-    let data = &mut data[offset..size + offset];
-
-    // Fill data buffer.
-    for (i, x) in data.iter_mut().enumerate() {
-        *x = i as u8;
+    let mut options = Options {
+        duration_us: DEFAULT_DURATION_MS * 1000,
+        format: Format::Table,
+    };
+    let mut benchmark = None;
+
+    // Parse arguments.
+    let mut idx = 1;
+    while idx < args.len() {
+        let arg = args[idx].as_str();
+        let value = args.get(idx + 1).map(|v| v.as_str());
+
+        match (arg, value) {
+            ("--format", Some(value)) => {
+                options.format = match value {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    "table" => Format::Table,
+                    _ => {
+                        print_usage(&args[0]);
+                        return ExitCode::FAILURE;
+                    }
+                };
+                idx += 2;
+            }
+            ("--duration", Some(value)) => {
+                options.duration_us = match value.parse::<u64>().map(|v| v.checked_mul(1000)) {
+                    Ok(Some(v)) if v > 0 => v,
+                    _ => {
+                        print_usage(&args[0]);
+                        return ExitCode::FAILURE;
+                    }
+                };
+                idx += 2;
+            }
+            (_, _) if benchmark.is_none() && !arg.starts_with("--") => {
+                benchmark = Some(arg);
+                idx += 1;
+            }
+            _ => {
+                print_usage(&args[0]);
+                return ExitCode::FAILURE;
+            }
+        }
     }
 
-    if args[1] == "fletcher2" {
-        if let Err(e) = benchmark_fletcher2(data, iterations, duration_us, display_units) {
-            eprintln!("{e}");
+    let benchmark = match benchmark {
+        Some(v) => v,
+        None => {
+            print_usage(&args[0]);
             return ExitCode::FAILURE;
         }
-    } else if args[1] == "fletcher4" {
-        if let Err(e) = benchmark_fletcher4(data, iterations, duration_us, display_units) {
-            eprintln!("{e}");
-            return ExitCode::FAILURE;
+    };
+
+    match run(benchmark, &options) {
+        Ok(measurements) => {
+            print_measurements(&measurements, options.format);
+            ExitCode::SUCCESS
         }
-    } else if args[1] == "sha256" {
-        if let Err(e) = benchmark_sha256(data, iterations, duration_us, display_units) {
+        Err(e) => {
             eprintln!("{e}");
-            return ExitCode::FAILURE;
+            ExitCode::FAILURE
         }
-    } else {
-        print_usage(&args[0]);
-        return ExitCode::FAILURE;
     }
-
-    ExitCode::SUCCESS
 }
@@ -9,11 +9,12 @@ use std::io::Write;
 use std::process;
 
 use rzfs::checksum;
-use rzfs::checksum::Checksum;
 use rzfs::compression;
-use rzfs::compression::{Compression, Decompression};
+use rzfs::compression::Compression;
+use rzfs::io::{BlockReader, Vdev, VdevDevice, VdevLeaf};
 use rzfs::phys;
 use rzfs::userspace;
+use rzfs::userspace::BlockDevice;
 
 ////////////////////////////////////////////////////////////////////////////////
 
@@ -51,287 +52,86 @@ fn bytes_to_hex(bytes: &[u8]) -> String {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-/// Reads `sectors` from a [`phys::Dva`].
-fn dva_read(
-    blk: &userspace::BlockDevice,
-    dva: &phys::Dva,
-    sectors: u32,
-) -> Result<Vec<u8>, Box<dyn Error>> {
-    if sectors > dva.allocated {
-        todo!("error");
-    }
-
-    let size = usize::try_from(sectors)?;
-    let size = match size.checked_shl(phys::SECTOR_SHIFT) {
-        Some(v) => v,
-        None => todo!("error"),
-    };
-
-    if dva.is_gang {
-        todo!("implement gang");
-    }
-
-    let mut data = vec![0; size];
-    blk.read(
-        &mut data,
-        dva.offset + phys::BootBlock::BLOCK_DEVICE_OFFSET + phys::BootBlock::SECTORS,
-    )?;
-
-    Ok(data)
-}
-
-////////////////////////////////////////////////////////////////////////////////
-
-/// Reads an embedded block pointer.
-fn block_pointer_embedded_read(
-    _ptr: &phys::BlockPointerEmbedded,
-) -> Result<Vec<u8>, Box<dyn Error>> {
-    todo!();
-}
-
-/// Reads a regular block pointer.
-fn block_pointer_regular_read(
-    blk_devs: &[userspace::BlockDevice],
+/// Checks that re-compression of the logical bytes produces the physical bytes.
+fn block_pointer_recompress_check(
     ptr: &phys::BlockPointerRegular,
-) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut data: Option<Vec<u8>> = None;
-
-    // Read all the dvas.
-    for dva in ptr.dvas.iter().flatten() {
-        let vdev: usize = dva.vdev.try_into().unwrap();
-        if vdev >= blk_devs.len() {
-            todo!("implement error handling");
-        }
-
-        let blk = &blk_devs[vdev];
-
-        // Read physical bytes.
-        let phys_bytes = dva_read(blk, dva, ptr.physical_sectors)?;
-
-        // Verify checksum.
-        let computed_checksum = match ptr.checksum_type {
-            phys::ChecksumType::Fletcher2 => {
-                let mut h = checksum::Fletcher2::new(checksum::Fletcher2Implementation::Generic)?;
-                h.hash(&phys_bytes, ptr.order)?
-            }
-            phys::ChecksumType::Fletcher4 => {
-                let mut h = checksum::Fletcher4::new(checksum::Fletcher4Implementation::Generic)?;
-                h.hash(&phys_bytes, ptr.order)?
-            }
-            _ => todo!("Implement: {}", ptr.checksum_type),
-        };
-
-        match ptr.checksum_type {
-            phys::ChecksumType::Off => (),
-            _ => {
-                if computed_checksum != ptr.checksum_value.words {
-                    todo!(
-                        "handle checksum error: {:?} {:?}",
-                        computed_checksum,
-                        ptr.checksum_value.words
-                    );
-                }
-            }
-        }
-
-        // Decompress.
-        let decompressed_size = usize::try_from(ptr.logical_sectors)?;
-        let decompressed_size = match decompressed_size.checked_shl(phys::SECTOR_SHIFT) {
-            Some(v) => v,
-            None => todo!("error"),
-        };
-        let mut decompressed_data = vec![0; decompressed_size];
-
-        match ptr.compression {
-            phys::CompressionType::Off => {
-                if ptr.logical_sectors != ptr.physical_sectors {
-                    todo!("handle error");
-                }
-                data = Some(phys_bytes)
-            }
-            phys::CompressionType::Lzjb => {
-                let mut lzjb = compression::LzjbDecoder {};
-                lzjb.decompress(&mut decompressed_data, &phys_bytes, 0)?;
-
-                let mut recompressed_data = vec![0; decompressed_size];
-                let mut lzjb = compression::LzjbEncoder::new();
-                let matches =
-                    match lzjb.compress_pre_v21(&mut recompressed_data, &decompressed_data, 0) {
-                        Ok(v) => {
-                            if v <= phys_bytes.len() {
-                                recompressed_data[0..phys_bytes.len()] == phys_bytes
-                            } else {
-                                false
-                            }
-                        }
-                        Err(_) => false,
-                    };
-
-                if !matches {
-                    recompressed_data.fill(0);
-                    let csize = lzjb.compress(&mut recompressed_data, &decompressed_data, 0)?;
-                    assert!(csize <= phys_bytes.len());
-                    assert_eq!(recompressed_data[0..phys_bytes.len()], phys_bytes);
+    phys_bytes: &[u8],
+    decompressed_data: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let decompressed_size = decompressed_data.len();
+
+    match ptr.compression {
+        phys::CompressionType::Lzjb => {
+            let mut recompressed_data = vec![0; decompressed_size];
+            let mut lzjb = compression::LzjbEncoder::new();
+            let matches = match lzjb.compress_pre_v21(&mut recompressed_data, decompressed_data, 0)
+            {
+                Ok(v) => {
+                    if v <= phys_bytes.len() {
+                        recompressed_data[0..phys_bytes.len()] == *phys_bytes
+                    } else {
+                        false
+                    }
                 }
+                Err(_) => false,
+            };
 
-                // Save most recent DVA as response.
-                data = Some(decompressed_data);
+            if !matches {
+                recompressed_data.fill(0);
+                let csize = lzjb.compress(&mut recompressed_data, decompressed_data, 0)?;
+                assert!(csize <= phys_bytes.len());
+                assert_eq!(recompressed_data[0..phys_bytes.len()], *phys_bytes);
             }
-            _ => todo!("Implement: {}", ptr.compression),
         }
+        phys::CompressionType::Lz4 => {
+            let mut recompressed_data = vec![0; decompressed_size];
+            let mut lz4 = compression::Lz4Encoder::new();
+            let csize = lz4.compress(&mut recompressed_data, decompressed_data, 0)?;
+            assert!(csize <= phys_bytes.len());
+            assert_eq!(recompressed_data[0..phys_bytes.len()], *phys_bytes);
+        }
+        phys::CompressionType::Zle => {
+            let mut recompressed_data = vec![0; decompressed_size];
+            let mut zle = compression::ZleEncoder {};
+            let csize = zle.compress(
+                &mut recompressed_data,
+                decompressed_data,
+                compression::ZLE_LEVEL,
+            )?;
+            assert!(csize <= phys_bytes.len());
+            assert_eq!(recompressed_data[0..csize], phys_bytes[0..csize]);
+        }
+        phys::CompressionType::Zstd => {
+            let header = compression::ZstdHeader::from_bytes(phys_bytes)?;
+            println!("zstd level: {} version: {}", header.level, header.version);
+        }
+        _ => (),
     }
 
-    match data {
-        Some(bytes) => Ok(bytes),
-        None => todo!("handle error"),
-    }
+    Ok(())
 }
 
-/// Reads the [`phys::BlockPointer`].
+/// Reads the [`phys::BlockPointer`], and checks re-compression of regular blocks.
 fn block_pointer_read(
-    blk_devs: &[userspace::BlockDevice],
+    reader: &mut BlockReader<'_>,
     ptr: &phys::BlockPointer,
 ) -> Result<Vec<u8>, Box<dyn Error>> {
     match ptr {
-        phys::BlockPointer::Embedded(emb) => block_pointer_embedded_read(emb),
-        phys::BlockPointer::Encrypted(_) => todo!("Implement encrypted"),
-        phys::BlockPointer::Regular(reg) => block_pointer_regular_read(blk_devs, reg),
-    }
-}
-
-////////////////////////////////////////////////////////////////////////////////
-
-type DnodeReadResult = Result<Option<(phys::EndianOrder, Vec<u8>)>, Box<dyn Error>>;
-
-/// Reads the [`phys::Dnode`]. Returns [None] if empty.
-fn dnode_read_block(
-    blk_devs: &[userspace::BlockDevice],
-    dnode: &phys::Dnode,
-    block_id: u64,
-) -> DnodeReadResult {
-    // Return [None] if the block id is not allocated.
-    if block_id > dnode.max_block_id {
-        return Ok(None);
-    }
-
-    // Number of block pointers per indirect block.
-    let block_pointers_per_block = 1u64.checked_shl(dnode.indirect_block_shift.into()).unwrap()
-        / (phys::BlockPointer::SIZE as u64);
-
-    let levels = usize::from(dnode.levels);
-    let mut block_ids: [u64; 8] = [0; 8];
-    let mut block_pointer_idxs: [usize; 8] = [0; 8];
-
-    if levels > block_ids.len() {
-        todo!("Error too many levels");
-    }
-
-    // Level 0 block is the requested block id.
-    block_ids[0] = block_id;
-
-    // Compute the block ids and block pointers for the intermediate levels.
-    for level in 1..levels {
-        let block_id = block_ids[level - 1];
-        block_ids[level] = block_id / block_pointers_per_block;
-        block_pointer_idxs[level] =
-            ((block_id % block_pointers_per_block) as usize) * phys::BlockPointer::SIZE;
-    }
-
-    // Read the top most block pointer.
-    let ptr = match usize::try_from(block_ids[levels - 1]) {
-        Ok(idx) => {
-            if let Some(Some(ptr)) = dnode.pointers().get(idx) {
-                ptr
-            } else {
-                // Block id is too large for dnode.pointers(), or empty.
-                // That means the block is not allocated, so return None.
-                return Ok(None);
-            }
-        }
-        Err(_) => {
-            // Block id is too large for usize - return None, because this
-            // block is not allocated.
-            return Ok(None);
+        phys::BlockPointer::Regular(reg) => {
+            let phys_bytes = reader.read_physical(reg)?;
+            let decompressed_data = reader.decompress(reg, phys_bytes.clone())?;
+            block_pointer_recompress_check(reg, &phys_bytes, &decompressed_data)?;
+            Ok(decompressed_data)
         }
-    };
-
-    // Block order and bytes.
-    let mut order = ptr.order();
-    let mut block_bytes = block_pointer_read(blk_devs, ptr)?;
-
-    // Read intermediate block pointers.
-    let mut level = levels - 1;
-
-    while level > 0 {
-        // Decode the block as an intermediate block.
-        let mut bl_decoder = phys::BigLittleEndianDecoder::from_bytes(&block_bytes, order);
-        let decoder = bl_decoder.decoder_as_mut();
-
-        // Seek to index of block pointer.
-        decoder.seek(block_pointer_idxs[level])?;
-
-        // Decode block pointer.
-        let ptr = match phys::BlockPointer::from_decoder(decoder)? {
-            Some(ptr) => ptr,
-            None => return Ok(None),
-        };
-
-        // Read data for next block.
-        order = ptr.order();
-        block_bytes = block_pointer_read(blk_devs, &ptr)?;
-
-        // Decrement level.
-        level -= 1;
-    }
-
-    Ok(Some((order, block_bytes)))
-}
-
-/// Reads the [`phys::Dnode`] object. Returns [None] if empty.
-fn dnode_read_object(
-    blk_devs: &[userspace::BlockDevice],
-    dnode: &phys::Dnode,
-    object_id: u64,
-    object_size: usize,
-) -> DnodeReadResult {
-    // Compute the block size.
-    let data_block_size = usize::from(dnode.data_block_size_sectors)
-        .checked_shl(phys::SECTOR_SHIFT)
-        .unwrap();
-
-    // Check the block size is a multiple of the object size.
-    assert!(
-        (data_block_size % object_size) == 0,
-        "{data_block_size} % {object_size}"
-    );
-
-    // Compute the number of objects per block.
-    let objects_per_block = data_block_size / object_size;
-
-    // Compute the block id, and the object index in the block.
-    let block_id = object_id / (objects_per_block as u64);
-    let idx_in_block = (object_id as usize) % objects_per_block;
-
-    // Read the block.
-    if let Some((endian, block)) = dnode_read_block(blk_devs, dnode, block_id)? {
-        // Read the object in the block.
-        let mut data = vec![0; object_size];
-
-        let start = idx_in_block * object_size;
-        let end = start + object_size;
-        data.copy_from_slice(&block[start..end]);
-
-        return Ok(Some((endian, data)));
+        _ => Ok(reader.read(ptr)?),
     }
-
-    Ok(None)
 }
 
-////////////////////////////////////////////////////////////////////////////////
+////////
 
 /// Reads the [`phys::Dnode`] object for a [`phys::DmuType::Dnode`]. Returns [None] if empty.
 fn _dnode_read_dnode(
-    blk_devs: &[userspace::BlockDevice],
+    reader: &mut BlockReader<'_>,
     dnode: &phys::Dnode,
     object_id: u64,
 ) -> Result<Option<(phys::EndianOrder, phys::Dnode)>, Box<dyn Error>> {
@@ -340,7 +140,7 @@ fn _dnode_read_dnode(
         // phys::DmuType::DslDataSet => (),
         _ => todo!("todo error {}", dnode.dmu),
     };
-    match dnode_read_object(blk_devs, dnode, object_id, phys::Dnode::SIZE)? {
+    match reader.read_dnode_object(dnode, object_id, phys::Dnode::SIZE)? {
         Some((endian, dnode_bytes)) => {
             let mut bl_decoder = phys::BigLittleEndianDecoder::from_bytes(&dnode_bytes, endian);
             let decoder = bl_decoder.decoder_as_mut();
@@ -355,11 +155,11 @@ fn _dnode_read_dnode(
 }
 
 fn dnode_dump_zap(
-    blk_devs: &[userspace::BlockDevice],
+    reader: &mut BlockReader<'_>,
     dnode: &phys::Dnode,
     depth: usize,
 ) -> Result<(), Box<dyn Error>> {
-    let (endian, zap_header_block) = dnode_read_block(blk_devs, dnode, 0)?.unwrap();
+    let (endian, zap_header_block) = reader.read_dnode_block(dnode, 0)?.unwrap();
     let mut bl_decoder = phys::BigLittleEndianDecoder::from_bytes(&zap_header_block, endian);
     let decoder = bl_decoder.decoder_as_mut();
 
@@ -407,7 +207,7 @@ fn dnode_dump_zap(
 
                 for leaf_pointer in leaf_hashset {
                     let (endian, zap_leaf_block_bytes) =
-                        dnode_read_block(blk_devs, dnode, *leaf_pointer)?.unwrap();
+                        reader.read_dnode_block(dnode, *leaf_pointer)?.unwrap();
                     let mut bl_decoder =
                         phys::BigLittleEndianDecoder::from_bytes(&zap_leaf_block_bytes, endian);
                     let decoder = bl_decoder.decoder_as_mut();
@@ -542,7 +342,7 @@ fn dnode_dump_zap(
 ////////////////////////////////////////////////////////////////////////////////
 
 fn dump_dsl_dataset(
-    blk_devs: &[userspace::BlockDevice],
+    reader: &mut BlockReader<'_>,
     object_set: &phys::ObjectSet,
     depth: usize,
 ) -> Result<(), Box<dyn Error>> {
@@ -554,7 +354,7 @@ fn dump_dsl_dataset(
     );
 
     for block_id in 0..object_set.dnode.max_block_id + 1 {
-        let block_opt = dnode_read_block(blk_devs, &object_set.dnode, block_id)?;
+        let block_opt = reader.read_dnode_block(&object_set.dnode, block_id)?;
 
         if let Some((order, block_bytes)) = block_opt {
             let mut object_id = (block_bytes.len() / phys::Dnode::SIZE) * (block_id as usize);
@@ -629,7 +429,7 @@ fn dump_dsl_dataset(
                             );
 
                             if let Some(ptr) = dsl_data_set.block_pointer {
-                                let object_set_bytes = block_pointer_read(blk_devs, &ptr)?;
+                                let object_set_bytes = block_pointer_read(reader, &ptr)?;
                                 let mut bl_decoder = phys::BigLittleEndianDecoder::from_bytes(
                                     &object_set_bytes,
                                     ptr.order(),
@@ -637,7 +437,7 @@ fn dump_dsl_dataset(
                                 let decoder = bl_decoder.decoder_as_mut();
                                 let object_set = phys::ObjectSet::from_decoder(decoder)?;
 
-                                dump_dsl_dataset(blk_devs, &object_set, depth + 4)?;
+                                dump_dsl_dataset(reader, &object_set, depth + 4)?;
                             }
                         }
                         phys::DmuType::BpObjectHeader => {}
@@ -697,7 +497,7 @@ fn dump_dsl_dataset(
                     }
 
                     if is_zap {
-                        dnode_dump_zap(blk_devs, &dnode, depth + 4)?;
+                        dnode_dump_zap(reader, &dnode, depth + 4)?;
                         println!();
                     } else if let phys::DmuType::PackedNvList = dnode.dmu {
                         let mut bl_decoder =
@@ -705,8 +505,7 @@ fn dump_dsl_dataset(
                         let decoder = bl_decoder.decoder_as_mut();
                         let nv_list_size = decoder.get_u64()?;
                         let nv_list_size = usize::try_from(nv_list_size)?;
-                        let (_endian, nv_list_bytes) =
-                            dnode_read_block(blk_devs, &dnode, 0)?.unwrap();
+                        let (_endian, nv_list_bytes) = reader.read_dnode_block(&dnode, 0)?.unwrap();
                         // TODO: handle multiple blocks
                         assert!(nv_list_bytes.len() >= nv_list_size);
 
@@ -727,8 +526,7 @@ fn dump_dsl_dataset(
                         let mut block_id = 0;
                         let mut todo = sm_header.length_bytes;
                         while todo > 0 {
-                            let (order, data) =
-                                dnode_read_block(blk_devs, &dnode, block_id)?.unwrap();
+                            let (order, data) = reader.read_dnode_block(&dnode, block_id)?.unwrap();
                             let mut data_to_process = &data[0..data.len()];
 
                             if data.len() as u64 > todo {
@@ -761,7 +559,7 @@ fn dump_dsl_dataset(
                         assert!(dnode.pointers().len() == 1);
                         assert!(dnode.pointers()[0].is_none());
                     } else if let phys::DmuType::PlainFileContents = dnode.dmu {
-                        if let Some((_order, data)) = dnode_read_block(blk_devs, &dnode, 0)? {
+                        if let Some((_order, data)) = reader.read_dnode_block(&dnode, 0)? {
                             let mut size = data.len();
                             match dnode.bonus_type {
                                 phys::DmuType::Znode => {
@@ -783,7 +581,7 @@ fn dump_dsl_dataset(
                             };
 
                             for block_id in 0..dnode.max_block_id + 1 {
-                                dnode_read_block(blk_devs, &dnode, block_id)?;
+                                reader.read_dnode_block(&dnode, block_id)?;
                             }
 
                             println!("File size: {size}");
@@ -811,9 +609,9 @@ fn dump_dsl_dataset(
                         let mut dva_allocated = 0;
 
                         for idx in 0..bp_header.block_pointers_count {
-                            let (order, bp_bytes) =
-                                dnode_read_object(blk_devs, &dnode, idx, phys::BlockPointer::SIZE)?
-                                    .unwrap();
+                            let (order, bp_bytes) = reader
+                                .read_dnode_object(&dnode, idx, phys::BlockPointer::SIZE)?
+                                .unwrap();
 
                             let mut bl_decoder =
                                 phys::BigLittleEndianDecoder::from_bytes(&bp_bytes, order);
@@ -840,7 +638,7 @@ fn dump_dsl_dataset(
                                 width = depth + 4
                             );
                     } else if let phys::DmuType::ObjectArray = dnode.dmu {
-                        let (order, data) = dnode_read_block(blk_devs, &dnode, 0)?.unwrap();
+                        let (order, data) = reader.read_dnode_block(&dnode, 0)?.unwrap();
                         let mut bl_decoder = phys::BigLittleEndianDecoder::from_bytes(&data, order);
                         let decoder = bl_decoder.decoder_as_mut();
                         let mut idx = 0;
@@ -872,7 +670,7 @@ fn dump_dsl_dataset(
 ////////////////////////////////////////////////////////////////////////////////
 
 fn dump_root(
-    blk_devs: &[userspace::BlockDevice],
+    reader: &mut BlockReader<'_>,
     nv: &phys::NvList,
     uberblock: &phys::UberBlock,
 ) -> Result<(), Box<dyn Error>> {
@@ -899,13 +697,13 @@ fn dump_root(
 
     ////////////////////////////////////
     // Read Meta ObjectSet.
-    let meta_object_set_bytes = block_pointer_read(blk_devs, &uberblock.ptr)?;
+    let meta_object_set_bytes = block_pointer_read(reader, &uberblock.ptr)?;
     let mut bl_decoder =
         phys::BigLittleEndianDecoder::from_bytes(&meta_object_set_bytes, uberblock.ptr.order());
     let decoder = bl_decoder.decoder_as_mut();
     let meta_object_set = phys::ObjectSet::from_decoder(decoder)?;
     println!("os zil: {:?}", meta_object_set.zil_header);
-    dump_dsl_dataset(blk_devs, &meta_object_set, 0)?;
+    dump_dsl_dataset(reader, &meta_object_set, 0)?;
 
     Ok(())
 }
@@ -1084,10 +882,66 @@ fn dump_nv_list(list: &phys::NvList, depth: usize) -> Result<(), Box<dyn Error>>
     Ok(())
 }
 
+/// Creates the top level [`Vdev`] from the labels of the block devices.
+fn vdevs_open<'a>(
+    block_devices: &'a [userspace::BlockDeviceFile],
+    sha256: &mut checksum::Sha256,
+) -> Result<Vec<Vdev<'a>>, Box<dyn Error>> {
+    ////////////////////////////////////
+    // Read the first label NV pairs of each block device.
+    let mut nv_pairs_bytes = Vec::new();
+
+    for block_device in block_devices {
+        let label_sectors = phys::Label::offsets(block_device.sectors())?;
+        let nv_pairs_offset = label_sectors[0] + phys::LabelNvPairs::LABEL_OFFSET;
+        let mut bytes = vec![0; phys::LabelNvPairs::SIZE];
+        block_device.read(&mut bytes, nv_pairs_offset)?;
+        nv_pairs_bytes.push((bytes, nv_pairs_offset));
+    }
+
+    ////////////////////////////////////
+    // Decode the guid and vdev tree of each block device.
+    let mut devices = Vec::new();
+    let mut pools = Vec::new();
+
+    for ((bytes, offset), block_device) in nv_pairs_bytes.iter().zip(block_devices) {
+        let nv_pairs = phys::LabelNvPairs::from_bytes(bytes, *offset, sha256)?;
+        let nv_list = phys::NvList::from_bytes(nv_pairs.payload)?;
+
+        if let phys::LabelConfig::Storage(pool) = phys::LabelConfig::from_list(&nv_list)? {
+            devices.push(VdevDevice {
+                guid: pool.guid,
+                device: block_device,
+            });
+            pools.push(pool);
+        }
+    }
+
+    ////////////////////////////////////
+    // Create the top level vdevs, indexed by id.
+    let mut vdevs = Vec::new();
+
+    for pool in &pools {
+        let id = usize::try_from(pool.vdev_tree.id)?;
+        if vdevs.len() <= id {
+            vdevs.resize_with(id + 1, || {
+                Vdev::Leaf(VdevLeaf {
+                    guid: 0,
+                    device: None,
+                })
+            });
+        }
+        vdevs[id] = Vdev::from_tree(&pool.vdev_tree, &devices)?;
+    }
+
+    Ok(vdevs)
+}
+
 fn dump() -> Result<(), Box<dyn Error>> {
     let args: Vec<String> = env::args().collect();
     if args.len() < 2 {
-        println!("Usage: {} PATH", &args[0]);
+        println!("Usage: {} PATH...", &args[0]);
+        println!("       {} import-scan [PATH...]", &args[0]);
         process::exit(1);
     }
 
@@ -1098,8 +952,8 @@ fn dump() -> Result<(), Box<dyn Error>> {
     let mut block_devices = Vec::new();
 
     for path in &args[1..] {
-        let block_device = userspace::BlockDevice::open(path)?;
-        println!("Sectors: {}", block_device.sectors);
+        let block_device = userspace::BlockDeviceFile::open(path)?;
+        println!("Sectors: {}", block_device.sectors());
         block_devices.push(block_device);
     }
 
@@ -1117,7 +971,7 @@ fn dump() -> Result<(), Box<dyn Error>> {
 
     ////////////////////////////////////
     // Get label sectors.
-    let label_sectors = phys::Label::offsets(block_device.sectors)?;
+    let label_sectors = phys::Label::offsets(block_device.sectors())?;
 
     ////////////////////////////////////
     // Parse each label.
@@ -1228,7 +1082,16 @@ fn dump() -> Result<(), Box<dyn Error>> {
         }
 
         if let Some(uberblock) = max_uberblock {
-            dump_root(&block_devices, &nv_list, &uberblock)?;
+            let vdevs = vdevs_open(&block_devices, &mut sha256)?;
+            let mut reader = BlockReader::new(&vdevs);
+            let res = dump_root(&mut reader, &nv_list, &uberblock);
+
+            // Log copies that returned bad data, even if the read succeeded.
+            for bad_copy in reader.take_bad_copies() {
+                println!("Bad copy: {bad_copy:?}");
+            }
+
+            res?;
             break;
         }
     }
@@ -1236,8 +1099,92 @@ fn dump() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Prints a vdev of a scanned pool, and its children.
+fn import_scan_vdev(vdev: &userspace::ScanVdev, depth: usize) {
+    let vdev_type: &str = vdev.vdev_type.into();
+    let indent = "  ".repeat(depth);
+
+    match (&vdev.path, vdev.state) {
+        (Some(path), Some(state)) => {
+            println!("{indent}{vdev_type} {path} guid: {} {state}", vdev.guid)
+        }
+        (None, Some(state)) => {
+            println!(
+                "{indent}{vdev_type}-{} guid: {} {state}",
+                vdev.id, vdev.guid
+            )
+        }
+        _ => println!("{indent}{vdev_type}-{} guid: {}", vdev.id, vdev.guid),
+    }
+
+    for child in &vdev.children {
+        import_scan_vdev(child, depth + 1);
+    }
+}
+
+/// Scans devices for pools, like `zpool import` without arguments.
+fn import_scan(paths: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut scanner = userspace::PoolScanner::new()?;
+
+    let default_paths = [String::from("/dev")];
+    let paths = if paths.is_empty() {
+        &default_paths[..]
+    } else {
+        paths
+    };
+
+    // Report unreadable paths, and continue with the others.
+    for path in paths {
+        if let Err(err) = scanner.scan_path(path) {
+            println!("{path}: {err}");
+        }
+    }
+
+    for pool in scanner.pools() {
+        println!("pool: {} id: {}", pool.name, pool.guid);
+        println!("state: {} txg: {}", pool.state, pool.txg);
+
+        for (id, vdev) in pool.vdevs.iter().enumerate() {
+            match vdev {
+                Some(vdev) => import_scan_vdev(vdev, 1),
+                None => println!("  vdev-{id} missing"),
+            }
+        }
+
+        for device in &pool.devices {
+            let partition = match device.partition {
+                Some(v) => format!(" partition: {v}"),
+                None => String::new(),
+            };
+            println!(
+                "device: {}{partition} guid: {} txg: {} labels: {:?}",
+                device.path, device.guid, device.txg, device.labels
+            );
+        }
+
+        for device in pool.unused_devices() {
+            println!(
+                "device: {} guid: {} not in config",
+                device.path, device.guid
+            );
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
 fn main() {
-    if let Err(e) = dump() {
+    let args: Vec<String> = env::args().collect();
+
+    let res = if args.len() > 1 && args[1] == "import-scan" {
+        import_scan(&args[2..])
+    } else {
+        dump()
+    };
+
+    if let Err(e) = res {
         println!("{e}");
     }
 }
@@ -13,6 +13,7 @@ const MASK_LEAF_PIAFB_EDX_SSE_2: u32 = 1 << 26;
 
 const MASK_LEAF_PIAFB_ECX_SSE_3: u32 = 1 << 0;
 const MASK_LEAF_PIAFB_ECX_SSSE_3: u32 = 1 << 9;
+const MASK_LEAF_PIAFB_ECX_SSE_4_1: u32 = 1 << 19;
 const MASK_LEAF_PIAFB_ECX_AVX: u32 = 1 << 28;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -145,6 +146,19 @@ pub(crate) fn is_sse3_supported() -> bool {
     (cpuid.ecx & MASK_LEAF_PIAFB_ECX_SSE_3) != 0
 }
 
+/// Is SSE4.1 supported by the CPU.
+pub(crate) fn is_sse41_supported() -> bool {
+    // Get number of leaves.
+    let (leaf_max, _) = arch::__get_cpuid_max(0);
+    if leaf_max < LEAF_PROCESSOR_INFO_AND_FEATURE_BITS {
+        return false;
+    }
+
+    let cpuid = arch::__cpuid(LEAF_PROCESSOR_INFO_AND_FEATURE_BITS);
+
+    (cpuid.ecx & MASK_LEAF_PIAFB_ECX_SSE_4_1) != 0
+}
+
 /// Is SSSE3 supported by the CPU.
 pub(crate) fn is_ssse3_supported() -> bool {
     // Get number of leaves.
@@ -202,6 +216,11 @@ mod tests {
         arch::is_sse3_supported();
     }
 
+    #[test]
+    fn sse41() {
+        arch::is_sse41_supported();
+    }
+
     #[test]
     fn ssse3() {
         arch::is_ssse3_supported();
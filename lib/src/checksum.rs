@@ -1,16 +1,44 @@
 // SPDX-License-Identifier: GPL-2.0 OR MIT
 
+pub(crate) mod blake3;
+pub use blake3::{Blake3, Blake3Implementation};
+
 pub(crate) mod common;
 pub use common::{Checksum, ChecksumError};
 
+pub(crate) mod edonr;
+pub use edonr::Edonr;
+
+pub(crate) mod embedded;
+pub use embedded::{
+    embedded_checksum, embedded_verify, gang_header_verifier, EmbeddedChecksumError,
+    EmbeddedVerifyError,
+};
+
 pub(crate) mod fletcher2;
 pub use fletcher2::{Fletcher2, Fletcher2Implementation};
 
 pub(crate) mod fletcher4;
-pub use fletcher4::{Fletcher4, Fletcher4Implementation};
+pub use fletcher4::{Fletcher4, Fletcher4Implementation, Fletcher4State};
 
 pub(crate) mod label;
 pub use label::{label_checksum, label_verify, LabelChecksumError, LabelVerifyError};
 
+#[cfg(feature = "std")]
+pub(crate) mod registry;
+#[cfg(feature = "std")]
+pub use registry::{ChecksumImplementations, ChecksumRegistry};
+
 pub(crate) mod sha256;
 pub use sha256::{Sha256, Sha256Implementation};
+
+pub(crate) mod sha512;
+pub use sha512::{Sha512_256, Sha512_256Implementation};
+
+pub(crate) mod skein;
+pub use skein::Skein;
+
+#[cfg(feature = "std")]
+pub(crate) mod verify;
+#[cfg(feature = "std")]
+pub use verify::{verify_block, BlockVerifyError};
@@ -0,0 +1,1673 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! BLAKE3 checksum.
+ *
+ * BLAKE3 splits the input into 1024 byte chunks, which are the leaves of a
+ * binary tree. Each chunk is hashed independently, so SIMD implementations
+ * hash several chunks in parallel, with one chunk per vector lane.
+ *
+ * ZFS uses the per pool checksum salt as the BLAKE3 key, and stores the
+ * 256 bit result as four [u64] in the byte order of the data.
+ */
+use crate::checksum::{Checksum, ChecksumError};
+use crate::phys::{ChecksumType, EndianOrder};
+
+use core::cmp;
+use core::fmt;
+use core::fmt::Display;
+
+#[cfg(all(
+    target_arch = "x86",
+    any(
+        feature = "blake3-sse2",
+        feature = "blake3-sse41",
+        feature = "blake3-avx2",
+        feature = "blake3-avx512",
+    ),
+))]
+use core::arch::x86 as arch;
+
+#[cfg(all(
+    target_arch = "x86_64",
+    any(
+        feature = "blake3-sse2",
+        feature = "blake3-sse41",
+        feature = "blake3-avx2",
+        feature = "blake3-avx512",
+    ),
+))]
+use core::arch::x86_64 as arch;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(feature = "blake3-sse2", feature = "blake3-sse41"),
+))]
+use crate::arch::x86_any::is_sse2_supported;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-sse41",
+))]
+use crate::arch::x86_any::{is_sse41_supported, is_ssse3_supported};
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-avx2",
+))]
+use crate::arch::x86_any::{is_avx2_supported, is_avx_supported};
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-avx512",
+))]
+use crate::arch::x86_any::is_avx512f_supported;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// BLAKE3 block size in bytes.
+const BLAKE3_BLOCK_SIZE: usize = 64;
+
+/// BLAKE3 block size in u32.
+const BLAKE3_BLOCK_U32_COUNT: usize = 16;
+
+/// BLAKE3 chunk size in bytes.
+const BLAKE3_CHUNK_SIZE: usize = 1024;
+
+/// Number of blocks in a chunk.
+const BLAKE3_CHUNK_BLOCKS: usize = BLAKE3_CHUNK_SIZE / BLAKE3_BLOCK_SIZE;
+
+/// BLAKE3 key and chaining value in u32.
+const BLAKE3_KEY_U32_COUNT: usize = 8;
+
+/// BLAKE3 key size in bytes.
+const BLAKE3_KEY_SIZE: usize = 32;
+
+/// Maximum depth of the chaining value stack, for 2^64 bytes of input.
+const BLAKE3_MAX_DEPTH: usize = 54;
+
+/// Maximum number of chunks hashed by one [`Blake3HashChunks`] call.
+const BLAKE3_MAX_CHUNKS: usize = 16;
+
+/// Number of rounds per block.
+const BLAKE3_ROUNDS: usize = 7;
+
+/// Flag for the first block of a chunk.
+const FLAG_CHUNK_START: u32 = 1 << 0;
+
+/// Flag for the last block of a chunk.
+const FLAG_CHUNK_END: u32 = 1 << 1;
+
+/// Flag for a parent node.
+const FLAG_PARENT: u32 = 1 << 2;
+
+/// Flag for the root node.
+const FLAG_ROOT: u32 = 1 << 3;
+
+/// Flag for keyed hashing.
+const FLAG_KEYED_HASH: u32 = 1 << 4;
+
+/// Initial values, which are the same as SHA-256.
+const BLAKE3_IV: [u32; BLAKE3_KEY_U32_COUNT] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+/// Message word order for each round.
+const BLAKE3_MSG_SCHEDULE: [[usize; BLAKE3_BLOCK_U32_COUNT]; BLAKE3_ROUNDS] = [
+    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+    [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8],
+    [3, 4, 10, 12, 13, 2, 7, 14, 6, 5, 9, 0, 11, 15, 8, 1],
+    [10, 7, 12, 9, 14, 3, 13, 15, 4, 0, 11, 2, 5, 8, 1, 6],
+    [12, 13, 9, 11, 15, 10, 14, 8, 7, 2, 5, 3, 0, 1, 6, 4],
+    [9, 14, 11, 5, 8, 12, 15, 1, 13, 3, 0, 10, 2, 6, 4, 7],
+    [11, 15, 5, 0, 1, 9, 8, 6, 14, 10, 2, 12, 3, 4, 7, 13],
+];
+
+/// State words of the four column, and four diagonal G functions.
+const BLAKE3_G_INDICES: [[usize; 4]; 8] = [
+    [0, 4, 8, 12],
+    [1, 5, 9, 13],
+    [2, 6, 10, 14],
+    [3, 7, 11, 15],
+    [0, 5, 10, 15],
+    [1, 6, 11, 12],
+    [2, 7, 8, 13],
+    [3, 4, 9, 14],
+];
+
+/** Blake3 implementation.
+ *
+ * The SIMD implementations hash multiple chunks in parallel, so they are only
+ * used for data larger than one chunk.
+ *
+ * - [`Blake3Implementation::SSE2`] hashes 4 chunks at a time.
+ * - [`Blake3Implementation::SSE41`] hashes 4 chunks at a time, and uses
+ *   `SSSE3` byte shuffles for rotations.
+ * - [`Blake3Implementation::AVX2`] hashes 8 chunks at a time.
+ * - [`Blake3Implementation::AVX512`] hashes 16 chunks at a time.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Blake3Implementation {
+    /// Generic.
+    Generic,
+
+    /// SSE2.
+    SSE2,
+
+    /// SSE4.1 with SSE2 and SSSE3.
+    SSE41,
+
+    /// AVX2 with AVX.
+    AVX2,
+
+    /// AVX-512F.
+    AVX512,
+}
+
+const ALL_BLAKE3_IMPLEMENTATIONS: [Blake3Implementation; 5] = [
+    Blake3Implementation::Generic,
+    Blake3Implementation::SSE2,
+    Blake3Implementation::SSE41,
+    Blake3Implementation::AVX2,
+    Blake3Implementation::AVX512,
+];
+
+impl Blake3Implementation {
+    /// Get a slice with all of the [`Blake3Implementation`].
+    pub fn all() -> &'static [Blake3Implementation] {
+        &ALL_BLAKE3_IMPLEMENTATIONS
+    }
+
+    /// Get the string name of the implementation.
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Blake3Implementation::Generic => "generic",
+            Blake3Implementation::SSE2 => "sse2",
+            Blake3Implementation::SSE41 => "sse4.1",
+            Blake3Implementation::AVX2 => "avx2",
+            Blake3Implementation::AVX512 => "avx512",
+        }
+    }
+
+    /// Get the implementation context.
+    fn get_implementation_ctx(&self) -> Result<&'static Blake3ImplementationCtx, ChecksumError> {
+        match self {
+            Blake3Implementation::Generic => Ok(&BLAKE3_IMPL_CTX_GENERIC),
+
+            #[cfg(feature = "blake3-sse2")]
+            Blake3Implementation::SSE2 => Ok(&BLAKE3_IMPL_CTX_SSE2),
+
+            #[cfg(feature = "blake3-sse41")]
+            Blake3Implementation::SSE41 => Ok(&BLAKE3_IMPL_CTX_SSE41),
+
+            #[cfg(feature = "blake3-avx2")]
+            Blake3Implementation::AVX2 => Ok(&BLAKE3_IMPL_CTX_AVX2),
+
+            #[cfg(feature = "blake3-avx512")]
+            Blake3Implementation::AVX512 => Ok(&BLAKE3_IMPL_CTX_AVX512),
+
+            #[cfg(any(
+                not(feature = "blake3-sse2"),
+                not(feature = "blake3-sse41"),
+                not(feature = "blake3-avx2"),
+                not(feature = "blake3-avx512"),
+            ))]
+            _ => Err(ChecksumError::Unsupported {
+                checksum: ChecksumType::Blake3,
+                implementation: self.to_str(),
+            }),
+        }
+    }
+}
+
+impl Display for Blake3Implementation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Hash full chunks, and write the chaining value of each chunk to `out`.
+ *
+ * - `data` is a multiple of [`BLAKE3_CHUNK_SIZE`], and has at most
+ *   [`BLAKE3_MAX_CHUNKS`] chunks.
+ * - `counter` is the chunk counter of the first chunk.
+ * - `out` has one entry per chunk.
+ */
+type Blake3HashChunks = fn(
+    key: &[u32; BLAKE3_KEY_U32_COUNT],
+    data: &[u8],
+    counter: u64,
+    flags: u32,
+    out: &mut [[u32; BLAKE3_KEY_U32_COUNT]],
+);
+
+/// Is the implementation supported by the CPU.
+type Blake3IsSupported = fn() -> bool;
+
+struct Blake3ImplementationCtx {
+    /// Implementation of [`Blake3HashChunks`].
+    hash_chunks: Blake3HashChunks,
+
+    /// Is the implementation supported by the CPU.
+    is_supported: Blake3IsSupported,
+}
+
+const BLAKE3_IMPL_CTX_GENERIC: Blake3ImplementationCtx = Blake3ImplementationCtx {
+    hash_chunks: Blake3::hash_chunks_generic,
+    is_supported: || true,
+};
+
+#[cfg(feature = "blake3-sse2")]
+const BLAKE3_IMPL_CTX_SSE2: Blake3ImplementationCtx = Blake3ImplementationCtx {
+    hash_chunks: Blake3::hash_chunks_sse2,
+    is_supported: is_sse2_supported,
+};
+
+#[cfg(feature = "blake3-sse41")]
+const BLAKE3_IMPL_CTX_SSE41: Blake3ImplementationCtx = Blake3ImplementationCtx {
+    hash_chunks: Blake3::hash_chunks_sse41,
+    is_supported: || is_sse2_supported() && is_ssse3_supported() && is_sse41_supported(),
+};
+
+#[cfg(feature = "blake3-avx2")]
+const BLAKE3_IMPL_CTX_AVX2: Blake3ImplementationCtx = Blake3ImplementationCtx {
+    hash_chunks: Blake3::hash_chunks_avx2,
+    is_supported: || is_avx_supported() && is_avx2_supported(),
+};
+
+#[cfg(feature = "blake3-avx512")]
+const BLAKE3_IMPL_CTX_AVX512: Blake3ImplementationCtx = Blake3ImplementationCtx {
+    hash_chunks: Blake3::hash_chunks_avx512,
+    is_supported: is_avx512f_supported,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Compress a block, and return the full 512 bit output.
+ *
+ * The first 256 bits are the new chaining value.
+ */
+fn compress(
+    cv: &[u32; BLAKE3_KEY_U32_COUNT],
+    block: &[u8; BLAKE3_BLOCK_SIZE],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; BLAKE3_BLOCK_U32_COUNT] {
+    // Decode the block.
+    let mut m = [0; BLAKE3_BLOCK_U32_COUNT];
+    for (i, x) in block.chunks_exact(4).enumerate() {
+        m[i] = u32::from_le_bytes(x.try_into().unwrap());
+    }
+
+    let mut v = [
+        cv[0],
+        cv[1],
+        cv[2],
+        cv[3],
+        cv[4],
+        cv[5],
+        cv[6],
+        cv[7],
+        BLAKE3_IV[0],
+        BLAKE3_IV[1],
+        BLAKE3_IV[2],
+        BLAKE3_IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+
+    for schedule in &BLAKE3_MSG_SCHEDULE {
+        for (g, [a, b, c, d]) in BLAKE3_G_INDICES.iter().enumerate() {
+            let (a, b, c, d) = (*a, *b, *c, *d);
+
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(m[schedule[2 * g]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(16);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(12);
+            v[a] = v[a].wrapping_add(v[b]).wrapping_add(m[schedule[2 * g + 1]]);
+            v[d] = (v[d] ^ v[a]).rotate_right(8);
+            v[c] = v[c].wrapping_add(v[d]);
+            v[b] = (v[b] ^ v[c]).rotate_right(7);
+        }
+    }
+
+    for i in 0..BLAKE3_KEY_U32_COUNT {
+        v[i] ^= v[i + BLAKE3_KEY_U32_COUNT];
+        v[i + BLAKE3_KEY_U32_COUNT] ^= cv[i];
+    }
+
+    v
+}
+
+/// Compress a block, and return the new chaining value.
+fn compress_cv(
+    cv: &[u32; BLAKE3_KEY_U32_COUNT],
+    block: &[u8; BLAKE3_BLOCK_SIZE],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; BLAKE3_KEY_U32_COUNT] {
+    compress(cv, block, counter, block_len, flags)[0..BLAKE3_KEY_U32_COUNT]
+        .try_into()
+        .unwrap()
+}
+
+/// Encode two chaining values into a parent block.
+fn parent_block(
+    left: &[u32; BLAKE3_KEY_U32_COUNT],
+    right: &[u32; BLAKE3_KEY_U32_COUNT],
+) -> [u8; BLAKE3_BLOCK_SIZE] {
+    let mut block = [0; BLAKE3_BLOCK_SIZE];
+    for (x, v) in block
+        .chunks_exact_mut(4)
+        .zip(left.iter().chain(right.iter()))
+    {
+        x.copy_from_slice(&v.to_le_bytes());
+    }
+    block
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-sse2",
+))]
+/// Rotate each u32 right, using shifts.
+macro_rules! rotr_sse2 {
+    ($x:expr, $n:literal) => {
+        arch::_mm_or_si128(
+            arch::_mm_srli_epi32::<$n>($x),
+            arch::_mm_slli_epi32::<{ 32 - $n }>($x),
+        )
+    };
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-sse41",
+))]
+/// Rotate each u32 right, using byte shuffles for multiples of 8.
+macro_rules! rotr_sse41 {
+    ($x:expr, 16) => {
+        arch::_mm_shuffle_epi8(
+            $x,
+            arch::_mm_set_epi8(13, 12, 15, 14, 9, 8, 11, 10, 5, 4, 7, 6, 1, 0, 3, 2),
+        )
+    };
+    ($x:expr, 8) => {
+        arch::_mm_shuffle_epi8(
+            $x,
+            arch::_mm_set_epi8(12, 15, 14, 13, 8, 11, 10, 9, 4, 7, 6, 5, 0, 3, 2, 1),
+        )
+    };
+    ($x:expr, $n:literal) => {
+        arch::_mm_or_si128(
+            arch::_mm_srli_epi32::<$n>($x),
+            arch::_mm_slli_epi32::<{ 32 - $n }>($x),
+        )
+    };
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-avx2",
+))]
+/// Rotate each u32 right, using byte shuffles for multiples of 8.
+macro_rules! rotr_avx2 {
+    ($x:expr, 16) => {
+        arch::_mm256_shuffle_epi8(
+            $x,
+            arch::_mm256_set_epi8(
+                13, 12, 15, 14, 9, 8, 11, 10, 5, 4, 7, 6, 1, 0, 3, 2, 13, 12, 15, 14, 9, 8, 11, 10,
+                5, 4, 7, 6, 1, 0, 3, 2,
+            ),
+        )
+    };
+    ($x:expr, 8) => {
+        arch::_mm256_shuffle_epi8(
+            $x,
+            arch::_mm256_set_epi8(
+                12, 15, 14, 13, 8, 11, 10, 9, 4, 7, 6, 5, 0, 3, 2, 1, 12, 15, 14, 13, 8, 11, 10, 9,
+                4, 7, 6, 5, 0, 3, 2, 1,
+            ),
+        )
+    };
+    ($x:expr, $n:literal) => {
+        arch::_mm256_or_si256(
+            arch::_mm256_srli_epi32::<$n>($x),
+            arch::_mm256_slli_epi32::<{ 32 - $n }>($x),
+        )
+    };
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "blake3-avx512",
+))]
+/// Rotate each u32 right.
+macro_rules! rotr_avx512 {
+    ($x:expr, $n:literal) => {
+        arch::_mm512_ror_epi32::<$n>($x)
+    };
+}
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    any(
+        feature = "blake3-sse2",
+        feature = "blake3-sse41",
+        feature = "blake3-avx2",
+        feature = "blake3-avx512",
+    ),
+))]
+/** Implement [`Blake3HashChunks`] with one chunk per vector lane.
+ *
+ * State and message words are transposed, so that each vector holds the same
+ * word of every chunk. If there are fewer chunks than lanes, then the unused
+ * lanes hash the first chunk again, and their result is discarded.
+ */
+macro_rules! hash_chunks_simd {
+    (
+        $name:ident,
+        $features:literal,
+        $lanes:literal,
+        $set1:path,
+        $add:path,
+        $xor:path,
+        $loadu:path,
+        $storeu:path,
+        $rotr:ident
+    ) => {
+        fn $name(
+            key: &[u32; BLAKE3_KEY_U32_COUNT],
+            data: &[u8],
+            counter: u64,
+            flags: u32,
+            out: &mut [[u32; BLAKE3_KEY_U32_COUNT]],
+        ) {
+            #[target_feature(enable = $features)]
+            unsafe fn hash_chunks_impl(
+                key: &[u32; BLAKE3_KEY_U32_COUNT],
+                data: &[u8],
+                counter: u64,
+                flags: u32,
+                out: &mut [[u32; BLAKE3_KEY_U32_COUNT]],
+            ) {
+                let chunks = data.len() / BLAKE3_CHUNK_SIZE;
+                let mut chunk = 0;
+
+                while chunk < chunks {
+                    let count = cmp::min($lanes, chunks - chunk);
+
+                    // Offset and counter of each lane.
+                    let mut offsets = [0; $lanes];
+                    let mut counter_low = [0_u32; $lanes];
+                    let mut counter_high = [0_u32; $lanes];
+
+                    for lane in 0..$lanes {
+                        let index = chunk + if lane < count { lane } else { 0 };
+                        let lane_counter = counter + index as u64;
+
+                        offsets[lane] = index * BLAKE3_CHUNK_SIZE;
+                        counter_low[lane] = lane_counter as u32;
+                        counter_high[lane] = (lane_counter >> 32) as u32;
+                    }
+
+                    let counter_low = $loadu(counter_low.as_ptr() as *const _);
+                    let counter_high = $loadu(counter_high.as_ptr() as *const _);
+
+                    // Chaining values start with the key.
+                    let mut h = [$set1(0); BLAKE3_KEY_U32_COUNT];
+                    for (h, k) in h.iter_mut().zip(key.iter()) {
+                        *h = $set1(*k as i32);
+                    }
+
+                    for block in 0..BLAKE3_CHUNK_BLOCKS {
+                        // Transpose the message words of each lane.
+                        let mut words = [[0_u32; $lanes]; BLAKE3_BLOCK_U32_COUNT];
+                        for lane in 0..$lanes {
+                            let offset = offsets[lane] + block * BLAKE3_BLOCK_SIZE;
+                            let block_data = &data[offset..offset + BLAKE3_BLOCK_SIZE];
+
+                            for (word, x) in block_data.chunks_exact(4).enumerate() {
+                                words[word][lane] = u32::from_le_bytes(x.try_into().unwrap());
+                            }
+                        }
+
+                        let mut m = [$set1(0); BLAKE3_BLOCK_U32_COUNT];
+                        for (m, w) in m.iter_mut().zip(words.iter()) {
+                            *m = $loadu(w.as_ptr() as *const _);
+                        }
+
+                        let mut block_flags = flags;
+                        if block == 0 {
+                            block_flags |= FLAG_CHUNK_START;
+                        }
+                        if block == BLAKE3_CHUNK_BLOCKS - 1 {
+                            block_flags |= FLAG_CHUNK_END;
+                        }
+
+                        let mut v = [
+                            h[0],
+                            h[1],
+                            h[2],
+                            h[3],
+                            h[4],
+                            h[5],
+                            h[6],
+                            h[7],
+                            $set1(BLAKE3_IV[0] as i32),
+                            $set1(BLAKE3_IV[1] as i32),
+                            $set1(BLAKE3_IV[2] as i32),
+                            $set1(BLAKE3_IV[3] as i32),
+                            counter_low,
+                            counter_high,
+                            $set1(BLAKE3_BLOCK_SIZE as i32),
+                            $set1(block_flags as i32),
+                        ];
+
+                        for schedule in &BLAKE3_MSG_SCHEDULE {
+                            for (g, [a, b, c, d]) in BLAKE3_G_INDICES.iter().enumerate() {
+                                let (a, b, c, d) = (*a, *b, *c, *d);
+
+                                v[a] = $add($add(v[a], v[b]), m[schedule[2 * g]]);
+                                v[d] = $rotr!($xor(v[d], v[a]), 16);
+                                v[c] = $add(v[c], v[d]);
+                                v[b] = $rotr!($xor(v[b], v[c]), 12);
+                                v[a] = $add($add(v[a], v[b]), m[schedule[2 * g + 1]]);
+                                v[d] = $rotr!($xor(v[d], v[a]), 8);
+                                v[c] = $add(v[c], v[d]);
+                                v[b] = $rotr!($xor(v[b], v[c]), 7);
+                            }
+                        }
+
+                        for i in 0..BLAKE3_KEY_U32_COUNT {
+                            h[i] = $xor(v[i], v[i + BLAKE3_KEY_U32_COUNT]);
+                        }
+                    }
+
+                    // Transpose the chaining values back.
+                    let mut cvs = [[0_u32; $lanes]; BLAKE3_KEY_U32_COUNT];
+                    for (cv, h) in cvs.iter_mut().zip(h.iter()) {
+                        $storeu(cv.as_mut_ptr() as *mut _, *h);
+                    }
+
+                    for lane in 0..count {
+                        for (i, cv) in cvs.iter().enumerate() {
+                            out[chunk + lane][i] = cv[lane];
+                        }
+                    }
+
+                    chunk += count;
+                }
+            }
+
+            unsafe { hash_chunks_impl(key, data, counter, flags, out) }
+        }
+    };
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`crate::phys::ChecksumType::Blake3`] implementation.
+pub struct Blake3 {
+    /// Key words.
+    key: [u32; BLAKE3_KEY_U32_COUNT],
+
+    /// Flags for every compression.
+    flags: u32,
+
+    /// Chunk counter of the current chunk.
+    chunk_counter: u64,
+
+    /// Chaining value of the current chunk.
+    chunk_cv: [u32; BLAKE3_KEY_U32_COUNT],
+
+    /// Number of blocks compressed in the current chunk.
+    chunk_blocks: usize,
+
+    /// Number of bytes used in [`Blake3::buffer`].
+    buffer_fill: usize,
+
+    /// Partial block buffer.
+    buffer: [u8; BLAKE3_BLOCK_SIZE],
+
+    /// Number of entries used in [`Blake3::stack`].
+    stack_len: usize,
+
+    /// Chaining values of completed subtrees.
+    stack: [[u32; BLAKE3_KEY_U32_COUNT]; BLAKE3_MAX_DEPTH],
+
+    /// Byte order of the result.
+    order: EndianOrder,
+
+    /// Implementation context.
+    impl_ctx: &'static Blake3ImplementationCtx,
+}
+
+impl Blake3 {
+    /** Create a new Blake3 instance.
+     *
+     * `salt` is used as the key. ZFS uses the 32 byte per pool checksum salt,
+     * stored in the MOS. [`None`] computes a plain unkeyed BLAKE3 hash.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] if the implementation is not supported.
+     */
+    pub fn new(
+        implementation: Blake3Implementation,
+        salt: Option<&[u8; BLAKE3_KEY_SIZE]>,
+    ) -> Result<Blake3, ChecksumError> {
+        let ctx = implementation.get_implementation_ctx()?;
+
+        if !(ctx.is_supported)() {
+            return Err(ChecksumError::Unsupported {
+                checksum: ChecksumType::Blake3,
+                implementation: implementation.to_str(),
+            });
+        }
+
+        let (key, flags) = match salt {
+            Some(salt) => {
+                let mut key = [0; BLAKE3_KEY_U32_COUNT];
+                for (k, x) in key.iter_mut().zip(salt.chunks_exact(4)) {
+                    *k = u32::from_le_bytes(x.try_into().unwrap());
+                }
+                (key, FLAG_KEYED_HASH)
+            }
+            None => (BLAKE3_IV, 0),
+        };
+
+        Ok(Blake3 {
+            key,
+            flags,
+            chunk_counter: 0,
+            chunk_cv: key,
+            chunk_blocks: 0,
+            buffer_fill: 0,
+            buffer: [0; BLAKE3_BLOCK_SIZE],
+            stack_len: 0,
+            stack: [[0; BLAKE3_KEY_U32_COUNT]; BLAKE3_MAX_DEPTH],
+            order: EndianOrder::Little,
+            impl_ctx: ctx,
+        })
+    }
+
+    /// Start the next chunk.
+    fn chunk_reset(&mut self, chunk_counter: u64) {
+        self.chunk_counter = chunk_counter;
+        self.chunk_cv = self.key;
+        self.chunk_blocks = 0;
+        self.buffer_fill = 0;
+    }
+
+    /// Flags for the next block of the current chunk.
+    fn chunk_flags(&self) -> u32 {
+        if self.chunk_blocks == 0 {
+            self.flags | FLAG_CHUNK_START
+        } else {
+            self.flags
+        }
+    }
+
+    /** Push the chaining value of a completed chunk.
+     *
+     * `total_chunks` is the number of chunks completed so far, including this
+     * one. Each trailing zero bit of `total_chunks` completes a subtree, which
+     * is merged with the chaining value on top of the stack.
+     */
+    fn push_chunk_cv(&mut self, cv: [u32; BLAKE3_KEY_U32_COUNT], total_chunks: u64) {
+        let mut cv = cv;
+        let mut total_chunks = total_chunks;
+
+        while total_chunks & 1 == 0 {
+            self.stack_len -= 1;
+            let block = parent_block(&self.stack[self.stack_len], &cv);
+            cv = compress_cv(
+                &self.key,
+                &block,
+                0,
+                BLAKE3_BLOCK_SIZE as u32,
+                self.flags | FLAG_PARENT,
+            );
+            total_chunks >>= 1;
+        }
+
+        self.stack[self.stack_len] = cv;
+        self.stack_len += 1;
+    }
+
+    fn hash_chunks_generic(
+        key: &[u32; BLAKE3_KEY_U32_COUNT],
+        data: &[u8],
+        counter: u64,
+        flags: u32,
+        out: &mut [[u32; BLAKE3_KEY_U32_COUNT]],
+    ) {
+        for (index, (chunk, cv)) in data
+            .chunks_exact(BLAKE3_CHUNK_SIZE)
+            .zip(out.iter_mut())
+            .enumerate()
+        {
+            *cv = *key;
+
+            for (block_index, block) in chunk.chunks_exact(BLAKE3_BLOCK_SIZE).enumerate() {
+                let mut block_flags = flags;
+                if block_index == 0 {
+                    block_flags |= FLAG_CHUNK_START;
+                }
+                if block_index == BLAKE3_CHUNK_BLOCKS - 1 {
+                    block_flags |= FLAG_CHUNK_END;
+                }
+
+                *cv = compress_cv(
+                    cv,
+                    block.try_into().unwrap(),
+                    counter + index as u64,
+                    BLAKE3_BLOCK_SIZE as u32,
+                    block_flags,
+                );
+            }
+        }
+    }
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        feature = "blake3-sse2",
+    ))]
+    // Intrinsics used:
+    // +-------------------+------+
+    // | _mm_add_epi32     | SSE2 |
+    // | _mm_loadu_si128   | SSE2 |
+    // | _mm_or_si128      | SSE2 |
+    // | _mm_set1_epi32    | SSE2 |
+    // | _mm_slli_epi32    | SSE2 |
+    // | _mm_srli_epi32    | SSE2 |
+    // | _mm_storeu_si128  | SSE2 |
+    // | _mm_xor_si128     | SSE2 |
+    // +-------------------+------+
+    hash_chunks_simd!(
+        hash_chunks_sse2,
+        "sse2",
+        4,
+        arch::_mm_set1_epi32,
+        arch::_mm_add_epi32,
+        arch::_mm_xor_si128,
+        arch::_mm_loadu_si128,
+        arch::_mm_storeu_si128,
+        rotr_sse2
+    );
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        feature = "blake3-sse41",
+    ))]
+    // Intrinsics used:
+    // +-------------------+-------+
+    // | _mm_add_epi32     | SSE2  |
+    // | _mm_loadu_si128   | SSE2  |
+    // | _mm_or_si128      | SSE2  |
+    // | _mm_set1_epi32    | SSE2  |
+    // | _mm_set_epi8      | SSE2  |
+    // | _mm_shuffle_epi8  | SSSE3 |
+    // | _mm_slli_epi32    | SSE2  |
+    // | _mm_srli_epi32    | SSE2  |
+    // | _mm_storeu_si128  | SSE2  |
+    // | _mm_xor_si128     | SSE2  |
+    // +-------------------+-------+
+    hash_chunks_simd!(
+        hash_chunks_sse41,
+        "sse2,ssse3,sse4.1",
+        4,
+        arch::_mm_set1_epi32,
+        arch::_mm_add_epi32,
+        arch::_mm_xor_si128,
+        arch::_mm_loadu_si128,
+        arch::_mm_storeu_si128,
+        rotr_sse41
+    );
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        feature = "blake3-avx2",
+    ))]
+    // Intrinsics used:
+    // +---------------------+------+
+    // | _mm256_add_epi32    | AVX2 |
+    // | _mm256_loadu_si256  | AVX  |
+    // | _mm256_or_si256     | AVX2 |
+    // | _mm256_set1_epi32   | AVX  |
+    // | _mm256_set_epi8     | AVX  |
+    // | _mm256_shuffle_epi8 | AVX2 |
+    // | _mm256_slli_epi32   | AVX2 |
+    // | _mm256_srli_epi32   | AVX2 |
+    // | _mm256_storeu_si256 | AVX  |
+    // | _mm256_xor_si256    | AVX2 |
+    // +---------------------+------+
+    hash_chunks_simd!(
+        hash_chunks_avx2,
+        "avx,avx2",
+        8,
+        arch::_mm256_set1_epi32,
+        arch::_mm256_add_epi32,
+        arch::_mm256_xor_si256,
+        arch::_mm256_loadu_si256,
+        arch::_mm256_storeu_si256,
+        rotr_avx2
+    );
+
+    #[cfg(all(
+        any(target_arch = "x86", target_arch = "x86_64"),
+        feature = "blake3-avx512",
+    ))]
+    // Intrinsics used:
+    // +---------------------+---------+
+    // | _mm512_add_epi32    | AVX512F |
+    // | _mm512_loadu_si512  | AVX512F |
+    // | _mm512_ror_epi32    | AVX512F |
+    // | _mm512_set1_epi32   | AVX512F |
+    // | _mm512_storeu_si512 | AVX512F |
+    // | _mm512_xor_si512    | AVX512F |
+    // +---------------------+---------+
+    hash_chunks_simd!(
+        hash_chunks_avx512,
+        "avx512f",
+        16,
+        arch::_mm512_set1_epi32,
+        arch::_mm512_add_epi32,
+        arch::_mm512_xor_si512,
+        arch::_mm512_loadu_si512,
+        arch::_mm512_storeu_si512,
+        rotr_avx512
+    );
+}
+
+impl Checksum for Blake3 {
+    fn reset(&mut self, order: EndianOrder) -> Result<(), ChecksumError> {
+        self.chunk_reset(0);
+        self.buffer = [0; BLAKE3_BLOCK_SIZE];
+        self.stack_len = 0;
+        self.order = order;
+
+        Ok(())
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<(), ChecksumError> {
+        // Make data pointer mutable, in case of self.buffer_fill.
+        let mut data = data;
+
+        while !data.is_empty() {
+            // If the chunk is full, and more data follows, then complete it.
+            if self.chunk_blocks == BLAKE3_CHUNK_BLOCKS - 1 && self.buffer_fill == BLAKE3_BLOCK_SIZE
+            {
+                let cv = compress_cv(
+                    &self.chunk_cv,
+                    &self.buffer,
+                    self.chunk_counter,
+                    BLAKE3_BLOCK_SIZE as u32,
+                    self.chunk_flags() | FLAG_CHUNK_END,
+                );
+                let total_chunks = self.chunk_counter + 1;
+                self.push_chunk_cv(cv, total_chunks);
+                self.chunk_reset(total_chunks);
+            }
+
+            // At a chunk boundary, hash full chunks with the implementation.
+            // The last chunk is never hashed here, because it might be the
+            // root, and needs a different flag.
+            if self.chunk_blocks == 0 && self.buffer_fill == 0 && data.len() > BLAKE3_CHUNK_SIZE {
+                let chunks = cmp::min((data.len() - 1) / BLAKE3_CHUNK_SIZE, BLAKE3_MAX_CHUNKS);
+                let full_chunks_data = &data[0..chunks * BLAKE3_CHUNK_SIZE];
+
+                let mut cvs = [[0; BLAKE3_KEY_U32_COUNT]; BLAKE3_MAX_CHUNKS];
+                (self.impl_ctx.hash_chunks)(
+                    &self.key,
+                    full_chunks_data,
+                    self.chunk_counter,
+                    self.flags,
+                    &mut cvs[0..chunks],
+                );
+
+                for cv in &cvs[0..chunks] {
+                    let total_chunks = self.chunk_counter + 1;
+                    self.push_chunk_cv(*cv, total_chunks);
+                    self.chunk_counter = total_chunks;
+                }
+
+                data = &data[full_chunks_data.len()..];
+                continue;
+            }
+
+            // If the block is full, and more data follows, then compress it.
+            if self.buffer_fill == BLAKE3_BLOCK_SIZE {
+                self.chunk_cv = compress_cv(
+                    &self.chunk_cv,
+                    &self.buffer,
+                    self.chunk_counter,
+                    BLAKE3_BLOCK_SIZE as u32,
+                    self.chunk_flags(),
+                );
+                self.chunk_blocks += 1;
+                self.buffer_fill = 0;
+            }
+
+            // Todo is minimum of block fill needed, and input data.
+            let todo = cmp::min(BLAKE3_BLOCK_SIZE - self.buffer_fill, data.len());
+
+            // Copy to block.
+            self.buffer[self.buffer_fill..self.buffer_fill + todo].copy_from_slice(&data[0..todo]);
+            self.buffer_fill += todo;
+
+            // Update data to skip copied block.
+            data = &data[todo..];
+        }
+
+        // Success.
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<[u64; 4], ChecksumError> {
+        // Output node of the last chunk.
+        self.buffer[self.buffer_fill..BLAKE3_BLOCK_SIZE].fill(0);
+
+        let mut cv = self.chunk_cv;
+        let mut block = self.buffer;
+        let mut counter = self.chunk_counter;
+        let mut block_len = self.buffer_fill as u32;
+        let mut flags = self.chunk_flags() | FLAG_CHUNK_END;
+
+        // Merge with the stack, from right to left.
+        while self.stack_len > 0 {
+            self.stack_len -= 1;
+
+            let right = compress_cv(&cv, &block, counter, block_len, flags);
+
+            cv = self.key;
+            block = parent_block(&self.stack[self.stack_len], &right);
+            counter = 0;
+            block_len = BLAKE3_BLOCK_SIZE as u32;
+            flags = self.flags | FLAG_PARENT;
+        }
+
+        // Root node.
+        let output = compress(&cv, &block, counter, block_len, flags | FLAG_ROOT);
+
+        // The result bytes are the little endian encoding of the output, and
+        // ZFS stores them as is, so decode them in the byte order of the data.
+        let mut result = [0; 4];
+        for (i, value) in result.iter_mut().enumerate() {
+            let v = u64::from(output[2 * i]) | (u64::from(output[2 * i + 1]) << 32);
+            *value = match self.order {
+                EndianOrder::Big => v.swap_bytes(),
+                EndianOrder::Little => v,
+            };
+        }
+
+        Ok(result)
+    }
+
+    fn hash(&mut self, data: &[u8], order: EndianOrder) -> Result<[u64; 4], ChecksumError> {
+        self.reset(order)?;
+        self.update(data)?;
+        self.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::cmp;
+
+    use crate::checksum::{Blake3, Blake3Implementation, Checksum, ChecksumError};
+    use crate::phys::EndianOrder;
+
+    /** 128 byte random data.
+     *
+     * Refer to `docs/BLAKE3.md` for script to generate test cases.
+     */
+    const TEST_VECTOR_A: [u8; 128] = [
+        0xbc, 0x4b, 0x4d, 0x58, 0x43, 0xca, 0x34, 0x35, 0xe4, 0xd0, 0x59, 0xe4, 0xd0, 0x2b, 0x08,
+        0xe3, 0x2f, 0xe3, 0x78, 0xe1, 0xe6, 0xf6, 0xf1, 0x34, 0x84, 0xdc, 0x1e, 0x0e, 0x12, 0x28,
+        0x2e, 0xbe, 0x53, 0xbd, 0x1a, 0xf9, 0x8a, 0x97, 0x6e, 0xab, 0x7c, 0x06, 0xed, 0x50, 0xa8,
+        0xc9, 0xe4, 0x1e, 0xb8, 0xaf, 0xb8, 0x8c, 0x94, 0xb5, 0x15, 0xed, 0xa8, 0x3f, 0x9d, 0x99,
+        0x9c, 0x26, 0xe8, 0x1d, 0x87, 0x29, 0x1f, 0x60, 0x64, 0xca, 0xd1, 0xe8, 0x48, 0x7e, 0xe4,
+        0xf2, 0x56, 0xf3, 0x59, 0x73, 0x04, 0x39, 0xb2, 0x62, 0x56, 0xea, 0xf1, 0x44, 0xf0, 0x06,
+        0x28, 0x2e, 0x56, 0x16, 0xd3, 0x80, 0x0d, 0x47, 0x9e, 0x87, 0x3f, 0x52, 0x64, 0x30, 0x63,
+        0x6d, 0x64, 0x58, 0xcb, 0x84, 0x4d, 0xf7, 0x1c, 0x6e, 0xc7, 0x07, 0x86, 0x3d, 0x17, 0xec,
+        0x51, 0x8f, 0x51, 0x6e, 0x5a, 0x52, 0x64, 0xee,
+    ];
+
+    /// Salt of bytes 0 to 31.
+    const TEST_SALT: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+
+    /** Checksums without a salt, in little endian order.
+     *
+     * Size 0 is the BLAKE3 empty message result from the reference.
+     */
+    const TEST_VECTOR_A_CHECKSUMS: [(usize, [u64; 4]); 27] = [
+        (
+            0,
+            [
+                0xa6a1f9f5b94913af,
+                0x49c9dc36ea4d40a0,
+                0xb712c1adc925cb9b,
+                0x62321fe4ca939acc,
+            ],
+        ),
+        (
+            1,
+            [
+                0x998cd4333d857b29,
+                0xcac37baae2fe1943,
+                0x059d3fb2f4686993,
+                0xfd77e447b5efa2bc,
+            ],
+        ),
+        (
+            4,
+            [
+                0x0900f7caf935f760,
+                0x356ab2b418cdc8a8,
+                0x7850dd6f96b744fd,
+                0x5af841757b963a24,
+            ],
+        ),
+        (
+            8,
+            [
+                0x8132a722d63dc507,
+                0x197654c5cf5a9cf8,
+                0xb6ccace2a93d4a10,
+                0x485b80579d952df8,
+            ],
+        ),
+        (
+            16,
+            [
+                0x5834c1a0d59d040b,
+                0x7a31b8ceffd390f2,
+                0x01eb9a879755dd8d,
+                0x93f366085b565bce,
+            ],
+        ),
+        (
+            32,
+            [
+                0xd92d7031bea83ee8,
+                0x124bc33f57668218,
+                0x550c3721f249cf3e,
+                0xb05ecddb4837ee1f,
+            ],
+        ),
+        (
+            63,
+            [
+                0x7b66e3c72823dcd5,
+                0x7da07347d05c4118,
+                0xde4d48aba3199757,
+                0x29bf2e8ec5c345b1,
+            ],
+        ),
+        (
+            64,
+            [
+                0x2d0780bc242a5928,
+                0x7f549061c99e8b9e,
+                0xe6b868f1158b99e6,
+                0xea36c769c5ad20f4,
+            ],
+        ),
+        (
+            65,
+            [
+                0x3c05dc08bc03a996,
+                0x5e5d2f595e0d85a6,
+                0x225202409e1dda14,
+                0x52241c10154d7348,
+            ],
+        ),
+        (
+            127,
+            [
+                0xa0e7aae7bbd5b57a,
+                0xe497970e78891d33,
+                0x4eaac8c34303bb03,
+                0x6c9a4aff54616bb6,
+            ],
+        ),
+        (
+            128,
+            [
+                0x9bea085700721ffb,
+                0x20ace6036b9ff059,
+                0x257856bb059def22,
+                0x9577555c674de309,
+            ],
+        ),
+        (
+            1023,
+            [
+                0x1cd293a67d5851ba,
+                0x59cacf31190883b3,
+                0x46acab9593d6d922,
+                0x9dec33431b1022e5,
+            ],
+        ),
+        (
+            1024,
+            [
+                0x578763e45b9d3ec3,
+                0xb98d2961fde01ab2,
+                0xa5f82655f67d06ab,
+                0x7abaa60701f216a5,
+            ],
+        ),
+        (
+            1025,
+            [
+                0x17ef458442d5064e,
+                0x97bd59a35c80b402,
+                0x40c643d4b2fac638,
+                0x9c153c8b961113f4,
+            ],
+        ),
+        (
+            2048,
+            [
+                0x5a6b034eaae30388,
+                0x040e524bd9f88c20,
+                0x367f527e9c04e2be,
+                0xe18449dc87e03586,
+            ],
+        ),
+        (
+            2049,
+            [
+                0x3f5a6c1e69cfd4b1,
+                0x9b3604cd5def542a,
+                0x955e57630a626bac,
+                0x73b40d05f761d8a6,
+            ],
+        ),
+        (
+            3072,
+            [
+                0x6e01c709ae6fccea,
+                0x5e8d901dd01117ae,
+                0x24a0fca7b92784ba,
+                0xe83b2ef77fee0313,
+            ],
+        ),
+        (
+            4096,
+            [
+                0x9d818eda063b73be,
+                0x6b7396cd789479a4,
+                0x4373bf5b44438ecd,
+                0xf15bae6681f6a9af,
+            ],
+        ),
+        (
+            4097,
+            [
+                0xf54d714f5c3ede5b,
+                0x9085a50605740e80,
+                0xa552cc6920f3d7ca,
+                0x36687e77e399fed6,
+            ],
+        ),
+        (
+            5120,
+            [
+                0x37eca7f46222e79f,
+                0x0cb141114c91e671,
+                0x135f1f3878b37bd2,
+                0x27ef62ac953053fb,
+            ],
+        ),
+        (
+            8192,
+            [
+                0xbc393ecf490eaad7,
+                0xc9237fb27f40b295,
+                0x3e93f93ead440e46,
+                0xa15cd632fd17bd38,
+            ],
+        ),
+        (
+            16384,
+            [
+                0x8bf426ee3b2cb740,
+                0x22c7f6e508ee805b,
+                0x92cc7bd99cb70c19,
+                0xe7479f02badb2819,
+            ],
+        ),
+        (
+            16385,
+            [
+                0xe528dc62d6ff7731,
+                0x0b3e8b460dfbf060,
+                0x4483192cd57b783e,
+                0x5745e43d8dba6fdf,
+            ],
+        ),
+        (
+            17408,
+            [
+                0x477d01b6a9aaf439,
+                0x78e38b58c776745f,
+                0x2652a24b64c73ab0,
+                0xeeccd4129672f1ce,
+            ],
+        ),
+        (
+            32768,
+            [
+                0x7ff5064cca37eff5,
+                0x9b712aa9f483ef73,
+                0x4da25d1c7c13087b,
+                0x048693079b35efea,
+            ],
+        ),
+        (
+            33792,
+            [
+                0xf87615f41e7ab644,
+                0xa2b2e5492ba56a4c,
+                0x9b7e8a520603c427,
+                0x0c3bc7b6291445cd,
+            ],
+        ),
+        (
+            131072,
+            [
+                0x4b05741aa2695fba,
+                0x63deb1e92e1e3856,
+                0x1bb5b25fc79321c4,
+                0xcce03a27f1f1e9ac,
+            ],
+        ),
+    ];
+
+    /// Checksums with [`TEST_SALT`], in little endian order.
+    const TEST_VECTOR_A_SALTED_CHECKSUMS: [(usize, [u64; 4]); 27] = [
+        (
+            0,
+            [
+                0xcd715d99192b4973,
+                0x0998c0ec4dd7e9b1,
+                0xc295bc001b2f73eb,
+                0x8f47d6d49d5fb17c,
+            ],
+        ),
+        (
+            1,
+            [
+                0xcc46abd44be87caa,
+                0x172935acbaa50abd,
+                0xf6b0985e74a183a5,
+                0x6b45846011c8f646,
+            ],
+        ),
+        (
+            4,
+            [
+                0x1aecc78a2512d0ce,
+                0x81e50df4f37458f2,
+                0x8329704200b53622,
+                0xc3c09bd53d287667,
+            ],
+        ),
+        (
+            8,
+            [
+                0x4a7d2783bb98b377,
+                0x70a703a0c218b5a9,
+                0x5733ddd595c40073,
+                0x4c08524deab69f70,
+            ],
+        ),
+        (
+            16,
+            [
+                0x0c0fd12551210ecb,
+                0x0b8ff340d8ef157a,
+                0xc745c0f9fa7d0914,
+                0x498c98b04de77a0e,
+            ],
+        ),
+        (
+            32,
+            [
+                0x9d58ee6801cfaca4,
+                0xd668fd0d328ccbca,
+                0x65f78e870b4ffcd1,
+                0x839abf8c8131881f,
+            ],
+        ),
+        (
+            63,
+            [
+                0xd0d86c0e7ea21511,
+                0xbc39a6437598dad0,
+                0xa4dd36c403da9b64,
+                0xf3125dd636847b02,
+            ],
+        ),
+        (
+            64,
+            [
+                0xc59c92b738780722,
+                0xf3511052ac89e937,
+                0xa96ec645746781e6,
+                0x81a6bd8b5756a90e,
+            ],
+        ),
+        (
+            65,
+            [
+                0x12bbb145e7af0757,
+                0x88fc478240f62894,
+                0x02d9d883891df0c9,
+                0x29de399523ca918d,
+            ],
+        ),
+        (
+            127,
+            [
+                0x52ca2175f2fd1537,
+                0xf94dee594105eb4f,
+                0x1d21431dfb65c2d4,
+                0xe496571be5f52cf3,
+            ],
+        ),
+        (
+            128,
+            [
+                0xd984e1fec1d6ff58,
+                0xef3677540afffd10,
+                0x2bacdb79841c9bde,
+                0x89a6167508ecd364,
+            ],
+        ),
+        (
+            1023,
+            [
+                0x6098bb6582042004,
+                0xf9c483cb58c5c90c,
+                0xecc14c313ea0a38c,
+                0x6ee2c1954a0ad700,
+            ],
+        ),
+        (
+            1024,
+            [
+                0x53015cfb3d6e85ca,
+                0xbb6e5786c3aed5b6,
+                0x1b7881f531b57eb6,
+                0xdc0b14171fb46608,
+            ],
+        ),
+        (
+            1025,
+            [
+                0xafaa871415bcf816,
+                0xf1e6bb51e330f8ee,
+                0x12f24ea16e06c5ad,
+                0x365c5f048854c118,
+            ],
+        ),
+        (
+            2048,
+            [
+                0x71ce78ddbeaedf5e,
+                0x6dcafbd7efc8d3ea,
+                0x691fb2a4b560ddb0,
+                0xaf02d0954c3dcbec,
+            ],
+        ),
+        (
+            2049,
+            [
+                0xcafde7ee0b7eb1c5,
+                0xdc6cad4e5bbbb370,
+                0x15d3d5e3587fa9c4,
+                0x4a6bdc57b917bb1c,
+            ],
+        ),
+        (
+            3072,
+            [
+                0x2f03fb8553d55917,
+                0xfd940db198dda321,
+                0x09ef59b974333001,
+                0x029ce20c0edade2f,
+            ],
+        ),
+        (
+            4096,
+            [
+                0xe7268877b2df7c89,
+                0x4dff4f8bbb9a0e49,
+                0x32dee06a460a07a8,
+                0xcbaaad66c8338b50,
+            ],
+        ),
+        (
+            4097,
+            [
+                0x07184fc76a8c32d0,
+                0x20b402cb40335894,
+                0xa4862ba79b98a309,
+                0x008c8ccb2469810c,
+            ],
+        ),
+        (
+            5120,
+            [
+                0xa812b1ccd778f5b7,
+                0xe49a517769538d32,
+                0x33e594efa9d29112,
+                0xac75bc956f5c6cae,
+            ],
+        ),
+        (
+            8192,
+            [
+                0xc7752ff6532326ca,
+                0xdcf86f5a96f2dc58,
+                0xf8449644bdba9150,
+                0x1c42cdf800616c17,
+            ],
+        ),
+        (
+            16384,
+            [
+                0xb7f903c7f929c988,
+                0x72aa3f02be2fceee,
+                0xf5c1f2ac305c6ad2,
+                0xd8dac186dc79c0a8,
+            ],
+        ),
+        (
+            16385,
+            [
+                0x2d5462c1fe8db0e7,
+                0xf54338ff36cbcc23,
+                0x29d39c85b7824ac3,
+                0xe2bfdcd31a14a6f5,
+            ],
+        ),
+        (
+            17408,
+            [
+                0x382d9563b7fde291,
+                0x4b9034dbde2c2bf1,
+                0x42794b25919a1be9,
+                0x081f6adb4eaa8c53,
+            ],
+        ),
+        (
+            32768,
+            [
+                0xb9cffca40cce946d,
+                0x669b3d1e4f809ac2,
+                0x49de0ccd21227fb8,
+                0x5b424d0682ffdb6b,
+            ],
+        ),
+        (
+            33792,
+            [
+                0x8b55cd4d07b22ead,
+                0xce1e03a905e3a145,
+                0x0b247c670b3a6013,
+                0x483d111c2eff7b18,
+            ],
+        ),
+        (
+            131072,
+            [
+                0xe7a39cb55fef0f69,
+                0xa4a35d214eba9dc9,
+                0xcca59cbb1126a5b6,
+                0x1d84309de55031dd,
+            ],
+        ),
+    ];
+
+    fn run_test_vector(
+        h: &mut Blake3,
+        order: EndianOrder,
+        vector: &[u8],
+        checksums: &[(usize, [u64; 4])],
+    ) {
+        // Test sizes.
+        for (size, checksum) in checksums {
+            let size = *size;
+
+            // Checksums are in little endian order.
+            let checksum = match order {
+                EndianOrder::Big => checksum.map(u64::swap_bytes),
+                EndianOrder::Little => *checksum,
+            };
+
+            if size <= vector.len() {
+                // Single update call.
+                assert_eq!(h.hash(&vector[0..size], order).unwrap(), checksum);
+
+                // Partial update.
+                h.reset(order).unwrap();
+                let mut offset = 0;
+
+                h.update(&vector[0..size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..offset + size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..size]).unwrap();
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+            } else {
+                // Multiple calls.
+                let mut todo = size;
+                h.reset(order).unwrap();
+
+                while todo > 0 {
+                    let can_do = cmp::min(todo, vector.len());
+                    h.update(&vector[0..can_do]).unwrap();
+                    todo -= can_do;
+                }
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+
+                // Single update call, to use multiple chunks at a time.
+                let mut data = vec![0; size];
+                for (i, x) in data.iter_mut().enumerate() {
+                    *x = vector[i % vector.len()];
+                }
+                assert_eq!(h.hash(&data, order).unwrap(), checksum);
+            }
+        }
+    }
+
+    fn test_required_implementation(implementation: Blake3Implementation) {
+        let mut h = Blake3::new(implementation, None).unwrap();
+        let mut s = Blake3::new(implementation, Some(&TEST_SALT)).unwrap();
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            run_test_vector(&mut h, order, &TEST_VECTOR_A, &TEST_VECTOR_A_CHECKSUMS);
+            run_test_vector(
+                &mut s,
+                order,
+                &TEST_VECTOR_A,
+                &TEST_VECTOR_A_SALTED_CHECKSUMS,
+            );
+        }
+    }
+
+    fn test_optional_implementation(implementation: Blake3Implementation) {
+        let supported = !matches!(
+            Blake3::new(implementation, None),
+            Err(ChecksumError::Unsupported {
+                checksum: _,
+                implementation: _,
+            })
+        );
+
+        if supported {
+            test_required_implementation(implementation);
+        }
+    }
+
+    #[test]
+    fn blake3_all() {
+        assert_eq!(Blake3Implementation::all().len(), 5);
+    }
+
+    #[test]
+    fn blake3_str() {
+        assert_eq!(format!("{}", Blake3Implementation::Generic), "generic");
+        assert_eq!(format!("{}", Blake3Implementation::SSE2), "sse2");
+        assert_eq!(format!("{}", Blake3Implementation::SSE41), "sse4.1");
+        assert_eq!(format!("{}", Blake3Implementation::AVX2), "avx2");
+        assert_eq!(format!("{}", Blake3Implementation::AVX512), "avx512");
+    }
+
+    #[test]
+    fn blake3_generic() {
+        test_required_implementation(Blake3Implementation::Generic)
+    }
+
+    #[test]
+    fn blake3_sse2() {
+        test_optional_implementation(Blake3Implementation::SSE2)
+    }
+
+    #[test]
+    fn blake3_sse41() {
+        test_optional_implementation(Blake3Implementation::SSE41)
+    }
+
+    #[test]
+    fn blake3_avx2() {
+        test_optional_implementation(Blake3Implementation::AVX2)
+    }
+
+    #[test]
+    fn blake3_avx512() {
+        test_optional_implementation(Blake3Implementation::AVX512)
+    }
+}
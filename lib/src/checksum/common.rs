@@ -16,6 +16,12 @@ pub enum ChecksumError {
         /// Implementation.
         implementation: &'static str,
     },
+
+    /// Salted [`ChecksumType`] without a salt.
+    MissingSalt {
+        /// Salted checksum.
+        checksum: ChecksumType,
+    },
 }
 
 impl fmt::Display for ChecksumError {
@@ -30,6 +36,9 @@ impl fmt::Display for ChecksumError {
                     "Unsupported Checksum {checksum} implementation {implementation}"
                 )
             }
+            ChecksumError::MissingSalt { checksum } => {
+                write!(f, "Missing salt for Checksum {checksum}")
+            }
         }
     }
 }
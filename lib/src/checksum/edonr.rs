@@ -0,0 +1,951 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Edon-R 512 checksum.
+ *
+ * Edon-R is built on quasigroup operations over a double chaining pipe of
+ * sixteen u64. ZFS uses Edon-R 512, truncated to the first 256 bits.
+ *
+ * The per pool checksum salt is too small to fill a 128 byte block, so ZFS
+ * expands it to `H(salt) || H(H(salt))`, and feeds that block into the hash
+ * before the data, where it serves as a MAC key.
+ */
+use crate::checksum::{Checksum, ChecksumError};
+use crate::phys::EndianOrder;
+
+use core::cmp;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Edon-R 512 block size in bytes.
+const EDONR_512_BLOCK_SIZE: usize = 128;
+
+/// Edon-R 512 block size in u64.
+const EDONR_512_BLOCK_U64_COUNT: usize = 16;
+
+/// Edon-R 512 digest size in bytes.
+const EDONR_512_DIGEST_SIZE: usize = 64;
+
+/// Edon-R 512 digest size in u64.
+const EDONR_512_DIGEST_U64_COUNT: usize = 8;
+
+/// Number of bytes used to encode the length in bits in the last block.
+const EDONR_512_LENGTH_SIZE: usize = 8;
+
+/// Initial double chaining pipe.
+const EDONR_512_INITIAL_PIPE: [u64; EDONR_512_BLOCK_U64_COUNT] = [
+    0x8081828384858687,
+    0x88898a8b8c8d8e8f,
+    0x9091929394959697,
+    0x98999a9b9c9d9e9f,
+    0xa0a1a2a3a4a5a6a7,
+    0xa8a9aaabacadaeaf,
+    0xb0b1b2b3b4b5b6b7,
+    0xb8b9babbbcbdbebf,
+    0xc0c1c2c3c4c5c6c7,
+    0xc8c9cacbcccdcecf,
+    0xd0d1d2d3d4d5d6d7,
+    0xd8d9dadbdcdddedf,
+    0xe0e1e2e3e4e5e6e7,
+    0xe8e9eaebecedeeef,
+    0xf0f1f2f3f4f5f6f7,
+    0xf8f9fafbfcfdfeff,
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Quasigroup operation of Edon-R 512.
+ *
+ * `x` goes through the first Latin Square, and `y` through the second
+ * orthogonal Latin Square. The results are then diagonalized and combined.
+ */
+#[inline(always)]
+fn quasigroup(
+    x: &[u64; EDONR_512_DIGEST_U64_COUNT],
+    y: &[u64; EDONR_512_DIGEST_U64_COUNT],
+) -> [u64; EDONR_512_DIGEST_U64_COUNT] {
+    // First Latin Square.
+    let s0 = 0xaaaaaaaaaaaaaaaa_u64
+        .wrapping_add(x[0])
+        .wrapping_add(x[1])
+        .wrapping_add(x[2])
+        .wrapping_add(x[4])
+        .wrapping_add(x[7]);
+    let s1 = (x[0].wrapping_add(x[1]).wrapping_add(x[3]))
+        .wrapping_add(x[4])
+        .wrapping_add(x[7])
+        .rotate_left(5);
+    let s2 = (x[0].wrapping_add(x[1]).wrapping_add(x[4]))
+        .wrapping_add(x[6])
+        .wrapping_add(x[7])
+        .rotate_left(15);
+    let s3 = (x[2].wrapping_add(x[3]).wrapping_add(x[5]))
+        .wrapping_add(x[6])
+        .wrapping_add(x[7])
+        .rotate_left(22);
+    let s4 = (x[1].wrapping_add(x[2]).wrapping_add(x[3]))
+        .wrapping_add(x[5])
+        .wrapping_add(x[6])
+        .rotate_left(31);
+    let s5 = (x[0].wrapping_add(x[2]).wrapping_add(x[3]))
+        .wrapping_add(x[4])
+        .wrapping_add(x[5])
+        .rotate_left(40);
+    let s6 = (x[0].wrapping_add(x[1]).wrapping_add(x[5]))
+        .wrapping_add(x[6])
+        .wrapping_add(x[7])
+        .rotate_left(50);
+    let s7 = (x[2].wrapping_add(x[3]).wrapping_add(x[4]))
+        .wrapping_add(x[5])
+        .wrapping_add(x[6])
+        .rotate_left(59);
+
+    // Diagonalization of the first Latin Square.
+    let u0 = s3 ^ s5 ^ s6;
+    let u1 = s2 ^ s5 ^ s6;
+    let u2 = s2 ^ s3 ^ s5;
+    let u3 = s0 ^ s1 ^ s4;
+    let u4 = s0 ^ s4 ^ s7;
+    let u5 = s1 ^ s6 ^ s7;
+    let u6 = s2 ^ s3 ^ s4;
+    let u7 = s0 ^ s1 ^ s7;
+
+    // Second orthogonal Latin Square.
+    let t0 = 0x5555555555555555_u64
+        .wrapping_add(y[0])
+        .wrapping_add(y[1])
+        .wrapping_add(y[2])
+        .wrapping_add(y[5])
+        .wrapping_add(y[7]);
+    let t1 = (y[0].wrapping_add(y[1]).wrapping_add(y[3]))
+        .wrapping_add(y[4])
+        .wrapping_add(y[6])
+        .rotate_left(3);
+    let t2 = (y[0].wrapping_add(y[1]).wrapping_add(y[2]))
+        .wrapping_add(y[3])
+        .wrapping_add(y[5])
+        .rotate_left(17);
+    let t3 = (y[0].wrapping_add(y[2]).wrapping_add(y[3]))
+        .wrapping_add(y[4])
+        .wrapping_add(y[7])
+        .rotate_left(23);
+    let t4 = (y[0].wrapping_add(y[1]).wrapping_add(y[4]))
+        .wrapping_add(y[6])
+        .wrapping_add(y[7])
+        .rotate_left(31);
+    let t5 = (y[2].wrapping_add(y[3]).wrapping_add(y[4]))
+        .wrapping_add(y[5])
+        .wrapping_add(y[6])
+        .rotate_left(37);
+    let t6 = (y[1].wrapping_add(y[2]).wrapping_add(y[5]))
+        .wrapping_add(y[6])
+        .wrapping_add(y[7])
+        .rotate_left(45);
+    let t7 = (y[3].wrapping_add(y[4]).wrapping_add(y[5]))
+        .wrapping_add(y[6])
+        .wrapping_add(y[7])
+        .rotate_left(59);
+
+    // Diagonalization of the second Latin Square, and combination.
+    [
+        u3.wrapping_add(t0 ^ t1 ^ t5),
+        u4.wrapping_add(t2 ^ t6 ^ t7),
+        u5.wrapping_add(t0 ^ t1 ^ t3),
+        u6.wrapping_add(t0 ^ t3 ^ t4),
+        u7.wrapping_add(t1 ^ t2 ^ t5),
+        u0.wrapping_add(t3 ^ t4 ^ t6),
+        u1.wrapping_add(t2 ^ t5 ^ t7),
+        u2.wrapping_add(t4 ^ t6 ^ t7),
+    ]
+}
+
+/// Reverses the order of the words.
+#[inline(always)]
+fn reversed(x: &[u64]) -> [u64; EDONR_512_DIGEST_U64_COUNT] {
+    let mut r = [0; EDONR_512_DIGEST_U64_COUNT];
+    for (i, v) in r.iter_mut().enumerate() {
+        *v = x[EDONR_512_DIGEST_U64_COUNT - 1 - i];
+    }
+    r
+}
+
+/// Splits off eight words.
+#[inline(always)]
+fn half(x: &[u64]) -> [u64; EDONR_512_DIGEST_U64_COUNT] {
+    x[0..EDONR_512_DIGEST_U64_COUNT].try_into().unwrap()
+}
+
+/// Updates the double chaining pipe with full blocks.
+fn update_blocks(pipe: &mut [u64; EDONR_512_BLOCK_U64_COUNT], data: &[u8]) {
+    for block in data.chunks_exact(EDONR_512_BLOCK_SIZE) {
+        // Decode the block.
+        let mut d = [0; EDONR_512_BLOCK_U64_COUNT];
+        for (i, x) in block.chunks_exact(8).enumerate() {
+            d[i] = u64::from_le_bytes(x.try_into().unwrap());
+        }
+
+        let (d0, d1) = d.split_at(EDONR_512_DIGEST_U64_COUNT);
+        let (c0, c1) = pipe.split_at(EDONR_512_DIGEST_U64_COUNT);
+
+        // First row of quasigroup e-transformations.
+        let p = quasigroup(&reversed(d1), &half(d0));
+        let q = quasigroup(&p, &half(d1));
+
+        // Second row of quasigroup e-transformations.
+        let p = quasigroup(&half(c1), &p);
+        let q = quasigroup(&p, &q);
+
+        // Third row of quasigroup e-transformations.
+        let p = quasigroup(&p, &half(c0));
+        let q = quasigroup(&q, &p);
+
+        // Fourth row of quasigroup e-transformations.
+        let p = quasigroup(&reversed(d0), &p);
+        let q = quasigroup(&p, &q);
+
+        // Edon-R tweak on the original SHA-3 Edon-R submission.
+        for i in 0..EDONR_512_DIGEST_U64_COUNT {
+            pipe[i] ^= d[EDONR_512_DIGEST_U64_COUNT + i] ^ p[i];
+            pipe[EDONR_512_DIGEST_U64_COUNT + i] ^= d[i] ^ q[i];
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Edon-R 512 hash state.
+#[derive(Clone, Copy)]
+struct EdonrState {
+    /// Double chaining pipe.
+    pipe: [u64; EDONR_512_BLOCK_U64_COUNT],
+
+    /// Number of bytes processed, not including [`EdonrState::buffer`].
+    bytes_processed: u64,
+
+    /// Number of bytes used in [`EdonrState::buffer`].
+    buffer_fill: usize,
+
+    /// Partial block buffer.
+    buffer: [u8; EDONR_512_BLOCK_SIZE],
+}
+
+impl EdonrState {
+    /// Create a new state, with the initial double chaining pipe.
+    fn new() -> EdonrState {
+        EdonrState {
+            pipe: EDONR_512_INITIAL_PIPE,
+            bytes_processed: 0,
+            buffer_fill: 0,
+            buffer: [0; EDONR_512_BLOCK_SIZE],
+        }
+    }
+
+    /// Update the state with data.
+    fn update(&mut self, data: &[u8]) {
+        // Make data pointer mutable, in case of self.buffer_fill.
+        let mut data = data;
+
+        // If block has some data, fill that up first.
+        if self.buffer_fill > 0 {
+            // Todo is minimum of block fill needed, and input data.
+            let todo = cmp::min(EDONR_512_BLOCK_SIZE - self.buffer_fill, data.len());
+
+            // Copy to block.
+            self.buffer[self.buffer_fill..self.buffer_fill + todo].copy_from_slice(&data[0..todo]);
+            self.buffer_fill += todo;
+
+            // Update data to skip copied block.
+            data = &data[todo..];
+
+            // If block is full, consume it.
+            if self.buffer_fill == EDONR_512_BLOCK_SIZE {
+                self.bytes_processed += EDONR_512_BLOCK_SIZE as u64;
+                update_blocks(&mut self.pipe, &self.buffer);
+                self.buffer_fill = 0;
+            }
+        }
+
+        // Calculate remainder.
+        let remainder = data.len() % EDONR_512_BLOCK_SIZE;
+
+        // Update full blocks.
+        let full_blocks_data = &data[0..data.len() - remainder];
+        self.bytes_processed += full_blocks_data.len() as u64;
+        update_blocks(&mut self.pipe, full_blocks_data);
+
+        // Check if remainder exists, to prevent clobbering fill with 0.
+        if remainder > 0 {
+            self.buffer[0..remainder].copy_from_slice(&data[data.len() - remainder..]);
+            self.buffer_fill = remainder;
+        }
+    }
+
+    /// Pad the data, and return the 512 bit digest.
+    fn finalize(&mut self) -> [u8; EDONR_512_DIGEST_SIZE] {
+        let byte_length = self.bytes_processed + (self.buffer_fill as u64);
+        let bit_length = byte_length.wrapping_mul(8);
+
+        // Set the 1 bit.
+        // NOTE: EdonrState::update guarantees that buffer_fill will be less
+        //       than the size of the buffer, and at least one byte will be
+        //       available.
+        self.buffer[self.buffer_fill] = 0x80;
+        self.buffer_fill += 1;
+
+        // If last block does not have enough space for 64 bit length,
+        // then pad it out with zeroes.
+        if self.buffer_fill > EDONR_512_BLOCK_SIZE - EDONR_512_LENGTH_SIZE {
+            self.buffer[self.buffer_fill..EDONR_512_BLOCK_SIZE].fill(0);
+            self.buffer_fill = 0;
+
+            update_blocks(&mut self.pipe, &self.buffer);
+        }
+
+        // Set zero bits until 64 bits are remaining.
+        self.buffer[self.buffer_fill..EDONR_512_BLOCK_SIZE - EDONR_512_LENGTH_SIZE].fill(0);
+
+        // Encode length in bits.
+        self.buffer[EDONR_512_BLOCK_SIZE - EDONR_512_LENGTH_SIZE..EDONR_512_BLOCK_SIZE]
+            .copy_from_slice(&u64::to_le_bytes(bit_length));
+
+        // Process last block.
+        update_blocks(&mut self.pipe, &self.buffer);
+
+        // Digest is the second half of the double chaining pipe.
+        let mut digest = [0; EDONR_512_DIGEST_SIZE];
+        for (i, x) in digest.chunks_exact_mut(8).enumerate() {
+            x.copy_from_slice(&u64::to_le_bytes(self.pipe[EDONR_512_DIGEST_U64_COUNT + i]));
+        }
+
+        digest
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`crate::phys::ChecksumType::Edonr`] implementation.
+pub struct Edonr {
+    /// State after processing the expanded salt block.
+    initial: EdonrState,
+
+    /// Ongoing state.
+    state: EdonrState,
+
+    /// Byte order of the result.
+    order: EndianOrder,
+}
+
+impl Edonr {
+    /** Create a new Edon-R instance.
+     *
+     * `salt` is the 32 byte per pool checksum salt, stored in the MOS. It is
+     * expanded to a full block of `H(salt) || H(H(salt))`.
+     */
+    pub fn new(salt: &[u8]) -> Edonr {
+        // Expand the salt to a full block.
+        let mut salt_block = [0; EDONR_512_BLOCK_SIZE];
+
+        let mut h = EdonrState::new();
+        h.update(salt);
+        salt_block[0..EDONR_512_DIGEST_SIZE].copy_from_slice(&h.finalize());
+
+        let mut h = EdonrState::new();
+        h.update(&salt_block[0..EDONR_512_DIGEST_SIZE]);
+        salt_block[EDONR_512_DIGEST_SIZE..].copy_from_slice(&h.finalize());
+
+        // Feed the salt block into the hash.
+        let mut initial = EdonrState::new();
+        initial.update(&salt_block);
+
+        Edonr {
+            initial,
+            state: initial,
+            order: EndianOrder::Little,
+        }
+    }
+}
+
+impl Checksum for Edonr {
+    fn reset(&mut self, order: EndianOrder) -> Result<(), ChecksumError> {
+        self.state = self.initial;
+        self.order = order;
+
+        Ok(())
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<(), ChecksumError> {
+        self.state.update(data);
+
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<[u64; 4], ChecksumError> {
+        let digest = self.state.finalize();
+
+        // ZFS stores the first 256 bits of the digest as is, so decode them
+        // in the byte order of the data.
+        let mut result = [0; 4];
+        for (value, x) in result.iter_mut().zip(digest.chunks_exact(8)) {
+            let x = x.try_into().unwrap();
+            *value = match self.order {
+                EndianOrder::Big => u64::from_be_bytes(x),
+                EndianOrder::Little => u64::from_le_bytes(x),
+            };
+        }
+
+        Ok(result)
+    }
+
+    fn hash(&mut self, data: &[u8], order: EndianOrder) -> Result<[u64; 4], ChecksumError> {
+        self.reset(order)?;
+        self.update(data)?;
+        self.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::cmp;
+
+    use crate::checksum::edonr::{EdonrState, EDONR_512_DIGEST_U64_COUNT};
+    use crate::checksum::{Checksum, Edonr};
+    use crate::phys::EndianOrder;
+
+    /** 128 byte random data.
+     *
+     * Refer to `docs/EDONR.md` for program to generate test cases.
+     */
+    const TEST_VECTOR_A: [u8; 128] = [
+        0xbc, 0x4b, 0x4d, 0x58, 0x43, 0xca, 0x34, 0x35, 0xe4, 0xd0, 0x59, 0xe4, 0xd0, 0x2b, 0x08,
+        0xe3, 0x2f, 0xe3, 0x78, 0xe1, 0xe6, 0xf6, 0xf1, 0x34, 0x84, 0xdc, 0x1e, 0x0e, 0x12, 0x28,
+        0x2e, 0xbe, 0x53, 0xbd, 0x1a, 0xf9, 0x8a, 0x97, 0x6e, 0xab, 0x7c, 0x06, 0xed, 0x50, 0xa8,
+        0xc9, 0xe4, 0x1e, 0xb8, 0xaf, 0xb8, 0x8c, 0x94, 0xb5, 0x15, 0xed, 0xa8, 0x3f, 0x9d, 0x99,
+        0x9c, 0x26, 0xe8, 0x1d, 0x87, 0x29, 0x1f, 0x60, 0x64, 0xca, 0xd1, 0xe8, 0x48, 0x7e, 0xe4,
+        0xf2, 0x56, 0xf3, 0x59, 0x73, 0x04, 0x39, 0xb2, 0x62, 0x56, 0xea, 0xf1, 0x44, 0xf0, 0x06,
+        0x28, 0x2e, 0x56, 0x16, 0xd3, 0x80, 0x0d, 0x47, 0x9e, 0x87, 0x3f, 0x52, 0x64, 0x30, 0x63,
+        0x6d, 0x64, 0x58, 0xcb, 0x84, 0x4d, 0xf7, 0x1c, 0x6e, 0xc7, 0x07, 0x86, 0x3d, 0x17, 0xec,
+        0x51, 0x8f, 0x51, 0x6e, 0x5a, 0x52, 0x64, 0xee,
+    ];
+
+    /** Edon-R 512 digests, as little endian words.
+     *
+     * These are plain `EdonRHash` results, without the ZFS salt block.
+     */
+    const TEST_VECTOR_A_DIGESTS: [(usize, [u64; EDONR_512_DIGEST_U64_COUNT]); 18] = [
+        (
+            0,
+            [
+                0x7ea6427714d7cb79,
+                0xe08aafd2fc7e386c,
+                0xf12b6677fd07fefa,
+                0x85cbfaafd2458b9c,
+                0x91df953026c4ebfb,
+                0x344b32bb1971e485,
+                0xd8e5fb999e2fee21,
+                0x53e4f9cfa4ba26fb,
+            ],
+        ),
+        (
+            4,
+            [
+                0xf418402edb35116f,
+                0x99dc5cc38b9bd8d3,
+                0xb00c9e5cd300bf44,
+                0x8e078a9d00fae23,
+                0xf0a0429ef61f4400,
+                0xcbff49b29425bdc7,
+                0xde72bf14675b9b96,
+                0x99e75d6c58eff69f,
+            ],
+        ),
+        (
+            8,
+            [
+                0x1df749053deb3e7c,
+                0x7b9ffcc959b5efc2,
+                0x33f6e644f91265a6,
+                0x49349d62582170ad,
+                0x1bc0b4ea04c8fc8d,
+                0xed14d14dff575d36,
+                0xc24fec989ed2a080,
+                0x3280a1e381c69131,
+            ],
+        ),
+        (
+            16,
+            [
+                0xf38066ed7bf91fcb,
+                0x88f2f5f0ad6dd608,
+                0x2b35815ebeb0c17e,
+                0x9a2b27e83fdc1dfd,
+                0x9ce35de99bcd3a10,
+                0x2da1e8901ea9878c,
+                0x93b34bca68e680ec,
+                0xd8bb52ca7ca7473e,
+            ],
+        ),
+        (
+            32,
+            [
+                0x1979199d1ab2b7a,
+                0xf121f9c5b7ebf3ee,
+                0xb4f4e8f02bfac8f2,
+                0xb5c357965e70616a,
+                0xe7835389c0dae666,
+                0x385dbeaa406798e7,
+                0x8ea2ac463d82f06,
+                0x351c890fc737d373,
+            ],
+        ),
+        (
+            63,
+            [
+                0x574d435f8ccfe43d,
+                0x70438cbe994dbe45,
+                0x6ddfc486aeebe14,
+                0x4f98cd27f0e76109,
+                0xca48b3d857ff0cb2,
+                0x8c021d1791a3afe7,
+                0x9aa160509e39d1d,
+                0x30bd340fc24c371a,
+            ],
+        ),
+        (
+            64,
+            [
+                0x49e6b9bc73aceb7c,
+                0x647cab284d35f2fc,
+                0x1f3df47d8b91018f,
+                0xb6e6f715dfcf5a78,
+                0x4f40aa2c3ef6fe62,
+                0xcd7a9ea47ebde5d2,
+                0xd3ea62ae889714d2,
+                0x6f033f9e8eb1e53c,
+            ],
+        ),
+        (
+            65,
+            [
+                0x5cdd78ef2a5ca2f8,
+                0x7ff95cbc7f839b4c,
+                0x62806f7c3412fb0f,
+                0xbcb50eeb6a58a3b4,
+                0x2dfcae86f8b762a6,
+                0xa77e9d782e3093a5,
+                0x7a942156bd872553,
+                0x7959fda27c205733,
+            ],
+        ),
+        (
+            119,
+            [
+                0xbe9306e59566d685,
+                0x1432c0e6fed32dd5,
+                0x5a9b2ebd011d1791,
+                0x7b77e19799e00018,
+                0x6db795b17c6bed1d,
+                0x40a716011c704d02,
+                0x9dc246f2502a21d9,
+                0xfe1c3c48e46a99f4,
+            ],
+        ),
+        (
+            120,
+            [
+                0x1d16c0cc9fafc69a,
+                0x4df592f7abeb5064,
+                0xc91b89de49f0dcb9,
+                0x9a31bf7aa27a1ae0,
+                0xcd20253f29c9b87b,
+                0x6ae650ee24baeb61,
+                0x2240b24ad097ef76,
+                0x44caac88e4f3f2b6,
+            ],
+        ),
+        (
+            127,
+            [
+                0xfbea6f02449ddaa6,
+                0x39da2db9bc451c25,
+                0x44f72aa636216771,
+                0xc562b15b88667db5,
+                0xf78b4127d13ac3fd,
+                0xeb864505106466e1,
+                0x2d82df949f2eb826,
+                0xdea21e7254e166e4,
+            ],
+        ),
+        (
+            128,
+            [
+                0x925f4cb2cf585b67,
+                0xfd89139752f4e86,
+                0x2a02a50225a4838e,
+                0x75a6a1cb81622ecf,
+                0xd6bf44ce41fa2920,
+                0x24e577446a6894a6,
+                0x7d93b672af42d03e,
+                0x7a66c76522373816,
+            ],
+        ),
+        (
+            129,
+            [
+                0x8f3d980d33d45457,
+                0x85c935d843b7dce4,
+                0xf92ef134cfc3f244,
+                0x7f5933ca88682ef4,
+                0xc9151aaa900cd5de,
+                0x520459431415433f,
+                0x220ec1857a8f18a3,
+                0x5079282acc19e7cc,
+            ],
+        ),
+        (
+            192,
+            [
+                0x8858a8b0c616840f,
+                0xe75b0560a97ab28b,
+                0x148c1d330a4300b2,
+                0x4e9318771980571a,
+                0x79bc0015d221d626,
+                0x34684e10a45498e,
+                0x5084c8690e5f39a0,
+                0x85f4f8b2a6c9a1ae,
+            ],
+        ),
+        (
+            256,
+            [
+                0xf3bb9c94b52ac4c1,
+                0xa2b0946f2583a704,
+                0xf1a4b2fa6fe1b555,
+                0xc924b17de7b169e1,
+                0x7cafaca8f5cdaa7a,
+                0x69daabe28917ab48,
+                0xd7c3c49589e1ef62,
+                0x5c6952cd25ee3e1b,
+            ],
+        ),
+        (
+            512,
+            [
+                0x88300cb27fbd4f42,
+                0x4aabe147224029df,
+                0x75a4974cc8a4ad60,
+                0x96ffbba7ccbf5e,
+                0x30247752f4ff3c5a,
+                0x2fd4266eb194a091,
+                0x93fad30d1a102c5f,
+                0xe091af649a22c938,
+            ],
+        ),
+        (
+            8192,
+            [
+                0x2460b9563ad4ccf5,
+                0x190dc32fb17875b,
+                0xb6272f2a5a5b10cd,
+                0x1ea1f939fdd214d0,
+                0xb23ef7b6b3ce795e,
+                0x1193526daffc443f,
+                0x6dc08a7219d90450,
+                0x83f6be269de99a94,
+            ],
+        ),
+        (
+            131072,
+            [
+                0xba21b72078f69652,
+                0xf445a17326df9e7b,
+                0x5d9d9e35e3c80c64,
+                0xd2446eb08ab6bb3e,
+                0xe7b3fe20338ca6b4,
+                0xdc225ae4e650a94d,
+                0xb2ccd8dcbb787bc4,
+                0x99f531bb2597d6bd,
+            ],
+        ),
+    ];
+
+    /// `zio_checksum_edonr_native` checksums with [`TEST_SALT`], in little endian order.
+    const TEST_VECTOR_A_SALTED_CHECKSUMS: [(usize, [u64; 4]); 18] = [
+        (
+            0,
+            [
+                0xc0f56ef4dcf77c41,
+                0xfd62c14b2021c944,
+                0x10dc0e9038822aaf,
+                0xbd76fbb793cbec23,
+            ],
+        ),
+        (
+            4,
+            [
+                0xdd8c6b960d800e9b,
+                0x1041a923a9f76bde,
+                0x4e1783c12e08bda2,
+                0xf88afdb7b7baaf4,
+            ],
+        ),
+        (
+            8,
+            [
+                0xa28619709f788227,
+                0x345231487958f4d8,
+                0xa3d4958a71249ff5,
+                0xc16b351a1700151c,
+            ],
+        ),
+        (
+            16,
+            [
+                0x7f15a56c41739d3e,
+                0x166b73e1073e5732,
+                0x4d85f225fd2a448e,
+                0x110e94753d4297ca,
+            ],
+        ),
+        (
+            32,
+            [
+                0x750f7bf56fc9691,
+                0x572bbb63caa8ba84,
+                0x5c6849e3c6efe551,
+                0x609deb327a6456e5,
+            ],
+        ),
+        (
+            63,
+            [
+                0x75bb48c20397002f,
+                0x41bf55fc6ff74316,
+                0xdaa10dfd860afef8,
+                0x4f6d70893d0c2ad3,
+            ],
+        ),
+        (
+            64,
+            [
+                0x623fc6c95ae73db,
+                0xb1fbe6beb18de00b,
+                0x91d42dc45dcf9bc1,
+                0x11af0e342a1f10e8,
+            ],
+        ),
+        (
+            65,
+            [
+                0xa943cb88d541318a,
+                0x680416ca758ebbbf,
+                0x5bf7da8a982615d,
+                0x240eae087ba90fc6,
+            ],
+        ),
+        (
+            119,
+            [
+                0xc2132638545eadf7,
+                0xa5ade85e7628f599,
+                0xeffacace4572c871,
+                0x316cd0c3200480e7,
+            ],
+        ),
+        (
+            120,
+            [
+                0x1d8422d35c176466,
+                0x4b4a10e3f23f0d3f,
+                0x17903817d5774d8d,
+                0xed6226fb0b303204,
+            ],
+        ),
+        (
+            127,
+            [
+                0x3111e5b86d95c94b,
+                0x6da894cee2fd1830,
+                0x1d02d2ec91b8088,
+                0x1b15982720f8f903,
+            ],
+        ),
+        (
+            128,
+            [
+                0x2f31a493e7d8a0e,
+                0x2b002cee2162adcc,
+                0x313022a5d36d7bd2,
+                0x6a371444eed179b7,
+            ],
+        ),
+        (
+            129,
+            [
+                0x841d946da98c55d0,
+                0x99488ace2e018de3,
+                0x70273caaa167713c,
+                0x16f971f456b27c51,
+            ],
+        ),
+        (
+            192,
+            [
+                0xec1f1bfff2cb8f8d,
+                0x7d8306db834989de,
+                0xc0a0e8fa69867a33,
+                0x6dc76236925ba63d,
+            ],
+        ),
+        (
+            256,
+            [
+                0xc3678bafee259d48,
+                0x494e0489d8079b38,
+                0x3c5cc3a5c82b4560,
+                0xfb5cc8512a71f1c6,
+            ],
+        ),
+        (
+            512,
+            [
+                0xa08e6bbfa7d8e987,
+                0x244189b5d25b2475,
+                0x46255a74fb947172,
+                0x9342efe41edd9075,
+            ],
+        ),
+        (
+            8192,
+            [
+                0x702cfcd37b345855,
+                0x3aaee61dd87b2f64,
+                0x62b93b3de0722b0f,
+                0x65d2d5c68739eb31,
+            ],
+        ),
+        (
+            131072,
+            [
+                0x87384ae883d79feb,
+                0xc5a634f7e1331c20,
+                0x545c9b8a64d02a34,
+                0xf852d853c4d78e4e,
+            ],
+        ),
+    ];
+
+    /// Salt of bytes 0 to 31.
+    const TEST_SALT: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+
+    /// Sizes around the block size, and padding boundaries.
+    const TEST_SIZES: [usize; 16] = [
+        0, 1, 8, 64, 119, 120, 121, 127, 128, 129, 247, 248, 255, 256, 4096, 131072,
+    ];
+
+    fn test_data(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i * 7 + (i >> 8)) as u8).collect()
+    }
+
+    #[test]
+    fn edonr_partial_update() {
+        let mut h = Edonr::new(&TEST_SALT);
+
+        for size in TEST_SIZES {
+            let data = test_data(size);
+            let expected = h.hash(&data, EndianOrder::Little).unwrap();
+
+            // Partial updates of various sizes.
+            for step in [1, 3, 64, 127, 128, 129] {
+                h.reset(EndianOrder::Little).unwrap();
+                for chunk in data.chunks(step) {
+                    h.update(chunk).unwrap();
+                }
+                assert_eq!(h.finalize().unwrap(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn edonr_order() {
+        let mut h = Edonr::new(&TEST_SALT);
+
+        for size in TEST_SIZES {
+            let data = test_data(size);
+            let little = h.hash(&data, EndianOrder::Little).unwrap();
+            let big = h.hash(&data, EndianOrder::Big).unwrap();
+
+            assert_eq!(big, little.map(u64::swap_bytes));
+        }
+    }
+
+    #[test]
+    fn edonr_salt() {
+        let mut a = Edonr::new(&TEST_SALT);
+        let mut b = Edonr::new(&TEST_SALT[1..]);
+
+        // Different salts give different results.
+        for size in TEST_SIZES {
+            let data = test_data(size);
+            assert_ne!(
+                a.hash(&data, EndianOrder::Little).unwrap(),
+                b.hash(&data, EndianOrder::Little).unwrap()
+            );
+        }
+
+        // Different data gives different results.
+        let data = test_data(256);
+        assert_ne!(
+            a.hash(&data[0..255], EndianOrder::Little).unwrap(),
+            a.hash(&data, EndianOrder::Little).unwrap()
+        );
+    }
+
+    /// Calls `update` with `size` bytes of `vector`, repeated as needed.
+    fn update_repeated(mut update: impl FnMut(&[u8]), vector: &[u8], size: usize) {
+        let mut todo = size;
+        while todo > 0 {
+            let can_do = cmp::min(todo, vector.len());
+            update(&vector[0..can_do]);
+            todo -= can_do;
+        }
+    }
+
+    #[test]
+    fn edonr_512() {
+        for (size, expected) in TEST_VECTOR_A_DIGESTS {
+            let mut h = EdonrState::new();
+            update_repeated(|data| h.update(data), &TEST_VECTOR_A, size);
+
+            let digest = h.finalize();
+            let mut words = [0; EDONR_512_DIGEST_U64_COUNT];
+            for (word, x) in words.iter_mut().zip(digest.chunks_exact(8)) {
+                *word = u64::from_le_bytes(x.try_into().unwrap());
+            }
+
+            assert_eq!(words, expected, "size {size}");
+        }
+    }
+
+    #[test]
+    fn edonr_salted() {
+        let mut h = Edonr::new(&TEST_SALT);
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            for (size, checksum) in TEST_VECTOR_A_SALTED_CHECKSUMS {
+                // Checksums are in little endian order.
+                let checksum = match order {
+                    EndianOrder::Big => checksum.map(u64::swap_bytes),
+                    EndianOrder::Little => checksum,
+                };
+
+                h.reset(order).unwrap();
+                update_repeated(|data| h.update(data).unwrap(), &TEST_VECTOR_A, size);
+                assert_eq!(h.finalize().unwrap(), checksum, "size {size}");
+            }
+        }
+    }
+}
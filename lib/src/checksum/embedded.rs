@@ -0,0 +1,796 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Embedded checksum.
+ *
+ * - An embedded checksum is stored in a [`ChecksumTail`] inside the block,
+ *   rather than in the [`BlockPointerRegular`] that points to it.
+ * - The checksum endian encoding is specified using the magic.
+ * - When calculating the checksum, the [`ChecksumTail`] value is set to a
+ *   verifier, which ties the block to its location.
+ *
+ * | Checksum                     | Function    | Tail             | Verifier                                |
+ * |------------------------------|-------------|------------------|-----------------------------------------|
+ * | [`ChecksumType::Label`]      | `sha256`    | end of block     | byte offset of block                    |
+ * | [`ChecksumType::GangHeader`] | `sha256`    | end of block     | vdev, byte offset, and birth of 1st DVA |
+ * | [`ChecksumType::Zilog`]      | `fletcher2` | end of block     | block pointer checksum value            |
+ * | [`ChecksumType::Zilog2`]     | `fletcher4` | ZIL chain header | block pointer checksum value            |
+ *
+ * Like [`crate::checksum::label_verify`], the caller supplies the instance of
+ * the checksum function, so the verification is available without `std`.
+ *
+ * ### ZIL
+ *
+ * The block pointer checksum value of a ZIL block is not a checksum, but the
+ * ZIL GUID, object set, and block sequence number.
+ *
+ * ### ZIL chain
+ *
+ * [`ChecksumType::Zilog2`] blocks start with a ZIL chain header, and only
+ * the used bytes, rounded up to 4096 bytes, are checksummed.
+ *
+ * - Bytes: 184
+ *
+ * ```text
+ * +----------+------+
+ * | Field    | Size |
+ * +----------+------+
+ * | padding  |    8 |
+ * | next     |  128 |
+ * | used     |    8 |
+ * | checksum |   40 |
+ * +----------+------+
+ * ```
+ */
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::checksum::{Checksum, ChecksumError};
+use crate::phys::{
+    BigLittleEndianDecoder, BinaryDecodeError, BinaryEncodeError, BlockPointer,
+    BlockPointerRegular, ChecksumTail, ChecksumTailDecodeError, ChecksumTailEncodeError,
+    ChecksumType, ChecksumValue, EndianOrder, SECTOR_SHIFT,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Byte offset of the used field in the ZIL chain header.
+const ZIL_CHAIN_USED_OFFSET: usize = 8 + BlockPointer::SIZE;
+
+/// Byte offset of the [`ChecksumTail`] in the ZIL chain header.
+const ZIL_CHAIN_TAIL_OFFSET: usize = ZIL_CHAIN_USED_OFFSET + 8;
+
+/// Byte size of the ZIL chain header.
+const ZIL_CHAIN_SIZE: usize = ZIL_CHAIN_TAIL_OFFSET + ChecksumTail::SIZE;
+
+/// Used bytes of [`ChecksumType::Zilog2`] blocks are rounded up to this size.
+const ZIL_MIN_BLOCK_SIZE: usize = 4096;
+
+/** Verifier for a gang header, pointed to by `ptr`.
+ *
+ * The verifier is the `vdev`, byte `offset`, and birth transaction group of
+ * the first [`crate::phys::Dva`].
+ *
+ * Returns [`None`] if `ptr` has no [`crate::phys::Dva`].
+ */
+pub fn gang_header_verifier(ptr: &BlockPointerRegular) -> Option<ChecksumValue> {
+    let dva = ptr.dvas[0].as_ref()?;
+
+    let birth_txg = if ptr.physical_birth_txg != 0 {
+        ptr.physical_birth_txg
+    } else {
+        ptr.logical_birth_txg
+    };
+
+    Some(ChecksumValue {
+        words: [
+            u64::from(dva.vdev),
+            dva.offset << SECTOR_SHIFT,
+            birth_txg,
+            0,
+        ],
+    })
+}
+
+/** Get the [`ChecksumTail`] offset.
+ *
+ * Returns [`None`] if `checksum` is not embedded.
+ */
+fn embedded_tail_offset(checksum: ChecksumType, length: usize) -> Option<usize> {
+    match checksum {
+        ChecksumType::Label | ChecksumType::GangHeader | ChecksumType::Zilog => {
+            Some(length - ChecksumTail::SIZE)
+        }
+        ChecksumType::Zilog2 => Some(ZIL_CHAIN_TAIL_OFFSET),
+        _ => None,
+    }
+}
+
+/** Get the used bytes of a [`ChecksumType::Zilog2`] block.
+ *
+ * Returns the used bytes, and the checksummed length.
+ */
+fn zil_used(data: &[u8], order: EndianOrder) -> Result<(u64, Option<usize>), BinaryDecodeError> {
+    let used = &data[ZIL_CHAIN_USED_OFFSET..ZIL_CHAIN_TAIL_OFFSET];
+    let mut decoder = BigLittleEndianDecoder::from_bytes(used, order);
+    let used = decoder.decoder_as_mut().get_u64()?;
+
+    let length = usize::try_from(used)
+        .ok()
+        .filter(|v| *v >= ZIL_CHAIN_SIZE && *v <= data.len())
+        .and_then(|v| v.checked_next_multiple_of(ZIL_MIN_BLOCK_SIZE))
+        .filter(|v| *v <= data.len());
+
+    Ok((used, length))
+}
+
+/** Compute the checksum of `data`, with the [`ChecksumTail`] set to `verifier`.
+ */
+fn embedded_hash<E: From<ChecksumError> + From<ChecksumTailEncodeError>>(
+    data: &[u8],
+    hasher: &mut dyn Checksum,
+    tail_offset: usize,
+    verifier: &ChecksumValue,
+    order: EndianOrder,
+) -> Result<[u64; 4], E> {
+    // Encode tail with verifier.
+    let verifier_tail = ChecksumTail {
+        order,
+        value: ChecksumValue {
+            words: verifier.words,
+        },
+    };
+
+    let mut verifier_tail_bytes = [0; ChecksumTail::SIZE];
+    verifier_tail.to_bytes(&mut verifier_tail_bytes)?;
+
+    // Compute checksum.
+    hasher.reset(order)?;
+    hasher.update(&data[0..tail_offset])?;
+    hasher.update(&verifier_tail_bytes)?;
+    hasher.update(&data[tail_offset + ChecksumTail::SIZE..])?;
+
+    Ok(hasher.finalize()?)
+}
+
+/** Compute the embedded checksum of the `data` block and encode it in `data`.
+ *
+ * - `data` to checksum
+ * - `checksum` embedded checksum type
+ * - `verifier` of the block, included in checksum
+ * - `hasher` instance of the checksum function of `checksum` to use
+ * - `order` to use for checksum
+ *
+ * For [`ChecksumType::Zilog2`], the used bytes in the ZIL chain header must
+ * already be set.
+ *
+ * # Errors
+ *
+ * Returns [`EmbeddedChecksumError`] in case of encoding error.
+ */
+pub fn embedded_checksum(
+    data: &mut [u8],
+    checksum: ChecksumType,
+    verifier: &ChecksumValue,
+    hasher: &mut dyn Checksum,
+    order: EndianOrder,
+) -> Result<(), EmbeddedChecksumError> {
+    // Check length.
+    let length = data.len();
+    let min_length = match checksum {
+        ChecksumType::Zilog2 => ZIL_CHAIN_SIZE,
+        _ => ChecksumTail::SIZE,
+    };
+    if length < min_length {
+        return Err(EmbeddedChecksumError::InvalidLength { length });
+    }
+
+    let tail_offset = match embedded_tail_offset(checksum, length) {
+        Some(v) => v,
+        None => return Err(EmbeddedChecksumError::NotEmbedded { checksum }),
+    };
+
+    // Get checksummed length.
+    let length = match checksum {
+        ChecksumType::Zilog2 => match zil_used(data, order)? {
+            (_, Some(v)) => v,
+            (used, None) => return Err(EmbeddedChecksumError::InvalidUsed { used, length }),
+        },
+        _ => length,
+    };
+
+    // Compute checksum.
+    let computed = embedded_hash::<EmbeddedChecksumError>(
+        &data[0..length],
+        hasher,
+        tail_offset,
+        verifier,
+        order,
+    )?;
+
+    // Encode tail with checksum.
+    let tail = ChecksumTail {
+        order,
+        value: ChecksumValue { words: computed },
+    };
+    let tail_bytes = &mut data[tail_offset..tail_offset + ChecksumTail::SIZE];
+    tail.to_bytes(tail_bytes.try_into().unwrap())?;
+
+    Ok(())
+}
+
+/** Verify the embedded checksum of the `data` block.
+ *
+ * - `data` to checksum
+ * - `checksum` embedded checksum type
+ * - `verifier` of the block, included in checksum
+ * - `hasher` instance of the checksum function of `checksum` to use
+ *
+ * # Errors
+ *
+ * Returns [`EmbeddedVerifyError`] in case of decoding error or checksum
+ * mismatch.
+ */
+pub fn embedded_verify(
+    data: &[u8],
+    checksum: ChecksumType,
+    verifier: &ChecksumValue,
+    hasher: &mut dyn Checksum,
+) -> Result<(), EmbeddedVerifyError> {
+    // Check length.
+    let length = data.len();
+    let min_length = match checksum {
+        ChecksumType::Zilog2 => ZIL_CHAIN_SIZE,
+        _ => ChecksumTail::SIZE,
+    };
+    if length < min_length {
+        return Err(EmbeddedVerifyError::InvalidLength { length });
+    }
+
+    let tail_offset = match embedded_tail_offset(checksum, length) {
+        Some(v) => v,
+        None => return Err(EmbeddedVerifyError::NotEmbedded { checksum }),
+    };
+
+    // Decode ChecksumTail.
+    let tail = &data[tail_offset..tail_offset + ChecksumTail::SIZE];
+    let tail = ChecksumTail::from_bytes(tail.try_into().unwrap())?;
+
+    // Get checksummed length.
+    let length = match checksum {
+        ChecksumType::Zilog2 => match zil_used(data, tail.order)? {
+            (_, Some(v)) => v,
+            (used, None) => return Err(EmbeddedVerifyError::InvalidUsed { used, length }),
+        },
+        _ => length,
+    };
+
+    // Compute checksum.
+    let computed = embedded_hash::<EmbeddedVerifyError>(
+        &data[0..length],
+        hasher,
+        tail_offset,
+        verifier,
+        tail.order,
+    )?;
+
+    // Compare checksum.
+    if tail.value.words == computed {
+        Ok(())
+    } else {
+        Err(EmbeddedVerifyError::Mismatch {
+            checksum,
+            computed: ChecksumValue { words: computed },
+            expected: tail.value,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Embedded checksum error.
+#[derive(Debug)]
+pub enum EmbeddedChecksumError {
+    /// [`crate::phys::BinaryDecoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// [`crate::checksum::Checksum`] error.
+    Checksum {
+        /// Error.
+        err: ChecksumError,
+    },
+
+    /// [`ChecksumTail`] error.
+    ChecksumTail {
+        /// Error.
+        err: ChecksumTailEncodeError,
+    },
+
+    /// Invalid length.
+    InvalidLength {
+        /// Length.
+        length: usize,
+    },
+
+    /// Invalid used bytes in ZIL chain header.
+    InvalidUsed {
+        /// Used bytes.
+        used: u64,
+        /// Length.
+        length: usize,
+    },
+
+    /// Checksum is not embedded.
+    NotEmbedded {
+        /// Checksum.
+        checksum: ChecksumType,
+    },
+}
+
+impl From<BinaryDecodeError> for EmbeddedChecksumError {
+    fn from(value: BinaryDecodeError) -> Self {
+        EmbeddedChecksumError::Binary { err: value }
+    }
+}
+
+impl From<ChecksumError> for EmbeddedChecksumError {
+    fn from(value: ChecksumError) -> Self {
+        EmbeddedChecksumError::Checksum { err: value }
+    }
+}
+
+impl From<ChecksumTailEncodeError> for EmbeddedChecksumError {
+    fn from(value: ChecksumTailEncodeError) -> Self {
+        EmbeddedChecksumError::ChecksumTail { err: value }
+    }
+}
+
+impl fmt::Display for EmbeddedChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbeddedChecksumError::Binary { err } => {
+                write!(f, "Embedded checksum error | {err}")
+            }
+            EmbeddedChecksumError::Checksum { err } => {
+                write!(f, "Embedded checksum error | {err}")
+            }
+            EmbeddedChecksumError::ChecksumTail { err } => {
+                write!(f, "Embedded checksum error | {err}")
+            }
+            EmbeddedChecksumError::InvalidLength { length } => {
+                write!(f, "Embedded checksum error, invalid length {length}")
+            }
+            EmbeddedChecksumError::InvalidUsed { used, length } => {
+                write!(
+                    f,
+                    "Embedded checksum error, invalid used {used} for length {length}"
+                )
+            }
+            EmbeddedChecksumError::NotEmbedded { checksum } => {
+                write!(f, "Embedded checksum error, {checksum} is not embedded")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for EmbeddedChecksumError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EmbeddedChecksumError::Binary { err } => Some(err),
+            EmbeddedChecksumError::Checksum { err } => Some(err),
+            EmbeddedChecksumError::ChecksumTail { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Embedded verify error.
+#[derive(Debug)]
+pub enum EmbeddedVerifyError {
+    /// [`crate::phys::BinaryDecoder`] error.
+    BinaryDecode {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// [`crate::phys::BinaryEncoder`] error.
+    BinaryEncode {
+        /// Error.
+        err: BinaryEncodeError,
+    },
+
+    /// [`crate::checksum::Checksum`] error.
+    Checksum {
+        /// Error.
+        err: ChecksumError,
+    },
+
+    /// [`ChecksumTail`] error.
+    ChecksumTailDecode {
+        /// Error.
+        err: ChecksumTailDecodeError,
+    },
+
+    /// [`ChecksumTail`] error.
+    ChecksumTailEncode {
+        /// Error.
+        err: ChecksumTailEncodeError,
+    },
+
+    /// Invalid length.
+    InvalidLength {
+        /// Length.
+        length: usize,
+    },
+
+    /// Invalid used bytes in ZIL chain header.
+    InvalidUsed {
+        /// Used bytes.
+        used: u64,
+        /// Length.
+        length: usize,
+    },
+
+    /// Checksum mismatch.
+    Mismatch {
+        /// Embedded checksum.
+        checksum: ChecksumType,
+        /// Computed checksum value.
+        computed: ChecksumValue,
+        /// Expected checksum value, from the [`ChecksumTail`].
+        expected: ChecksumValue,
+    },
+
+    /// Checksum is not embedded.
+    NotEmbedded {
+        /// Checksum.
+        checksum: ChecksumType,
+    },
+}
+
+impl From<BinaryDecodeError> for EmbeddedVerifyError {
+    fn from(value: BinaryDecodeError) -> Self {
+        EmbeddedVerifyError::BinaryDecode { err: value }
+    }
+}
+
+impl From<BinaryEncodeError> for EmbeddedVerifyError {
+    fn from(value: BinaryEncodeError) -> Self {
+        EmbeddedVerifyError::BinaryEncode { err: value }
+    }
+}
+
+impl From<ChecksumError> for EmbeddedVerifyError {
+    fn from(value: ChecksumError) -> Self {
+        EmbeddedVerifyError::Checksum { err: value }
+    }
+}
+
+impl From<ChecksumTailDecodeError> for EmbeddedVerifyError {
+    fn from(value: ChecksumTailDecodeError) -> Self {
+        EmbeddedVerifyError::ChecksumTailDecode { err: value }
+    }
+}
+
+impl From<ChecksumTailEncodeError> for EmbeddedVerifyError {
+    fn from(value: ChecksumTailEncodeError) -> Self {
+        EmbeddedVerifyError::ChecksumTailEncode { err: value }
+    }
+}
+
+impl fmt::Display for EmbeddedVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmbeddedVerifyError::BinaryDecode { err } => {
+                write!(f, "Embedded verify error | {err}")
+            }
+            EmbeddedVerifyError::BinaryEncode { err } => {
+                write!(f, "Embedded verify error | {err}")
+            }
+            EmbeddedVerifyError::Checksum { err } => {
+                write!(f, "Embedded verify error | {err}")
+            }
+            EmbeddedVerifyError::ChecksumTailDecode { err } => {
+                write!(f, "Embedded verify error | {err}")
+            }
+            EmbeddedVerifyError::ChecksumTailEncode { err } => {
+                write!(f, "Embedded verify error | {err}")
+            }
+            EmbeddedVerifyError::InvalidLength { length } => {
+                write!(f, "Embedded verify error, invalid length {length}")
+            }
+            EmbeddedVerifyError::InvalidUsed { used, length } => {
+                write!(
+                    f,
+                    "Embedded verify error, invalid used {used} for length {length}"
+                )
+            }
+            EmbeddedVerifyError::Mismatch {
+                checksum,
+                computed,
+                expected,
+            } => write!(
+                f,
+                "Embedded verify {checksum} mismatch, computed {:#016x?} expected {:#016x?}",
+                computed.words, expected.words,
+            ),
+            EmbeddedVerifyError::NotEmbedded { checksum } => {
+                write!(f, "Embedded verify error, {checksum} is not embedded")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for EmbeddedVerifyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            EmbeddedVerifyError::BinaryDecode { err } => Some(err),
+            EmbeddedVerifyError::BinaryEncode { err } => Some(err),
+            EmbeddedVerifyError::Checksum { err } => Some(err),
+            EmbeddedVerifyError::ChecksumTailDecode { err } => Some(err),
+            EmbeddedVerifyError::ChecksumTailEncode { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::checksum::{
+        embedded_checksum, embedded_verify, gang_header_verifier, label_checksum, Checksum,
+        EmbeddedChecksumError, EmbeddedVerifyError, Fletcher2, Fletcher2Implementation, Fletcher4,
+        Fletcher4Implementation, Sha256, Sha256Implementation,
+    };
+    use crate::phys::{
+        BlockPointerRegular, ChecksumTail, ChecksumType, ChecksumValue, CompressionType, DmuType,
+        Dva, EndianOrder,
+    };
+
+    const VERIFIER: ChecksumValue = ChecksumValue {
+        words: [0x0123456789abcdef, 0xfedcba9876543210, 54, 7],
+    };
+
+    /// Creates an instance of the checksum function of `checksum`.
+    fn hasher(checksum: ChecksumType) -> Box<dyn Checksum> {
+        match checksum {
+            ChecksumType::Label | ChecksumType::GangHeader => {
+                Box::new(Sha256::new(Sha256Implementation::Generic).unwrap())
+            }
+            ChecksumType::Zilog => {
+                Box::new(Fletcher2::new(Fletcher2Implementation::Generic).unwrap())
+            }
+            _ => Box::new(Fletcher4::new(Fletcher4Implementation::Generic).unwrap()),
+        }
+    }
+
+    #[test]
+    fn embedded() {
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            for checksum in [
+                ChecksumType::Label,
+                ChecksumType::GangHeader,
+                ChecksumType::Zilog,
+                ChecksumType::Zilog2,
+            ] {
+                let mut data: Vec<u8> = (0..8192).map(|x| (x * 13) as u8).collect();
+
+                // Set used bytes of ZIL chain header.
+                if checksum == ChecksumType::Zilog2 {
+                    let used = match order {
+                        EndianOrder::Big => 3000u64.to_be_bytes(),
+                        EndianOrder::Little => 3000u64.to_le_bytes(),
+                    };
+                    data[136..144].copy_from_slice(&used);
+                }
+
+                embedded_checksum(
+                    &mut data,
+                    checksum,
+                    &VERIFIER,
+                    hasher(checksum).as_mut(),
+                    order,
+                )
+                .unwrap();
+                embedded_verify(&data, checksum, &VERIFIER, hasher(checksum).as_mut()).unwrap();
+
+                // Wrong verifier.
+                let verifier = ChecksumValue {
+                    words: [0x0123456789abcdef, 0xfedcba9876543210, 54, 8],
+                };
+                assert!(matches!(
+                    embedded_verify(&data, checksum, &verifier, hasher(checksum).as_mut())
+                        .unwrap_err(),
+                    EmbeddedVerifyError::Mismatch { .. }
+                ));
+
+                // Corrupted data.
+                data[1000] ^= 1;
+                match embedded_verify(&data, checksum, &VERIFIER, hasher(checksum).as_mut())
+                    .unwrap_err()
+                {
+                    EmbeddedVerifyError::Mismatch {
+                        checksum: mismatch, ..
+                    } => assert_eq!(mismatch, checksum),
+                    _ => panic!("expected mismatch"),
+                }
+                data[1000] ^= 1;
+
+                // Unused bytes of ZIL chain are not checksummed.
+                if checksum == ChecksumType::Zilog2 {
+                    data[6000] ^= 1;
+                    embedded_verify(&data, checksum, &VERIFIER, hasher(checksum).as_mut()).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn embedded_label() {
+        let mut data: Vec<u8> = (0..4096).map(|x| (x * 3) as u8).collect();
+        let mut sha256 = Sha256::new(Sha256Implementation::Generic).unwrap();
+
+        // Label verifier is the byte offset.
+        label_checksum(&mut data, 32, &mut sha256, EndianOrder::Little).unwrap();
+        let verifier = ChecksumValue {
+            words: [32 * 512, 0, 0, 0],
+        };
+        embedded_verify(
+            &data,
+            ChecksumType::Label,
+            &verifier,
+            hasher(ChecksumType::Label).as_mut(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn embedded_zilog2() {
+        let mut data = vec![0; 4096];
+        let order = EndianOrder::Little;
+        data[136..144].copy_from_slice(&200u64.to_le_bytes());
+        embedded_checksum(
+            &mut data,
+            ChecksumType::Zilog2,
+            &VERIFIER,
+            hasher(ChecksumType::Zilog2).as_mut(),
+            order,
+        )
+        .unwrap();
+
+        // Checksum tail is in the ZIL chain header.
+        let tail = ChecksumTail::from_bytes(data[144..184].try_into().unwrap()).unwrap();
+        assert_eq!(tail.order, order);
+
+        // Checksum is computed over verifier.
+        let mut expected = data.clone();
+        ChecksumTail {
+            order,
+            value: ChecksumValue {
+                words: VERIFIER.words,
+            },
+        }
+        .to_bytes((&mut expected[144..184]).try_into().unwrap())
+        .unwrap();
+        let mut fletcher4 = Fletcher4::new(Fletcher4Implementation::Generic).unwrap();
+        assert_eq!(tail.value.words, fletcher4.hash(&expected, order).unwrap());
+    }
+
+    #[test]
+    fn embedded_error() {
+        let data = [0; 4096];
+
+        // Not embedded.
+        assert!(matches!(
+            embedded_verify(
+                &data,
+                ChecksumType::Fletcher4,
+                &VERIFIER,
+                hasher(ChecksumType::Fletcher4).as_mut()
+            )
+            .unwrap_err(),
+            EmbeddedVerifyError::NotEmbedded {
+                checksum: ChecksumType::Fletcher4
+            }
+        ));
+
+        // Too short.
+        assert!(matches!(
+            embedded_verify(
+                &data[0..39],
+                ChecksumType::Zilog,
+                &VERIFIER,
+                hasher(ChecksumType::Zilog).as_mut()
+            )
+            .unwrap_err(),
+            EmbeddedVerifyError::InvalidLength { length: 39 }
+        ));
+        assert!(matches!(
+            embedded_verify(
+                &data[0..183],
+                ChecksumType::Zilog2,
+                &VERIFIER,
+                hasher(ChecksumType::Zilog2).as_mut()
+            )
+            .unwrap_err(),
+            EmbeddedVerifyError::InvalidLength { length: 183 }
+        ));
+
+        // Missing magic.
+        assert!(matches!(
+            embedded_verify(
+                &data,
+                ChecksumType::GangHeader,
+                &VERIFIER,
+                hasher(ChecksumType::GangHeader).as_mut()
+            )
+            .unwrap_err(),
+            EmbeddedVerifyError::ChecksumTailDecode { .. }
+        ));
+
+        // Used bytes larger than block.
+        let mut data = [0; 4096];
+        data[136..144].copy_from_slice(&4097u64.to_le_bytes());
+        assert!(matches!(
+            embedded_checksum(
+                &mut data,
+                ChecksumType::Zilog2,
+                &VERIFIER,
+                hasher(ChecksumType::Zilog2).as_mut(),
+                EndianOrder::Little
+            )
+            .unwrap_err(),
+            EmbeddedChecksumError::InvalidUsed {
+                used: 4097,
+                length: 4096
+            }
+        ));
+    }
+
+    #[test]
+    fn gang_header() {
+        let mut ptr = BlockPointerRegular {
+            checksum_type: ChecksumType::Fletcher4,
+            checksum_value: ChecksumValue { words: [0; 4] },
+            compression: CompressionType::Off,
+            dedup: false,
+            dmu: DmuType::None,
+            dvas: [None, None, None],
+            order: EndianOrder::Little,
+            fill_count: 1,
+            level: 0,
+            logical_birth_txg: 10,
+            logical_sectors: 1,
+            physical_birth_txg: 0,
+            physical_sectors: 1,
+        };
+        assert!(gang_header_verifier(&ptr).is_none());
+
+        ptr.dvas[0] = Some(Dva {
+            allocated: 1,
+            offset: 100,
+            is_gang: true,
+            vdev: 2,
+        });
+        assert_eq!(
+            gang_header_verifier(&ptr).unwrap().words,
+            [2, 100 * 512, 10, 0]
+        );
+
+        ptr.physical_birth_txg = 11;
+        assert_eq!(
+            gang_header_verifier(&ptr).unwrap().words,
+            [2, 100 * 512, 11, 0]
+        );
+    }
+}
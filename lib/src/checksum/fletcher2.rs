@@ -1607,13 +1607,13 @@ mod tests {
     }
 
     fn test_optional_implementation(implementation: Fletcher2Implementation) {
-        let supported = match Fletcher2::new(implementation) {
-            _e @ Err(ChecksumError::Unsupported {
+        let supported = !matches!(
+            Fletcher2::new(implementation),
+            Err(ChecksumError::Unsupported {
                 checksum: _,
                 implementation: _,
-            }) => false,
-            _ => true,
-        };
+            })
+        );
 
         if supported {
             test_required_implementation(implementation);
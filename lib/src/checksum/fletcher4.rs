@@ -324,6 +324,9 @@ pub struct Fletcher4 {
     /// Ongoing checksum.
     state: [u64; FLETCHER_4_U64_COUNT * FLETCHER_4_MAX_SIMD_WIDTH],
 
+    /// Number of bytes updated since reset.
+    length: u64,
+
     /// Byte order of input data.
     order: EndianOrder,
 
@@ -334,6 +337,89 @@ pub struct Fletcher4 {
     update_blocks: Fletcher4UpdateBlock,
 }
 
+/** Intermediate [`Fletcher4`] state, for combining checksums of segments.
+ *
+ * - A large block can be split into consecutive segments, which are
+ *   checksummed in parallel, and then combined in order.
+ * - Every segment, except the last, must be a multiple of 4 bytes, because
+ *   trailing bytes that do not fill a 4 byte word are not checksummed.
+ * - The state is independent of the [`EndianOrder`], but all segments must
+ *   be checksummed with the same order.
+ */
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Fletcher4State {
+    /// Sum of words.
+    pub a: u64,
+
+    /// Sum of `a`.
+    pub b: u64,
+
+    /// Sum of `b`.
+    pub c: u64,
+
+    /// Sum of `c`.
+    pub d: u64,
+
+    /// Number of 4 byte words.
+    pub words: u64,
+}
+
+impl Fletcher4State {
+    /** Combine the state of this segment, with the state of the `next`
+     * segment that follows it.
+     *
+     * For `n` words in `next`, each word of this segment is added `n` more
+     * times to `b`, `n (n + 1) / 2` more times to `c`, and
+     * `n (n + 1) (n + 2) / 6` more times to `d`.
+     */
+    pub fn combine(&self, next: &Fletcher4State) -> Fletcher4State {
+        let n = next.words;
+
+        // Binomial coefficients, dividing the even and multiple of three
+        // factors, so the wrapping multiplication is exact modulo 2^64.
+        let mut f = [n, n.wrapping_add(1), n.wrapping_add(2)];
+        Fletcher4State::divide_first_multiple(&mut f[0..2], 2);
+        let n2 = f[0].wrapping_mul(f[1]);
+
+        let mut f = [n, n.wrapping_add(1), n.wrapping_add(2)];
+        Fletcher4State::divide_first_multiple(&mut f, 2);
+        Fletcher4State::divide_first_multiple(&mut f, 3);
+        let n3 = f[0].wrapping_mul(f[1]).wrapping_mul(f[2]);
+
+        Fletcher4State {
+            a: self.a.wrapping_add(next.a),
+            b: self
+                .b
+                .wrapping_add(n.wrapping_mul(self.a))
+                .wrapping_add(next.b),
+            c: self
+                .c
+                .wrapping_add(n.wrapping_mul(self.b))
+                .wrapping_add(n2.wrapping_mul(self.a))
+                .wrapping_add(next.c),
+            d: self
+                .d
+                .wrapping_add(n.wrapping_mul(self.c))
+                .wrapping_add(n2.wrapping_mul(self.b))
+                .wrapping_add(n3.wrapping_mul(self.a))
+                .wrapping_add(next.d),
+            words: self.words.wrapping_add(n),
+        }
+    }
+
+    /// Get the checksum value.
+    pub fn value(&self) -> [u64; 4] {
+        [self.a, self.b, self.c, self.d]
+    }
+
+    /// Divide the first value of `f` that is a multiple of `m`.
+    fn divide_first_multiple(f: &mut [u64], m: u64) {
+        if let Some(v) = f.iter_mut().find(|v| **v % m == 0) {
+            *v /= m;
+        }
+    }
+}
+
 /** Sum v and then multiply by m.
  *
  * ```text
@@ -387,12 +473,31 @@ impl Fletcher4 {
             buffer_fill: 0,
             buffer: Default::default(),
             state: Default::default(),
+            length: 0,
             order: EndianOrder::Little,
             impl_ctx: ctx,
             update_blocks: ctx.update_blocks_little,
         })
     }
 
+    /** Export the intermediate state, to combine with other segments.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] in case of error.
+     */
+    pub fn state(&mut self) -> Result<Fletcher4State, ChecksumError> {
+        let [a, b, c, d] = self.finalize()?;
+
+        Ok(Fletcher4State {
+            a,
+            b,
+            c,
+            d,
+            words: self.length / FLETCHER_4_BLOCK_SIZE as u64,
+        })
+    }
+
     /** Finish a check that is one stream.
      *
      * For one stream, this is a NO-OP.
@@ -1469,6 +1574,7 @@ impl Checksum for Fletcher4 {
         self.buffer_fill = 0;
         self.buffer = Default::default();
         self.state = Default::default();
+        self.length = 0;
 
         self.order = order;
         self.update_blocks = match self.order {
@@ -1482,6 +1588,7 @@ impl Checksum for Fletcher4 {
     fn update(&mut self, data: &[u8]) -> Result<(), ChecksumError> {
         // Make data pointer mutable, in case of self.buffer_fill.
         let mut data = data;
+        self.length += data.len() as u64;
 
         // If block has some data, fill that up first.
         if self.buffer_fill > 0 {
@@ -1556,7 +1663,9 @@ mod tests {
 
     use core::cmp;
 
-    use crate::checksum::{Checksum, ChecksumError, Fletcher4, Fletcher4Implementation};
+    use crate::checksum::{
+        Checksum, ChecksumError, Fletcher4, Fletcher4Implementation, Fletcher4State,
+    };
     use crate::phys::EndianOrder;
 
     /** 128 byte random data.
@@ -1854,19 +1963,141 @@ mod tests {
     }
 
     fn test_optional_implementation(implementation: Fletcher4Implementation) {
-        let supported = match Fletcher4::new(implementation) {
-            _e @ Err(ChecksumError::Unsupported {
+        let supported = !matches!(
+            Fletcher4::new(implementation),
+            Err(ChecksumError::Unsupported {
                 checksum: _,
                 implementation: _,
-            }) => false,
-            _ => true,
-        };
+            })
+        );
 
         if supported {
             test_required_implementation(implementation);
         }
     }
 
+    /** Combine the states of `segments` consecutive segments of `data`,
+     * checksummed in parallel.
+     */
+    fn hash_parallel(
+        implementation: Fletcher4Implementation,
+        data: &[u8],
+        order: EndianOrder,
+        segments: usize,
+    ) -> [u64; 4] {
+        // Segment size is a multiple of 4 bytes.
+        let segment_size = (data.len() / segments) & !3;
+
+        let states: Vec<Fletcher4State> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..segments)
+                .map(|i| {
+                    let start = i * segment_size;
+                    let end = if i == segments - 1 {
+                        data.len()
+                    } else {
+                        start + segment_size
+                    };
+                    let segment = &data[start..end];
+
+                    scope.spawn(move || {
+                        let mut h = Fletcher4::new(implementation).unwrap();
+                        h.reset(order).unwrap();
+                        h.update(segment).unwrap();
+                        h.state().unwrap()
+                    })
+                })
+                .collect();
+
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        states
+            .iter()
+            .fold(Fletcher4State::default(), |acc, state| acc.combine(state))
+            .value()
+    }
+
+    fn test_combine_implementation(implementation: Fletcher4Implementation) {
+        let mut h = match Fletcher4::new(implementation) {
+            Ok(h) => h,
+            Err(_) => return,
+        };
+
+        // Data is 256 KiB of the test vector, with a trailing partial word.
+        let data: Vec<u8> = TEST_VECTOR_A
+            .iter()
+            .cycle()
+            .take(256 * 1024 + 3)
+            .copied()
+            .collect();
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            // Split at two points.
+            for (x, y) in [
+                (0, 0),
+                (0, 4),
+                (4, 8),
+                (12, 12),
+                (100, 1000),
+                (4096, 131072),
+            ] {
+                h.reset(order).unwrap();
+                h.update(&data[0..x]).unwrap();
+                let s0 = h.state().unwrap();
+
+                h.reset(order).unwrap();
+                h.update(&data[x..y]).unwrap();
+                let s1 = h.state().unwrap();
+
+                h.reset(order).unwrap();
+                h.update(&data[y..]).unwrap();
+                let s2 = h.state().unwrap();
+
+                let serial = h.hash(&data, order).unwrap();
+
+                // Combine is associative.
+                assert_eq!(s0.combine(&s1).combine(&s2).value(), serial);
+                assert_eq!(s0.combine(&s1.combine(&s2)).value(), serial);
+
+                // Empty state is the identity.
+                assert_eq!(Fletcher4State::default().combine(&s0), s0);
+                assert_eq!(s0.combine(&Fletcher4State::default()), s0);
+            }
+
+            // Parallel segments.
+            let serial = h.hash(&data, order).unwrap();
+            for segments in [1, 2, 3, 7, 16] {
+                assert_eq!(
+                    hash_parallel(implementation, &data, order, segments),
+                    serial
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fletcher4_combine() {
+        for implementation in Fletcher4Implementation::all() {
+            test_combine_implementation(*implementation);
+        }
+    }
+
+    #[test]
+    fn fletcher4_combine_large() {
+        // 16 MiB record, split across 16 threads.
+        let data: Vec<u8> = (0..16 * 1024 * 1024).map(|x| (x * 31) as u8).collect();
+
+        let implementation = Fletcher4Implementation::Generic;
+        let mut h = Fletcher4::new(implementation).unwrap();
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            assert_eq!(
+                hash_parallel(implementation, &data, order, 16),
+                h.hash(&data, order).unwrap()
+            );
+        }
+    }
+
     #[test]
     fn fletcher4_all() {
         assert_eq!(Fletcher4Implementation::all().len(), 8);
@@ -0,0 +1,515 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Checksum dispatch by [`ChecksumType`].
+ *
+ * Each checksum has several implementations, and which one is the fastest
+ * depends on the CPU. Like OpenZFS, the supported implementations are
+ * benchmarked once per process, and the fastest is used from then on.
+ */
+use core::fmt;
+
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::checksum::{
+    Blake3, Blake3Implementation, Checksum, ChecksumError, Edonr, Fletcher2,
+    Fletcher2Implementation, Fletcher4, Fletcher4Implementation, Sha256, Sha256Implementation,
+    Sha512_256, Sha512_256Implementation, Skein,
+};
+use crate::phys::{ChecksumType, ENDIAN_ORDER_NATIVE};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Size of the data hashed by each benchmark iteration.
+const BENCHMARK_SIZE: usize = 16 * 1024;
+
+/// Minimum duration of the benchmark of each implementation.
+const BENCHMARK_DURATION: Duration = Duration::from_millis(1);
+
+/// Number of [`ChecksumType`] values, and cached [`Checksum`] instances.
+const CHECKSUM_TYPE_COUNT: usize = ChecksumType::Blake3 as usize + 1;
+
+/// Fastest implementations, benchmarked on first use.
+static FASTEST: OnceLock<ChecksumImplementations> = OnceLock::new();
+
+/** Benchmark a [`Checksum`], and return the number of bytes per second.
+ *
+ * Returns [`None`] if the implementation is not supported.
+ */
+fn benchmark(checksum: Result<impl Checksum, ChecksumError>, data: &[u8]) -> Option<f64> {
+    let mut checksum = checksum.ok()?;
+
+    // Warm up.
+    checksum.hash(data, ENDIAN_ORDER_NATIVE).ok()?;
+
+    let start = Instant::now();
+    let mut bytes = 0;
+
+    loop {
+        checksum.hash(data, ENDIAN_ORDER_NATIVE).ok()?;
+        bytes += data.len();
+
+        let elapsed = start.elapsed();
+        if elapsed >= BENCHMARK_DURATION {
+            return Some(bytes as f64 / elapsed.as_secs_f64());
+        }
+    }
+}
+
+/** Benchmark all implementations, and return the fastest.
+ *
+ * `generic` is used if no other implementation is faster.
+ */
+fn fastest<I: Copy, C: Checksum>(
+    all: &[I],
+    generic: I,
+    new: impl Fn(I) -> Result<C, ChecksumError>,
+    data: &[u8],
+) -> I {
+    let mut best = generic;
+    let mut best_speed = 0.0;
+
+    for implementation in all {
+        if let Some(speed) = benchmark(new(*implementation), data) {
+            if speed > best_speed {
+                best = *implementation;
+                best_speed = speed;
+            }
+        }
+    }
+
+    best
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Implementation to use for each [`ChecksumType`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChecksumImplementations {
+    /// [`ChecksumType::Blake3`].
+    pub blake3: Blake3Implementation,
+
+    /// [`ChecksumType::Fletcher2`] and [`ChecksumType::Zilog`].
+    pub fletcher2: Fletcher2Implementation,
+
+    /// [`ChecksumType::Fletcher4`] and [`ChecksumType::Zilog2`].
+    pub fletcher4: Fletcher4Implementation,
+
+    /// [`ChecksumType::Sha256`], [`ChecksumType::Label`], and
+    /// [`ChecksumType::GangHeader`].
+    pub sha256: Sha256Implementation,
+
+    /// [`ChecksumType::Sha512_256`].
+    pub sha512_256: Sha512_256Implementation,
+}
+
+impl ChecksumImplementations {
+    /// Generic implementations, which are supported by every CPU.
+    pub fn generic() -> ChecksumImplementations {
+        ChecksumImplementations {
+            blake3: Blake3Implementation::Generic,
+            fletcher2: Fletcher2Implementation::Generic,
+            fletcher4: Fletcher4Implementation::Generic,
+            sha256: Sha256Implementation::Generic,
+            sha512_256: Sha512_256Implementation::Generic,
+        }
+    }
+
+    /** Fastest supported implementations.
+     *
+     * Every supported implementation is benchmarked on the first call, and the
+     * result is reused by later calls.
+     */
+    pub fn fastest() -> ChecksumImplementations {
+        *FASTEST.get_or_init(|| {
+            let mut data = vec![0; BENCHMARK_SIZE];
+            for (i, x) in data.iter_mut().enumerate() {
+                *x = i as u8;
+            }
+
+            ChecksumImplementations {
+                blake3: fastest(
+                    Blake3Implementation::all(),
+                    Blake3Implementation::Generic,
+                    |i| Blake3::new(i, None),
+                    &data,
+                ),
+                fletcher2: fastest(
+                    Fletcher2Implementation::all(),
+                    Fletcher2Implementation::Generic,
+                    Fletcher2::new,
+                    &data,
+                ),
+                fletcher4: fastest(
+                    Fletcher4Implementation::all(),
+                    Fletcher4Implementation::Generic,
+                    Fletcher4::new,
+                    &data,
+                ),
+                sha256: fastest(
+                    Sha256Implementation::all(),
+                    Sha256Implementation::Generic,
+                    Sha256::new,
+                    &data,
+                ),
+                sha512_256: fastest(
+                    Sha512_256Implementation::all(),
+                    Sha512_256Implementation::Generic,
+                    Sha512_256::new,
+                    &data,
+                ),
+            }
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Registry of [`Checksum`] implementations for every [`ChecksumType`].
+ *
+ * [`ChecksumType::Skein`], [`ChecksumType::Edonr`], and
+ * [`ChecksumType::Blake3`] are salted with the per pool checksum salt, which
+ * is set with [`ChecksumRegistry::with_salt`].
+ *
+ * [`ChecksumRegistry::checksum`] creates one instance per [`ChecksumType`],
+ * and reuses it, so that verifying a block does not allocate, or repeat the
+ * key setup of a salted checksum.
+ */
+pub struct ChecksumRegistry {
+    implementations: ChecksumImplementations,
+    salt: Option<[u8; 32]>,
+    cache: [Option<Box<dyn Checksum>>; CHECKSUM_TYPE_COUNT],
+}
+
+impl fmt::Debug for ChecksumRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Checksum instances do not implement Debug.
+        f.debug_struct("ChecksumRegistry")
+            .field("implementations", &self.implementations)
+            .field("salt", &self.salt)
+            .finish()
+    }
+}
+
+impl Default for ChecksumRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChecksumRegistry {
+    /// Create a new checksum registry, with the fastest implementations.
+    pub fn new() -> ChecksumRegistry {
+        ChecksumRegistry {
+            implementations: ChecksumImplementations::fastest(),
+            salt: None,
+            cache: Default::default(),
+        }
+    }
+
+    /** Create a new checksum registry, with the given implementations.
+     *
+     * Used to override the benchmark, for example to test a specific
+     * implementation.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] if an implementation is not supported.
+     */
+    pub fn with_implementations(
+        implementations: ChecksumImplementations,
+    ) -> Result<ChecksumRegistry, ChecksumError> {
+        Blake3::new(implementations.blake3, None)?;
+        Fletcher2::new(implementations.fletcher2)?;
+        Fletcher4::new(implementations.fletcher4)?;
+        Sha256::new(implementations.sha256)?;
+        Sha512_256::new(implementations.sha512_256)?;
+
+        Ok(ChecksumRegistry {
+            implementations,
+            salt: None,
+            cache: Default::default(),
+        })
+    }
+
+    /// Set the per pool checksum salt, stored in the MOS.
+    pub fn with_salt(mut self, salt: [u8; 32]) -> ChecksumRegistry {
+        self.salt = Some(salt);
+        self.cache = Default::default();
+        self
+    }
+
+    /// Get the implementations used by the registry.
+    pub fn implementations(&self) -> &ChecksumImplementations {
+        &self.implementations
+    }
+
+    /** Gets a [`Checksum`] for a [`ChecksumType`].
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError::Unsupported`] for [`ChecksumType::Inherit`],
+     * [`ChecksumType::On`], [`ChecksumType::Off`], and
+     * [`ChecksumType::NoParity`], which do not have a checksum function.
+     *
+     * Returns [`ChecksumError::MissingSalt`] for salted checksums, if the
+     * salt was not set with [`ChecksumRegistry::with_salt`].
+     */
+    pub fn checksum_for(&self, checksum: ChecksumType) -> Result<Box<dyn Checksum>, ChecksumError> {
+        let implementations = &self.implementations;
+
+        match checksum {
+            ChecksumType::Inherit
+            | ChecksumType::On
+            | ChecksumType::Off
+            | ChecksumType::NoParity => Err(ChecksumError::Unsupported {
+                checksum,
+                implementation: "registry",
+            }),
+            ChecksumType::Fletcher2 | ChecksumType::Zilog => {
+                Ok(Box::new(Fletcher2::new(implementations.fletcher2)?))
+            }
+            ChecksumType::Fletcher4 | ChecksumType::Zilog2 => {
+                Ok(Box::new(Fletcher4::new(implementations.fletcher4)?))
+            }
+            ChecksumType::Sha256 | ChecksumType::Label | ChecksumType::GangHeader => {
+                Ok(Box::new(Sha256::new(implementations.sha256)?))
+            }
+            ChecksumType::Sha512_256 => Ok(Box::new(Sha512_256::new(implementations.sha512_256)?)),
+            ChecksumType::Skein => Ok(Box::new(Skein::new(self.salt(checksum)?))),
+            ChecksumType::Edonr => Ok(Box::new(Edonr::new(self.salt(checksum)?))),
+            ChecksumType::Blake3 => Ok(Box::new(Blake3::new(
+                implementations.blake3,
+                Some(self.salt(checksum)?),
+            )?)),
+        }
+    }
+
+    /** Gets the cached [`Checksum`] for a [`ChecksumType`], and creates it
+     * on first use.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] like [`ChecksumRegistry::checksum_for`].
+     */
+    pub fn checksum(&mut self, checksum: ChecksumType) -> Result<&mut dyn Checksum, ChecksumError> {
+        let index = usize::from(u8::from(checksum));
+
+        let cached = match self.cache[index].take() {
+            Some(v) => v,
+            None => self.checksum_for(checksum)?,
+        };
+
+        Ok(self.cache[index].insert(cached).as_mut())
+    }
+
+    /// Get the salt for a salted [`ChecksumType`].
+    fn salt(&self, checksum: ChecksumType) -> Result<&[u8; 32], ChecksumError> {
+        self.salt
+            .as_ref()
+            .ok_or(ChecksumError::MissingSalt { checksum })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::checksum::{
+        Blake3, Blake3Implementation, Checksum, ChecksumError, ChecksumImplementations,
+        ChecksumRegistry, Fletcher2, Fletcher4, Sha256, Sha512_256,
+    };
+    use crate::phys::{ChecksumType, EndianOrder};
+
+    /// Every [`ChecksumType`] with a checksum function.
+    const CHECKSUMS: [ChecksumType; 11] = [
+        ChecksumType::Label,
+        ChecksumType::GangHeader,
+        ChecksumType::Zilog,
+        ChecksumType::Fletcher2,
+        ChecksumType::Fletcher4,
+        ChecksumType::Sha256,
+        ChecksumType::Zilog2,
+        ChecksumType::Sha512_256,
+        ChecksumType::Skein,
+        ChecksumType::Edonr,
+        ChecksumType::Blake3,
+    ];
+
+    #[test]
+    fn fastest() {
+        let fastest = ChecksumImplementations::fastest();
+
+        // Selected implementations are supported.
+        assert!(ChecksumRegistry::with_implementations(fastest).is_ok());
+
+        // Benchmark is done once.
+        assert_eq!(ChecksumImplementations::fastest(), fastest);
+        assert_eq!(ChecksumRegistry::new().implementations(), &fastest);
+    }
+
+    #[test]
+    fn checksum_for() {
+        let data: Vec<u8> = (0..4096).map(|x| x as u8).collect();
+        let salt = [7; 32];
+
+        let fastest = ChecksumRegistry::new().with_salt(salt);
+        let generic = ChecksumRegistry::with_implementations(ChecksumImplementations::generic())
+            .unwrap()
+            .with_salt(salt);
+
+        // Fastest and generic implementations agree.
+        for checksum in CHECKSUMS {
+            for order in [EndianOrder::Big, EndianOrder::Little] {
+                assert_eq!(
+                    fastest
+                        .checksum_for(checksum)
+                        .unwrap()
+                        .hash(&data, order)
+                        .unwrap(),
+                    generic
+                        .checksum_for(checksum)
+                        .unwrap()
+                        .hash(&data, order)
+                        .unwrap(),
+                );
+            }
+        }
+
+        // Registry hands out the right checksum.
+        let order = EndianOrder::Little;
+        let expected = [
+            (
+                ChecksumType::Zilog,
+                Fletcher2::new(generic.implementations().fletcher2)
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+            ),
+            (
+                ChecksumType::Zilog2,
+                Fletcher4::new(generic.implementations().fletcher4)
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+            ),
+            (
+                ChecksumType::Label,
+                Sha256::new(generic.implementations().sha256)
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+            ),
+            (
+                ChecksumType::Sha512_256,
+                Sha512_256::new(generic.implementations().sha512_256)
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+            ),
+            (
+                ChecksumType::Blake3,
+                Blake3::new(Blake3Implementation::Generic, Some(&salt))
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+            ),
+        ];
+
+        for (checksum, value) in expected {
+            assert_eq!(
+                generic
+                    .checksum_for(checksum)
+                    .unwrap()
+                    .hash(&data, order)
+                    .unwrap(),
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn checksum() {
+        let data: Vec<u8> = (0..4096).map(|x| (x * 3) as u8).collect();
+        let mut registry = ChecksumRegistry::new().with_salt([7; 32]);
+
+        for checksum in CHECKSUMS {
+            let expected = registry
+                .checksum_for(checksum)
+                .unwrap()
+                .hash(&data, EndianOrder::Little)
+                .unwrap();
+
+            // Cached instance agrees, and is reset for each use.
+            for _ in 0..2 {
+                let cached = registry.checksum(checksum).unwrap();
+                cached.reset(EndianOrder::Big).unwrap();
+                cached.update(&data[0..100]).unwrap();
+                assert_eq!(cached.hash(&data, EndianOrder::Little).unwrap(), expected);
+            }
+
+            // Same instance is reused.
+            let first = registry.checksum(checksum).unwrap() as *mut dyn Checksum as *mut u8;
+            let second = registry.checksum(checksum).unwrap() as *mut dyn Checksum as *mut u8;
+            assert_eq!(first, second);
+        }
+
+        // Salted instances are recreated with the new salt.
+        let salted = registry
+            .checksum(ChecksumType::Skein)
+            .unwrap()
+            .hash(&data, EndianOrder::Little)
+            .unwrap();
+        let mut registry = registry.with_salt([8; 32]);
+        assert_ne!(
+            registry
+                .checksum(ChecksumType::Skein)
+                .unwrap()
+                .hash(&data, EndianOrder::Little)
+                .unwrap(),
+            salted
+        );
+
+        // Errors are not cached.
+        let mut unsalted = ChecksumRegistry::new();
+        for _ in 0..2 {
+            assert!(matches!(
+                unsalted.checksum(ChecksumType::Edonr),
+                Err(ChecksumError::MissingSalt { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn checksum_for_error() {
+        let registry = ChecksumRegistry::new();
+
+        for checksum in [
+            ChecksumType::Inherit,
+            ChecksumType::On,
+            ChecksumType::Off,
+            ChecksumType::NoParity,
+        ] {
+            assert!(matches!(
+                registry.checksum_for(checksum),
+                Err(ChecksumError::Unsupported {
+                    checksum: c,
+                    implementation: _,
+                }) if c == checksum
+            ));
+        }
+
+        // Salted checksums need a salt.
+        for checksum in [
+            ChecksumType::Skein,
+            ChecksumType::Edonr,
+            ChecksumType::Blake3,
+        ] {
+            assert!(matches!(
+                registry.checksum_for(checksum),
+                Err(ChecksumError::MissingSalt { checksum: c }) if c == checksum
+            ));
+        }
+    }
+}
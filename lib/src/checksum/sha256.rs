@@ -2889,13 +2889,13 @@ mod tests {
     }
 
     fn test_optional_implementation(implementation: Sha256Implementation) {
-        let supported = match Sha256::new(implementation) {
-            _e @ Err(ChecksumError::Unsupported {
+        let supported = !matches!(
+            Sha256::new(implementation),
+            Err(ChecksumError::Unsupported {
                 checksum: _,
                 implementation: _,
-            }) => false,
-            _ => true,
-        };
+            })
+        );
 
         if supported {
             test_required_implementation(implementation);
@@ -0,0 +1,972 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! SHA-512/256 checksum.
+ *
+ * SHA-512/256 is SHA-512 with different initial values, and the result
+ * truncated to the first 256 bits. On 64 bit CPUs, it is faster than SHA-256,
+ * because it processes 128 byte blocks with 64 bit operations.
+ */
+use crate::checksum::{Checksum, ChecksumError};
+use crate::phys::{ChecksumType, EndianOrder};
+
+use core::cmp;
+use core::fmt;
+use core::fmt::Display;
+
+#[cfg(all(
+    any(target_arch = "x86", target_arch = "x86_64"),
+    feature = "sha512-bmi",
+))]
+use crate::arch::x86_any::{is_bmi1_supported, is_bmi2_supported};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Sha512 block size in bytes.
+const SHA_512_BLOCK_SIZE: usize = 128;
+
+/// Sha512 in u64.
+const SHA_512_U64_COUNT: usize = 8;
+
+/// Number of bytes used to encode the length in bits in the last block.
+const SHA_512_LENGTH_SIZE: usize = 16;
+
+/// Number of rounds per block.
+const SHA_512_ROUNDS: usize = 80;
+
+/** Sha512_256 implementation.
+ *
+ * - [`Sha512_256Implementation::BMI`] uses `BMI1` and `BMI2`. AMD has released
+ *   processors with just `BMI1` support, but this implementation requires both.
+ *   `BMI` uses integer registers, and does not use any floating point
+ *   registers.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Sha512_256Implementation {
+    /// Generic.
+    Generic,
+
+    /// BMI1 and BMI2.
+    BMI,
+}
+
+struct Sha512Constants {
+    k: [u64; SHA_512_ROUNDS],
+    h: [u64; SHA_512_U64_COUNT],
+}
+
+const SHA_512_256_CONSTANTS: Sha512Constants = Sha512Constants {
+    k: [
+        0x428a2f98d728ae22,
+        0x7137449123ef65cd,
+        0xb5c0fbcfec4d3b2f,
+        0xe9b5dba58189dbbc,
+        0x3956c25bf348b538,
+        0x59f111f1b605d019,
+        0x923f82a4af194f9b,
+        0xab1c5ed5da6d8118,
+        0xd807aa98a3030242,
+        0x12835b0145706fbe,
+        0x243185be4ee4b28c,
+        0x550c7dc3d5ffb4e2,
+        0x72be5d74f27b896f,
+        0x80deb1fe3b1696b1,
+        0x9bdc06a725c71235,
+        0xc19bf174cf692694,
+        0xe49b69c19ef14ad2,
+        0xefbe4786384f25e3,
+        0x0fc19dc68b8cd5b5,
+        0x240ca1cc77ac9c65,
+        0x2de92c6f592b0275,
+        0x4a7484aa6ea6e483,
+        0x5cb0a9dcbd41fbd4,
+        0x76f988da831153b5,
+        0x983e5152ee66dfab,
+        0xa831c66d2db43210,
+        0xb00327c898fb213f,
+        0xbf597fc7beef0ee4,
+        0xc6e00bf33da88fc2,
+        0xd5a79147930aa725,
+        0x06ca6351e003826f,
+        0x142929670a0e6e70,
+        0x27b70a8546d22ffc,
+        0x2e1b21385c26c926,
+        0x4d2c6dfc5ac42aed,
+        0x53380d139d95b3df,
+        0x650a73548baf63de,
+        0x766a0abb3c77b2a8,
+        0x81c2c92e47edaee6,
+        0x92722c851482353b,
+        0xa2bfe8a14cf10364,
+        0xa81a664bbc423001,
+        0xc24b8b70d0f89791,
+        0xc76c51a30654be30,
+        0xd192e819d6ef5218,
+        0xd69906245565a910,
+        0xf40e35855771202a,
+        0x106aa07032bbd1b8,
+        0x19a4c116b8d2d0c8,
+        0x1e376c085141ab53,
+        0x2748774cdf8eeb99,
+        0x34b0bcb5e19b48a8,
+        0x391c0cb3c5c95a63,
+        0x4ed8aa4ae3418acb,
+        0x5b9cca4f7763e373,
+        0x682e6ff3d6b2b8a3,
+        0x748f82ee5defb2fc,
+        0x78a5636f43172f60,
+        0x84c87814a1f0ab72,
+        0x8cc702081a6439ec,
+        0x90befffa23631e28,
+        0xa4506cebde82bde9,
+        0xbef9a3f7b2c67915,
+        0xc67178f2e372532b,
+        0xca273eceea26619c,
+        0xd186b8c721c0c207,
+        0xeada7dd6cde0eb1e,
+        0xf57d4f7fee6ed178,
+        0x06f067aa72176fba,
+        0x0a637dc5a2c898a6,
+        0x113f9804bef90dae,
+        0x1b710b35131c471b,
+        0x28db77f523047d84,
+        0x32caab7b40c72493,
+        0x3c9ebe0a15c9bebc,
+        0x431d67c49c100d4c,
+        0x4cc5d4becb3e42b6,
+        0x597f299cfc657e2a,
+        0x5fcb6fab3ad6faec,
+        0x6c44198c4a475817,
+    ],
+    // Initial values for the 256 bit truncation, which are different from
+    // the initial values of SHA-512.
+    h: [
+        0x22312194fc2bf72c,
+        0x9f555fa3c84c64c2,
+        0x2393b86b6f53b151,
+        0x963877195940eabd,
+        0x96283ee2a88effe3,
+        0xbe5e1e2553863992,
+        0x2b0199fc2c85b8aa,
+        0x0eb72ddc81c52ca2,
+    ],
+};
+
+const ALL_SHA_512_256_IMPLEMENTATIONS: [Sha512_256Implementation; 2] = [
+    Sha512_256Implementation::Generic,
+    Sha512_256Implementation::BMI,
+];
+
+impl Sha512_256Implementation {
+    /** Get a slice with all of the [`Sha512_256Implementation`].
+     *
+     * Runtime support depends on CPU. Calling [`Sha512_256::new`] might still
+     * fail with [`ChecksumError::Unsupported`].
+     */
+    pub fn all() -> &'static [Sha512_256Implementation] {
+        &ALL_SHA_512_256_IMPLEMENTATIONS
+    }
+
+    /// Get the string name of the implementation.
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            Sha512_256Implementation::Generic => "generic",
+            Sha512_256Implementation::BMI => "bmi",
+        }
+    }
+
+    /** Get implementation context.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] if the implementation is not supported.
+     */
+    fn get_implementation_ctx(
+        &self,
+    ) -> Result<&'static Sha512_256ImplementationCtx, ChecksumError> {
+        let ctx = match self {
+            Sha512_256Implementation::Generic => &SHA_512_256_IMPL_CTX_GENERIC,
+
+            #[cfg(feature = "sha512-bmi")]
+            Sha512_256Implementation::BMI => &SHA_512_256_IMPL_CTX_BMI,
+
+            #[cfg(not(feature = "sha512-bmi"))]
+            _ => {
+                return Err(ChecksumError::Unsupported {
+                    checksum: ChecksumType::Sha512_256,
+                    implementation: self.to_str(),
+                })
+            }
+        };
+
+        Ok(ctx)
+    }
+}
+
+impl Display for Sha512_256Implementation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+/// Update state. Data length is a multiple of [`SHA_512_BLOCK_SIZE`].
+type Sha512_256UpdateBlock = fn(state: &mut [u64], data: &[u8]);
+
+/// Is the implementation supported by the CPU.
+type Sha512_256IsSupported = fn() -> bool;
+
+/// Sha512_256 implementation context.
+struct Sha512_256ImplementationCtx {
+    /// Implementation of [`Sha512_256UpdateBlock`].
+    update_blocks: Sha512_256UpdateBlock,
+
+    /// Is the implementation supported by the CPU.
+    is_supported: Sha512_256IsSupported,
+}
+
+const SHA_512_256_IMPL_CTX_GENERIC: Sha512_256ImplementationCtx = Sha512_256ImplementationCtx {
+    update_blocks: Sha512_256::update_blocks_generic,
+    is_supported: || true,
+};
+
+#[cfg(feature = "sha512-bmi")]
+const SHA_512_256_IMPL_CTX_BMI: Sha512_256ImplementationCtx = Sha512_256ImplementationCtx {
+    update_blocks: Sha512_256::update_blocks_bmi,
+    is_supported: || is_bmi1_supported() && is_bmi2_supported(),
+};
+
+/// [`crate::phys::ChecksumType::Sha512_256`] implementation.
+pub struct Sha512_256 {
+    /// Number of bytes processed.
+    bytes_processed: u64,
+
+    /// Number of bytes used in [`Sha512_256::buffer`].
+    buffer_fill: usize,
+
+    /// Partial block buffer.
+    buffer: [u8; SHA_512_BLOCK_SIZE],
+
+    /// Ongoing checksum.
+    state: [u64; SHA_512_U64_COUNT],
+
+    /// Byte order of the result.
+    order: EndianOrder,
+
+    /// Implementation context.
+    impl_ctx: &'static Sha512_256ImplementationCtx,
+}
+
+impl Sha512_256 {
+    /** Create a new Sha512_256 instance.
+     *
+     * # Errors
+     *
+     * Returns [`ChecksumError`] if the implementation is not supported.
+     */
+    pub fn new(implementation: Sha512_256Implementation) -> Result<Sha512_256, ChecksumError> {
+        let ctx = implementation.get_implementation_ctx()?;
+
+        if !(ctx.is_supported)() {
+            return Err(ChecksumError::Unsupported {
+                checksum: ChecksumType::Sha512_256,
+                implementation: implementation.to_str(),
+            });
+        }
+
+        Ok(Sha512_256 {
+            bytes_processed: 0,
+            buffer_fill: 0,
+            buffer: [0; SHA_512_BLOCK_SIZE],
+            state: SHA_512_256_CONSTANTS.h,
+            order: EndianOrder::Little,
+            impl_ctx: ctx,
+        })
+    }
+
+    /** Update blocks.
+     *
+     * Always inlined, so that implementations that enable additional CPU
+     * features can reuse the code, and let the compiler use the instructions.
+     */
+    #[inline(always)]
+    fn update_blocks_common(state: &mut [u64], data: &[u8]) {
+        /// Do a round of calculations. Caller must swap variables.
+        macro_rules! round {
+            ($round:expr,
+             $w:expr,
+             $a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $g:expr, $h:expr
+            ) => {
+                let ch = ($e & $f) ^ ((!$e) & $g);
+                let s1 = $e.rotate_right(14) ^ $e.rotate_right(18) ^ $e.rotate_right(41);
+
+                let temp1 = $h.wrapping_add(s1);
+                let temp1 = temp1.wrapping_add(ch);
+                let temp1 = temp1.wrapping_add(SHA_512_256_CONSTANTS.k[$round]);
+                let temp1 = temp1.wrapping_add($w[$round % 16]);
+                $round += 1;
+
+                // Caller swaps variables.
+                $d = $d.wrapping_add(temp1);
+
+                let maj = ($a & $b) ^ ($a & $c) ^ ($b & $c);
+                let s0 = $a.rotate_right(28) ^ $a.rotate_right(34) ^ $a.rotate_right(39);
+
+                let temp2 = s0.wrapping_add(maj);
+
+                // Caller swaps variables.
+                $h = temp1.wrapping_add(temp2);
+            };
+        }
+
+        /** Schedule the next value, and do a round of calculations.
+         *
+         * The schedule is kept in a circular buffer of 16 values.
+         *
+         * Caller must swap variables.
+         */
+        macro_rules! schedule_and_round {
+            ($round:expr,
+             $w:expr,
+             $a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr, $g:expr, $h:expr
+            ) => {
+                let w15 = $w[($round + 1) % 16];
+                let s0 = w15.rotate_right(1) ^ w15.rotate_right(8) ^ (w15 >> 7);
+
+                let w2 = $w[($round + 14) % 16];
+                let s1 = w2.rotate_right(19) ^ w2.rotate_right(61) ^ (w2 >> 6);
+
+                let wi = $w[$round % 16]
+                    .wrapping_add(s0)
+                    .wrapping_add($w[($round + 9) % 16])
+                    .wrapping_add(s1);
+                $w[$round % 16] = wi;
+
+                round!($round, $w, $a, $b, $c, $d, $e, $f, $g, $h);
+            };
+        }
+
+        let mut w: [u64; 16] = [0; 16];
+
+        let mut a = state[0];
+        let mut b = state[1];
+        let mut c = state[2];
+        let mut d = state[3];
+        let mut e = state[4];
+        let mut f = state[5];
+        let mut g = state[6];
+        let mut h = state[7];
+
+        // Iterate one block at a time.
+        for block in data.chunks_exact(SHA_512_BLOCK_SIZE).by_ref() {
+            // Initialize w[0..16].
+            for (i, x) in block.chunks_exact(8).by_ref().enumerate() {
+                w[i] = u64::from_be_bytes(x.try_into().unwrap());
+            }
+
+            let mut round = 0;
+
+            // Unroll the code, and instead of swapping registers,
+            // swap the variables when invoking the macro.
+            round!(round, w, a, b, c, d, e, f, g, h);
+            round!(round, w, h, a, b, c, d, e, f, g);
+            round!(round, w, g, h, a, b, c, d, e, f);
+            round!(round, w, f, g, h, a, b, c, d, e);
+            round!(round, w, e, f, g, h, a, b, c, d);
+            round!(round, w, d, e, f, g, h, a, b, c);
+            round!(round, w, c, d, e, f, g, h, a, b);
+            round!(round, w, b, c, d, e, f, g, h, a);
+
+            round!(round, w, a, b, c, d, e, f, g, h);
+            round!(round, w, h, a, b, c, d, e, f, g);
+            round!(round, w, g, h, a, b, c, d, e, f);
+            round!(round, w, f, g, h, a, b, c, d, e);
+            round!(round, w, e, f, g, h, a, b, c, d);
+            round!(round, w, d, e, f, g, h, a, b, c);
+            round!(round, w, c, d, e, f, g, h, a, b);
+            round!(round, w, b, c, d, e, f, g, h, a);
+
+            while round < SHA_512_ROUNDS {
+                // Unroll the code, and instead of swapping registers,
+                // swap the variables when invoking the macro.
+                schedule_and_round!(round, w, a, b, c, d, e, f, g, h);
+                schedule_and_round!(round, w, h, a, b, c, d, e, f, g);
+                schedule_and_round!(round, w, g, h, a, b, c, d, e, f);
+                schedule_and_round!(round, w, f, g, h, a, b, c, d, e);
+                schedule_and_round!(round, w, e, f, g, h, a, b, c, d);
+                schedule_and_round!(round, w, d, e, f, g, h, a, b, c);
+                schedule_and_round!(round, w, c, d, e, f, g, h, a, b);
+                schedule_and_round!(round, w, b, c, d, e, f, g, h, a);
+            }
+
+            a = a.wrapping_add(state[0]);
+            b = b.wrapping_add(state[1]);
+            c = c.wrapping_add(state[2]);
+            d = d.wrapping_add(state[3]);
+            e = e.wrapping_add(state[4]);
+            f = f.wrapping_add(state[5]);
+            g = g.wrapping_add(state[6]);
+            h = h.wrapping_add(state[7]);
+
+            state[0] = a;
+            state[1] = b;
+            state[2] = c;
+            state[3] = d;
+            state[4] = e;
+            state[5] = f;
+            state[6] = g;
+            state[7] = h;
+        }
+    }
+
+    fn update_blocks_generic(state: &mut [u64], data: &[u8]) {
+        Sha512_256::update_blocks_common(state, data)
+    }
+
+    #[cfg(all(
+        feature = "sha512-bmi",
+        any(target_arch = "x86", target_arch = "x86_64")
+    ))]
+    fn update_blocks_bmi(state: &mut [u64], data: &[u8]) {
+        #[target_feature(enable = "bmi1,bmi2")]
+        unsafe fn update_blocks_bmi_impl(state: &mut [u64], data: &[u8]) {
+            // BMI has ANDN for ch, and RORX for non-destructive rotates.
+            Sha512_256::update_blocks_common(state, data)
+        }
+
+        unsafe { update_blocks_bmi_impl(state, data) }
+    }
+}
+
+impl Checksum for Sha512_256 {
+    fn reset(&mut self, order: EndianOrder) -> Result<(), ChecksumError> {
+        self.bytes_processed = 0;
+        self.buffer = [0; SHA_512_BLOCK_SIZE];
+        self.buffer_fill = 0;
+        self.state = SHA_512_256_CONSTANTS.h;
+        self.order = order;
+
+        Ok(())
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<(), ChecksumError> {
+        // Make data pointer mutable, in case of self.buffer_fill.
+        let mut data = data;
+
+        // If block has some data, fill that up first.
+        if self.buffer_fill > 0 {
+            // Todo is minimum of block fill needed, and input data.
+            let todo = cmp::min(SHA_512_BLOCK_SIZE - self.buffer_fill, data.len());
+
+            // Copy to block.
+            self.buffer[self.buffer_fill..self.buffer_fill + todo].copy_from_slice(&data[0..todo]);
+            self.buffer_fill += todo;
+
+            // Update data to skip copied block.
+            data = &data[todo..];
+
+            // If block is full, consume it.
+            if self.buffer_fill == SHA_512_BLOCK_SIZE {
+                let full_blocks_data = &self.buffer[0..self.buffer_fill];
+                self.bytes_processed += SHA_512_BLOCK_SIZE as u64;
+                (self.impl_ctx.update_blocks)(&mut self.state, full_blocks_data);
+                self.buffer_fill = 0;
+            }
+        }
+
+        // Calculate remainder.
+        let remainder = data.len() % SHA_512_BLOCK_SIZE;
+
+        // Update full blocks.
+        let full_blocks_data = &data[0..data.len() - remainder];
+        self.bytes_processed += full_blocks_data.len() as u64;
+        (self.impl_ctx.update_blocks)(&mut self.state, full_blocks_data);
+
+        // Check if remainder exists, to prevent clobbering fill with 0.
+        if remainder > 0 {
+            self.buffer[0..remainder].copy_from_slice(&data[data.len() - remainder..]);
+            self.buffer_fill = remainder;
+        }
+
+        // Success.
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<[u64; 4], ChecksumError> {
+        let byte_length = self.bytes_processed + (self.buffer_fill as u64);
+        let bit_length = u128::from(byte_length) * 8;
+
+        // Set the 1 bit.
+        // NOTE: Sha512_256::update guarantees that buffer_fill will be less
+        //       than the size of the buffer, and at least one byte will be
+        //       available.
+        self.buffer[self.buffer_fill] = 0x80;
+        self.buffer_fill += 1;
+
+        // If last block does not have enough space for 128 bit length,
+        // then pad it out with zeroes.
+        if self.buffer_fill > SHA_512_BLOCK_SIZE - SHA_512_LENGTH_SIZE {
+            self.buffer[self.buffer_fill..SHA_512_BLOCK_SIZE].fill(0);
+            self.buffer_fill = 0;
+
+            let data = &self.buffer[0..SHA_512_BLOCK_SIZE];
+            (self.impl_ctx.update_blocks)(&mut self.state, data);
+        }
+
+        // Set zero bits until 128 bits are remaining.
+        self.buffer[self.buffer_fill..SHA_512_BLOCK_SIZE - SHA_512_LENGTH_SIZE].fill(0);
+
+        // Encode length in bits.
+        self.buffer[SHA_512_BLOCK_SIZE - SHA_512_LENGTH_SIZE..SHA_512_BLOCK_SIZE]
+            .copy_from_slice(&u128::to_be_bytes(bit_length));
+
+        // Process last block.
+        let data = &self.buffer[0..SHA_512_BLOCK_SIZE];
+        (self.impl_ctx.update_blocks)(&mut self.state, data);
+
+        // Truncate result to 256 bits. Unlike SHA-256, ZFS stores the digest
+        // as is, so decode it in the byte order of the data.
+        let mut result = [0; 4];
+        for (value, state) in result.iter_mut().zip(self.state) {
+            let x = state.to_be_bytes();
+            *value = match self.order {
+                EndianOrder::Big => u64::from_be_bytes(x),
+                EndianOrder::Little => u64::from_le_bytes(x),
+            };
+        }
+
+        Ok(result)
+    }
+
+    fn hash(&mut self, data: &[u8], order: EndianOrder) -> Result<[u64; 4], ChecksumError> {
+        self.reset(order)?;
+        self.update(data)?;
+        self.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::cmp;
+
+    use crate::checksum::{Checksum, ChecksumError, Sha512_256, Sha512_256Implementation};
+    use crate::phys::EndianOrder;
+
+    /** 128 byte random data.
+     *
+     * Refer to `docs/SHA.md` for script to generate test cases.
+     */
+    const TEST_VECTOR_A: [u8; 128] = [
+        0xbc, 0x4b, 0x4d, 0x58, 0x43, 0xca, 0x34, 0x35, 0xe4, 0xd0, 0x59, 0xe4, 0xd0, 0x2b, 0x08,
+        0xe3, 0x2f, 0xe3, 0x78, 0xe1, 0xe6, 0xf6, 0xf1, 0x34, 0x84, 0xdc, 0x1e, 0x0e, 0x12, 0x28,
+        0x2e, 0xbe, 0x53, 0xbd, 0x1a, 0xf9, 0x8a, 0x97, 0x6e, 0xab, 0x7c, 0x06, 0xed, 0x50, 0xa8,
+        0xc9, 0xe4, 0x1e, 0xb8, 0xaf, 0xb8, 0x8c, 0x94, 0xb5, 0x15, 0xed, 0xa8, 0x3f, 0x9d, 0x99,
+        0x9c, 0x26, 0xe8, 0x1d, 0x87, 0x29, 0x1f, 0x60, 0x64, 0xca, 0xd1, 0xe8, 0x48, 0x7e, 0xe4,
+        0xf2, 0x56, 0xf3, 0x59, 0x73, 0x04, 0x39, 0xb2, 0x62, 0x56, 0xea, 0xf1, 0x44, 0xf0, 0x06,
+        0x28, 0x2e, 0x56, 0x16, 0xd3, 0x80, 0x0d, 0x47, 0x9e, 0x87, 0x3f, 0x52, 0x64, 0x30, 0x63,
+        0x6d, 0x64, 0x58, 0xcb, 0x84, 0x4d, 0xf7, 0x1c, 0x6e, 0xc7, 0x07, 0x86, 0x3d, 0x17, 0xec,
+        0x51, 0x8f, 0x51, 0x6e, 0x5a, 0x52, 0x64, 0xee,
+    ];
+
+    /// Checksums in little endian order.
+    const TEST_VECTOR_A_CHECKSUMS: [(usize, [u64; 4]); 33] = [
+        (
+            0,
+            [
+                0x28ed56efd1b872c6,
+                0x614512c62c387ab,
+                0x7473f9b8d73add9b,
+                0x7a96f0ce1ec0d098,
+            ],
+        ),
+        (
+            4,
+            [
+                0xbc0b31d97285e370,
+                0xb369587bc1efbf61,
+                0x18edeee77d6257ce,
+                0xbad6466a2ec49b61,
+            ],
+        ),
+        (
+            8,
+            [
+                0x96e003421aecc622,
+                0x16f9ccf06e81c305,
+                0x9748dce1b5ca67be,
+                0xb40ac035df8aae9e,
+            ],
+        ),
+        (
+            16,
+            [
+                0x83b938836a7cda33,
+                0xa91dd6fd7d7ac0b0,
+                0x8d3774fdbffd1f2b,
+                0x8adb8d680f38930c,
+            ],
+        ),
+        (
+            32,
+            [
+                0x2e6f900c42bc7659,
+                0x9c64c29db2b84571,
+                0xdf2c78a1e679fbcb,
+                0xdf330244c10f5899,
+            ],
+        ),
+        (
+            64,
+            [
+                0xd988ba61ca992e1d,
+                0xb4e9cbd0c02735aa,
+                0x6e1b43cecc116a0d,
+                0x7fe5def48d74a23f,
+            ],
+        ),
+        (
+            110,
+            [
+                0x4ff79303e9efbb0d,
+                0x487d6f85c51e6826,
+                0xe187d890e3670ef4,
+                0x174bf4ffe636ad35,
+            ],
+        ),
+        (
+            111,
+            [
+                0x50ca1d64e7e9da5c,
+                0xf3135426efd542e1,
+                0xc9e33c7e98e20139,
+                0x56af3550d2dc150e,
+            ],
+        ),
+        (
+            112,
+            [
+                0x56bb2fcf3367f39c,
+                0x861c583dd93b4ed5,
+                0xa3e353c790de17c8,
+                0x470cf19ca38fe663,
+            ],
+        ),
+        (
+            113,
+            [
+                0xf30fa553a6501491,
+                0xfdfc592f6a3e7d66,
+                0x2a0237f53e2700a8,
+                0xcadcba335f584bf4,
+            ],
+        ),
+        (
+            114,
+            [
+                0xaa4e4d375d1ee178,
+                0xfd1ac56cac32e55d,
+                0x68f140184f40aba4,
+                0x107bb4c895d500b7,
+            ],
+        ),
+        (
+            115,
+            [
+                0x42a7347560906d69,
+                0xb14303a58805b5e8,
+                0xe6b4d1985be415b4,
+                0xdc6b478fcb731a53,
+            ],
+        ),
+        (
+            116,
+            [
+                0xa9cca13b612a2aa2,
+                0x7b47ab8230d650c7,
+                0x1d92ee1f1382fe5e,
+                0x4bf6d56e208f3e60,
+            ],
+        ),
+        (
+            117,
+            [
+                0x6584d1e20008a380,
+                0x806b74036498e937,
+                0xf0813ea4a935a9e3,
+                0xddef9bf661a79f0c,
+            ],
+        ),
+        (
+            118,
+            [
+                0xe18a48f08e6a37db,
+                0x5440ce945614a579,
+                0x466a57298d4bb6fd,
+                0xbc9d29e915f28927,
+            ],
+        ),
+        (
+            119,
+            [
+                0x633c852781b7f10f,
+                0xe1fb4915270ae0b6,
+                0x3981ddcabe368bf7,
+                0x475b3fbd67de5e,
+            ],
+        ),
+        (
+            120,
+            [
+                0x5fdf288323588e53,
+                0x7072d0764e4afe4b,
+                0x52a24494bce419b9,
+                0xd0fab59b55f2af96,
+            ],
+        ),
+        (
+            121,
+            [
+                0x8bf4ea4dacf8fe34,
+                0x7b071d154eda83bc,
+                0x7ca66170250e733e,
+                0x9b52596f24f6ff73,
+            ],
+        ),
+        (
+            122,
+            [
+                0x8715cf80d7575518,
+                0x6b315a7a4f7c3118,
+                0x860ca764839bc43c,
+                0x8458a5db82c2a0f9,
+            ],
+        ),
+        (
+            123,
+            [
+                0x564b835599c122ab,
+                0xcc3ae068954802a4,
+                0xfd759f94dcebd6d,
+                0x4c553f8290763d0b,
+            ],
+        ),
+        (
+            124,
+            [
+                0x6aa7858fc591485b,
+                0x300e9d2649c13af,
+                0xefd4bad119f4bff9,
+                0x6073de54b49b0d53,
+            ],
+        ),
+        (
+            125,
+            [
+                0xb915c37ee1b49ded,
+                0x5a250b71b1d517bd,
+                0x54c8c7d4d0583487,
+                0x5dba8baa9e9b36df,
+            ],
+        ),
+        (
+            126,
+            [
+                0x69169dae8feab1ab,
+                0x5d6da4ed2f5f1856,
+                0x9bbab7c5de7951bf,
+                0x173e8ab3ada0a546,
+            ],
+        ),
+        (
+            127,
+            [
+                0xffe80c5cd94bfaaa,
+                0xeec2287bc15a5ead,
+                0xe491b4412e9378dd,
+                0x88ff0897c3248b4f,
+            ],
+        ),
+        (
+            128,
+            [
+                0xdde398032dd78499,
+                0xec6e216029484e9a,
+                0xd6dfadc617030a0f,
+                0x534dadd74153cb2c,
+            ],
+        ),
+        (
+            256,
+            [
+                0x6af566ee19e63c80,
+                0xf0506ca1d5639e54,
+                0xaec39ee3da841e02,
+                0xeda413c2dd8c486f,
+            ],
+        ),
+        (
+            384,
+            [
+                0x55f53b794d33c97f,
+                0x945d15d2d1a2f5d1,
+                0x906f60b98b07ee9e,
+                0x4e8b4406c123c796,
+            ],
+        ),
+        (
+            512,
+            [
+                0xb24885509f1a2edb,
+                0x9a12dc1e9db2d9c0,
+                0xc8e2305d16ce1bcf,
+                0xeb51234627e7ed8c,
+            ],
+        ),
+        (
+            8192,
+            [
+                0xc4ad0394beb8b890,
+                0x2af46aa7af1d1f27,
+                0x58d46ab516d6b0ae,
+                0xe9a0b46dc031f835,
+            ],
+        ),
+        (
+            16384,
+            [
+                0x3f01f81b06c85417,
+                0xe5be96692a83d825,
+                0xe8e8c3e35c4748d4,
+                0x3b4fc3b76c49a1ee,
+            ],
+        ),
+        (
+            32768,
+            [
+                0x25e86825a700255a,
+                0xb1c563dce568b60c,
+                0x1579b40bbeb18bc6,
+                0x194caf2167f92c5a,
+            ],
+        ),
+        (
+            65536,
+            [
+                0x5721eabdcad3c95e,
+                0x3c37a63ddde6e2fe,
+                0xa466e66d8dcf1319,
+                0x9a25775008cbb874,
+            ],
+        ),
+        (
+            131072,
+            [
+                0xa05caf9832dff910,
+                0x8c69020de3321f0a,
+                0x59e14b1b981ce41c,
+                0xd3c47f8e3cb236fd,
+            ],
+        ),
+    ];
+
+    fn run_test_vector(
+        h: &mut Sha512_256,
+        order: EndianOrder,
+        vector: &[u8],
+        checksums: &[(usize, [u64; 4])],
+    ) {
+        // Test sizes.
+        for (size, checksum) in checksums {
+            let size = *size;
+
+            // Checksums are in little endian order.
+            let checksum = match order {
+                EndianOrder::Big => checksum.map(u64::swap_bytes),
+                EndianOrder::Little => *checksum,
+            };
+
+            if size <= vector.len() {
+                // Single update call.
+                assert_eq!(h.hash(&vector[0..size], order).unwrap(), checksum);
+
+                // Partial update.
+                h.reset(order).unwrap();
+                let mut offset = 0;
+
+                h.update(&vector[0..size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..offset + size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..size]).unwrap();
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+            } else {
+                // Multiple calls.
+                let mut todo = size;
+                h.reset(order).unwrap();
+
+                while todo > 0 {
+                    let can_do = cmp::min(todo, vector.len());
+                    h.update(&vector[0..can_do]).unwrap();
+                    todo -= can_do;
+                }
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+            }
+        }
+    }
+
+    fn test_required_implementation(implementation: Sha512_256Implementation) {
+        let mut h = Sha512_256::new(implementation).unwrap();
+
+        run_test_vector(
+            &mut h,
+            EndianOrder::Big,
+            &TEST_VECTOR_A,
+            &TEST_VECTOR_A_CHECKSUMS,
+        );
+
+        run_test_vector(
+            &mut h,
+            EndianOrder::Little,
+            &TEST_VECTOR_A,
+            &TEST_VECTOR_A_CHECKSUMS,
+        );
+    }
+
+    fn test_optional_implementation(implementation: Sha512_256Implementation) {
+        let supported = !matches!(
+            Sha512_256::new(implementation),
+            Err(ChecksumError::Unsupported {
+                checksum: _,
+                implementation: _,
+            })
+        );
+
+        if supported {
+            test_required_implementation(implementation);
+        }
+    }
+
+    #[test]
+    fn sha512_256_all() {
+        assert_eq!(Sha512_256Implementation::all().len(), 2);
+    }
+
+    #[test]
+    fn sha512_256_str() {
+        assert_eq!(format!("{}", Sha512_256Implementation::Generic), "generic");
+        assert_eq!(format!("{}", Sha512_256Implementation::BMI), "bmi");
+    }
+
+    #[test]
+    fn sha512_256_generic() {
+        test_required_implementation(Sha512_256Implementation::Generic)
+    }
+
+    #[test]
+    fn sha512_256_bmi() {
+        test_optional_implementation(Sha512_256Implementation::BMI)
+    }
+}
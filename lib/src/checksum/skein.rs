@@ -0,0 +1,712 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Skein-512/256 checksum.
+ *
+ * Skein is built on the Threefish block cipher, chained with Unique Block
+ * Iteration (UBI). Each UBI call processes a message of a given type (key,
+ * configuration, message, output), with a tweak that encodes the type, and
+ * the number of bytes processed so far.
+ *
+ * ZFS uses the per pool checksum salt as the Skein MAC key, and stores the
+ * 256 bit result as four [u64] in the byte order of the data.
+ */
+use crate::checksum::{Checksum, ChecksumError};
+use crate::phys::EndianOrder;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Skein-512 block size in bytes.
+const SKEIN_512_BLOCK_SIZE: usize = 64;
+
+/// Skein-512 state in u64.
+const SKEIN_512_U64_COUNT: usize = 8;
+
+/// Number of Threefish-512 rounds.
+const SKEIN_512_ROUNDS: usize = 72;
+
+/// Number of bits of output.
+const SKEIN_512_256_OUTPUT_BITS: u64 = 256;
+
+/// Key schedule parity constant.
+const SKEIN_KS_PARITY: u64 = 0x1bd11bdaa9fc1a22;
+
+/// Configuration schema identifier "SHA3" and version 1.
+const SKEIN_SCHEMA_VERSION: u64 = 0x0000_0001_3341_4853;
+
+/// Number of bytes used by the configuration block.
+const SKEIN_CONFIG_SIZE: u64 = 32;
+
+/// Tweak flag for the first block of a UBI call.
+const TWEAK_FIRST: u64 = 1 << 62;
+
+/// Tweak flag for the last block of a UBI call.
+const TWEAK_FINAL: u64 = 1 << 63;
+
+/// Tweak type shift.
+const TWEAK_TYPE_SHIFT: u32 = 56;
+
+/// Tweak type for the key.
+const TWEAK_TYPE_KEY: u64 = 0;
+
+/// Tweak type for the configuration.
+const TWEAK_TYPE_CONFIG: u64 = 4;
+
+/// Tweak type for the message.
+const TWEAK_TYPE_MESSAGE: u64 = 48;
+
+/// Tweak type for the output.
+const TWEAK_TYPE_OUTPUT: u64 = 63;
+
+/// Rotation constants for each of the eight rounds, and four mixes.
+const ROTATIONS: [[u32; 4]; 8] = [
+    [46, 36, 19, 37],
+    [33, 27, 14, 42],
+    [17, 49, 36, 39],
+    [44, 9, 54, 56],
+    [39, 30, 34, 24],
+    [13, 50, 10, 17],
+    [25, 29, 39, 43],
+    [8, 35, 56, 22],
+];
+
+/// Word permutation for each of the four rounds between key injections.
+const PERMUTATIONS: [[usize; 8]; 4] = [
+    [0, 1, 2, 3, 4, 5, 6, 7],
+    [2, 1, 4, 7, 6, 5, 0, 3],
+    [4, 1, 6, 3, 0, 5, 2, 7],
+    [6, 1, 0, 7, 2, 5, 4, 3],
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Chaining state and tweak of a UBI call.
+#[derive(Clone, Copy)]
+struct Ubi {
+    /// Chaining state.
+    state: [u64; SKEIN_512_U64_COUNT],
+
+    /// Tweak: number of bytes processed, and flags.
+    tweak: [u64; 2],
+}
+
+impl Ubi {
+    /// Starts a new UBI call of the type.
+    fn start(&mut self, tweak_type: u64) {
+        self.tweak = [0, TWEAK_FIRST | (tweak_type << TWEAK_TYPE_SHIFT)];
+    }
+
+    /** Processes one block.
+     *
+     * `byte_count` is the number of message bytes in the block, which is less
+     * than [`SKEIN_512_BLOCK_SIZE`] for the last padded block.
+     */
+    fn process_block(&mut self, block: &[u8], byte_count: usize) {
+        self.tweak[0] = self.tweak[0].wrapping_add(byte_count as u64);
+
+        // Decode the block.
+        let mut w = [0; SKEIN_512_U64_COUNT];
+        for (i, x) in block.chunks_exact(8).enumerate() {
+            w[i] = u64::from_le_bytes(x.try_into().unwrap());
+        }
+
+        // Extend the key schedule with parity words.
+        let mut ks = [0; SKEIN_512_U64_COUNT + 1];
+        ks[0..SKEIN_512_U64_COUNT].copy_from_slice(&self.state);
+        ks[SKEIN_512_U64_COUNT] = self.state.iter().fold(SKEIN_KS_PARITY, |p, x| p ^ x);
+
+        let ts = [self.tweak[0], self.tweak[1], self.tweak[0] ^ self.tweak[1]];
+
+        // Initial key injection.
+        let mut x = [0; SKEIN_512_U64_COUNT];
+        for i in 0..SKEIN_512_U64_COUNT {
+            x[i] = w[i].wrapping_add(ks[i]);
+        }
+        x[5] = x[5].wrapping_add(ts[0]);
+        x[6] = x[6].wrapping_add(ts[1]);
+
+        // Rounds, with a key injection after every four rounds.
+        for round in 0..SKEIN_512_ROUNDS {
+            let permutation = &PERMUTATIONS[round % 4];
+            let rotations = &ROTATIONS[round % 8];
+
+            for mix in 0..4 {
+                let a = permutation[2 * mix];
+                let b = permutation[2 * mix + 1];
+
+                x[a] = x[a].wrapping_add(x[b]);
+                x[b] = x[b].rotate_left(rotations[mix]) ^ x[a];
+            }
+
+            if round % 4 == 3 {
+                let s = round / 4 + 1;
+
+                for (i, v) in x.iter_mut().enumerate() {
+                    *v = v.wrapping_add(ks[(s + i) % (SKEIN_512_U64_COUNT + 1)]);
+                }
+                x[5] = x[5].wrapping_add(ts[s % 3]);
+                x[6] = x[6].wrapping_add(ts[(s + 1) % 3]);
+                x[7] = x[7].wrapping_add(s as u64);
+            }
+        }
+
+        // Feed forward.
+        for i in 0..SKEIN_512_U64_COUNT {
+            self.state[i] = x[i] ^ w[i];
+        }
+
+        self.tweak[1] &= !TWEAK_FIRST;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`crate::phys::ChecksumType::Skein`] implementation.
+pub struct Skein {
+    /// UBI state after processing the salt and configuration.
+    initial: Ubi,
+
+    /// Ongoing UBI state.
+    ubi: Ubi,
+
+    /// Number of bytes used in [`Skein::buffer`].
+    buffer_fill: usize,
+
+    /// Partial block buffer.
+    buffer: [u8; SKEIN_512_BLOCK_SIZE],
+
+    /// Byte order of the result.
+    order: EndianOrder,
+}
+
+impl Skein {
+    /** Create a new Skein instance.
+     *
+     * `salt` is used as the MAC key. ZFS uses the 32 byte per pool checksum
+     * salt, stored in the MOS. An empty `salt` computes plain Skein-512-256.
+     */
+    pub fn new(salt: &[u8]) -> Skein {
+        let mut skein = Skein {
+            initial: Ubi {
+                state: [0; SKEIN_512_U64_COUNT],
+                tweak: [0; 2],
+            },
+            ubi: Ubi {
+                state: [0; SKEIN_512_U64_COUNT],
+                tweak: [0; 2],
+            },
+            buffer_fill: 0,
+            buffer: [0; SKEIN_512_BLOCK_SIZE],
+            order: EndianOrder::Little,
+        };
+
+        // Process the key.
+        if !salt.is_empty() {
+            skein.ubi.start(TWEAK_TYPE_KEY);
+            skein.update_ubi(salt);
+            skein.finish_ubi();
+        }
+
+        // Process the configuration.
+        let mut config = [0; SKEIN_512_BLOCK_SIZE];
+        config[0..8].copy_from_slice(&u64::to_le_bytes(SKEIN_SCHEMA_VERSION));
+        config[8..16].copy_from_slice(&u64::to_le_bytes(SKEIN_512_256_OUTPUT_BITS));
+
+        skein.ubi.start(TWEAK_TYPE_CONFIG);
+        skein.ubi.tweak[1] |= TWEAK_FINAL;
+        skein.ubi.process_block(&config, SKEIN_CONFIG_SIZE as usize);
+
+        // Start the message.
+        skein.ubi.start(TWEAK_TYPE_MESSAGE);
+        skein.initial = skein.ubi;
+
+        skein
+    }
+
+    /** Update the UBI state.
+     *
+     * The last block is always kept in the buffer, because it must be
+     * processed with [`TWEAK_FINAL`] by [`Skein::finish_ubi`].
+     */
+    fn update_ubi(&mut self, data: &[u8]) {
+        // Make data pointer mutable, in case of self.buffer_fill.
+        let mut data = data;
+
+        // Only process the buffer, if more data follows.
+        if self.buffer_fill + data.len() > SKEIN_512_BLOCK_SIZE {
+            if self.buffer_fill > 0 {
+                let todo = SKEIN_512_BLOCK_SIZE - self.buffer_fill;
+                self.buffer[self.buffer_fill..].copy_from_slice(&data[0..todo]);
+                data = &data[todo..];
+
+                self.ubi.process_block(&self.buffer, SKEIN_512_BLOCK_SIZE);
+                self.buffer_fill = 0;
+            }
+
+            // Process all full blocks, except for the last one.
+            while data.len() > SKEIN_512_BLOCK_SIZE {
+                self.ubi
+                    .process_block(&data[0..SKEIN_512_BLOCK_SIZE], SKEIN_512_BLOCK_SIZE);
+                data = &data[SKEIN_512_BLOCK_SIZE..];
+            }
+        }
+
+        // Buffer the remainder.
+        self.buffer[self.buffer_fill..self.buffer_fill + data.len()].copy_from_slice(data);
+        self.buffer_fill += data.len();
+    }
+
+    /// Process the last buffered block of the UBI call, padded with zeroes.
+    fn finish_ubi(&mut self) {
+        self.ubi.tweak[1] |= TWEAK_FINAL;
+        self.buffer[self.buffer_fill..].fill(0);
+        self.ubi.process_block(&self.buffer, self.buffer_fill);
+        self.buffer_fill = 0;
+    }
+}
+
+impl Checksum for Skein {
+    fn reset(&mut self, order: EndianOrder) -> Result<(), ChecksumError> {
+        self.ubi = self.initial;
+        self.buffer_fill = 0;
+        self.order = order;
+
+        Ok(())
+    }
+
+    fn update(&mut self, data: &[u8]) -> Result<(), ChecksumError> {
+        self.update_ubi(data);
+
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<[u64; 4], ChecksumError> {
+        self.finish_ubi();
+
+        // Output is a single block, since it is less than 512 bits.
+        self.ubi.start(TWEAK_TYPE_OUTPUT);
+        self.ubi.tweak[1] |= TWEAK_FINAL;
+        let counter = [0; SKEIN_512_BLOCK_SIZE];
+        self.ubi.process_block(&counter, 8);
+
+        // The result bytes are the little endian encoding of the state, and
+        // ZFS stores them as is, so decode them in the byte order of the data.
+        let mut result = [0; 4];
+        for (i, value) in result.iter_mut().enumerate() {
+            *value = match self.order {
+                EndianOrder::Big => self.ubi.state[i].swap_bytes(),
+                EndianOrder::Little => self.ubi.state[i],
+            };
+        }
+
+        Ok(result)
+    }
+
+    fn hash(&mut self, data: &[u8], order: EndianOrder) -> Result<[u64; 4], ChecksumError> {
+        self.reset(order)?;
+        self.update(data)?;
+        self.finalize()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::cmp;
+
+    use crate::checksum::{Checksum, Skein};
+    use crate::phys::EndianOrder;
+
+    /** 128 byte random data.
+     *
+     * Refer to `docs/SKEIN.md` for script to generate test cases.
+     */
+    const TEST_VECTOR_A: [u8; 128] = [
+        0xbc, 0x4b, 0x4d, 0x58, 0x43, 0xca, 0x34, 0x35, 0xe4, 0xd0, 0x59, 0xe4, 0xd0, 0x2b, 0x08,
+        0xe3, 0x2f, 0xe3, 0x78, 0xe1, 0xe6, 0xf6, 0xf1, 0x34, 0x84, 0xdc, 0x1e, 0x0e, 0x12, 0x28,
+        0x2e, 0xbe, 0x53, 0xbd, 0x1a, 0xf9, 0x8a, 0x97, 0x6e, 0xab, 0x7c, 0x06, 0xed, 0x50, 0xa8,
+        0xc9, 0xe4, 0x1e, 0xb8, 0xaf, 0xb8, 0x8c, 0x94, 0xb5, 0x15, 0xed, 0xa8, 0x3f, 0x9d, 0x99,
+        0x9c, 0x26, 0xe8, 0x1d, 0x87, 0x29, 0x1f, 0x60, 0x64, 0xca, 0xd1, 0xe8, 0x48, 0x7e, 0xe4,
+        0xf2, 0x56, 0xf3, 0x59, 0x73, 0x04, 0x39, 0xb2, 0x62, 0x56, 0xea, 0xf1, 0x44, 0xf0, 0x06,
+        0x28, 0x2e, 0x56, 0x16, 0xd3, 0x80, 0x0d, 0x47, 0x9e, 0x87, 0x3f, 0x52, 0x64, 0x30, 0x63,
+        0x6d, 0x64, 0x58, 0xcb, 0x84, 0x4d, 0xf7, 0x1c, 0x6e, 0xc7, 0x07, 0x86, 0x3d, 0x17, 0xec,
+        0x51, 0x8f, 0x51, 0x6e, 0x5a, 0x52, 0x64, 0xee,
+    ];
+
+    /// Salt of bytes 0 to 31.
+    const TEST_SALT: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+
+    /** Checksums without a salt, in little endian order.
+     *
+     * Size 0 is the Skein-512-256 empty message result from the reference.
+     */
+    const TEST_VECTOR_A_CHECKSUMS: [(usize, [u64; 4]); 16] = [
+        (
+            0,
+            [
+                0x85318b4a55c4cc39,
+                0x248a63fea1e79d3b,
+                0x1043f2555ab3e6cc,
+                0x21265d338087e109,
+            ],
+        ),
+        (
+            4,
+            [
+                0xc4b5b883308a30a3,
+                0x14ab8da041d46001,
+                0x2ecd6ca72e7092d0,
+                0x6895181f911c1efb,
+            ],
+        ),
+        (
+            8,
+            [
+                0x671f5d379fb3e418,
+                0x368b20a2aca91583,
+                0x4ac6a8a9f9c5b326,
+                0xb4320a23222bc0d3,
+            ],
+        ),
+        (
+            16,
+            [
+                0xa564899c2ed2fc6e,
+                0x109b8c5698fa9026,
+                0xa11d43617082686f,
+                0xc83aee12243b8acc,
+            ],
+        ),
+        (
+            32,
+            [
+                0x438e7140d6d12c63,
+                0xfe878e8d40fc2d78,
+                0xcfba616a5b09b539,
+                0x68788caed369cf5b,
+            ],
+        ),
+        (
+            63,
+            [
+                0x1587070aba393bcd,
+                0x9471750d375d63d0,
+                0x8452e4cdf54c98ec,
+                0xeafa760990f63b77,
+            ],
+        ),
+        (
+            64,
+            [
+                0x2b34f1d427310258,
+                0x258b512ed176085b,
+                0x23ff86305aac2739,
+                0x262dec62c6c83f95,
+            ],
+        ),
+        (
+            65,
+            [
+                0x99a9c8ff7954e0ba,
+                0x6f23b612fa3e05c7,
+                0xc929112b085decd7,
+                0x1cdbf6ffe6cd97a7,
+            ],
+        ),
+        (
+            127,
+            [
+                0x6d1931d5b166c3dc,
+                0x40a6853542412e1d,
+                0x4f3da0f5ecc3508b,
+                0xcf3982c961e4b3d6,
+            ],
+        ),
+        (
+            128,
+            [
+                0xb7639ceda8aa36fd,
+                0xa8ef7840fdf9d347,
+                0xb5f112ab981f1ae3,
+                0x1a42412edbd6ff7c,
+            ],
+        ),
+        (
+            129,
+            [
+                0xa99d8257af944260,
+                0x9afdf1a6265aec35,
+                0x9746e26c138d437c,
+                0x309e1642dc6bdca1,
+            ],
+        ),
+        (
+            192,
+            [
+                0x6ce0a7e94bd391a9,
+                0x1efff4c884706400,
+                0x7ec398a0bbb49639,
+                0xe17409e6c22b41f1,
+            ],
+        ),
+        (
+            256,
+            [
+                0x947873f3a88ca885,
+                0x881bcd91caf595e3,
+                0x1ff20efb5dc47b33,
+                0xdd8064f2a5ece9fd,
+            ],
+        ),
+        (
+            512,
+            [
+                0x873fdaf77c5d7ce5,
+                0xf592a20914411ae2,
+                0x83f572ec4d93695f,
+                0xa4b360a012b32061,
+            ],
+        ),
+        (
+            8192,
+            [
+                0x23c24006ba0cc7c0,
+                0xb96011b19e94ef06,
+                0xa2544799b48e1b26,
+                0x1d1c28428b6a03e9,
+            ],
+        ),
+        (
+            131072,
+            [
+                0x84b6ec24a54302b7,
+                0x381345ae1fae87a8,
+                0xe6fd1384b10777d6,
+                0xcd6931184e9f4f3b,
+            ],
+        ),
+    ];
+
+    /// Checksums with [`TEST_SALT`], in little endian order.
+    const TEST_VECTOR_A_SALTED_CHECKSUMS: [(usize, [u64; 4]); 16] = [
+        (
+            0,
+            [
+                0xbd6236a24c859328,
+                0x3b55e5dee5541e0f,
+                0xa93155d7b0506739,
+                0x4d869a0017ffb32e,
+            ],
+        ),
+        (
+            4,
+            [
+                0xe62f748fb51e1301,
+                0x5c5dfb527f539db7,
+                0xc87f85f01d88cc26,
+                0x5fb6d20d6e6a047,
+            ],
+        ),
+        (
+            8,
+            [
+                0xf20c460b6fd8b9e,
+                0xb18dbb8f77883bb3,
+                0x9c13422668423146,
+                0x79d87bcafc7952d8,
+            ],
+        ),
+        (
+            16,
+            [
+                0xb206b9a4d75d304c,
+                0xeba698a56849569d,
+                0x16d2a110f3a2f486,
+                0xabf43b4e73e2adef,
+            ],
+        ),
+        (
+            32,
+            [
+                0x5f59bb28e990a97b,
+                0xc0741131d4df8439,
+                0x5f339ee93b7ae720,
+                0x9518b8a4e86a1e1,
+            ],
+        ),
+        (
+            63,
+            [
+                0x62fe046be57dba1f,
+                0xf6a91ea9a36a24e3,
+                0xca0573cca3709863,
+                0x1986927a0dda8bce,
+            ],
+        ),
+        (
+            64,
+            [
+                0x9bec893b6a369705,
+                0xc2e8a364a06671df,
+                0xed4af5d2ca4c77eb,
+                0xa9ca535b70641818,
+            ],
+        ),
+        (
+            65,
+            [
+                0xb3ecb289231e0fd8,
+                0xb1e83f6eb2e99760,
+                0x8ce21a93e561e26,
+                0xd08957dc4dd2fb59,
+            ],
+        ),
+        (
+            127,
+            [
+                0x96c8917e75cb4175,
+                0x2cf9e3f2455abad3,
+                0xbce1cde36ff8f43a,
+                0x59b3283a28b28e99,
+            ],
+        ),
+        (
+            128,
+            [
+                0x18d80e40c6dea9ab,
+                0xda11be285155dced,
+                0x71994b08a450ca54,
+                0xc62744f1ff0b8782,
+            ],
+        ),
+        (
+            129,
+            [
+                0x9fd29669474235d5,
+                0xfa91359ee7f19fd7,
+                0xd53c4f9641a0181,
+                0xa0153c473c9f70,
+            ],
+        ),
+        (
+            192,
+            [
+                0xebd54662bd2aa0b0,
+                0xb26fd6f27f69995a,
+                0x9bc2800a61139b6b,
+                0x5052f947a07a55b9,
+            ],
+        ),
+        (
+            256,
+            [
+                0x588bc73f9c6de8c5,
+                0x1dce744d5f1792b6,
+                0x61eb134678cfb40f,
+                0xb1a0404c467d330c,
+            ],
+        ),
+        (
+            512,
+            [
+                0x1684e765a0980f8,
+                0xba4621fa4687072e,
+                0x45569e153c10984,
+                0x30becc5c7ec90004,
+            ],
+        ),
+        (
+            8192,
+            [
+                0x22f9e6f7cf5d2794,
+                0x8509121250376e66,
+                0x27cd98695f7596f1,
+                0x878e5b959557780d,
+            ],
+        ),
+        (
+            131072,
+            [
+                0x4579648bacef9343,
+                0xa7eb6379a2806953,
+                0xab54aa0492433189,
+                0xdd445ae53bd19d85,
+            ],
+        ),
+    ];
+
+    fn run_test_vector(
+        h: &mut Skein,
+        order: EndianOrder,
+        vector: &[u8],
+        checksums: &[(usize, [u64; 4])],
+    ) {
+        // Test sizes.
+        for (size, checksum) in checksums {
+            let size = *size;
+
+            // Checksums are in little endian order.
+            let checksum = match order {
+                EndianOrder::Big => checksum.map(u64::swap_bytes),
+                EndianOrder::Little => *checksum,
+            };
+
+            if size <= vector.len() {
+                // Single update call.
+                assert_eq!(h.hash(&vector[0..size], order).unwrap(), checksum);
+
+                // Partial update.
+                h.reset(order).unwrap();
+                let mut offset = 0;
+
+                h.update(&vector[0..size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..offset + size / 3]).unwrap();
+                offset += size / 3;
+
+                h.update(&vector[offset..size]).unwrap();
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+            } else {
+                // Multiple calls.
+                let mut todo = size;
+                h.reset(order).unwrap();
+
+                while todo > 0 {
+                    let can_do = cmp::min(todo, vector.len());
+                    h.update(&vector[0..can_do]).unwrap();
+                    todo -= can_do;
+                }
+
+                assert_eq!(h.finalize().unwrap(), checksum);
+            }
+        }
+    }
+
+    #[test]
+    fn skein() {
+        let mut h = Skein::new(&[]);
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            run_test_vector(&mut h, order, &TEST_VECTOR_A, &TEST_VECTOR_A_CHECKSUMS);
+        }
+    }
+
+    #[test]
+    fn skein_salted() {
+        let mut h = Skein::new(&TEST_SALT);
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            run_test_vector(
+                &mut h,
+                order,
+                &TEST_VECTOR_A,
+                &TEST_VECTOR_A_SALTED_CHECKSUMS,
+            );
+        }
+    }
+}
@@ -0,0 +1,584 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Block checksum verification.
+ *
+ * - The checksum of a block is stored in the [`BlockPointerRegular`] that
+ *   points to it, and is calculated over the physical (compressed) bytes.
+ * - [`ChecksumType::On`] is resolved to [`ChecksumType::Fletcher4`], or
+ *   [`ChecksumType::Sha256`] for dedup blocks.
+ * - [`ChecksumType::Off`] and [`ChecksumType::NoParity`] blocks are not
+ *   checksummed.
+ * - [`ChecksumType::Inherit`] is only valid as a dataset property, and is never
+ *   stored in a block pointer.
+ * - [`ChecksumType::Zilog`] and [`ChecksumType::Zilog2`] are embedded in the
+ *   block, and the block pointer checksum value is the verifier.
+ * - [`ChecksumType::Label`] and [`ChecksumType::GangHeader`] are embedded in
+ *   the block, and are never stored in a block pointer.
+ * - The data is hashed in the [`EndianOrder`] of the block pointer, so
+ *   byteswapped blocks are verified without swapping the data.
+ */
+use core::fmt;
+
+use std::error;
+
+use crate::checksum::{embedded_verify, ChecksumError, ChecksumRegistry, EmbeddedVerifyError};
+use crate::phys::{BlockPointerRegular, ChecksumType, ChecksumValue};
+
+#[cfg(doc)]
+use crate::phys::EndianOrder;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Resolve the [`ChecksumType`] to compute for a [`BlockPointerRegular`].
+ *
+ * Returns [`None`] if the block is not checksummed.
+ *
+ * # Errors
+ *
+ * Returns [`BlockVerifyError`] if the [`ChecksumType`] cannot be stored in a
+ * block pointer, or is embedded in the block.
+ */
+fn resolve_checksum(ptr: &BlockPointerRegular) -> Result<Option<ChecksumType>, BlockVerifyError> {
+    match ptr.checksum_type {
+        ChecksumType::Inherit => Err(BlockVerifyError::Inherit {}),
+        ChecksumType::On => Ok(Some(if ptr.dedup {
+            ChecksumType::Sha256
+        } else {
+            ChecksumType::Fletcher4
+        })),
+        ChecksumType::Off | ChecksumType::NoParity => Ok(None),
+        ChecksumType::Label | ChecksumType::GangHeader => Err(BlockVerifyError::Embedded {
+            checksum: ptr.checksum_type,
+        }),
+        ChecksumType::Zilog | ChecksumType::Zilog2 => Ok(Some(ptr.checksum_type)),
+        ChecksumType::Fletcher2
+        | ChecksumType::Fletcher4
+        | ChecksumType::Sha256
+        | ChecksumType::Sha512_256
+        | ChecksumType::Skein
+        | ChecksumType::Edonr
+        | ChecksumType::Blake3 => Ok(Some(ptr.checksum_type)),
+    }
+}
+
+/** Verify the checksum of the `data` block pointed to by `ptr`.
+ *
+ * - `ptr` pointing to the block
+ * - `data` physical bytes of the block
+ * - `checksums` registry, with the per pool checksum salt required for
+ *   [`ChecksumType::Skein`], [`ChecksumType::Edonr`], and
+ *   [`ChecksumType::Blake3`]
+ *
+ * # Errors
+ *
+ * Returns [`BlockVerifyError`] in case of checksum error or mismatch.
+ */
+pub fn verify_block(
+    ptr: &BlockPointerRegular,
+    data: &[u8],
+    checksums: &mut ChecksumRegistry,
+) -> Result<(), BlockVerifyError> {
+    let checksum = match resolve_checksum(ptr)? {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let hasher = checksums.checksum(checksum)?;
+
+    // ZIL blocks embed the checksum, and the block pointer has the verifier.
+    if matches!(checksum, ChecksumType::Zilog | ChecksumType::Zilog2) {
+        embedded_verify(data, checksum, &ptr.checksum_value, hasher)?;
+        return Ok(());
+    }
+
+    // Compute checksum.
+    let computed = hasher.hash(data, ptr.order)?;
+
+    // Compare checksum.
+    if ptr.checksum_value.words == computed {
+        Ok(())
+    } else {
+        Err(BlockVerifyError::Mismatch {
+            checksum,
+            computed: ChecksumValue { words: computed },
+            expected: ChecksumValue {
+                words: ptr.checksum_value.words,
+            },
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Block verify error.
+#[derive(Debug)]
+pub enum BlockVerifyError {
+    /// [`crate::checksum::Checksum`] error.
+    Checksum {
+        /// Error.
+        err: ChecksumError,
+    },
+
+    /// Checksum is embedded in the block.
+    Embedded {
+        /// Embedded checksum.
+        checksum: ChecksumType,
+    },
+
+    /// [`crate::checksum::embedded_verify`] error.
+    EmbeddedVerify {
+        /// Error.
+        err: EmbeddedVerifyError,
+    },
+
+    /// [`ChecksumType::Inherit`] in block pointer.
+    Inherit {},
+
+    /// Checksum mismatch.
+    Mismatch {
+        /// Resolved checksum.
+        checksum: ChecksumType,
+        /// Computed checksum value.
+        computed: ChecksumValue,
+        /// Expected checksum value, from the block pointer.
+        expected: ChecksumValue,
+    },
+}
+
+impl From<ChecksumError> for BlockVerifyError {
+    fn from(value: ChecksumError) -> Self {
+        BlockVerifyError::Checksum { err: value }
+    }
+}
+
+impl From<EmbeddedVerifyError> for BlockVerifyError {
+    fn from(value: EmbeddedVerifyError) -> Self {
+        BlockVerifyError::EmbeddedVerify { err: value }
+    }
+}
+
+impl fmt::Display for BlockVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockVerifyError::Checksum { err } => {
+                write!(f, "Block verify error | {err}")
+            }
+            BlockVerifyError::Embedded { checksum } => {
+                write!(f, "Block verify error, embedded checksum {checksum}")
+            }
+            BlockVerifyError::EmbeddedVerify { err } => {
+                write!(f, "Block verify error | {err}")
+            }
+            BlockVerifyError::Inherit {} => {
+                write!(f, "Block verify error, inherit checksum in block pointer")
+            }
+            BlockVerifyError::Mismatch {
+                checksum,
+                computed,
+                expected,
+            } => write!(
+                f,
+                "Block verify {checksum} mismatch, computed {:#016x?} expected {:#016x?}",
+                computed.words, expected.words,
+            ),
+        }
+    }
+}
+
+impl error::Error for BlockVerifyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BlockVerifyError::Checksum { err } => Some(err),
+            BlockVerifyError::EmbeddedVerify { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::checksum::{
+        verify_block, BlockVerifyError, ChecksumError, ChecksumImplementations, ChecksumRegistry,
+        EmbeddedVerifyError,
+    };
+    use crate::phys::{
+        BlockPointer, BlockPointerRegular, ChecksumTail, ChecksumType, ChecksumValue,
+        CompressionType, DmuType, EndianOrder,
+    };
+
+    /** 128 byte random data.
+     *
+     * Refer to `docs/VERIFY.md` for script to generate test cases.
+     */
+    const TEST_VECTOR_A: [u8; 128] = [
+        0xbc, 0x4b, 0x4d, 0x58, 0x43, 0xca, 0x34, 0x35, 0xe4, 0xd0, 0x59, 0xe4, 0xd0, 0x2b, 0x08,
+        0xe3, 0x2f, 0xe3, 0x78, 0xe1, 0xe6, 0xf6, 0xf1, 0x34, 0x84, 0xdc, 0x1e, 0x0e, 0x12, 0x28,
+        0x2e, 0xbe, 0x53, 0xbd, 0x1a, 0xf9, 0x8a, 0x97, 0x6e, 0xab, 0x7c, 0x06, 0xed, 0x50, 0xa8,
+        0xc9, 0xe4, 0x1e, 0xb8, 0xaf, 0xb8, 0x8c, 0x94, 0xb5, 0x15, 0xed, 0xa8, 0x3f, 0x9d, 0x99,
+        0x9c, 0x26, 0xe8, 0x1d, 0x87, 0x29, 0x1f, 0x60, 0x64, 0xca, 0xd1, 0xe8, 0x48, 0x7e, 0xe4,
+        0xf2, 0x56, 0xf3, 0x59, 0x73, 0x04, 0x39, 0xb2, 0x62, 0x56, 0xea, 0xf1, 0x44, 0xf0, 0x06,
+        0x28, 0x2e, 0x56, 0x16, 0xd3, 0x80, 0x0d, 0x47, 0x9e, 0x87, 0x3f, 0x52, 0x64, 0x30, 0x63,
+        0x6d, 0x64, 0x58, 0xcb, 0x84, 0x4d, 0xf7, 0x1c, 0x6e, 0xc7, 0x07, 0x86, 0x3d, 0x17, 0xec,
+        0x51, 0x8f, 0x51, 0x6e, 0x5a, 0x52, 0x64, 0xee,
+    ];
+
+    /// Salt of bytes 0 to 31.
+    const TEST_SALT: [u8; 32] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e,
+        0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+        0x1e, 0x1f,
+    ];
+
+    /// Length of the block for [`TEST_VECTOR_A_CHECKSUMS`].
+    const TEST_BLOCK_SIZE: usize = 8192;
+
+    /** Checksums of [`TEST_VECTOR_A`] repeated to [`TEST_BLOCK_SIZE`] bytes,
+     * salted with [`TEST_SALT`], for a little and a big endian block.
+     */
+    const TEST_VECTOR_A_CHECKSUMS: [(ChecksumType, [u64; 4], [u64; 4]); 7] = [
+        (
+            ChecksumType::Fletcher2,
+            [
+                0x3a94b284742cea80,
+                0xf8893eb1315d5e00,
+                0xfb9d45b2ff26da40,
+                0x79b14be4b2c384c0,
+            ],
+            [
+                0x6d344472d4ba5240,
+                0xdd31713e8938ed00,
+                0x6f56f29624f7d2c0,
+                0xd68671e36b1d79c0,
+            ],
+        ),
+        (
+            ChecksumType::Fletcher4,
+            [
+                0x00000436d8a837c0,
+                0x0010dde115f0bd40,
+                0x2d0623e6a0511340,
+                0x2f2678bec58fce40,
+            ],
+            [
+                0x000003bba858f300,
+                0x000ef6672cfaff40,
+                0x27f846f1cb5a43c0,
+                0x1e53675f84390500,
+            ],
+        ),
+        (
+            ChecksumType::Sha256,
+            [
+                0x0c75a875bcb35e5f,
+                0xa4fb74395c534e04,
+                0x49ed5650ecf7c098,
+                0x1946cc77b593a752,
+            ],
+            [
+                0x0c75a875bcb35e5f,
+                0xa4fb74395c534e04,
+                0x49ed5650ecf7c098,
+                0x1946cc77b593a752,
+            ],
+        ),
+        (
+            ChecksumType::Sha512_256,
+            [
+                0xc4ad0394beb8b890,
+                0x2af46aa7af1d1f27,
+                0x58d46ab516d6b0ae,
+                0xe9a0b46dc031f835,
+            ],
+            [
+                0x90b8b8be9403adc4,
+                0x271f1dafa76af42a,
+                0xaeb0d616b56ad458,
+                0x35f831c06db4a0e9,
+            ],
+        ),
+        (
+            ChecksumType::Skein,
+            [
+                0x22f9e6f7cf5d2794,
+                0x8509121250376e66,
+                0x27cd98695f7596f1,
+                0x878e5b959557780d,
+            ],
+            [
+                0x94275dcff7e6f922,
+                0x666e375012120985,
+                0xf196755f6998cd27,
+                0x0d785795955b8e87,
+            ],
+        ),
+        (
+            ChecksumType::Edonr,
+            [
+                0x702cfcd37b345855,
+                0x3aaee61dd87b2f64,
+                0x62b93b3de0722b0f,
+                0x65d2d5c68739eb31,
+            ],
+            [
+                0x5558347bd3fc2c70,
+                0x642f7bd81de6ae3a,
+                0x0f2b72e03d3bb962,
+                0x31eb3987c6d5d265,
+            ],
+        ),
+        (
+            ChecksumType::Blake3,
+            [
+                0xc7752ff6532326ca,
+                0xdcf86f5a96f2dc58,
+                0xf8449644bdba9150,
+                0x1c42cdf800616c17,
+            ],
+            [
+                0xca262353f62f75c7,
+                0x58dcf2965a6ff8dc,
+                0x5091babd449644f8,
+                0x176c6100f8cd421c,
+            ],
+        ),
+    ];
+
+    /// Length of the block for [`TEST_VECTOR_A_ZIL_CHECKSUMS`].
+    const TEST_ZIL_BLOCK_SIZE: usize = 4096;
+
+    /// Used bytes of the [`ChecksumType::Zilog2`] block.
+    const TEST_ZIL_USED: u64 = 1024;
+
+    /// ZIL block verifier.
+    const TEST_ZIL_VERIFIER: [u64; 4] = [0x1234, 0x5678, 54, 3];
+
+    /** Embedded checksums of [`TEST_VECTOR_A`] repeated to
+     * [`TEST_ZIL_BLOCK_SIZE`] bytes, for a little and a big endian block.
+     */
+    const TEST_VECTOR_A_ZIL_CHECKSUMS: [(ChecksumType, [u64; 4], [u64; 4]); 2] = [
+        (
+            ChecksumType::Zilog,
+            [
+                0x80cec97caab0d281,
+                0x97d08c5754dd7be8,
+                0x0bc21fb75d54f588,
+                0xc142ea226b9dde9f,
+            ],
+            [
+                0x8ce3bcaaa4cdbf6e,
+                0xcba6b52479bbc0a7,
+                0xb39c587fdf059db2,
+                0x3c9ce61486da1caa,
+            ],
+        ),
+        (
+            ChecksumType::Zilog2,
+            [
+                0x00000214e72e77a4,
+                0x00041f049a5344f0,
+                0x0572d0ef2fd47f72,
+                0x697425508930cdc2,
+            ],
+            [
+                0x000001d7d3e5f7bd,
+                0x0003a877932f3cf3,
+                0x04d773f101967583,
+                0xd08f1d49325301db,
+            ],
+        ),
+    ];
+
+    /// [`TEST_VECTOR_A`] repeated to `size` bytes.
+    fn test_data(size: usize) -> Vec<u8> {
+        TEST_VECTOR_A.iter().copied().cycle().take(size).collect()
+    }
+
+    /// Expected checksum of `checksum` in `order` from `table`.
+    fn expected(
+        table: &[(ChecksumType, [u64; 4], [u64; 4])],
+        checksum: ChecksumType,
+        order: EndianOrder,
+    ) -> [u64; 4] {
+        let (_, little, big) = table.iter().find(|(c, _, _)| *c == checksum).unwrap();
+        match order {
+            EndianOrder::Big => *big,
+            EndianOrder::Little => *little,
+        }
+    }
+
+    /// Block pointer with checksum value `words`.
+    fn block_pointer(
+        checksum_type: ChecksumType,
+        words: [u64; 4],
+        dedup: bool,
+        order: EndianOrder,
+    ) -> BlockPointerRegular {
+        BlockPointerRegular {
+            checksum_type,
+            checksum_value: ChecksumValue { words },
+            compression: CompressionType::Off,
+            dedup,
+            dmu: DmuType::None,
+            dvas: [None, None, None],
+            order,
+            fill_count: 1,
+            level: 0,
+            logical_birth_txg: 1,
+            logical_sectors: 8,
+            physical_birth_txg: 1,
+            physical_sectors: 8,
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let data = test_data(TEST_BLOCK_SIZE);
+        let mut salted = ChecksumRegistry::new().with_salt(TEST_SALT);
+        let mut generic =
+            ChecksumRegistry::with_implementations(ChecksumImplementations::generic())
+                .unwrap()
+                .with_salt(TEST_SALT);
+
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            for (checksum_type, computed, dedup) in [
+                (ChecksumType::On, ChecksumType::Fletcher4, false),
+                (ChecksumType::On, ChecksumType::Sha256, true),
+                (ChecksumType::Fletcher2, ChecksumType::Fletcher2, false),
+                (ChecksumType::Fletcher4, ChecksumType::Fletcher4, false),
+                (ChecksumType::Sha256, ChecksumType::Sha256, true),
+                (ChecksumType::Sha512_256, ChecksumType::Sha512_256, false),
+                (ChecksumType::Skein, ChecksumType::Skein, false),
+                (ChecksumType::Edonr, ChecksumType::Edonr, false),
+                (ChecksumType::Blake3, ChecksumType::Blake3, false),
+            ] {
+                let words = expected(&TEST_VECTOR_A_CHECKSUMS, computed, order);
+                let mut ptr = block_pointer(checksum_type, words, dedup, order);
+                assert!(verify_block(&ptr, &data, &mut salted).is_ok());
+                assert!(verify_block(&ptr, &data, &mut generic).is_ok());
+
+                // Wrong salt.
+                if matches!(
+                    checksum_type,
+                    ChecksumType::Skein | ChecksumType::Edonr | ChecksumType::Blake3
+                ) {
+                    assert!(matches!(
+                        verify_block(&ptr, &data, &mut ChecksumRegistry::new().with_salt([4; 32]))
+                            .unwrap_err(),
+                        BlockVerifyError::Mismatch { .. }
+                    ));
+                }
+
+                // Corrupted checksum.
+                ptr.checksum_value.words[3] ^= 1;
+                match verify_block(&ptr, &data, &mut salted).unwrap_err() {
+                    BlockVerifyError::Mismatch {
+                        checksum,
+                        computed: computed_value,
+                        expected,
+                    } => {
+                        assert_eq!(checksum, computed);
+                        assert_eq!(computed_value.words, words);
+                        assert_eq!(expected.words, ptr.checksum_value.words);
+                    }
+                    _ => panic!("expected mismatch"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn verify_unchecked() {
+        let data = [0xff; 512];
+
+        for checksum_type in [ChecksumType::Off, ChecksumType::NoParity] {
+            let ptr = block_pointer(checksum_type, [0; 4], false, EndianOrder::Little);
+            assert!(verify_block(&ptr, &data, &mut ChecksumRegistry::new()).is_ok());
+        }
+    }
+
+    #[test]
+    fn verify_zil() {
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            for (checksum_type, tail_offset) in [
+                (
+                    ChecksumType::Zilog,
+                    TEST_ZIL_BLOCK_SIZE - ChecksumTail::SIZE,
+                ),
+                (ChecksumType::Zilog2, 8 + BlockPointer::SIZE + 8),
+            ] {
+                let mut data = test_data(TEST_ZIL_BLOCK_SIZE);
+
+                // ZIL chain header used bytes.
+                if checksum_type == ChecksumType::Zilog2 {
+                    let used = match order {
+                        EndianOrder::Big => TEST_ZIL_USED.to_be_bytes(),
+                        EndianOrder::Little => TEST_ZIL_USED.to_le_bytes(),
+                    };
+                    data[tail_offset - 8..tail_offset].copy_from_slice(&used);
+                }
+
+                let tail = ChecksumTail {
+                    order,
+                    value: ChecksumValue {
+                        words: expected(&TEST_VECTOR_A_ZIL_CHECKSUMS, checksum_type, order),
+                    },
+                };
+                tail.to_bytes(
+                    (&mut data[tail_offset..tail_offset + ChecksumTail::SIZE])
+                        .try_into()
+                        .unwrap(),
+                )
+                .unwrap();
+
+                // Block pointer checksum value is the verifier.
+                let mut ptr = block_pointer(checksum_type, TEST_ZIL_VERIFIER, false, order);
+                assert!(verify_block(&ptr, &data, &mut ChecksumRegistry::new()).is_ok());
+
+                // Next block in the ZIL chain has the next sequence.
+                ptr.checksum_value.words[3] += 1;
+                assert!(matches!(
+                    verify_block(&ptr, &data, &mut ChecksumRegistry::new()).unwrap_err(),
+                    BlockVerifyError::EmbeddedVerify {
+                        err: EmbeddedVerifyError::Mismatch { .. }
+                    }
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn verify_error() {
+        let data = [0xff; 512];
+        let order = EndianOrder::Little;
+
+        // Inherit is never stored in a block pointer.
+        let ptr = block_pointer(ChecksumType::Inherit, [0; 4], false, order);
+        assert!(matches!(
+            verify_block(&ptr, &data, &mut ChecksumRegistry::new()).unwrap_err(),
+            BlockVerifyError::Inherit {}
+        ));
+
+        // Label and gang header checksums are not in the block pointer.
+        for checksum_type in [ChecksumType::Label, ChecksumType::GangHeader] {
+            let ptr = block_pointer(checksum_type, [0; 4], false, order);
+            assert!(matches!(
+                verify_block(&ptr, &data, &mut ChecksumRegistry::new()).unwrap_err(),
+                BlockVerifyError::Embedded { checksum } if checksum == checksum_type
+            ));
+        }
+
+        // Salted checksums need the salt.
+        let ptr = block_pointer(ChecksumType::Blake3, [0; 4], false, order);
+        assert!(matches!(
+            verify_block(&ptr, &data, &mut ChecksumRegistry::new()).unwrap_err(),
+            BlockVerifyError::Checksum {
+                err: ChecksumError::MissingSalt {
+                    checksum: ChecksumType::Blake3
+                }
+            }
+        ));
+    }
+}
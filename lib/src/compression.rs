@@ -3,5 +3,20 @@
 pub(crate) mod common;
 pub use common::{Compression, CompressionError, Decompression, DecompressionError};
 
+pub(crate) mod gzip;
+pub use gzip::GzipDecoder;
+
+pub(crate) mod lz4;
+pub use lz4::{Lz4Decoder, Lz4Encoder};
+
 pub(crate) mod lzjb;
 pub use lzjb::{LzjbDecoder, LzjbEncoder};
+
+pub(crate) mod registry;
+pub use registry::{compression_select, DecompressionRegistry};
+
+pub(crate) mod zle;
+pub use zle::{ZleDecoder, ZleEncoder, ZLE_LEVEL};
+
+pub(crate) mod zstd;
+pub use zstd::{ZstdDecoder, ZstdHeader};
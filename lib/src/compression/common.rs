@@ -14,6 +14,12 @@ pub enum CompressionError {
     /// Not compressable. Output would be larger than input.
     NotCompressable {},
 
+    /// Invalid level.
+    InvalidLevel {
+        /// Invalid level value.
+        level: u32,
+    },
+
     /// Unsupported [`CompressionType`].
     Unsupported {
         /// Unsupported compression.
@@ -27,6 +33,9 @@ impl fmt::Display for CompressionError {
             CompressionError::NotCompressable {} => {
                 write!(f, "Compression error, not compressable")
             }
+            CompressionError::InvalidLevel { level } => {
+                write!(f, "Compression error, invalid level {level}")
+            }
             CompressionError::Unsupported { compression } => {
                 write!(f, "Unsupported Compression {compression}")
             }
@@ -82,6 +91,12 @@ pub enum DecompressionError {
         offset: usize,
     },
 
+    /// Invalid level.
+    InvalidLevel {
+        /// Invalid level value.
+        level: u32,
+    },
+
     /// Unsupported [`CompressionType`].
     Unsupported {
         /// Unsupported value.
@@ -102,6 +117,9 @@ impl fmt::Display for DecompressionError {
             DecompressionError::InvalidInput { offset } => {
                 write!(f, "Decompression error, invalid input at offset {offset}")
             }
+            DecompressionError::InvalidLevel { level } => {
+                write!(f, "Decompression error, invalid level {level}")
+            }
             DecompressionError::Unsupported { compression } => {
                 write!(
                     f,
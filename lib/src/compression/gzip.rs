@@ -0,0 +1,956 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Gzip compression.
+ *
+ * Despite the name, ZFS stores gzip compressed data as a zlib stream
+ * (RFC 1950), which wraps deflate compressed data (RFC 1951). The level of
+ * compression (1 to 9) only affects compression, and does not need to be known
+ * for decompression.
+ *
+ * ### Byte layout.
+ *
+ * ```text
+ * +-----+-----+-----------------------+--------------+---------+
+ * | CMF | FLG | deflate blocks        | ADLER32 (BE) | padding |
+ * +-----+-----+-----------------------+--------------+---------+
+ * ```
+ *
+ * - CMF is the compression method (8 for deflate) in the lower four bits, and
+ *   the base two logarithm of the window size minus eight in the upper bits.
+ * - FLG has a check value in the lower five bits, such that CMF * 256 + FLG
+ *   is a multiple of 31, a preset dictionary flag in bit 5, and the
+ *   compression level in the upper bits.
+ * - ADLER32 is a checksum of the decompressed data.
+ *
+ * Deflate data is a sequence of bits, read starting from the least
+ * significant bit of each byte. Each block starts with a three bit header.
+ *
+ * ```text
+ *        2 1 0
+ * +-----+-+-+-+
+ * | ... |t t|f|
+ * +-----+-+-+-+
+ *
+ * f - 1 if this is the final block
+ * t - block type: 0 stored, 1 fixed Huffman codes, 2 dynamic Huffman codes
+ * ```
+ *
+ * Stored blocks are aligned to a byte, and contain a little endian [u16]
+ * length, its one's complement, and the uncompressed bytes.
+ *
+ * Huffman blocks contain a sequence of literal bytes, and length and distance
+ * pairs, which copy previously decompressed bytes, similar to LZ77. Fixed
+ * blocks use predefined codes, and dynamic blocks encode the code lengths
+ * before the data.
+ */
+use core::result::Result;
+
+use crate::compression::{Decompression, DecompressionError};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bytes in the zlib header.
+const HEADER_SIZE: usize = 2;
+
+/// Number of bytes in the zlib Adler-32 footer.
+const FOOTER_SIZE: usize = 4;
+
+/// Compression method for deflate.
+const HEADER_METHOD_DEFLATE: u8 = 8;
+
+/// Mask for compression method.
+const HEADER_METHOD_MASK: u8 = 0x0f;
+
+/// Shift for compression info (window size).
+const HEADER_INFO_SHIFT: u32 = 4;
+
+/// Maximum compression info (window size of 32 KiB).
+const HEADER_INFO_MAX: u8 = 7;
+
+/// Header check value must be a multiple of this.
+const HEADER_CHECK_MODULO: u16 = 31;
+
+/// Preset dictionary flag.
+const HEADER_FLAG_DICTIONARY: u8 = 0x20;
+
+/// Modulo for Adler-32 sums.
+const ADLER_MODULO: u32 = 65521;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Block type of stored (uncompressed) data.
+const BLOCK_TYPE_STORED: u32 = 0;
+
+/// Block type of data compressed with fixed Huffman codes.
+const BLOCK_TYPE_FIXED: u32 = 1;
+
+/// Block type of data compressed with dynamic Huffman codes.
+const BLOCK_TYPE_DYNAMIC: u32 = 2;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Maximum number of bits in a Huffman code.
+const CODE_BITS_MAX: usize = 15;
+
+/// Maximum number of literal and length codes.
+const LITERAL_LENGTH_CODES_MAX: usize = 286;
+
+/// Number of literal and length codes, including two unused fixed codes.
+const LITERAL_LENGTH_CODES_FIXED: usize = 288;
+
+/// Maximum number of distance codes.
+const DISTANCE_CODES_MAX: usize = 30;
+
+/// Number of code length codes.
+const CODE_LENGTH_CODES: usize = 19;
+
+/// Symbol that ends a block.
+const SYMBOL_END_OF_BLOCK: u16 = 256;
+
+/// Order in which code length code lengths are encoded.
+const CODE_LENGTH_ORDER: [usize; CODE_LENGTH_CODES] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Base lengths for length symbols 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+
+/// Extra bits for length symbols 257 to 285.
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances for distance symbols 0 to 29.
+const DISTANCE_BASE: [u16; DISTANCE_CODES_MAX] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// Extra bits for distance symbols 0 to 29.
+const DISTANCE_EXTRA: [u8; DISTANCE_CODES_MAX] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Reads bits from the source, starting at the least significant bit.
+struct BitReader<'a> {
+    src: &'a [u8],
+    src_idx: usize,
+    bits: u32,
+    bits_count: u32,
+}
+
+impl BitReader<'_> {
+    /// Gets `count` bits (at most 16) from the source.
+    fn get_bits(&mut self, count: u32) -> Result<u32, DecompressionError> {
+        while self.bits_count < count {
+            let value = match self.src.get(self.src_idx) {
+                Some(v) => *v,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: self.src_idx,
+                        capacity: self.src.len(),
+                        count: 1,
+                    })
+                }
+            };
+            self.bits |= u32::from(value) << self.bits_count;
+            self.bits_count += u8::BITS;
+            self.src_idx += 1;
+        }
+
+        let value = self.bits & ((1 << count) - 1);
+        self.bits >>= count;
+        self.bits_count -= count;
+
+        Ok(value)
+    }
+
+    /// Discards the remaining bits of the current byte.
+    fn align(&mut self) {
+        self.bits = 0;
+        self.bits_count = 0;
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Canonical Huffman code.
+ *
+ * Codes are decoded one bit at a time. Codes of the same length are
+ * consecutive numbers, so only the number of codes of each length, and the
+ * symbols sorted by code are needed.
+ */
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; CODE_BITS_MAX + 1],
+
+    /// Symbols sorted by code.
+    symbols: [u16; LITERAL_LENGTH_CODES_FIXED],
+}
+
+impl Huffman {
+    /** Creates a Huffman code from code lengths of each symbol.
+     *
+     * Returns the number of unused codes, which is zero for a complete code,
+     * or [`None`] if the lengths are over subscribed.
+     */
+    fn new(lengths: &[u8]) -> (Huffman, Option<i32>) {
+        let mut huffman = Huffman {
+            counts: [0; CODE_BITS_MAX + 1],
+            symbols: [0; LITERAL_LENGTH_CODES_FIXED],
+        };
+
+        // Count the number of codes of each length.
+        for length in lengths {
+            huffman.counts[usize::from(*length)] += 1;
+        }
+
+        // Check for an over subscribed code. There is one code of length
+        // zero (no code), and each additional bit doubles the number of codes.
+        let mut left: i32 = 1;
+        for count in &huffman.counts[1..] {
+            left <<= 1;
+            left -= i32::from(*count);
+            if left < 0 {
+                return (huffman, None);
+            }
+        }
+
+        // Compute the offset of the first symbol of each length.
+        let mut offsets = [0u16; CODE_BITS_MAX + 1];
+        for length in 1..CODE_BITS_MAX {
+            offsets[length + 1] = offsets[length] + huffman.counts[length];
+        }
+
+        // Sort symbols by length, and then by symbol.
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                let offset = &mut offsets[usize::from(*length)];
+                huffman.symbols[usize::from(*offset)] = symbol as u16;
+                *offset += 1;
+            }
+        }
+
+        (huffman, Some(left))
+    }
+
+    /// Decodes the next symbol.
+    fn decode(&self, reader: &mut BitReader<'_>) -> Result<u16, DecompressionError> {
+        // Code being decoded, first code of the current length, and index of
+        // the first symbol of the current length.
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+
+        for count in &self.counts[1..] {
+            // Huffman codes are packed starting from the most significant bit.
+            code |= reader.get_bits(1)? as i32;
+
+            let count = i32::from(*count);
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+
+        // Ran out of codes, which is possible for an incomplete code.
+        Err(DecompressionError::InvalidInput {
+            offset: reader.src_idx,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Gzip decompression decoder.
+pub struct GzipDecoder {}
+
+impl GzipDecoder {
+    /// Decodes a stored block.
+    fn decode_stored(
+        reader: &mut BitReader<'_>,
+        dst: &mut [u8],
+        dst_idx: &mut usize,
+    ) -> Result<(), DecompressionError> {
+        // Stored data starts at a byte boundary.
+        reader.align();
+
+        // Decode the length, and its one's complement.
+        let src = reader.src;
+        let src_idx = reader.src_idx;
+        if src.len() - src_idx < 4 {
+            return Err(DecompressionError::EndOfInput {
+                offset: src_idx,
+                capacity: src.len(),
+                count: 4,
+            });
+        }
+
+        let length = u16::from_le_bytes(src[src_idx..src_idx + 2].try_into().unwrap());
+        let length_complement =
+            u16::from_le_bytes(src[src_idx + 2..src_idx + 4].try_into().unwrap());
+        if length != !length_complement {
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+
+        let length = usize::from(length);
+        let src_idx = src_idx + 4;
+
+        // Check the source and destination sizes.
+        if src.len() - src_idx < length {
+            return Err(DecompressionError::EndOfInput {
+                offset: src_idx,
+                capacity: src.len(),
+                count: length,
+            });
+        }
+
+        if dst.len() - *dst_idx < length {
+            return Err(DecompressionError::InvalidInput {
+                offset: src_idx - 4,
+            });
+        }
+
+        // Copy the bytes.
+        dst[*dst_idx..*dst_idx + length].copy_from_slice(&src[src_idx..src_idx + length]);
+        *dst_idx += length;
+        reader.src_idx = src_idx + length;
+
+        Ok(())
+    }
+
+    /// Decodes a block of Huffman codes.
+    fn decode_codes(
+        reader: &mut BitReader<'_>,
+        dst: &mut [u8],
+        dst_idx: &mut usize,
+        literal_length: &Huffman,
+        distance: &Huffman,
+    ) -> Result<(), DecompressionError> {
+        loop {
+            let symbol = literal_length.decode(reader)?;
+
+            if symbol < SYMBOL_END_OF_BLOCK {
+                ////////////////////////////////
+                // Literal byte.
+                match dst.get_mut(*dst_idx) {
+                    Some(v) => *v = symbol as u8,
+                    None => {
+                        return Err(DecompressionError::InvalidInput {
+                            offset: reader.src_idx,
+                        })
+                    }
+                }
+                *dst_idx += 1;
+            } else if symbol == SYMBOL_END_OF_BLOCK {
+                ////////////////////////////////
+                // End of block.
+                return Ok(());
+            } else {
+                ////////////////////////////////
+                // Length and distance pair.
+                let symbol = usize::from(symbol - SYMBOL_END_OF_BLOCK - 1);
+                if symbol >= LENGTH_BASE.len() {
+                    return Err(DecompressionError::InvalidInput {
+                        offset: reader.src_idx,
+                    });
+                }
+                let length = usize::from(LENGTH_BASE[symbol])
+                    + reader.get_bits(u32::from(LENGTH_EXTRA[symbol]))? as usize;
+
+                let symbol = usize::from(distance.decode(reader)?);
+                if symbol >= DISTANCE_BASE.len() {
+                    return Err(DecompressionError::InvalidInput {
+                        offset: reader.src_idx,
+                    });
+                }
+                let offset = usize::from(DISTANCE_BASE[symbol])
+                    + reader.get_bits(u32::from(DISTANCE_EXTRA[symbol]))? as usize;
+
+                // Safely compute the copy index.
+                let cpy_idx = match dst_idx.checked_sub(offset) {
+                    Some(v) => v,
+                    None => {
+                        return Err(DecompressionError::InvalidInput {
+                            offset: reader.src_idx,
+                        })
+                    }
+                };
+
+                // Check that the copy fits in the destination.
+                if dst.len() - *dst_idx < length {
+                    return Err(DecompressionError::InvalidInput {
+                        offset: reader.src_idx,
+                    });
+                }
+
+                // Copy the bytes one byte at a time, because the copy may
+                // overlap with the bytes being written.
+                for cpy_idx in cpy_idx..cpy_idx + length {
+                    dst[*dst_idx] = dst[cpy_idx];
+                    *dst_idx += 1;
+                }
+            }
+        }
+    }
+
+    /// Decodes a block of fixed Huffman codes.
+    fn decode_fixed(
+        reader: &mut BitReader<'_>,
+        dst: &mut [u8],
+        dst_idx: &mut usize,
+    ) -> Result<(), DecompressionError> {
+        let mut lengths = [0u8; LITERAL_LENGTH_CODES_FIXED];
+        lengths[0..144].fill(8);
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        lengths[280..288].fill(8);
+        let (literal_length, _) = Huffman::new(&lengths);
+
+        let (distance, _) = Huffman::new(&[5; DISTANCE_CODES_MAX]);
+
+        GzipDecoder::decode_codes(reader, dst, dst_idx, &literal_length, &distance)
+    }
+
+    /// Decodes a block of dynamic Huffman codes.
+    fn decode_dynamic(
+        reader: &mut BitReader<'_>,
+        dst: &mut [u8],
+        dst_idx: &mut usize,
+    ) -> Result<(), DecompressionError> {
+        ////////////////////////////////
+        // Decode the number of codes.
+        let header_idx = reader.src_idx;
+        let literal_length_count = reader.get_bits(5)? as usize + 257;
+        let distance_count = reader.get_bits(5)? as usize + 1;
+        let code_length_count = reader.get_bits(4)? as usize + 4;
+
+        if literal_length_count > LITERAL_LENGTH_CODES_MAX || distance_count > DISTANCE_CODES_MAX {
+            return Err(DecompressionError::InvalidInput { offset: header_idx });
+        }
+
+        ////////////////////////////////
+        // Decode the code length code.
+        let mut lengths = [0u8; LITERAL_LENGTH_CODES_MAX + DISTANCE_CODES_MAX];
+        for symbol in &CODE_LENGTH_ORDER[0..code_length_count] {
+            lengths[*symbol] = reader.get_bits(3)? as u8;
+        }
+
+        // The code length code must be complete.
+        let code_length = match Huffman::new(&lengths[0..CODE_LENGTH_CODES]) {
+            (v, Some(0)) => v,
+            _ => return Err(DecompressionError::InvalidInput { offset: header_idx }),
+        };
+
+        ////////////////////////////////
+        // Decode the literal, length and distance code lengths.
+        let total = literal_length_count + distance_count;
+        let mut idx = 0;
+        while idx < total {
+            let symbol = code_length.decode(reader)?;
+
+            // Symbols 0 to 15 are code lengths.
+            if symbol < 16 {
+                lengths[idx] = symbol as u8;
+                idx += 1;
+                continue;
+            }
+
+            // Otherwise, repeat the previous length, or zero.
+            let (length, repeat) = match symbol {
+                16 => {
+                    if idx == 0 {
+                        return Err(DecompressionError::InvalidInput {
+                            offset: reader.src_idx,
+                        });
+                    }
+                    (lengths[idx - 1], 3 + reader.get_bits(2)? as usize)
+                }
+                17 => (0, 3 + reader.get_bits(3)? as usize),
+                _ => (0, 11 + reader.get_bits(7)? as usize),
+            };
+
+            if total - idx < repeat {
+                return Err(DecompressionError::InvalidInput {
+                    offset: reader.src_idx,
+                });
+            }
+
+            lengths[idx..idx + repeat].fill(length);
+            idx += repeat;
+        }
+
+        // There must be a code for the end of block.
+        if lengths[usize::from(SYMBOL_END_OF_BLOCK)] == 0 {
+            return Err(DecompressionError::InvalidInput {
+                offset: reader.src_idx,
+            });
+        }
+
+        ////////////////////////////////
+        // Build the codes. Incomplete codes are only allowed if they have a
+        // single code of length one.
+        let (literal_length, left) = Huffman::new(&lengths[0..literal_length_count]);
+        match left {
+            Some(0) => (),
+            Some(_)
+                if literal_length.counts[0] + literal_length.counts[1]
+                    == literal_length_count as u16 => {}
+            _ => {
+                return Err(DecompressionError::InvalidInput {
+                    offset: reader.src_idx,
+                })
+            }
+        }
+
+        let (distance, left) = Huffman::new(&lengths[literal_length_count..total]);
+        match left {
+            Some(0) => (),
+            Some(_) if distance.counts[0] + distance.counts[1] == distance_count as u16 => {}
+            _ => {
+                return Err(DecompressionError::InvalidInput {
+                    offset: reader.src_idx,
+                })
+            }
+        }
+
+        GzipDecoder::decode_codes(reader, dst, dst_idx, &literal_length, &distance)
+    }
+
+    /// Computes the Adler-32 checksum of the data.
+    fn adler32(data: &[u8]) -> u32 {
+        // Sums are reduced every 5552 bytes, which is the largest number of
+        // bytes that cannot overflow a [u32].
+        let mut a: u32 = 1;
+        let mut b: u32 = 0;
+
+        for chunk in data.chunks(5552) {
+            for value in chunk {
+                a += u32::from(*value);
+                b += a;
+            }
+            a %= ADLER_MODULO;
+            b %= ADLER_MODULO;
+        }
+
+        (b << 16) | a
+    }
+}
+
+impl Decompression for GzipDecoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        ////////////////////////////////
+        // Decode the zlib header.
+        if src.len() < HEADER_SIZE {
+            return Err(DecompressionError::EndOfInput {
+                offset: 0,
+                capacity: src.len(),
+                count: HEADER_SIZE,
+            });
+        }
+
+        let cmf = src[0];
+        let flg = src[1];
+
+        if (cmf & HEADER_METHOD_MASK) != HEADER_METHOD_DEFLATE
+            || (cmf >> HEADER_INFO_SHIFT) > HEADER_INFO_MAX
+        {
+            return Err(DecompressionError::InvalidInput { offset: 0 });
+        }
+
+        if !u16::from_be_bytes([cmf, flg]).is_multiple_of(HEADER_CHECK_MODULO)
+            || (flg & HEADER_FLAG_DICTIONARY) != 0
+        {
+            return Err(DecompressionError::InvalidInput { offset: 1 });
+        }
+
+        ////////////////////////////////
+        // Decode deflate blocks.
+        let mut reader = BitReader {
+            src,
+            src_idx: HEADER_SIZE,
+            bits: 0,
+            bits_count: 0,
+        };
+        let mut dst_idx = 0;
+
+        loop {
+            let block_idx = reader.src_idx;
+            let is_final = reader.get_bits(1)? != 0;
+
+            match reader.get_bits(2)? {
+                BLOCK_TYPE_STORED => GzipDecoder::decode_stored(&mut reader, dst, &mut dst_idx)?,
+                BLOCK_TYPE_FIXED => GzipDecoder::decode_fixed(&mut reader, dst, &mut dst_idx)?,
+                BLOCK_TYPE_DYNAMIC => GzipDecoder::decode_dynamic(&mut reader, dst, &mut dst_idx)?,
+                _ => return Err(DecompressionError::InvalidInput { offset: block_idx }),
+            }
+
+            if is_final {
+                break;
+            }
+        }
+
+        ////////////////////////////////
+        // Check that all of the destination was decompressed.
+        if dst_idx != dst.len() {
+            return Err(DecompressionError::EndOfInput {
+                offset: reader.src_idx,
+                capacity: src.len(),
+                count: 1,
+            });
+        }
+
+        ////////////////////////////////
+        // Verify the checksum, which starts at the next byte. Any remaining
+        // bytes are padding, and are ignored.
+        reader.align();
+        let src_idx = reader.src_idx;
+        if src.len() - src_idx < FOOTER_SIZE {
+            return Err(DecompressionError::EndOfInput {
+                offset: src_idx,
+                capacity: src.len(),
+                count: FOOTER_SIZE,
+            });
+        }
+
+        let checksum = u32::from_be_bytes(src[src_idx..src_idx + FOOTER_SIZE].try_into().unwrap());
+        if checksum != GzipDecoder::adler32(dst) {
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compression::{Decompression, GzipDecoder};
+
+    // Decompressed data, Compressed Data.
+    const TEST_VECTORS: &[(&[u8], &[u8])] = &[
+        // Dynamic Huffman codes, level 1.
+        (
+            &[
+                0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e,
+                0x65, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x7a,
+                0x66, 0x73, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73,
+                0x73, 0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74,
+                0x65, 0x72, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72, 0x20, 0x7a, 0x66, 0x73, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x61,
+                0x70, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64,
+                0x7a, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63,
+                0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63,
+                0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x73, 0x6e, 0x61,
+                0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x6c, 0x7a,
+                0x34, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x66, 0x73,
+                0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x6c, 0x61,
+                0x62, 0x65, 0x6c, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20,
+                0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73,
+                0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f,
+                0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73,
+                0x75, 0x6d, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x73,
+                0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f,
+                0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x64, 0x61, 0x74,
+                0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69,
+                0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20, 0x6d, 0x69,
+                0x72, 0x72, 0x6f, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x7a, 0x73, 0x74,
+                0x64, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64,
+                0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x64,
+                0x6e, 0x6f, 0x64, 0x65, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x72, 0x61, 0x69,
+                0x64, 0x7a, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x70, 0x6f,
+                0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x75, 0x62,
+                0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65,
+                0x63, 0x74, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68,
+                0x6f, 0x74, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63,
+                0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20,
+                0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x64,
+                0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75,
+                0x6d, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e,
+            ],
+            &[
+                0x78, 0x01, 0x6d, 0x52, 0x4b, 0x6e, 0xc4, 0x20, 0x0c, 0xbd, 0x8a, 0x0f, 0x31, 0x17,
+                0x22, 0xe0, 0xce, 0xd0, 0x21, 0x18, 0x01, 0x99, 0x05, 0xa7, 0x6f, 0x8c, 0x83, 0xe3,
+                0x56, 0x5d, 0x20, 0x41, 0xf4, 0xec, 0xf7, 0xcb, 0xb1, 0x61, 0xdd, 0x12, 0xf9, 0x37,
+                0xf8, 0x44, 0x19, 0x81, 0xb6, 0xef, 0x86, 0x1d, 0xd2, 0x78, 0xc0, 0xf8, 0x6a, 0x10,
+                0x5c, 0x77, 0xfc, 0xf6, 0x2f, 0xf4, 0xef, 0x76, 0xec, 0xd0, 0xb2, 0x2b, 0xed, 0x45,
+                0x1d, 0x42, 0xa6, 0x80, 0xe0, 0x69, 0x2f, 0x15, 0x5b, 0x8b, 0x94, 0x27, 0xbe, 0x50,
+                0xcc, 0x1d, 0x2b, 0xec, 0xb1, 0x56, 0xaa, 0xbf, 0x56, 0x0c, 0x57, 0x80, 0xcf, 0x5a,
+                0x59, 0x5d, 0x0c, 0x43, 0x5f, 0x4a, 0x30, 0x61, 0x27, 0xb3, 0xdd, 0xac, 0xa4, 0x32,
+                0xc3, 0xe2, 0xf8, 0x58, 0x08, 0x8b, 0xb5, 0x6f, 0x7b, 0xbf, 0x3c, 0x31, 0x44, 0x16,
+                0x4c, 0xf0, 0x72, 0x24, 0xbe, 0x93, 0xdb, 0x30, 0x4d, 0x7d, 0xf2, 0x56, 0x3d, 0xca,
+                0x6d, 0x37, 0x0a, 0x5a, 0x31, 0xa3, 0xf5, 0x00, 0x9f, 0x80, 0x9f, 0x3b, 0x1e, 0x8b,
+                0xb6, 0x77, 0xd6, 0x7d, 0x85, 0x23, 0x09, 0xde, 0xfb, 0x97, 0xa0, 0x15, 0x90, 0x1d,
+                0x63, 0xc5, 0xcf, 0x11, 0xcb, 0x9a, 0x95, 0xca, 0x26, 0xef, 0xad, 0xe2, 0x0c, 0xf7,
+                0xd0, 0x3a, 0x63, 0x0e, 0xb1, 0xa2, 0x3f, 0xab, 0x33, 0x15, 0xe9, 0x47, 0xe1, 0x16,
+                0x1b, 0x92, 0xc9, 0x94, 0xff, 0xa7, 0x13, 0x33, 0x29, 0xd0, 0x55, 0xaf, 0xd0, 0xff,
+                0x43, 0x26, 0xb0, 0xa9, 0x74, 0xd9, 0x50, 0x83, 0x6b, 0x98, 0x1b, 0x9e, 0x6c, 0xf7,
+                0xbc, 0x64, 0x7e, 0x99, 0xe2, 0xff, 0xe4, 0xb4, 0x3b, 0xcd, 0xad, 0x25, 0x6a, 0xd2,
+                0xb8, 0xf9, 0x01, 0x2a, 0x5d, 0x09, 0xd3,
+            ],
+        ),
+        // Dynamic Huffman codes, level 9.
+        (
+            &[
+                0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e,
+                0x65, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x7a,
+                0x66, 0x73, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73,
+                0x73, 0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74,
+                0x65, 0x72, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72, 0x20, 0x7a, 0x66, 0x73, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x61,
+                0x70, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64,
+                0x7a, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63,
+                0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63,
+                0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x73, 0x6e, 0x61,
+                0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x6c, 0x7a,
+                0x34, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x66, 0x73,
+                0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x6c, 0x61,
+                0x62, 0x65, 0x6c, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20,
+                0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73,
+                0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f,
+                0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73,
+                0x75, 0x6d, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x73,
+                0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f,
+                0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x64, 0x61, 0x74,
+                0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69,
+                0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20, 0x6d, 0x69,
+                0x72, 0x72, 0x6f, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x7a, 0x73, 0x74,
+                0x64, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64,
+                0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x64,
+                0x6e, 0x6f, 0x64, 0x65, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x72, 0x61, 0x69,
+                0x64, 0x7a, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x70, 0x6f,
+                0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x75, 0x62,
+                0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65,
+                0x63, 0x74, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68,
+                0x6f, 0x74, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63,
+                0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20,
+                0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x64,
+                0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75,
+                0x6d, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e,
+            ],
+            &[
+                0x78, 0xda, 0x6d, 0x52, 0x4b, 0x12, 0x83, 0x20, 0x0c, 0xbd, 0x4a, 0x0e, 0xd1, 0x0b,
+                0x21, 0xa4, 0x2d, 0x2d, 0x12, 0x06, 0xd0, 0x05, 0xa7, 0xaf, 0x24, 0x82, 0xa8, 0x5d,
+                0x30, 0x63, 0x9c, 0x97, 0xf7, 0x49, 0xb2, 0x4c, 0x18, 0x27, 0x47, 0xfa, 0x0b, 0xda,
+                0x91, 0x47, 0xa0, 0xe9, 0x93, 0x30, 0x83, 0x2b, 0x0f, 0x28, 0xcf, 0x04, 0x46, 0x65,
+                0x55, 0x6b, 0xfd, 0x46, 0xfd, 0x4d, 0xcb, 0x0c, 0xc9, 0xab, 0x90, 0xde, 0x94, 0xc1,
+                0x78, 0x32, 0x08, 0x9a, 0xe6, 0x10, 0x31, 0x25, 0x4b, 0x9e, 0xf1, 0x81, 0xac, 0xcf,
+                0x18, 0x61, 0xb6, 0x31, 0x52, 0x3c, 0x51, 0x14, 0x15, 0xf8, 0xb5, 0x3a, 0x2a, 0x6b,
+                0xca, 0x5d, 0x80, 0x61, 0x5b, 0xdb, 0xc8, 0xdc, 0x45, 0xa5, 0xa7, 0x9a, 0xab, 0xef,
+                0x2a, 0x3e, 0xd6, 0xe3, 0xf7, 0x9e, 0xa9, 0x42, 0x84, 0x80, 0xc1, 0x4d, 0x50, 0x72,
+                0x3b, 0x35, 0xa1, 0x63, 0x71, 0xa9, 0xef, 0x81, 0x47, 0x46, 0x41, 0x1f, 0x9e, 0x53,
+                0x36, 0xb0, 0x1a, 0x5c, 0xff, 0xa3, 0x4f, 0x9d, 0x9b, 0xef, 0x7d, 0x38, 0x32, 0xc1,
+                0xa3, 0xa3, 0xb1, 0xf5, 0x91, 0x5c, 0xe2, 0xbd, 0x8a, 0x0d, 0xad, 0x57, 0x56, 0xc6,
+                0xba, 0xa7, 0xc9, 0x2d, 0x7d, 0x9d, 0xd6, 0x1b, 0x1b, 0x51, 0x9f, 0x69, 0xfa, 0x4f,
+                0xd1, 0x96, 0x18, 0x32, 0x13, 0xb6, 0x7f, 0xd9, 0xc9, 0x2d, 0x71, 0x5b, 0xaf, 0x48,
+                0xfc, 0x11, 0x13, 0x18, 0x3b, 0x6d, 0x2c, 0x3d, 0x60, 0x6b, 0xae, 0x3e, 0x59, 0x6d,
+                0xb9, 0xdc, 0xde, 0x1e, 0x6a, 0x3f, 0x00, 0x0e, 0x77, 0x3b, 0x8f, 0xc1, 0xd3, 0x0f,
+                0x2a, 0x5d, 0x09, 0xd3,
+            ],
+        ),
+        // Fixed Huffman codes.
+        (
+            &[
+                0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x20, 0x68, 0x65,
+                0x6c, 0x6c, 0x6f, 0x20, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+            ],
+            &[
+                0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x27, 0x01, 0x68, 0x03,
+                0x08, 0xb1,
+            ],
+        ),
+        // Stored block.
+        (
+            &[
+                0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x2c, 0x20,
+                0x6e, 0x6f, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e,
+            ],
+            &[
+                0x78, 0x01, 0x01, 0x1c, 0x00, 0xe3, 0xff, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x64, 0x20,
+                0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x2c, 0x20, 0x6e, 0x6f, 0x20, 0x63, 0x6f, 0x6d, 0x70,
+                0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e, 0x99, 0x11, 0x0a, 0xb8,
+            ],
+        ),
+    ];
+
+    const TEST_VECTORS_DECOMPRESS_ERRORS: &[(usize, &[u8])] = &[
+        // TEST_VECTORS_ZEROS 512, invalid compression method.
+        (
+            512,
+            &[
+                0x79, 0xda, 0x63, 0x60, 0x18, 0x05, 0x23, 0x19, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, invalid header check.
+        (
+            512,
+            &[
+                0x78, 0xdb, 0x63, 0x60, 0x18, 0x05, 0x23, 0x19, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, invalid block type.
+        (
+            512,
+            &[
+                0x78, 0xda, 0x67, 0x60, 0x18, 0x05, 0x23, 0x19, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, invalid checksum.
+        (
+            512,
+            &[
+                0x78, 0xda, 0x63, 0x60, 0x18, 0x05, 0x23, 0x19, 0x00, 0x00, 0x02, 0x00, 0x00, 0x02,
+            ],
+        ),
+        // Stored block of 4 bytes, invalid length complement.
+        (
+            4,
+            &[
+                0x78, 0x01, 0x01, 0x04, 0x00, 0xfb, 0xfe, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00,
+                0x00, 0x01,
+            ],
+        ),
+    ];
+
+    /** Test vectors that decompress to all zeroes.
+     *
+     * Each is a tuple of the number of zeroes, and the compressed data.
+     */
+    const TEST_VECTORS_ZEROS: &[(usize, &[u8])] = &[
+        (
+            512,
+            &[
+                0x78, 0xda, 0x63, 0x60, 0x18, 0x05, 0x23, 0x19, 0x00, 0x00, 0x02, 0x00, 0x00, 0x01,
+            ],
+        ),
+        (
+            4096,
+            &[
+                0x78, 0xda, 0xed, 0xc1, 0x01, 0x0d, 0x00, 0x00, 0x00, 0xc2, 0xa0, 0xf7, 0x4f, 0x6d,
+                0x0f, 0x07, 0x14, 0x00, 0x00, 0x00, 0xf0, 0x6e, 0x10, 0x00, 0x00, 0x01,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn decompress() {
+        let mut gzip = GzipDecoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Decompress the data, with every level.
+            for level in 1..=9 {
+                output.fill(0);
+                gzip.decompress(&mut output, compressed_data, level)
+                    .unwrap();
+                assert_eq!(output, *decompressed_data);
+            }
+
+            // Now test with padding at the end, as if read from a sector.
+            let mut padded = compressed_data.to_vec();
+            padded.resize(compressed_data.len() + 512, 0xaa);
+
+            output.fill(0);
+            gzip.decompress(&mut output, &padded, 0).unwrap();
+            assert_eq!(output, *decompressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress_error() {
+        let mut gzip = GzipDecoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Truncated source.
+            for test_size in 0..compressed_data.len() {
+                assert!(gzip
+                    .decompress(&mut output, &compressed_data[0..test_size], 0)
+                    .is_err());
+            }
+
+            // Output is too small.
+            assert!(gzip
+                .decompress(&mut output[1..], compressed_data, 0)
+                .is_err());
+
+            // Output is too large.
+            let mut output = vec![0; decompressed_data.len() + 1];
+            assert!(gzip.decompress(&mut output, compressed_data, 0).is_err());
+        }
+
+        // Loop over invalid vectors.
+        for (size, compressed_data) in TEST_VECTORS_DECOMPRESS_ERRORS {
+            let mut output = vec![0; *size];
+            assert!(gzip.decompress(&mut output, compressed_data, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn decompress_zeros() {
+        let mut gzip = GzipDecoder {};
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Set the initial values to non-zero to check that data is
+            // actually written out.
+            let mut output = vec![33; *size];
+            gzip.decompress(&mut output, compressed_data, 0).unwrap();
+
+            // Should be all zeroes.
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+}
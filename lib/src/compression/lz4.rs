@@ -0,0 +1,926 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! LZ4 compression.
+ *
+ * LZ4 is an LZ77 type compression algorithm, that favors speed over
+ * compression ratio.
+ *
+ * ### Byte layout.
+ *
+ * ZFS prefixes the LZ4 block with a four byte big endian [u32], which is the
+ * number of bytes of LZ4 compressed data that follow. The remaining bytes up
+ * to the end of the physical block are padding, and are ignored.
+ *
+ * ```text
+ * +-------------+-------------------+---------+
+ * | LENGTH (BE) | LZ4 block data    | padding |
+ * +-------------+-------------------+---------+
+ *      4 bytes    LENGTH bytes
+ * ```
+ *
+ * The LZ4 block data is a sequence of one or more sequences. Each sequence
+ * starts with a TOKEN byte, followed by optional extra literal length bytes,
+ * literal bytes, a two byte little endian match OFFSET, and optional extra
+ * match length bytes. The last sequence is only a TOKEN, extra literal length
+ * bytes, and literal bytes.
+ *
+ * ```text
+ * +-------+-----------+----------+--------+-----------+-------+-----+
+ * | TOKEN | LIT (ext) | literals | OFFSET | MAT (ext) | TOKEN | ... |
+ * +-------+-----------+----------+--------+-----------+-------+-----+
+ * ```
+ *
+ * Bit layout of TOKEN.
+ *
+ * ```text
+ *                0
+ *  7 6 5 4 3 2 1 0
+ * +-------+-------+
+ * |  lit  |  mat  |
+ * +-------+-------+
+ *
+ * lit - number of literal bytes
+ * mat - match length, minus the minimum match length of 4
+ * ```
+ *
+ * If either length is 15, then more bytes follow with the remainder of the
+ * length. Each byte is added to the length, until a byte other than 255 is
+ * encountered.
+ *
+ * To decompress a match, take the current destination, subtract OFFSET, and
+ * use that as the source of the next match length decompressed bytes.
+ */
+use core::result::Result;
+
+use crate::compression::{Compression, CompressionError, Decompression, DecompressionError};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bytes in the ZFS big endian length header.
+const HEADER_SIZE: usize = 4;
+
+/// Number of bytes in an encoded match offset.
+const OFFSET_SIZE: usize = 2;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bits for each length in the token.
+const TOKEN_LENGTH_BITS: u32 = 4;
+
+/// Literal length uses top bits of token.
+const TOKEN_LITERAL_SHIFT: u32 = TOKEN_LENGTH_BITS;
+
+/// Match length uses bottom bits of token.
+const TOKEN_MATCH_MASK: u8 = (1 << TOKEN_LENGTH_BITS) - 1;
+
+/** Value of a token length, which indicates that the length is continued in
+ * the following bytes.
+ */
+const LENGTH_EXTENDED: usize = (1 << TOKEN_LENGTH_BITS) - 1;
+
+/// Value of an extra length byte, which indicates that more bytes follow.
+const LENGTH_EXTENDED_CONTINUE: u8 = 255;
+
+/** The minimum match length is four, and it is subtracted from the length
+ * before it is encoded.
+ */
+const MATCH_MIN: usize = 4;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Number of bytes at the end of the source, that are always encoded as
+ * literals.
+ */
+const LAST_LITERALS: usize = 5;
+
+/** A match must start at least this many bytes before the end of the source.
+ *
+ * This is `COPYLENGTH` (8) plus [`MATCH_MIN`] in the OpenZFS implementation.
+ */
+const MATCH_FIND_LIMIT: usize = 8 + MATCH_MIN;
+
+/// Sources shorter than this are encoded as literals.
+const SOURCE_LENGTH_MIN: usize = MATCH_FIND_LIMIT + 1;
+
+/// Maximum match offset.
+const DISTANCE_MAX: usize = (1 << 16) - 1;
+
+/** Controls how fast the search for a match skips ahead, when no matches are
+ * found.
+ */
+const SKIP_STRENGTH: u32 = 6;
+
+/// Multiplier for the hash of four bytes.
+const HASH_MULTIPLIER: u32 = 2654435761;
+
+/// Number of bits in the hash for sources larger than [`SOURCE_LENGTH_64K`].
+const HASH_LOG: u32 = 12;
+
+/// Number of bits in the hash for sources smaller than [`SOURCE_LENGTH_64K`].
+const HASH_LOG_64K: u32 = HASH_LOG + 1;
+
+/** Sources smaller than this use a larger hash table, because every index
+ * that is inserted into the table fits into a [u16].
+ */
+const SOURCE_LENGTH_64K: usize = (1 << 16) + (MATCH_FIND_LIMIT - 1);
+
+/** Length of hash table. Do not change in order to keep compression
+ * deterministic, because deduplication happens after compression.
+ */
+const TABLE_LENGTH: usize = 1 << HASH_LOG_64K;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** LZ4 compression encoder.
+ *
+ * The output is identical to the LZ4 compressor in OpenZFS, which is based on
+ * LZ4 r131. OpenZFS hashes four bytes in the native byte order of the host,
+ * and this encoder matches the output of little endian hosts.
+ */
+pub struct Lz4Encoder {
+    table: [u32; TABLE_LENGTH],
+}
+
+impl Default for Lz4Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lz4Encoder {
+    /// Create a new LZ4 compression encoder.
+    pub fn new() -> Lz4Encoder {
+        Lz4Encoder {
+            table: [0; TABLE_LENGTH],
+        }
+    }
+
+    /// Reads four bytes at `idx` as a little endian [u32].
+    fn read_u32(src: &[u8], idx: usize) -> u32 {
+        u32::from_le_bytes(src[idx..idx + 4].try_into().unwrap())
+    }
+
+    /// Hashes four bytes at `idx`, down to `hash_log` bits.
+    fn hash(src: &[u8], idx: usize, hash_log: u32) -> usize {
+        (Lz4Encoder::read_u32(src, idx).wrapping_mul(HASH_MULTIPLIER) >> (u32::BITS - hash_log))
+            as usize
+    }
+
+    /// Puts a byte into the destination.
+    fn put_u8(dst: &mut [u8], dst_idx: &mut usize, value: u8) -> Result<(), CompressionError> {
+        match dst.get_mut(*dst_idx) {
+            Some(v) => *v = value,
+            None => return Err(CompressionError::NotCompressable {}),
+        }
+        *dst_idx += 1;
+        Ok(())
+    }
+
+    /// Puts the extra bytes of a length into the destination.
+    fn put_length_extended(
+        dst: &mut [u8],
+        dst_idx: &mut usize,
+        mut length: usize,
+    ) -> Result<(), CompressionError> {
+        while length >= usize::from(LENGTH_EXTENDED_CONTINUE) {
+            Lz4Encoder::put_u8(dst, dst_idx, LENGTH_EXTENDED_CONTINUE)?;
+            length -= usize::from(LENGTH_EXTENDED_CONTINUE);
+        }
+        Lz4Encoder::put_u8(dst, dst_idx, length as u8)
+    }
+
+    /** Compresses `src` into an LZ4 block, without the ZFS length header.
+     *
+     * On success, returns the number of bytes used in `dst`.
+     */
+    fn compress_block(&mut self, dst: &mut [u8], src: &[u8]) -> Result<usize, CompressionError> {
+        // Reset the state of the encoder. An empty entry refers to index 0.
+        self.table.fill(0);
+
+        // Smaller sources use a larger table.
+        let hash_log = if src.len() < SOURCE_LENGTH_64K {
+            HASH_LOG_64K
+        } else {
+            HASH_LOG
+        };
+
+        // Source and destination indices.
+        let mut src_idx: usize = 0;
+        let mut dst_idx: usize = 0;
+
+        // Start of the literals, that have not been encoded yet.
+        let mut anchor: usize = 0;
+
+        'sequences: {
+            // Sources that are too short are only literals.
+            if src.len() < SOURCE_LENGTH_MIN {
+                break 'sequences;
+            }
+
+            // Last index where a match may start, and last index of a match.
+            let match_find_limit = src.len() - MATCH_FIND_LIMIT;
+            let match_limit = src.len() - LAST_LITERALS;
+
+            // The first byte is index 0, which is already in the empty table.
+            src_idx += 1;
+            let mut forward_hash = Lz4Encoder::hash(src, src_idx, hash_log);
+
+            loop {
+                ////////////////////////////////
+                // Find a match.
+                //
+                // The step size increases after every 64 failed attempts,
+                // which quickly skips over data that is not compressable.
+                let mut find_match_attempts: usize = (1 << SKIP_STRENGTH) + 3;
+                let mut forward_idx = src_idx;
+                let mut cpy_idx;
+
+                loop {
+                    let hash = forward_hash;
+                    let step = find_match_attempts >> SKIP_STRENGTH;
+                    find_match_attempts += 1;
+
+                    src_idx = forward_idx;
+                    forward_idx = src_idx + step;
+
+                    if forward_idx > match_find_limit {
+                        break 'sequences;
+                    }
+
+                    forward_hash = Lz4Encoder::hash(src, forward_idx, hash_log);
+                    cpy_idx = self.table[hash] as usize;
+                    self.table[hash] = src_idx as u32;
+
+                    if src_idx - cpy_idx <= DISTANCE_MAX
+                        && Lz4Encoder::read_u32(src, cpy_idx) == Lz4Encoder::read_u32(src, src_idx)
+                    {
+                        break;
+                    }
+                }
+
+                // Extend the match backwards.
+                while src_idx > anchor && cpy_idx > 0 && src[src_idx - 1] == src[cpy_idx - 1] {
+                    src_idx -= 1;
+                    cpy_idx -= 1;
+                }
+
+                ////////////////////////////////
+                // Encode the literals.
+                let literals = src_idx - anchor;
+                let mut token_idx = dst_idx;
+                dst_idx += 1;
+
+                // Check the output limit, in the same way as OpenZFS, which is
+                // stricter than needed, so that the same data is found to be
+                // not compressable.
+                if dst_idx + literals + (OFFSET_SIZE + 1 + LAST_LITERALS) + (literals >> 8)
+                    > dst.len()
+                {
+                    return Err(CompressionError::NotCompressable {});
+                }
+
+                if literals >= LENGTH_EXTENDED {
+                    dst[token_idx] = (LENGTH_EXTENDED << TOKEN_LITERAL_SHIFT) as u8;
+                    Lz4Encoder::put_length_extended(dst, &mut dst_idx, literals - LENGTH_EXTENDED)?;
+                } else {
+                    dst[token_idx] = (literals << TOKEN_LITERAL_SHIFT) as u8;
+                }
+
+                if dst.len() - dst_idx < literals {
+                    return Err(CompressionError::NotCompressable {});
+                }
+                dst[dst_idx..dst_idx + literals].copy_from_slice(&src[anchor..src_idx]);
+                dst_idx += literals;
+
+                loop {
+                    ////////////////////////////////
+                    // Encode the match offset.
+                    let offset = (src_idx - cpy_idx) as u16;
+                    for b in offset.to_le_bytes() {
+                        Lz4Encoder::put_u8(dst, &mut dst_idx, b)?;
+                    }
+
+                    // The first MATCH_MIN bytes are already known to match.
+                    src_idx += MATCH_MIN;
+                    cpy_idx += MATCH_MIN;
+                    anchor = src_idx;
+
+                    while src_idx < match_limit && src[src_idx] == src[cpy_idx] {
+                        src_idx += 1;
+                        cpy_idx += 1;
+                    }
+
+                    ////////////////////////////////
+                    // Encode the match length.
+                    let length = src_idx - anchor;
+
+                    if dst_idx + (1 + LAST_LITERALS) + (length >> 8) > dst.len() {
+                        return Err(CompressionError::NotCompressable {});
+                    }
+
+                    if length >= LENGTH_EXTENDED {
+                        dst[token_idx] += LENGTH_EXTENDED as u8;
+                        Lz4Encoder::put_length_extended(
+                            dst,
+                            &mut dst_idx,
+                            length - LENGTH_EXTENDED,
+                        )?;
+                    } else {
+                        dst[token_idx] += length as u8;
+                    }
+
+                    // Check for the end of the source.
+                    if src_idx > match_find_limit {
+                        anchor = src_idx;
+                        break 'sequences;
+                    }
+
+                    // Fill the table.
+                    self.table[Lz4Encoder::hash(src, src_idx - 2, hash_log)] = (src_idx - 2) as u32;
+
+                    // Check for an immediate match, with no literals.
+                    let hash = Lz4Encoder::hash(src, src_idx, hash_log);
+                    cpy_idx = self.table[hash] as usize;
+                    self.table[hash] = src_idx as u32;
+
+                    if src_idx - cpy_idx <= DISTANCE_MAX
+                        && Lz4Encoder::read_u32(src, cpy_idx) == Lz4Encoder::read_u32(src, src_idx)
+                    {
+                        token_idx = dst_idx;
+                        Lz4Encoder::put_u8(dst, &mut dst_idx, 0)?;
+                        continue;
+                    }
+
+                    break;
+                }
+
+                // Prepare for the next match.
+                anchor = src_idx;
+                src_idx += 1;
+                forward_hash = Lz4Encoder::hash(src, src_idx, hash_log);
+            }
+        }
+
+        ////////////////////////////////
+        // Encode the last literals.
+        let literals = src.len() - anchor;
+
+        if dst_idx + literals + 1 + ((literals + 255 - LENGTH_EXTENDED) / 255) > dst.len() {
+            return Err(CompressionError::NotCompressable {});
+        }
+
+        if literals >= LENGTH_EXTENDED {
+            Lz4Encoder::put_u8(
+                dst,
+                &mut dst_idx,
+                (LENGTH_EXTENDED << TOKEN_LITERAL_SHIFT) as u8,
+            )?;
+            Lz4Encoder::put_length_extended(dst, &mut dst_idx, literals - LENGTH_EXTENDED)?;
+        } else {
+            Lz4Encoder::put_u8(dst, &mut dst_idx, (literals << TOKEN_LITERAL_SHIFT) as u8)?;
+        }
+
+        if dst.len() - dst_idx < literals {
+            return Err(CompressionError::NotCompressable {});
+        }
+        dst[dst_idx..dst_idx + literals].copy_from_slice(&src[anchor..]);
+        dst_idx += literals;
+
+        Ok(dst_idx)
+    }
+}
+
+impl Compression for Lz4Encoder {
+    fn compress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<usize, CompressionError> {
+        // Reserve space for the header.
+        if dst.len() < HEADER_SIZE {
+            return Err(CompressionError::NotCompressable {});
+        }
+
+        // Compress the data after the header.
+        let length = self.compress_block(&mut dst[HEADER_SIZE..], src)?;
+
+        // Encode the length of the compressed data, because it is needed for
+        // decompression, and the end of the block may be padded.
+        let length_u32 = match u32::try_from(length) {
+            Ok(v) => v,
+            Err(_) => return Err(CompressionError::NotCompressable {}),
+        };
+        dst[0..HEADER_SIZE].copy_from_slice(&u32::to_be_bytes(length_u32));
+
+        Ok(HEADER_SIZE + length)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// LZ4 decompression decoder.
+pub struct Lz4Decoder {}
+
+impl Lz4Decoder {
+    /** Decodes the extra bytes of a length, starting at `src_idx`.
+     *
+     * On success, returns the sum of the extra bytes, and advances `src_idx`
+     * past them.
+     */
+    fn decode_length_extended(
+        src: &[u8],
+        src_idx: &mut usize,
+    ) -> Result<usize, DecompressionError> {
+        let mut length: usize = 0;
+
+        loop {
+            // Safely get the next length byte.
+            let value = match src.get(*src_idx) {
+                Some(v) => *v,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: *src_idx,
+                        capacity: src.len(),
+                        count: 1,
+                    })
+                }
+            };
+
+            // Safely add the length, which could only overflow on malicious
+            // input.
+            length = match length.checked_add(usize::from(value)) {
+                Some(v) => v,
+                None => return Err(DecompressionError::InvalidInput { offset: *src_idx }),
+            };
+
+            // Increment the source.
+            *src_idx += 1;
+
+            // Any value other than 255 terminates the length.
+            if value != LENGTH_EXTENDED_CONTINUE {
+                return Ok(length);
+            }
+        }
+    }
+}
+
+impl Decompression for Lz4Decoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        ////////////////////////////////
+        // Decode the length of the compressed data.
+        if src.len() < HEADER_SIZE {
+            return Err(DecompressionError::EndOfInput {
+                offset: 0,
+                capacity: src.len(),
+                count: HEADER_SIZE,
+            });
+        }
+
+        let length = u32::from_be_bytes(src[0..HEADER_SIZE].try_into().unwrap());
+        let length = match usize::try_from(length) {
+            Ok(v) => v,
+            Err(_) => return Err(DecompressionError::InvalidInput { offset: 0 }),
+        };
+
+        // Check that the compressed data fits in the source.
+        if src.len() - HEADER_SIZE < length {
+            return Err(DecompressionError::EndOfInput {
+                offset: HEADER_SIZE,
+                capacity: src.len(),
+                count: length,
+            });
+        }
+
+        // Ignore any padding after the compressed data.
+        let src = &src[0..HEADER_SIZE + length];
+
+        ////////////////////////////////
+        // Decode sequences.
+
+        // Source and destination indices.
+        let mut src_idx: usize = HEADER_SIZE;
+        let mut dst_idx: usize = 0;
+
+        loop {
+            // Safely get the next token.
+            let token_idx = src_idx;
+            let token = match src.get(src_idx) {
+                Some(v) => *v,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: src_idx,
+                        capacity: src.len(),
+                        count: 1,
+                    })
+                }
+            };
+
+            // Increment the source.
+            src_idx += 1;
+
+            ////////////////////////////////
+            // Decode the literal length.
+            let mut literals = usize::from(token >> TOKEN_LITERAL_SHIFT);
+            if literals == LENGTH_EXTENDED {
+                literals += Lz4Decoder::decode_length_extended(src, &mut src_idx)?;
+            }
+
+            // Check that there are enough literals in the source.
+            if src.len() - src_idx < literals {
+                return Err(DecompressionError::EndOfInput {
+                    offset: src_idx,
+                    capacity: src.len(),
+                    count: literals,
+                });
+            }
+
+            // Check that the literals fit in the destination.
+            if dst.len() - dst_idx < literals {
+                return Err(DecompressionError::InvalidInput { offset: token_idx });
+            }
+
+            // Copy literals from source to destination.
+            dst[dst_idx..dst_idx + literals].copy_from_slice(&src[src_idx..src_idx + literals]);
+            src_idx += literals;
+            dst_idx += literals;
+
+            // The last sequence only has literals, and ends exactly at the end
+            // of the compressed data.
+            if src_idx == src.len() {
+                break;
+            }
+
+            ////////////////////////////////
+            // Decode the match offset.
+            if src.len() - src_idx < OFFSET_SIZE {
+                return Err(DecompressionError::EndOfInput {
+                    offset: src_idx,
+                    capacity: src.len(),
+                    count: OFFSET_SIZE,
+                });
+            }
+
+            let offset = usize::from(u16::from_le_bytes(
+                src[src_idx..src_idx + OFFSET_SIZE].try_into().unwrap(),
+            ));
+
+            // Safely compute the copy index. An offset of zero is invalid.
+            let cpy_idx = match dst_idx.checked_sub(offset) {
+                Some(v) if offset != 0 => v,
+                _ => return Err(DecompressionError::InvalidInput { offset: src_idx }),
+            };
+
+            // Increment the source.
+            src_idx += OFFSET_SIZE;
+
+            ////////////////////////////////
+            // Decode the match length.
+            let mut length = usize::from(token & TOKEN_MATCH_MASK);
+            if length == LENGTH_EXTENDED {
+                length += Lz4Decoder::decode_length_extended(src, &mut src_idx)?;
+            }
+            let length = length + MATCH_MIN;
+
+            // Check that the match fits in the destination.
+            if dst.len() - dst_idx < length {
+                return Err(DecompressionError::InvalidInput { offset: token_idx });
+            }
+
+            // Copy the bytes one byte at a time, because the match may overlap
+            // with the bytes being written, when the offset is smaller than
+            // the length. Refer to [`crate::compression::LzjbDecoder`].
+            for cpy_idx in cpy_idx..cpy_idx + length {
+                dst[dst_idx] = dst[cpy_idx];
+                dst_idx += 1;
+            }
+        }
+
+        ////////////////////////////////
+        // Check that all of the destination was decompressed.
+        if dst_idx != dst.len() {
+            return Err(DecompressionError::EndOfInput {
+                offset: src_idx,
+                capacity: src.len(),
+                count: 1,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compression::{Compression, Decompression, Lz4Decoder, Lz4Encoder};
+
+    // Decompressed data, Compressed Data.
+    const TEST_VECTORS: &[(&[u8], &[u8])] = &[(
+        &[
+            0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77,
+            0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76,
+            0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f,
+            0x67, 0x20, 0x30, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b,
+            0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d,
+            0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61,
+            0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x31, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20,
+            0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f,
+            0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74,
+            0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x32, 0x2e,
+            0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f,
+            0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f,
+            0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64,
+            0x6f, 0x67, 0x20, 0x33, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63,
+            0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75,
+            0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c,
+            0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x34, 0x2e, 0x20, 0x54, 0x68, 0x65,
+            0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66,
+            0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20,
+            0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x35,
+            0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72,
+            0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20,
+            0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20,
+            0x64, 0x6f, 0x67, 0x20, 0x36, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69,
+            0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a,
+            0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20,
+            0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x30, 0x2e, 0x20, 0x54, 0x68,
+            0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20,
+            0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72,
+            0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20,
+            0x31, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62,
+            0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73,
+            0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79,
+            0x20, 0x64, 0x6f, 0x67, 0x20, 0x32, 0x2e, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75,
+            0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20,
+            0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65,
+            0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x33, 0x2e, 0x20, 0x54,
+            0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63, 0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e,
+            0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75, 0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65,
+            0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c, 0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67,
+            0x20, 0x34, 0x2e, 0x20, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+            0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+        ],
+        &[
+            0x00, 0x00, 0x00, 0x84, 0xff, 0x20, 0x54, 0x68, 0x65, 0x20, 0x71, 0x75, 0x69, 0x63,
+            0x6b, 0x20, 0x62, 0x72, 0x6f, 0x77, 0x6e, 0x20, 0x66, 0x6f, 0x78, 0x20, 0x6a, 0x75,
+            0x6d, 0x70, 0x73, 0x20, 0x6f, 0x76, 0x65, 0x72, 0x20, 0x74, 0x68, 0x65, 0x20, 0x6c,
+            0x61, 0x7a, 0x79, 0x20, 0x64, 0x6f, 0x67, 0x20, 0x30, 0x2e, 0x20, 0x2f, 0x00, 0x19,
+            0x1f, 0x31, 0x2f, 0x00, 0x1b, 0x1f, 0x32, 0x2f, 0x00, 0x1b, 0x1f, 0x33, 0x2f, 0x00,
+            0x1b, 0x1f, 0x34, 0x2f, 0x00, 0x1b, 0x1f, 0x35, 0x2f, 0x00, 0x1b, 0x1f, 0x36, 0x2f,
+            0x00, 0x1b, 0x0f, 0x49, 0x01, 0xac, 0x1f, 0x00, 0x01, 0x00, 0x2d, 0xf0, 0x18, 0x01,
+            0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+        ],
+    )];
+
+    const TEST_VECTORS_DECOMPRESS_ERRORS: &[(usize, &[u8])] = &[
+        // TEST_VECTORS_ZEROS 512, length is larger than source.
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0d, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, offset is zero.
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0c, 0x1f, 0x00, 0x00, 0x00, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, offset is too large.
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0c, 0x1f, 0x00, 0x02, 0x00, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, match length is too large.
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0c, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xee, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        // TEST_VECTORS_ZEROS 512, last literals are too short.
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0b, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xe8, 0x40, 0x00, 0x00, 0x00,
+                0x00,
+            ],
+        ),
+    ];
+
+    /** Test vectors that decompress to all zeroes.
+     *
+     * Each is a tuple of the number of zeroes, and the compressed data.
+     */
+    const TEST_VECTORS_ZEROS: &[(usize, &[u8])] = &[
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0c, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        (
+            4096,
+            &[
+                0x00, 0x00, 0x00, 0x1a, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xf6, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+        (
+            131072,
+            &[
+                0x00, 0x00, 0x02, 0x0c, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn compress() {
+        let mut lz4: Lz4Encoder = Default::default();
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Compress the data.
+            let ret = lz4.compress(&mut output, decompressed_data, 0).unwrap();
+
+            // Check result.
+            assert_eq!(ret, compressed_data.len());
+            assert_eq!(&output[0..ret], *compressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress() {
+        let mut lz4 = Lz4Decoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Decompress the data.
+            lz4.decompress(&mut output, compressed_data, 0).unwrap();
+
+            // Check result.
+            assert_eq!(output, *decompressed_data);
+
+            // Now test with padding at the end, as if read from a sector.
+            let mut padded = compressed_data.to_vec();
+            padded.resize(compressed_data.len() + 512, 0xaa);
+
+            output.fill(0);
+            lz4.decompress(&mut output, &padded, 0).unwrap();
+            assert_eq!(output, *decompressed_data);
+        }
+    }
+
+    #[test]
+    fn compress_error() {
+        let mut lz4 = Lz4Encoder::new();
+
+        // Loop over test vectors.
+        for (decompressed_data, _compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Compress the data.
+            let ret = lz4.compress(&mut output, decompressed_data, 0).unwrap();
+
+            // Provide an output that is too small.
+            for test_size in 0..ret {
+                assert!(lz4
+                    .compress(&mut output[0..test_size], decompressed_data, 0)
+                    .is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn decompress_error() {
+        let mut lz4 = Lz4Decoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Truncated source.
+            for test_size in 0..compressed_data.len() {
+                assert!(lz4
+                    .decompress(&mut output, &compressed_data[0..test_size], 0)
+                    .is_err());
+            }
+
+            // Output is too small.
+            assert!(lz4
+                .decompress(&mut output[1..], compressed_data, 0)
+                .is_err());
+
+            // Output is too large.
+            let mut output = vec![0; decompressed_data.len() + 1];
+            assert!(lz4.decompress(&mut output, compressed_data, 0).is_err());
+        }
+
+        // Loop over invalid vectors.
+        for (size, compressed_data) in TEST_VECTORS_DECOMPRESS_ERRORS {
+            let mut output = vec![0; *size];
+            assert!(lz4.decompress(&mut output, compressed_data, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn compress_zeros() {
+        let mut lz4 = Lz4Encoder::new();
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Output is the same size as input.
+            let input = vec![0; *size];
+            let mut output = vec![0; *size];
+            let ret = lz4.compress(&mut output, &input, 0).unwrap();
+
+            // Check result.
+            assert_eq!(&output[0..ret], *compressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress_zeros() {
+        let mut lz4 = Lz4Decoder {};
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Set the initial values to non-zero to check that data is
+            // actually written out.
+            let mut output = vec![33; *size];
+            lz4.decompress(&mut output, compressed_data, 0).unwrap();
+
+            // Should be all zeroes.
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+}
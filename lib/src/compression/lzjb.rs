@@ -430,7 +430,7 @@ impl Decompression for LzjbDecoder {
                 let offset = usize::from(length_offset & OFFSET_MASK);
 
                 // Safely compute the copy index.
-                let mut cpy_idx = match dst_idx.checked_sub(offset) {
+                let cpy_idx = match dst_idx.checked_sub(offset) {
                     Some(v) => v,
                     None => {
                         return Err(DecompressionError::InvalidInput {
@@ -450,10 +450,9 @@ impl Decompression for LzjbDecoder {
                 // the bytes. If cpy_idx + length > dst_idx, then it may copy
                 // bytes from dst_idx + 1 before copying them from
                 // dst_idx + 1 - length.
-                for _ in 0..length {
+                for cpy_idx in cpy_idx..cpy_idx + length {
                     dst[dst_idx] = dst[cpy_idx];
                     dst_idx += 1;
-                    cpy_idx += 1;
                 }
             } else {
                 // Copy source to destination.
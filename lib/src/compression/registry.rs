@@ -0,0 +1,333 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Decompression dispatch by [`CompressionType`].
+ *
+ * Block pointers store the [`CompressionType`] that was used to write the
+ * block, which is always a concrete algorithm. Dataset properties may also be
+ * [`CompressionType::Inherit`] or [`CompressionType::On`], which are resolved
+ * with [`compression_select`].
+ */
+use core::result::Result;
+
+use crate::compression::{
+    Decompression, DecompressionError, GzipDecoder, Lz4Decoder, LzjbDecoder, ZleDecoder,
+    ZstdDecoder, ZLE_LEVEL,
+};
+use crate::phys::{CompressionType, Feature, FeatureSet};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Resolves a child [`CompressionType`] against its parent.
+ *
+ * - [`CompressionType::Inherit`] uses the `parent` value.
+ * - [`CompressionType::On`] uses [`CompressionType::Lz4`] if
+ *   [`Feature::Lz4Compress`] is in `features`, else [`CompressionType::Lzjb`].
+ *
+ * A `parent` of [`CompressionType::Inherit`] is treated as
+ * [`CompressionType::Off`], because there is nothing left to inherit from.
+ */
+pub fn compression_select(
+    child: CompressionType,
+    parent: CompressionType,
+    features: &FeatureSet,
+) -> CompressionType {
+    let compression = match child {
+        CompressionType::Inherit => parent,
+        _ => child,
+    };
+
+    match compression {
+        CompressionType::Inherit => CompressionType::Off,
+        CompressionType::On => {
+            if features.contains(Feature::Lz4Compress) {
+                CompressionType::Lz4
+            } else {
+                CompressionType::Lzjb
+            }
+        }
+        _ => compression,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Decoder for [`CompressionType::Off`].
+ *
+ * Copies the source to the destination. Any extra source bytes are padding.
+ */
+struct OffDecoder {}
+
+impl Decompression for OffDecoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        match src.get(0..dst.len()) {
+            Some(v) => {
+                dst.copy_from_slice(v);
+                Ok(())
+            }
+            None => Err(DecompressionError::EndOfInput {
+                offset: 0,
+                capacity: src.len(),
+                count: dst.len(),
+            }),
+        }
+    }
+}
+
+/// Decoder for [`CompressionType::Empty`], which fills the destination with zeroes.
+struct EmptyDecoder {}
+
+impl Decompression for EmptyDecoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        _src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        dst.fill(0);
+        Ok(())
+    }
+}
+
+/** Decoder that always uses the same level.
+ *
+ * Used for algorithms like [`CompressionType::Zle`], where the level is fixed
+ * by the [`CompressionType`], and not by the caller.
+ */
+struct FixedLevelDecoder<D: Decompression> {
+    decoder: D,
+    level: u32,
+}
+
+impl<D: Decompression> Decompression for FixedLevelDecoder<D> {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        self.decoder.decompress(dst, src, self.level)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Registry of decoders for every supported [`CompressionType`].
+ *
+ * Holds one instance of each decoder, so that decoders with large state (such
+ * as [`ZstdDecoder`]) are only created once, and reused for every block.
+ */
+pub struct DecompressionRegistry {
+    empty: EmptyDecoder,
+    gzip: GzipDecoder,
+    lz4: Lz4Decoder,
+    lzjb: LzjbDecoder,
+    off: OffDecoder,
+    zle: FixedLevelDecoder<ZleDecoder>,
+    zstd: ZstdDecoder,
+}
+
+impl Default for DecompressionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecompressionRegistry {
+    /// Create a new decompression registry.
+    pub fn new() -> DecompressionRegistry {
+        DecompressionRegistry {
+            empty: EmptyDecoder {},
+            gzip: GzipDecoder {},
+            lz4: Lz4Decoder {},
+            lzjb: LzjbDecoder {},
+            off: OffDecoder {},
+            zle: FixedLevelDecoder {
+                decoder: ZleDecoder {},
+                level: ZLE_LEVEL,
+            },
+            zstd: ZstdDecoder::new(),
+        }
+    }
+
+    /** Gets the decoder for a [`CompressionType`].
+     *
+     * [`CompressionType::On`] is resolved using `features`. The `level`
+     * argument of [`Decompression::decompress`] is ignored by the returned
+     * decoder, because the level is determined by the [`CompressionType`].
+     *
+     * # Errors
+     *
+     * Returns [`DecompressionError::Unsupported`] for
+     * [`CompressionType::Inherit`], which must first be resolved against the
+     * parent with [`compression_select`].
+     */
+    pub fn decompressor_for(
+        &mut self,
+        compression: CompressionType,
+        features: &FeatureSet,
+    ) -> Result<&mut dyn Decompression, DecompressionError> {
+        // Resolve On, but leave Inherit as is.
+        let compression = match compression {
+            CompressionType::Inherit => compression,
+            _ => compression_select(compression, CompressionType::Off, features),
+        };
+
+        match compression {
+            CompressionType::Inherit | CompressionType::On => {
+                Err(DecompressionError::Unsupported { compression })
+            }
+            CompressionType::Off => Ok(&mut self.off),
+            CompressionType::Lzjb => Ok(&mut self.lzjb),
+            CompressionType::Empty => Ok(&mut self.empty),
+            CompressionType::Gzip1
+            | CompressionType::Gzip2
+            | CompressionType::Gzip3
+            | CompressionType::Gzip4
+            | CompressionType::Gzip5
+            | CompressionType::Gzip6
+            | CompressionType::Gzip7
+            | CompressionType::Gzip8
+            | CompressionType::Gzip9 => Ok(&mut self.gzip),
+            CompressionType::Zle => Ok(&mut self.zle),
+            CompressionType::Lz4 => Ok(&mut self.lz4),
+            CompressionType::Zstd => Ok(&mut self.zstd),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compression::{compression_select, DecompressionError, DecompressionRegistry};
+    use crate::phys::{CompressionType, Feature, FeatureSet};
+
+    /// 512 zeroes compressed with each [`CompressionType`].
+    const TEST_VECTORS_ZEROS: &[(CompressionType, &[u8])] = &[
+        (CompressionType::Empty, &[]),
+        (CompressionType::Zle, &[0xff, 0xff, 0xbf]),
+        (
+            CompressionType::Lz4,
+            &[
+                0x00, 0x00, 0x00, 0x0c, 0x1f, 0x00, 0x01, 0x00, 0xff, 0xe8, 0x50, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn select() {
+        let empty = FeatureSet::default();
+        let mut lz4 = FeatureSet::default();
+        lz4.insert(Feature::Lz4Compress);
+
+        // On depends on features.
+        assert_eq!(
+            compression_select(CompressionType::On, CompressionType::Off, &empty),
+            CompressionType::Lzjb
+        );
+        assert_eq!(
+            compression_select(CompressionType::On, CompressionType::Off, &lz4),
+            CompressionType::Lz4
+        );
+
+        // Inherit uses the parent, which may be On.
+        assert_eq!(
+            compression_select(CompressionType::Inherit, CompressionType::Gzip3, &lz4),
+            CompressionType::Gzip3
+        );
+        assert_eq!(
+            compression_select(CompressionType::Inherit, CompressionType::On, &lz4),
+            CompressionType::Lz4
+        );
+        assert_eq!(
+            compression_select(CompressionType::Inherit, CompressionType::Inherit, &lz4),
+            CompressionType::Off
+        );
+
+        // Concrete values are unchanged.
+        assert_eq!(
+            compression_select(CompressionType::Zstd, CompressionType::Lz4, &empty),
+            CompressionType::Zstd
+        );
+    }
+
+    #[test]
+    fn decompress_zeros() {
+        let mut registry = DecompressionRegistry::new();
+        let features = FeatureSet::default();
+
+        // Loop over test vectors.
+        for (compression, compressed_data) in TEST_VECTORS_ZEROS {
+            // Set the initial values to non-zero to check that data is
+            // actually written out.
+            let mut output = vec![33; 512];
+
+            // Level is ignored, and chosen by the registry.
+            registry
+                .decompressor_for(*compression, &features)
+                .unwrap()
+                .decompress(&mut output, compressed_data, 0)
+                .unwrap();
+
+            // Should be all zeroes.
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+
+    #[test]
+    fn decompress_on() {
+        let mut registry = DecompressionRegistry::new();
+        let mut features = FeatureSet::default();
+        features.insert(Feature::Lz4Compress);
+
+        // On resolves to Lz4.
+        let (_, compressed_data) = TEST_VECTORS_ZEROS[2];
+        let mut output = vec![33; 512];
+        registry
+            .decompressor_for(CompressionType::On, &features)
+            .unwrap()
+            .decompress(&mut output, compressed_data, 0)
+            .unwrap();
+        assert!(output.iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn decompress_off() {
+        let mut registry = DecompressionRegistry::new();
+        let features = FeatureSet::default();
+
+        let input: Vec<u8> = (0..=255).collect();
+        let decoder = registry
+            .decompressor_for(CompressionType::Off, &features)
+            .unwrap();
+
+        // Copies the source.
+        let mut output = vec![0; input.len()];
+        decoder.decompress(&mut output, &input, 0).unwrap();
+        assert_eq!(output, input);
+
+        // Source is too small.
+        let mut output = vec![0; input.len() + 1];
+        assert!(decoder.decompress(&mut output, &input, 0).is_err());
+    }
+
+    #[test]
+    fn decompressor_for_error() {
+        let mut registry = DecompressionRegistry::new();
+        let features = FeatureSet::default();
+
+        assert!(matches!(
+            registry.decompressor_for(CompressionType::Inherit, &features),
+            Err(DecompressionError::Unsupported {
+                compression: CompressionType::Inherit
+            })
+        ));
+    }
+}
@@ -0,0 +1,462 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Zero Length Encoding (ZLE) compression.
+ *
+ * ZLE only compresses runs of zero bytes, and copies all other bytes as is.
+ * It is used for blocks that are mostly zeroes, and have little else to
+ * compress.
+ *
+ * ### Byte layout.
+ *
+ * The byte layout of ZLE compressed data is a sequence of one byte LENGTH,
+ * followed by zero or more literal bytes.
+ *
+ * ```text
+ * +--------+----------+--------+----------+-----+
+ * | LENGTH | literals | LENGTH | literals | ... |
+ * +--------+----------+--------+----------+-----+
+ * ```
+ *
+ * The compression level `n` (64 in ZFS) splits the value of LENGTH into two
+ * ranges. If LENGTH + 1 is less than or equal to `n`, then LENGTH + 1 literal
+ * bytes follow. Otherwise, no bytes follow, and LENGTH + 1 - `n` zero bytes
+ * are decompressed.
+ *
+ * ```text
+ * 0                 n - 1                 255
+ * +-------------------+-------------------+
+ * |    1 to n bytes   |  1 to 256 - n     |
+ * |    of literals    |  zero bytes       |
+ * +-------------------+-------------------+
+ * ```
+ *
+ * A run of literals ends early at a sequence of two zero bytes, so a single
+ * zero byte may be encoded as a literal.
+ */
+use core::cmp;
+use core::result::Result;
+
+use crate::compression::{Compression, CompressionError, Decompression, DecompressionError};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Level used by ZFS for [`crate::phys::CompressionType::Zle`].
+pub const ZLE_LEVEL: u32 = 64;
+
+/// Minimum valid level.
+const LEVEL_MIN: u32 = 1;
+
+/// Maximum valid level.
+const LEVEL_MAX: u32 = 255;
+
+/** Number of distinct LENGTH values. Literal runs use the first `n`, and zero
+ * runs use the rest.
+ */
+const LENGTH_VALUES: usize = 256;
+
+/** Checks that the level is valid, and returns it as a [`usize`].
+ *
+ * A level of zero would leave no room for literals, and a level larger than
+ * 255 would leave no room for zeroes.
+ */
+fn level_to_usize(level: u32) -> Option<usize> {
+    if (LEVEL_MIN..=LEVEL_MAX).contains(&level) {
+        Some(level as usize)
+    } else {
+        None
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** ZLE compression encoder.
+ *
+ * The level is the maximum number of literal bytes in one run, which ZFS sets
+ * to 64.
+ */
+pub struct ZleEncoder {}
+
+impl Compression for ZleEncoder {
+    fn compress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        level: u32,
+    ) -> Result<usize, CompressionError> {
+        let n = match level_to_usize(level) {
+            Some(v) => v,
+            None => return Err(CompressionError::InvalidLevel { level }),
+        };
+
+        // Source and destination indices.
+        let mut src_idx: usize = 0;
+        let mut dst_idx: usize = 0;
+
+        while src_idx < src.len() {
+            // Check that there is space for the LENGTH and at least one more
+            // byte. This conforms to the existing ZFS behavior.
+            if dst.len() - dst_idx < 2 {
+                return Err(CompressionError::NotCompressable {});
+            }
+
+            // Reserve the LENGTH byte.
+            let start_idx = src_idx;
+            let length_idx = dst_idx;
+            dst_idx += 1;
+
+            if src[src_idx] == 0 {
+                // Count up to 256 - n zero bytes.
+                let end_idx = cmp::min(src_idx + (LENGTH_VALUES - n), src.len());
+                while src_idx < end_idx && src[src_idx] == 0 {
+                    src_idx += 1;
+                }
+
+                // At most 256 - n - 1 + n = 255, so it fits in a byte.
+                dst[length_idx] = (src_idx - start_idx - 1 + n) as u8;
+            } else {
+                // Check that there is space for a full run of literals, even if
+                // fewer are used. This conforms to the existing ZFS behavior.
+                if dst.len() - dst_idx < n {
+                    return Err(CompressionError::NotCompressable {});
+                }
+
+                // Copy up to n bytes, stopping before two zero bytes in a row.
+                let end_idx = cmp::min(src_idx + n, src.len());
+                while src_idx < end_idx - 1 && (src[src_idx] | src[src_idx + 1]) != 0 {
+                    dst[dst_idx] = src[src_idx];
+                    dst_idx += 1;
+                    src_idx += 1;
+                }
+
+                // The last byte before the end is not checked by the loop.
+                if src[src_idx] != 0 {
+                    dst[dst_idx] = src[src_idx];
+                    dst_idx += 1;
+                    src_idx += 1;
+                }
+
+                // At least one byte is copied, because the first byte is not
+                // zero, and at most n, which is at most 255.
+                dst[length_idx] = (src_idx - start_idx - 1) as u8;
+            }
+        }
+
+        Ok(dst_idx)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** ZLE decompression decoder.
+ *
+ * The level must be the same as the one used for compression.
+ */
+pub struct ZleDecoder {}
+
+impl Decompression for ZleDecoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        level: u32,
+    ) -> Result<(), DecompressionError> {
+        let n = match level_to_usize(level) {
+            Some(v) => v,
+            None => return Err(DecompressionError::InvalidLevel { level }),
+        };
+
+        // Source and destination indices.
+        let mut src_idx: usize = 0;
+        let mut dst_idx: usize = 0;
+
+        // Any bytes in the source after the destination is full are padding,
+        // and are ignored.
+        while dst_idx < dst.len() {
+            // Safely get the next length.
+            let length_idx = src_idx;
+            let length = match src.get(src_idx) {
+                Some(v) => usize::from(*v) + 1,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: src_idx,
+                        capacity: src.len(),
+                        count: 1,
+                    })
+                }
+            };
+
+            // Increment the source.
+            src_idx += 1;
+
+            if length <= n {
+                // Check that there are enough literals in the source.
+                if src.len() - src_idx < length {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: src_idx,
+                        capacity: src.len(),
+                        count: length,
+                    });
+                }
+
+                // Check that the literals fit in the destination.
+                if dst.len() - dst_idx < length {
+                    return Err(DecompressionError::InvalidInput { offset: length_idx });
+                }
+
+                // Copy literals from source to destination.
+                dst[dst_idx..dst_idx + length].copy_from_slice(&src[src_idx..src_idx + length]);
+                src_idx += length;
+                dst_idx += length;
+            } else {
+                let length = length - n;
+
+                // Check that the zeroes fit in the destination.
+                if dst.len() - dst_idx < length {
+                    return Err(DecompressionError::InvalidInput { offset: length_idx });
+                }
+
+                // Fill in zeroes.
+                dst[dst_idx..dst_idx + length].fill(0);
+                dst_idx += length;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compression::{Compression, Decompression, ZleDecoder, ZleEncoder, ZLE_LEVEL};
+
+    const LEVEL: u32 = ZLE_LEVEL;
+
+    // Decompressed data, Compressed Data.
+    const TEST_VECTORS: &[(&[u8], &[u8])] = &[(
+        &[
+            0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5a,
+            0x46, 0x53, 0x00, 0x5a, 0x4c, 0x45, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02,
+            0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+            0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e,
+            0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28, 0x29, 0x2a, 0x2b, 0x2c,
+            0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a,
+            0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+            0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f, 0x00, 0x00, 0xff,
+        ],
+        &[
+            0x02, 0x01, 0x02, 0x03, 0x49, 0x06, 0x5a, 0x46, 0x53, 0x00, 0x5a, 0x4c, 0x45, 0xff,
+            0xab, 0x3f, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c,
+            0x0d, 0x0e, 0x0f, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a,
+            0x1b, 0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27, 0x28,
+            0x29, 0x2a, 0x2b, 0x2c, 0x2d, 0x2e, 0x2f, 0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36,
+            0x37, 0x38, 0x39, 0x3a, 0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40, 0x0e, 0x41, 0x42, 0x43,
+            0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f, 0x41, 0x00,
+            0xff,
+        ],
+    )];
+
+    const TEST_VECTORS_DECOMPRESS_ERRORS: &[(usize, &[u8])] = &[
+        // TEST_VECTORS_ZEROS 512, zero run is too long.
+        (512, &[0xff, 0xff, 0xc0]),
+        // TEST_VECTORS_ZEROS 512, literals are too long.
+        (512, &[0xff, 0xff, 0x3f, 0x00]),
+    ];
+
+    /** Test vectors that decompress to all zeroes.
+     *
+     * Each is a tuple of the number of zeroes, and the compressed data.
+     */
+    const TEST_VECTORS_ZEROS: &[(usize, &[u8])] = &[
+        (512, &[0xff, 0xff, 0xbf]),
+        (
+            4096,
+            &[
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f,
+            ],
+        ),
+    ];
+
+    #[test]
+    fn compress() {
+        let mut zle = ZleEncoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Compress the data.
+            let ret = zle.compress(&mut output, decompressed_data, LEVEL).unwrap();
+
+            // Check result.
+            assert_eq!(ret, compressed_data.len());
+            assert_eq!(&output[0..ret], *compressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress() {
+        let mut zle = ZleDecoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Decompress the data.
+            zle.decompress(&mut output, compressed_data, LEVEL).unwrap();
+
+            // Check result.
+            assert_eq!(output, *decompressed_data);
+
+            // Now test with padding at the end, as if read from a sector.
+            let mut padded = compressed_data.to_vec();
+            padded.resize(compressed_data.len() + 512, 0xaa);
+
+            output.fill(0);
+            zle.decompress(&mut output, &padded, LEVEL).unwrap();
+            assert_eq!(output, *decompressed_data);
+        }
+    }
+
+    #[test]
+    fn round_trip_levels() {
+        let mut encoder = ZleEncoder {};
+        let mut decoder = ZleDecoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, _compressed_data) in TEST_VECTORS {
+            for level in [1, 2, 63, 64, 65, 254, 255] {
+                // Output is larger than input, for levels that do not compress.
+                let mut compressed = vec![0; 2 * decompressed_data.len()];
+                let ret = encoder
+                    .compress(&mut compressed, decompressed_data, level)
+                    .unwrap();
+
+                let mut output = vec![0; decompressed_data.len()];
+                decoder
+                    .decompress(&mut output, &compressed[0..ret], level)
+                    .unwrap();
+                assert_eq!(output, *decompressed_data);
+            }
+        }
+    }
+
+    #[test]
+    fn compress_error() {
+        let mut zle = ZleEncoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, _compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Compress the data.
+            let ret = zle.compress(&mut output, decompressed_data, LEVEL).unwrap();
+
+            // Provide an output that is too small.
+            for test_size in 0..ret {
+                assert!(zle
+                    .compress(&mut output[0..test_size], decompressed_data, LEVEL)
+                    .is_err());
+            }
+
+            // Invalid levels.
+            for level in [0, 256, u32::MAX] {
+                assert!(zle.compress(&mut output, decompressed_data, level).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn decompress_error() {
+        let mut zle = ZleDecoder {};
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Truncated source.
+            for test_size in 0..compressed_data.len() {
+                assert!(zle
+                    .decompress(&mut output, &compressed_data[0..test_size], LEVEL)
+                    .is_err());
+            }
+
+            // NOTE: An output that is too small cannot be detected, because
+            //       the remaining source is treated as padding.
+
+            // Output is too large.
+            let mut output = vec![0; decompressed_data.len() + 1];
+            assert!(zle.decompress(&mut output, compressed_data, LEVEL).is_err());
+
+            // Invalid levels.
+            for level in [0, 256, u32::MAX] {
+                assert!(zle.decompress(&mut output, compressed_data, level).is_err());
+            }
+        }
+
+        // Loop over invalid vectors.
+        for (size, compressed_data) in TEST_VECTORS_DECOMPRESS_ERRORS {
+            let mut output = vec![0; *size];
+            assert!(zle.decompress(&mut output, compressed_data, LEVEL).is_err());
+        }
+    }
+
+    #[test]
+    fn compress_zeros() {
+        let mut zle = ZleEncoder {};
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Output is the same size as input.
+            let input = vec![0; *size];
+            let mut output = vec![0; *size];
+            let ret = zle.compress(&mut output, &input, LEVEL).unwrap();
+
+            // Check result.
+            assert_eq!(&output[0..ret], *compressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress_zeros() {
+        let mut zle = ZleDecoder {};
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Set the initial values to non-zero to check that data is
+            // actually written out.
+            let mut output = vec![33; *size];
+            zle.decompress(&mut output, compressed_data, LEVEL).unwrap();
+
+            // Should be all zeroes.
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+}
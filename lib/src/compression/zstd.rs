@@ -0,0 +1,1887 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Zstandard compression.
+ *
+ * Zstandard (RFC 8878) combines LZ77 style matches with Huffman coded
+ * literals, and Finite State Entropy (FSE) coded sequences.
+ *
+ * ### Byte layout.
+ *
+ * ZFS prefixes the Zstandard frame with an eight byte header. The first four
+ * bytes are a big endian [u32], which is the number of bytes of compressed
+ * data that follow. The next four bytes are a big endian [u32], where the most
+ * significant byte is the ZFS zstd level, and the remaining bytes are the
+ * version of the Zstandard library that compressed the data. The remaining
+ * bytes up to the end of the physical block are padding, and are ignored.
+ *
+ * ```text
+ * +-------------+-------+-----------+------------------------+---------+
+ * | LENGTH (BE) | LEVEL | VERSION   | Zstandard frame        | padding |
+ * +-------------+-------+-----------+------------------------+---------+
+ *      4 bytes    1 byte   3 bytes    LENGTH bytes
+ * ```
+ *
+ * The frame does not have the four byte magic number (magicless format).
+ * It starts with a frame header, followed by one or more blocks, and an
+ * optional checksum.
+ *
+ * ```text
+ * +--------------+-------+-----+-------+----------+
+ * | FRAME HEADER | BLK 0 | ... | BLK N | CHECKSUM |
+ * +--------------+-------+-----+-------+----------+
+ * ```
+ *
+ * Each block starts with a three byte little endian header, with the last
+ * block flag, the block type, and the block size.
+ *
+ * ```text
+ *              2               1               0
+ *  7 6 5 4 3 2 1 0 7 6 5 4 3 2 1 0 7 6 5 4 3 2 1 0
+ * +---------------------------------------+---+-+
+ * |            block size (21)            |typ|l|
+ * +---------------------------------------+---+-+
+ * ```
+ *
+ * Compressed blocks contain a literals section, and a sequences section. Each
+ * sequence copies a number of literals, followed by a match from previously
+ * decompressed data.
+ */
+use core::result::Result;
+
+use crate::compression::{Decompression, DecompressionError};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bytes in the ZFS header.
+const HEADER_SIZE: usize = 8;
+
+/// Shift for the level in the ZFS header.
+const HEADER_LEVEL_SHIFT: u32 = 24;
+
+/// Mask for the version in the ZFS header.
+const HEADER_VERSION_MASK: u32 = (1 << HEADER_LEVEL_SHIFT) - 1;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Shift for the frame content size flag.
+const FRAME_CONTENT_SIZE_SHIFT: u32 = 6;
+
+/// Single segment flag, which means there is no window descriptor.
+const FRAME_SINGLE_SEGMENT: u8 = 0x20;
+
+/// Reserved bit, which must be zero.
+const FRAME_RESERVED: u8 = 0x08;
+
+/// Content checksum flag.
+const FRAME_CHECKSUM: u8 = 0x04;
+
+/// Mask for the dictionary id flag.
+const FRAME_DICTIONARY_MASK: u8 = 0x03;
+
+/// Number of bytes in the content checksum.
+const FRAME_CHECKSUM_SIZE: usize = 4;
+
+/// Value added to two byte frame content sizes.
+const FRAME_CONTENT_SIZE_2_OFFSET: u64 = 256;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Number of bytes in a block header.
+const BLOCK_HEADER_SIZE: usize = 3;
+
+/// Block of raw bytes.
+const BLOCK_TYPE_RAW: u32 = 0;
+
+/// Block of a single repeated byte.
+const BLOCK_TYPE_RLE: u32 = 1;
+
+/// Compressed block.
+const BLOCK_TYPE_COMPRESSED: u32 = 2;
+
+/// Shift for the block type.
+const BLOCK_TYPE_SHIFT: u32 = 1;
+
+/// Mask for the block type, after shifting.
+const BLOCK_TYPE_MASK: u32 = 0x3;
+
+/// Shift for the block size.
+const BLOCK_SIZE_SHIFT: u32 = 3;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Literals are raw bytes.
+const LITERALS_TYPE_RAW: u8 = 0;
+
+/// Literals are a single repeated byte.
+const LITERALS_TYPE_RLE: u8 = 1;
+
+/// Literals are Huffman coded, with a new Huffman table.
+const LITERALS_TYPE_COMPRESSED: u8 = 2;
+
+/// Maximum number of bits in a Huffman code.
+const HUFFMAN_BITS_MAX: u32 = 11;
+
+/// Maximum accuracy log of the FSE table for Huffman weights.
+const HUFFMAN_WEIGHTS_ACCURACY_LOG_MAX: u32 = 6;
+
+/// Maximum number of Huffman weights, encoded in the tree description.
+const HUFFMAN_WEIGHTS_MAX: usize = 255;
+
+/// Number of bytes in the jump table of four literal streams.
+const JUMP_TABLE_SIZE: usize = 6;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Predefined table is used.
+const MODE_PREDEFINED: u8 = 0;
+
+/// Table with a single symbol.
+const MODE_RLE: u8 = 1;
+
+/// FSE table description follows.
+const MODE_FSE: u8 = 2;
+
+/// Minimum accuracy log of an FSE table description.
+const FSE_ACCURACY_LOG_MIN: u32 = 5;
+
+/// Maximum number of entries in an FSE table.
+const FSE_TABLE_LENGTH_MAX: usize = 1 << 9;
+
+/// Maximum number of symbols in an FSE table.
+const FSE_SYMBOLS_MAX: usize = 64;
+
+/// Initial repeat offsets, at the start of a frame.
+const REPEAT_OFFSETS_INITIAL: [usize; 3] = [1, 4, 8];
+
+/// Offset values less than or equal to this refer to repeat offsets.
+const REPEAT_OFFSETS_COUNT: usize = 3;
+
+/// Accuracy log of predefined literals length table.
+const LITERALS_LENGTH_DEFAULT_ACCURACY_LOG: u32 = 6;
+
+/// Predefined literals length distribution.
+const LITERALS_LENGTH_DEFAULT_COUNTS: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+
+/// Accuracy log of predefined match length table.
+const MATCH_LENGTH_DEFAULT_ACCURACY_LOG: u32 = 6;
+
+/// Predefined match length distribution.
+const MATCH_LENGTH_DEFAULT_COUNTS: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1, -1, -1,
+];
+
+/// Accuracy log of predefined offset table.
+const OFFSET_DEFAULT_ACCURACY_LOG: u32 = 5;
+
+/// Predefined offset distribution.
+const OFFSET_DEFAULT_COUNTS: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+/// Baseline and number of extra bits of each literals length code.
+const LITERALS_LENGTH_CODES: [(u32, u32); 36] = [
+    (0, 0),
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 0),
+    (12, 0),
+    (13, 0),
+    (14, 0),
+    (15, 0),
+    (16, 1),
+    (18, 1),
+    (20, 1),
+    (22, 1),
+    (24, 2),
+    (28, 2),
+    (32, 3),
+    (40, 3),
+    (48, 4),
+    (64, 6),
+    (128, 7),
+    (256, 8),
+    (512, 9),
+    (1024, 10),
+    (2048, 11),
+    (4096, 12),
+    (8192, 13),
+    (16384, 14),
+    (32768, 15),
+    (65536, 16),
+];
+
+/// Baseline and number of extra bits of each match length code.
+const MATCH_LENGTH_CODES: [(u32, u32); 53] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 0),
+    (12, 0),
+    (13, 0),
+    (14, 0),
+    (15, 0),
+    (16, 0),
+    (17, 0),
+    (18, 0),
+    (19, 0),
+    (20, 0),
+    (21, 0),
+    (22, 0),
+    (23, 0),
+    (24, 0),
+    (25, 0),
+    (26, 0),
+    (27, 0),
+    (28, 0),
+    (29, 0),
+    (30, 0),
+    (31, 0),
+    (32, 0),
+    (33, 0),
+    (34, 0),
+    (35, 1),
+    (37, 1),
+    (39, 1),
+    (41, 1),
+    (43, 2),
+    (47, 2),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 5),
+    (131, 7),
+    (259, 8),
+    (515, 9),
+    (1027, 10),
+    (2051, 11),
+    (4099, 12),
+    (8195, 13),
+    (16387, 14),
+    (32771, 15),
+    (65539, 16),
+];
+
+/// Parameters of a sequences table.
+struct SequenceTableParameters {
+    /// Predefined distribution.
+    default_counts: &'static [i16],
+
+    /// Accuracy log of predefined distribution.
+    default_accuracy_log: u32,
+
+    /// Maximum accuracy log of an FSE table description.
+    accuracy_log_max: u32,
+
+    /// Number of symbols (codes).
+    symbols: usize,
+}
+
+/// Literals length table parameters.
+const LITERALS_LENGTH_TABLE: SequenceTableParameters = SequenceTableParameters {
+    default_counts: &LITERALS_LENGTH_DEFAULT_COUNTS,
+    default_accuracy_log: LITERALS_LENGTH_DEFAULT_ACCURACY_LOG,
+    accuracy_log_max: 9,
+    symbols: LITERALS_LENGTH_CODES.len(),
+};
+
+/// Match length table parameters.
+const MATCH_LENGTH_TABLE: SequenceTableParameters = SequenceTableParameters {
+    default_counts: &MATCH_LENGTH_DEFAULT_COUNTS,
+    default_accuracy_log: MATCH_LENGTH_DEFAULT_ACCURACY_LOG,
+    accuracy_log_max: 9,
+    symbols: MATCH_LENGTH_CODES.len(),
+};
+
+/// Offset table parameters, where codes are at most 31.
+const OFFSET_TABLE: SequenceTableParameters = SequenceTableParameters {
+    default_counts: &OFFSET_DEFAULT_COUNTS,
+    default_accuracy_log: OFFSET_DEFAULT_ACCURACY_LOG,
+    accuracy_log_max: 8,
+    symbols: 32,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// ZFS zstd header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ZstdHeader {
+    /// Number of bytes of compressed data, after the header.
+    pub compressed_size: u32,
+
+    /// ZFS zstd level, where 1 to 19 are regular levels, and larger values
+    /// are fast levels.
+    pub level: u8,
+
+    /// Version of the Zstandard library, such as 10405 for 1.4.5.
+    pub version: u32,
+}
+
+impl ZstdHeader {
+    /// Byte size of an encoded [`ZstdHeader`].
+    pub const SIZE: usize = HEADER_SIZE;
+
+    /** Decodes a [`ZstdHeader`] from the start of compressed data.
+     *
+     * # Errors
+     *
+     * Returns [`DecompressionError`] if there are not enough bytes.
+     */
+    pub fn from_bytes(src: &[u8]) -> Result<ZstdHeader, DecompressionError> {
+        if src.len() < HEADER_SIZE {
+            return Err(DecompressionError::EndOfInput {
+                offset: 0,
+                capacity: src.len(),
+                count: HEADER_SIZE,
+            });
+        }
+
+        let compressed_size = u32::from_be_bytes(src[0..4].try_into().unwrap());
+        let version_level = u32::from_be_bytes(src[4..8].try_into().unwrap());
+
+        Ok(ZstdHeader {
+            compressed_size,
+            level: (version_level >> HEADER_LEVEL_SHIFT) as u8,
+            version: version_level & HEADER_VERSION_MASK,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Reads bits from the start of a source, starting at the least significant bit.
+struct ForwardBitReader<'a> {
+    src: &'a [u8],
+    offset: usize,
+    bit_idx: usize,
+}
+
+impl ForwardBitReader<'_> {
+    /// Peeks at the next `count` bits, where bits past the end are zero.
+    fn peek_bits(&self, count: u32) -> u32 {
+        let mut value: u32 = 0;
+        for bit in 0..count as usize {
+            let idx = self.bit_idx + bit;
+            if let Some(byte) = self.src.get(idx / 8) {
+                value |= u32::from((byte >> (idx % 8)) & 1) << bit;
+            }
+        }
+        value
+    }
+
+    /// Consumes `count` bits.
+    fn consume_bits(&mut self, count: u32) -> Result<(), DecompressionError> {
+        self.bit_idx += count as usize;
+        if self.bit_idx > self.src.len() * 8 {
+            return Err(DecompressionError::EndOfInput {
+                offset: self.offset + self.src.len(),
+                capacity: self.offset + self.src.len(),
+                count: 1,
+            });
+        }
+        Ok(())
+    }
+
+    /// Gets the next `count` bits.
+    fn get_bits(&mut self, count: u32) -> Result<u32, DecompressionError> {
+        let value = self.peek_bits(count);
+        self.consume_bits(count)?;
+        Ok(value)
+    }
+
+    /// Number of bytes that have been (partially) read.
+    fn bytes_read(&self) -> usize {
+        self.bit_idx.div_ceil(8)
+    }
+}
+
+/** Reads bits from the end of a source, towards the start.
+ *
+ * The last byte has a one bit marker above the first bit of the stream. Bits
+ * are read from the most significant to the least significant, and bits
+ * before the start of the source are zero.
+ */
+struct BackwardBitReader<'a> {
+    src: &'a [u8],
+    bits_left: isize,
+}
+
+impl BackwardBitReader<'_> {
+    /// Creates a new [`BackwardBitReader`], where `offset` is used for errors.
+    fn new(src: &[u8], offset: usize) -> Result<BackwardBitReader<'_>, DecompressionError> {
+        let last = match src.last() {
+            Some(v) => *v,
+            None => {
+                return Err(DecompressionError::EndOfInput {
+                    offset,
+                    capacity: offset,
+                    count: 1,
+                })
+            }
+        };
+
+        // The last byte must have a marker bit.
+        if last == 0 {
+            return Err(DecompressionError::InvalidInput {
+                offset: offset + src.len() - 1,
+            });
+        }
+
+        Ok(BackwardBitReader {
+            src,
+            bits_left: (src.len() * 8) as isize - (last.leading_zeros() + 1) as isize,
+        })
+    }
+
+    /// Peeks at the next `count` (at most 56) bits.
+    fn peek_bits(&self, count: u32) -> u64 {
+        if count == 0 {
+            return 0;
+        }
+
+        let start = self.bits_left - count as isize;
+        let byte_idx = start.div_euclid(8);
+        let shift = start.rem_euclid(8);
+
+        // Load eight bytes, where bytes outside of the source are zero.
+        let mut value: u64 = 0;
+        for idx in 0..8 {
+            let src_idx = byte_idx + idx;
+            if src_idx >= 0 {
+                if let Some(byte) = self.src.get(src_idx as usize) {
+                    value |= u64::from(*byte) << (8 * idx);
+                }
+            }
+        }
+
+        (value >> shift) & ((1 << count) - 1)
+    }
+
+    /// Consumes `count` bits.
+    fn consume_bits(&mut self, count: u32) {
+        self.bits_left -= count as isize;
+    }
+
+    /// Gets the next `count` (at most 56) bits.
+    fn get_bits(&mut self, count: u32) -> u64 {
+        let value = self.peek_bits(count);
+        self.consume_bits(count);
+        value
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// FSE table entry.
+#[derive(Clone, Copy)]
+struct FseEntry {
+    /// Decoded symbol.
+    symbol: u8,
+
+    /// Number of bits to read for the next state.
+    bits: u8,
+
+    /// Base of the next state.
+    base: u16,
+}
+
+/// FSE decoding table.
+struct FseTable {
+    /// Table has at most `1 << accuracy_log` entries.
+    accuracy_log: u32,
+
+    /// Table entries.
+    entries: [FseEntry; FSE_TABLE_LENGTH_MAX],
+
+    /// Is the table initialized, and can be repeated.
+    is_valid: bool,
+}
+
+impl FseTable {
+    /// Creates an empty table.
+    fn new() -> FseTable {
+        FseTable {
+            accuracy_log: 0,
+            entries: [FseEntry {
+                symbol: 0,
+                bits: 0,
+                base: 0,
+            }; FSE_TABLE_LENGTH_MAX],
+            is_valid: false,
+        }
+    }
+
+    /// Initializes a table with a single symbol.
+    fn init_rle(&mut self, symbol: u8) {
+        self.accuracy_log = 0;
+        self.entries[0] = FseEntry {
+            symbol,
+            bits: 0,
+            base: 0,
+        };
+        self.is_valid = true;
+    }
+
+    /** Initializes a table from normalized counts of each symbol.
+     *
+     * A count of -1 is a symbol with a probability of less than one.
+     */
+    fn init_counts(&mut self, counts: &[i16], accuracy_log: u32) -> Result<(), ()> {
+        let size = 1 << accuracy_log;
+        let mut next = [0u16; FSE_SYMBOLS_MAX];
+
+        // Symbols with a probability of less than one go at the end.
+        let mut high_threshold = size;
+        for (symbol, count) in counts.iter().enumerate() {
+            if *count == -1 {
+                high_threshold -= 1;
+                self.entries[high_threshold].symbol = symbol as u8;
+                next[symbol] = 1;
+            } else {
+                next[symbol] = *count as u16;
+            }
+        }
+
+        // Spread the remaining symbols over the table.
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mask = size - 1;
+        let mut position = 0;
+        for (symbol, count) in counts.iter().enumerate() {
+            for _ in 0..*count {
+                self.entries[position].symbol = symbol as u8;
+                loop {
+                    position = (position + step) & mask;
+                    if position < high_threshold {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // All positions must have been visited.
+        if position != 0 {
+            return Err(());
+        }
+
+        // Compute the next states.
+        for entry in &mut self.entries[0..size] {
+            let symbol = usize::from(entry.symbol);
+            let state = next[symbol];
+            next[symbol] += 1;
+
+            let bits = accuracy_log - (u16::BITS - 1 - state.leading_zeros());
+            entry.bits = bits as u8;
+            entry.base = ((u32::from(state) << bits) - size as u32) as u16;
+        }
+
+        self.accuracy_log = accuracy_log;
+        self.is_valid = true;
+
+        Ok(())
+    }
+
+    /** Initializes a table from an FSE table description at the start of `src`.
+     *
+     * On success, returns the number of bytes used.
+     */
+    fn init_description(
+        &mut self,
+        src: &[u8],
+        offset: usize,
+        accuracy_log_max: u32,
+        symbols_max: usize,
+    ) -> Result<usize, DecompressionError> {
+        let mut reader = ForwardBitReader {
+            src,
+            offset,
+            bit_idx: 0,
+        };
+        let mut counts = [0i16; FSE_SYMBOLS_MAX];
+        let invalid = DecompressionError::InvalidInput { offset };
+
+        let accuracy_log = reader.get_bits(4)? + FSE_ACCURACY_LOG_MIN;
+        if accuracy_log > accuracy_log_max {
+            return Err(invalid);
+        }
+
+        // Remaining probability, plus one.
+        let mut remaining: i32 = (1 << accuracy_log) + 1;
+        let mut threshold: i32 = 1 << accuracy_log;
+        let mut bits = accuracy_log + 1;
+        let mut symbol = 0;
+
+        while remaining > 1 {
+            if symbol >= symbols_max {
+                return Err(invalid);
+            }
+
+            // Values smaller than max use one less bit.
+            let max = (2 * threshold - 1) - remaining;
+            let mut count = reader.peek_bits(bits - 1) as i32;
+            if count < max {
+                reader.consume_bits(bits - 1)?;
+            } else {
+                count = reader.peek_bits(bits) as i32;
+                if count >= threshold {
+                    count -= max;
+                }
+                reader.consume_bits(bits)?;
+            }
+
+            // Value is the count plus one.
+            count -= 1;
+            remaining -= count.abs();
+            counts[symbol] = count as i16;
+            symbol += 1;
+
+            // A zero count is followed by the number of repeated zeros.
+            if count == 0 {
+                loop {
+                    let repeat = reader.get_bits(2)?;
+                    symbol += repeat as usize;
+                    if symbol > symbols_max {
+                        return Err(invalid);
+                    }
+                    if repeat != 3 {
+                        break;
+                    }
+                }
+            }
+
+            // Reduce the number of bits, as the remaining probability drops.
+            while remaining < threshold {
+                bits -= 1;
+                threshold >>= 1;
+            }
+        }
+
+        if remaining != 1 {
+            return Err(invalid);
+        }
+
+        if self.init_counts(&counts[0..symbol], accuracy_log).is_err() {
+            return Err(invalid);
+        }
+
+        Ok(reader.bytes_read())
+    }
+
+    /// Gets the initial state.
+    fn init_state(&self, reader: &mut BackwardBitReader<'_>) -> usize {
+        reader.get_bits(self.accuracy_log) as usize
+    }
+
+    /// Gets the symbol of a state.
+    fn symbol(&self, state: usize) -> u8 {
+        self.entries[state].symbol
+    }
+
+    /// Gets the next state.
+    fn update_state(&self, state: usize, reader: &mut BackwardBitReader<'_>) -> usize {
+        let entry = &self.entries[state];
+        usize::from(entry.base) + reader.get_bits(u32::from(entry.bits)) as usize
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Huffman table entry.
+#[derive(Clone, Copy)]
+struct HuffmanEntry {
+    /// Decoded symbol.
+    symbol: u8,
+
+    /// Number of bits of the code.
+    bits: u8,
+}
+
+/// Huffman decoding table, indexed by the next [`HuffmanTable::bits_max`] bits.
+struct HuffmanTable {
+    /// Maximum number of bits in a code.
+    bits_max: u32,
+
+    /// Table entries.
+    entries: [HuffmanEntry; 1 << HUFFMAN_BITS_MAX],
+
+    /// Is the table initialized, and can be repeated.
+    is_valid: bool,
+}
+
+impl HuffmanTable {
+    /// Creates an empty table.
+    fn new() -> HuffmanTable {
+        HuffmanTable {
+            bits_max: 0,
+            entries: [HuffmanEntry { symbol: 0, bits: 0 }; 1 << HUFFMAN_BITS_MAX],
+            is_valid: false,
+        }
+    }
+
+    /** Initializes a table from a Huffman tree description at the start of
+     * `src`.
+     *
+     * On success, returns the number of bytes used.
+     */
+    fn init_description(
+        &mut self,
+        src: &[u8],
+        offset: usize,
+        fse: &mut FseTable,
+    ) -> Result<usize, DecompressionError> {
+        let invalid = DecompressionError::InvalidInput { offset };
+
+        let header = match src.first() {
+            Some(v) => usize::from(*v),
+            None => {
+                return Err(DecompressionError::EndOfInput {
+                    offset,
+                    capacity: offset,
+                    count: 1,
+                })
+            }
+        };
+
+        // Weights of each symbol, except the last, which is implied.
+        let mut weights = [0u8; HUFFMAN_WEIGHTS_MAX + 1];
+        let mut weights_count = 0;
+
+        let size = if header < 128 {
+            ////////////////////////////////
+            // Weights are FSE compressed, with two interleaved states.
+            let size = header;
+            let data = match src.get(1..1 + size) {
+                Some(v) => v,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: offset + 1,
+                        capacity: offset + src.len(),
+                        count: size,
+                    })
+                }
+            };
+
+            let used = fse.init_description(
+                data,
+                offset + 1,
+                HUFFMAN_WEIGHTS_ACCURACY_LOG_MAX,
+                HUFFMAN_BITS_MAX as usize + 1,
+            )?;
+
+            let mut reader = BackwardBitReader::new(&data[used..], offset + 1 + used)?;
+            let mut states = [fse.init_state(&mut reader), fse.init_state(&mut reader)];
+            let mut idx = 0;
+
+            loop {
+                if weights_count >= HUFFMAN_WEIGHTS_MAX {
+                    return Err(invalid);
+                }
+
+                // Decode a symbol, and update the state.
+                weights[weights_count] = fse.symbol(states[idx]);
+                weights_count += 1;
+                states[idx] = fse.update_state(states[idx], &mut reader);
+
+                // When the stream is over, the other state has the last symbol.
+                if reader.bits_left < 0 {
+                    if weights_count >= HUFFMAN_WEIGHTS_MAX {
+                        return Err(invalid);
+                    }
+                    weights[weights_count] = fse.symbol(states[idx ^ 1]);
+                    weights_count += 1;
+                    break;
+                }
+
+                idx ^= 1;
+            }
+
+            // The table is not repeated.
+            fse.is_valid = false;
+
+            size
+        } else {
+            ////////////////////////////////
+            // Weights are four bits each.
+            weights_count = header - 127;
+            let size = weights_count.div_ceil(2);
+            let data = match src.get(1..1 + size) {
+                Some(v) => v,
+                None => {
+                    return Err(DecompressionError::EndOfInput {
+                        offset: offset + 1,
+                        capacity: offset + src.len(),
+                        count: size,
+                    })
+                }
+            };
+
+            for (idx, weight) in weights[0..weights_count].iter_mut().enumerate() {
+                let value = data[idx / 2];
+                *weight = if idx % 2 == 0 {
+                    value >> 4
+                } else {
+                    value & 0xf
+                };
+            }
+
+            size
+        };
+
+        ////////////////////////////////
+        // Compute the implied last weight. The sum of 2^(weight-1) must be a
+        // power of two.
+        let mut total: u32 = 0;
+        for weight in &weights[0..weights_count] {
+            if u32::from(*weight) > HUFFMAN_BITS_MAX {
+                return Err(invalid);
+            }
+            if *weight > 0 {
+                total += 1 << (weight - 1);
+            }
+        }
+
+        if total == 0 {
+            return Err(invalid);
+        }
+
+        let bits_max = u32::BITS - total.leading_zeros();
+        if bits_max > HUFFMAN_BITS_MAX {
+            return Err(invalid);
+        }
+
+        let left = (1 << bits_max) - total;
+        if !left.is_power_of_two() {
+            return Err(invalid);
+        }
+        weights[weights_count] = (u32::BITS - left.leading_zeros()) as u8;
+        weights_count += 1;
+
+        ////////////////////////////////
+        // Fill the table, starting with the lowest weight (longest code), and
+        // then by symbol.
+        let mut position = 0;
+        for weight in 1..=bits_max as u8 {
+            let bits = (bits_max + 1 - u32::from(weight)) as u8;
+            let length = 1 << (weight - 1);
+
+            for (symbol, _) in weights[0..weights_count]
+                .iter()
+                .enumerate()
+                .filter(|(_, w)| **w == weight)
+            {
+                self.entries[position..position + length].fill(HuffmanEntry {
+                    symbol: symbol as u8,
+                    bits,
+                });
+                position += length;
+            }
+        }
+
+        self.bits_max = bits_max;
+        self.is_valid = true;
+
+        Ok(1 + size)
+    }
+
+    /// Decodes a stream of symbols into `dst`.
+    fn decode_stream(
+        &self,
+        dst: &mut [u8],
+        src: &[u8],
+        offset: usize,
+    ) -> Result<(), DecompressionError> {
+        let mut reader = BackwardBitReader::new(src, offset)?;
+
+        for value in dst {
+            let entry = &self.entries[reader.peek_bits(self.bits_max) as usize];
+            reader.consume_bits(u32::from(entry.bits));
+            *value = entry.symbol;
+        }
+
+        // The stream must be consumed exactly.
+        if reader.bits_left != 0 {
+            return Err(DecompressionError::InvalidInput { offset });
+        }
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Zstandard decompression decoder.
+pub struct ZstdDecoder {
+    huffman: HuffmanTable,
+    huffman_weights: FseTable,
+    literals_length: FseTable,
+    match_length: FseTable,
+    offset: FseTable,
+    repeat_offsets: [usize; REPEAT_OFFSETS_COUNT],
+}
+
+impl Default for ZstdDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Gets `count` bytes at `offset` from the source.
+fn get_bytes(src: &[u8], offset: usize, count: usize) -> Result<&[u8], DecompressionError> {
+    match src.get(offset..offset + count) {
+        Some(v) => Ok(v),
+        None => Err(DecompressionError::EndOfInput {
+            offset,
+            capacity: src.len(),
+            count,
+        }),
+    }
+}
+
+/// Decodes a little endian value of up to eight bytes.
+fn get_le(src: &[u8]) -> u64 {
+    src.iter()
+        .rev()
+        .fold(0, |value, byte| (value << 8) | u64::from(*byte))
+}
+
+impl ZstdDecoder {
+    /// Create a new Zstandard decompression decoder.
+    pub fn new() -> ZstdDecoder {
+        ZstdDecoder {
+            huffman: HuffmanTable::new(),
+            huffman_weights: FseTable::new(),
+            literals_length: FseTable::new(),
+            match_length: FseTable::new(),
+            offset: FseTable::new(),
+            repeat_offsets: REPEAT_OFFSETS_INITIAL,
+        }
+    }
+
+    /** Decodes the literals section of a compressed block at `src_idx`.
+     *
+     * Literals are stored at the end of the destination, and are moved into
+     * place as sequences are executed. The output never overwrites literals,
+     * that have not been used yet.
+     *
+     * On success, returns the number of bytes used, and the number of literals.
+     */
+    fn decode_literals(
+        &mut self,
+        dst: &mut [u8],
+        dst_idx: usize,
+        src: &[u8],
+        src_idx: usize,
+    ) -> Result<(usize, usize), DecompressionError> {
+        let header = get_bytes(src, src_idx, 1)?[0];
+        let literals_type = header & 0x3;
+        let size_format = (header >> 2) & 0x3;
+
+        ////////////////////////////////
+        // Decode the header.
+        let (header_size, regenerated_size, compressed_size, streams) = match literals_type {
+            LITERALS_TYPE_RAW | LITERALS_TYPE_RLE => {
+                let (header_size, regenerated_size) = match size_format {
+                    0 | 2 => (1, usize::from(header >> 3)),
+                    1 => (2, (get_le(get_bytes(src, src_idx, 2)?) >> 4) as usize),
+                    _ => (3, (get_le(get_bytes(src, src_idx, 3)?) >> 4) as usize),
+                };
+                let compressed_size = if literals_type == LITERALS_TYPE_RAW {
+                    regenerated_size
+                } else {
+                    1
+                };
+                (header_size, regenerated_size, compressed_size, 0)
+            }
+            _ => {
+                let (header_size, bits, streams) = match size_format {
+                    0 => (3, 10, 1),
+                    1 => (3, 10, 4),
+                    2 => (4, 14, 4),
+                    _ => (5, 18, 4),
+                };
+                let value = get_le(get_bytes(src, src_idx, header_size)?) >> 4;
+                let mask = (1 << bits) - 1;
+                (
+                    header_size,
+                    (value & mask) as usize,
+                    ((value >> bits) & mask) as usize,
+                    streams,
+                )
+            }
+        };
+
+        let data_idx = src_idx + header_size;
+        let data = get_bytes(src, data_idx, compressed_size)?;
+
+        // Literals must fit in the destination.
+        if dst.len() - dst_idx < regenerated_size {
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+        let literals = dst.len() - regenerated_size;
+        let literals = &mut dst[literals..];
+
+        ////////////////////////////////
+        // Decode the literals.
+        match literals_type {
+            LITERALS_TYPE_RAW => literals.copy_from_slice(data),
+            LITERALS_TYPE_RLE => literals.fill(data[0]),
+            _ => {
+                // Decode a new Huffman table, or repeat the previous one.
+                let mut used = 0;
+                if literals_type == LITERALS_TYPE_COMPRESSED {
+                    used =
+                        self.huffman
+                            .init_description(data, data_idx, &mut self.huffman_weights)?;
+                } else if !self.huffman.is_valid {
+                    return Err(DecompressionError::InvalidInput { offset: src_idx });
+                }
+
+                let data_idx = data_idx + used;
+                let data = &data[used..];
+
+                if streams == 1 {
+                    self.huffman.decode_stream(literals, data, data_idx)?;
+                } else {
+                    // Decode the jump table of the first three stream sizes.
+                    let jump_table = get_bytes(data, 0, JUMP_TABLE_SIZE)?;
+                    let mut sizes = [0; 4];
+                    for (idx, size) in sizes[0..3].iter_mut().enumerate() {
+                        *size = get_le(&jump_table[idx * 2..idx * 2 + 2]) as usize;
+                    }
+
+                    // The last size is the remainder.
+                    let total = sizes[0] + sizes[1] + sizes[2] + JUMP_TABLE_SIZE;
+                    sizes[3] = match data.len().checked_sub(total) {
+                        Some(v) => v,
+                        None => return Err(DecompressionError::InvalidInput { offset: data_idx }),
+                    };
+
+                    // Each stream regenerates a quarter of the literals,
+                    // rounded up, except the last one.
+                    let segment = regenerated_size.div_ceil(4);
+                    if regenerated_size < 3 * segment {
+                        return Err(DecompressionError::InvalidInput { offset: data_idx });
+                    }
+
+                    let mut stream_idx = JUMP_TABLE_SIZE;
+                    for (idx, size) in sizes.iter().enumerate() {
+                        let start = idx * segment;
+                        let end = if idx == 3 {
+                            regenerated_size
+                        } else {
+                            start + segment
+                        };
+
+                        self.huffman.decode_stream(
+                            &mut literals[start..end],
+                            &data[stream_idx..stream_idx + size],
+                            data_idx + stream_idx,
+                        )?;
+                        stream_idx += size;
+                    }
+                }
+            }
+        }
+
+        Ok((header_size + compressed_size, regenerated_size))
+    }
+
+    /// Initializes a sequences table, based on the mode.
+    fn init_sequence_table(
+        table: &mut FseTable,
+        parameters: &SequenceTableParameters,
+        mode: u8,
+        src: &[u8],
+        src_idx: usize,
+    ) -> Result<usize, DecompressionError> {
+        match mode {
+            MODE_PREDEFINED => {
+                // Predefined counts are always valid.
+                table
+                    .init_counts(parameters.default_counts, parameters.default_accuracy_log)
+                    .unwrap();
+                Ok(0)
+            }
+            MODE_RLE => {
+                let symbol = get_bytes(src, src_idx, 1)?[0];
+                if usize::from(symbol) >= parameters.symbols {
+                    return Err(DecompressionError::InvalidInput { offset: src_idx });
+                }
+                table.init_rle(symbol);
+                Ok(1)
+            }
+            MODE_FSE => table.init_description(
+                &src[src_idx..],
+                src_idx,
+                parameters.accuracy_log_max,
+                parameters.symbols,
+            ),
+            _ => {
+                // Repeat the previous table.
+                if !table.is_valid {
+                    return Err(DecompressionError::InvalidInput { offset: src_idx });
+                }
+                Ok(0)
+            }
+        }
+    }
+
+    /** Decodes and executes the sequences section of a compressed block at
+     * `src_idx`, up to `src_end`.
+     *
+     * On success, returns the new destination index.
+     */
+    fn decode_sequences(
+        &mut self,
+        dst: &mut [u8],
+        mut dst_idx: usize,
+        src: &[u8],
+        src_idx: usize,
+        literals_count: usize,
+    ) -> Result<usize, DecompressionError> {
+        ////////////////////////////////
+        // Decode the number of sequences.
+        let header = get_bytes(src, src_idx, 1)?[0];
+        let (mut src_idx, sequences) = match header {
+            0..=127 => (src_idx + 1, usize::from(header)),
+            128..=254 => {
+                let next = get_bytes(src, src_idx + 1, 1)?[0];
+                (
+                    src_idx + 2,
+                    (usize::from(header - 128) << 8) + usize::from(next),
+                )
+            }
+            255 => {
+                let next = get_bytes(src, src_idx + 1, 2)?;
+                (src_idx + 3, get_le(next) as usize + 0x7f00)
+            }
+        };
+
+        // Literals are at the end of the destination.
+        let mut literals_idx = dst.len() - literals_count;
+        let literals_end = dst.len();
+
+        if sequences > 0 {
+            ////////////////////////////////
+            // Decode the tables.
+            let modes = get_bytes(src, src_idx, 1)?[0];
+            if modes & 0x3 != 0 {
+                return Err(DecompressionError::InvalidInput { offset: src_idx });
+            }
+            src_idx += 1;
+
+            src_idx += ZstdDecoder::init_sequence_table(
+                &mut self.literals_length,
+                &LITERALS_LENGTH_TABLE,
+                modes >> 6,
+                src,
+                src_idx,
+            )?;
+            src_idx += ZstdDecoder::init_sequence_table(
+                &mut self.offset,
+                &OFFSET_TABLE,
+                (modes >> 4) & 0x3,
+                src,
+                src_idx,
+            )?;
+            src_idx += ZstdDecoder::init_sequence_table(
+                &mut self.match_length,
+                &MATCH_LENGTH_TABLE,
+                (modes >> 2) & 0x3,
+                src,
+                src_idx,
+            )?;
+
+            ////////////////////////////////
+            // Decode the sequences.
+            let mut reader = BackwardBitReader::new(&src[src_idx..], src_idx)?;
+
+            let mut literals_length_state = self.literals_length.init_state(&mut reader);
+            let mut offset_state = self.offset.init_state(&mut reader);
+            let mut match_length_state = self.match_length.init_state(&mut reader);
+
+            for sequence in 0..sequences {
+                let invalid = DecompressionError::InvalidInput { offset: src_idx };
+
+                // Decode the codes.
+                let literals_length_code =
+                    usize::from(self.literals_length.symbol(literals_length_state));
+                let offset_code = usize::from(self.offset.symbol(offset_state));
+                let match_length_code = usize::from(self.match_length.symbol(match_length_state));
+
+                // Decode the values, in the order offset, match length, and
+                // literals length.
+                let offset_value =
+                    (1usize << offset_code) + reader.get_bits(offset_code as u32) as usize;
+
+                let (base, bits) = MATCH_LENGTH_CODES[match_length_code];
+                let match_length = base as usize + reader.get_bits(bits) as usize;
+
+                let (base, bits) = LITERALS_LENGTH_CODES[literals_length_code];
+                let literals_length = base as usize + reader.get_bits(bits) as usize;
+
+                // Resolve repeat offsets. When the literals length is zero,
+                // the repeat offsets are shifted by one.
+                let offset = if offset_value > REPEAT_OFFSETS_COUNT {
+                    let offset = offset_value - REPEAT_OFFSETS_COUNT;
+                    self.repeat_offsets = [offset, self.repeat_offsets[0], self.repeat_offsets[1]];
+                    offset
+                } else {
+                    let idx = offset_value - 1 + usize::from(literals_length == 0);
+                    if idx == 0 {
+                        self.repeat_offsets[0]
+                    } else {
+                        let offset = if idx == REPEAT_OFFSETS_COUNT {
+                            self.repeat_offsets[0] - 1
+                        } else {
+                            self.repeat_offsets[idx]
+                        };
+                        if offset == 0 {
+                            return Err(invalid);
+                        }
+                        if idx > 1 {
+                            self.repeat_offsets[2] = self.repeat_offsets[1];
+                        }
+                        self.repeat_offsets[1] = self.repeat_offsets[0];
+                        self.repeat_offsets[0] = offset;
+                        offset
+                    }
+                };
+
+                // Update the states, in the order literals length, match
+                // length, and offset, except for the last sequence.
+                if sequence + 1 < sequences {
+                    literals_length_state = self
+                        .literals_length
+                        .update_state(literals_length_state, &mut reader);
+                    match_length_state = self
+                        .match_length
+                        .update_state(match_length_state, &mut reader);
+                    offset_state = self.offset.update_state(offset_state, &mut reader);
+                }
+
+                ////////////////////////////////
+                // Copy the literals.
+                if literals_end - literals_idx < literals_length {
+                    return Err(invalid);
+                }
+                dst.copy_within(literals_idx..literals_idx + literals_length, dst_idx);
+                literals_idx += literals_length;
+                dst_idx += literals_length;
+
+                ////////////////////////////////
+                // Copy the match, which must not overwrite unused literals.
+                let cpy_idx = match dst_idx.checked_sub(offset) {
+                    Some(v) => v,
+                    None => return Err(invalid),
+                };
+
+                if literals_idx - dst_idx < match_length {
+                    return Err(invalid);
+                }
+
+                // Copy the bytes one byte at a time, because the match may
+                // overlap with the bytes being written.
+                for cpy_idx in cpy_idx..cpy_idx + match_length {
+                    dst[dst_idx] = dst[cpy_idx];
+                    dst_idx += 1;
+                }
+            }
+
+            // The stream must be consumed exactly.
+            if reader.bits_left != 0 {
+                return Err(DecompressionError::InvalidInput { offset: src_idx });
+            }
+        } else if src_idx != src.len() {
+            // There are no sequences, so the block must be over.
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+
+        ////////////////////////////////
+        // Copy the remaining literals.
+        dst.copy_within(literals_idx..literals_end, dst_idx);
+        dst_idx += literals_end - literals_idx;
+
+        Ok(dst_idx)
+    }
+
+    /// Decodes a frame without a magic number.
+    fn decode_frame(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        mut src_idx: usize,
+    ) -> Result<(), DecompressionError> {
+        // Reset the state of the decoder.
+        self.huffman.is_valid = false;
+        self.huffman_weights.is_valid = false;
+        self.literals_length.is_valid = false;
+        self.match_length.is_valid = false;
+        self.offset.is_valid = false;
+        self.repeat_offsets = REPEAT_OFFSETS_INITIAL;
+
+        ////////////////////////////////
+        // Decode the frame header.
+        let descriptor = get_bytes(src, src_idx, 1)?[0];
+        if descriptor & FRAME_RESERVED != 0 {
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+        src_idx += 1;
+
+        let is_single_segment = descriptor & FRAME_SINGLE_SEGMENT != 0;
+
+        // Skip the window descriptor. The whole output is the window.
+        if !is_single_segment {
+            get_bytes(src, src_idx, 1)?;
+            src_idx += 1;
+        }
+
+        // Dictionaries are not supported.
+        let dictionary_size = [0, 1, 2, 4][usize::from(descriptor & FRAME_DICTIONARY_MASK)];
+        if get_le(get_bytes(src, src_idx, dictionary_size)?) != 0 {
+            return Err(DecompressionError::InvalidInput { offset: src_idx });
+        }
+        src_idx += dictionary_size;
+
+        // Check the content size, if present.
+        let content_size_size = match descriptor >> FRAME_CONTENT_SIZE_SHIFT {
+            0 => usize::from(is_single_segment),
+            1 => 2,
+            2 => 4,
+            _ => 8,
+        };
+        if content_size_size > 0 {
+            let mut content_size = get_le(get_bytes(src, src_idx, content_size_size)?);
+            if content_size_size == 2 {
+                content_size += FRAME_CONTENT_SIZE_2_OFFSET;
+            }
+            if content_size != dst.len() as u64 {
+                return Err(DecompressionError::InvalidInput { offset: src_idx });
+            }
+        }
+        src_idx += content_size_size;
+
+        ////////////////////////////////
+        // Decode the blocks.
+        let mut dst_idx = 0;
+
+        loop {
+            let header = get_le(get_bytes(src, src_idx, BLOCK_HEADER_SIZE)?) as u32;
+            let is_last = header & 1 != 0;
+            let block_type = (header >> BLOCK_TYPE_SHIFT) & BLOCK_TYPE_MASK;
+            let block_size = (header >> BLOCK_SIZE_SHIFT) as usize;
+
+            let block_idx = src_idx;
+            src_idx += BLOCK_HEADER_SIZE;
+
+            match block_type {
+                BLOCK_TYPE_RAW => {
+                    let data = get_bytes(src, src_idx, block_size)?;
+                    if dst.len() - dst_idx < block_size {
+                        return Err(DecompressionError::InvalidInput { offset: block_idx });
+                    }
+                    dst[dst_idx..dst_idx + block_size].copy_from_slice(data);
+                    dst_idx += block_size;
+                    src_idx += block_size;
+                }
+                BLOCK_TYPE_RLE => {
+                    let data = get_bytes(src, src_idx, 1)?;
+                    if dst.len() - dst_idx < block_size {
+                        return Err(DecompressionError::InvalidInput { offset: block_idx });
+                    }
+                    dst[dst_idx..dst_idx + block_size].fill(data[0]);
+                    dst_idx += block_size;
+                    src_idx += 1;
+                }
+                BLOCK_TYPE_COMPRESSED => {
+                    get_bytes(src, src_idx, block_size)?;
+                    let block = &src[0..src_idx + block_size];
+
+                    let (used, literals_count) =
+                        self.decode_literals(dst, dst_idx, block, src_idx)?;
+                    dst_idx =
+                        self.decode_sequences(dst, dst_idx, block, src_idx + used, literals_count)?;
+                    src_idx += block_size;
+                }
+                _ => return Err(DecompressionError::InvalidInput { offset: block_idx }),
+            }
+
+            if is_last {
+                break;
+            }
+        }
+
+        ////////////////////////////////
+        // Check that all of the destination was decompressed.
+        if dst_idx != dst.len() {
+            return Err(DecompressionError::EndOfInput {
+                offset: src_idx,
+                capacity: src.len(),
+                count: 1,
+            });
+        }
+
+        ////////////////////////////////
+        // Verify the checksum, if present.
+        if descriptor & FRAME_CHECKSUM != 0 {
+            let checksum = get_le(get_bytes(src, src_idx, FRAME_CHECKSUM_SIZE)?) as u32;
+            if checksum != xxh64(dst) as u32 {
+                return Err(DecompressionError::InvalidInput { offset: src_idx });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Decompression for ZstdDecoder {
+    fn decompress(
+        &mut self,
+        dst: &mut [u8],
+        src: &[u8],
+        _level: u32,
+    ) -> Result<(), DecompressionError> {
+        // Decode the header.
+        let header = ZstdHeader::from_bytes(src)?;
+        let length = match usize::try_from(header.compressed_size) {
+            Ok(v) => v,
+            Err(_) => return Err(DecompressionError::InvalidInput { offset: 0 }),
+        };
+
+        // Check that the compressed data fits in the source.
+        if src.len() - HEADER_SIZE < length {
+            return Err(DecompressionError::EndOfInput {
+                offset: HEADER_SIZE,
+                capacity: src.len(),
+                count: length,
+            });
+        }
+
+        // Ignore any padding after the compressed data.
+        self.decode_frame(dst, &src[0..HEADER_SIZE + length], HEADER_SIZE)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// XXH64 primes.
+const XXH64_PRIMES: [u64; 5] = [
+    0x9e3779b185ebca87,
+    0xc2b2ae3d27d4eb4f,
+    0x165667b19e3779f9,
+    0x85ebca77c2b2ae63,
+    0x27d4eb2f165667c5,
+];
+
+/// XXH64 round.
+fn xxh64_round(acc: u64, value: u64) -> u64 {
+    acc.wrapping_add(value.wrapping_mul(XXH64_PRIMES[1]))
+        .rotate_left(31)
+        .wrapping_mul(XXH64_PRIMES[0])
+}
+
+/// XXH64 merge of an accumulator.
+fn xxh64_merge(hash: u64, acc: u64) -> u64 {
+    (hash ^ xxh64_round(0, acc))
+        .wrapping_mul(XXH64_PRIMES[0])
+        .wrapping_add(XXH64_PRIMES[3])
+}
+
+/// Computes the XXH64 hash of data, with a seed of zero.
+fn xxh64(data: &[u8]) -> u64 {
+    let [p1, p2, p3, p4, p5] = XXH64_PRIMES;
+
+    let mut stripes = data.chunks_exact(32);
+    let mut hash = if data.len() >= 32 {
+        let mut acc = [p1.wrapping_add(p2), p2, 0, 0u64.wrapping_sub(p1)];
+        for stripe in &mut stripes {
+            for (idx, value) in acc.iter_mut().enumerate() {
+                let lane = u64::from_le_bytes(stripe[idx * 8..idx * 8 + 8].try_into().unwrap());
+                *value = xxh64_round(*value, lane);
+            }
+        }
+
+        let mut hash = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        for value in acc {
+            hash = xxh64_merge(hash, value);
+        }
+        hash
+    } else {
+        p5
+    };
+
+    hash = hash.wrapping_add(data.len() as u64);
+
+    let mut remainder = stripes.remainder();
+    while remainder.len() >= 8 {
+        let value = u64::from_le_bytes(remainder[0..8].try_into().unwrap());
+        hash ^= xxh64_round(0, value);
+        hash = hash.rotate_left(27).wrapping_mul(p1).wrapping_add(p4);
+        remainder = &remainder[8..];
+    }
+
+    if remainder.len() >= 4 {
+        let value = u64::from(u32::from_le_bytes(remainder[0..4].try_into().unwrap()));
+        hash ^= value.wrapping_mul(p1);
+        hash = hash.rotate_left(23).wrapping_mul(p2).wrapping_add(p3);
+        remainder = &remainder[4..];
+    }
+
+    for value in remainder {
+        hash ^= u64::from(*value).wrapping_mul(p5);
+        hash = hash.rotate_left(11).wrapping_mul(p1);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(p2);
+    hash ^= hash >> 29;
+    hash = hash.wrapping_mul(p3);
+    hash ^= hash >> 32;
+
+    hash
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compression::{Decompression, ZstdDecoder, ZstdHeader};
+
+    // Decompressed data, Compressed Data.
+    const TEST_VECTORS: &[(&[u8], &[u8])] = &[
+        // Level 1, without a checksum.
+        (
+            &[
+                0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e,
+                0x65, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x7a,
+                0x66, 0x73, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73,
+                0x73, 0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74,
+                0x65, 0x72, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72, 0x20, 0x7a, 0x66, 0x73, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x61,
+                0x70, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64,
+                0x7a, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63,
+                0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63,
+                0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x73, 0x6e, 0x61,
+                0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x6c, 0x7a,
+                0x34, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x66, 0x73,
+                0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x6c, 0x61,
+                0x62, 0x65, 0x6c, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20,
+                0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73,
+                0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f,
+                0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73,
+                0x75, 0x6d, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x73,
+                0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f,
+                0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x64, 0x61, 0x74,
+                0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69,
+                0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20, 0x6d, 0x69,
+                0x72, 0x72, 0x6f, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x7a, 0x73, 0x74,
+                0x64, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64,
+                0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x64,
+                0x6e, 0x6f, 0x64, 0x65, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x72, 0x61, 0x69,
+                0x64, 0x7a, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x70, 0x6f,
+                0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x75, 0x62,
+                0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65,
+                0x63, 0x74, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68,
+                0x6f, 0x74, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63,
+                0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20,
+                0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x64,
+                0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75,
+                0x6d, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e,
+            ],
+            &[
+                0x00, 0x00, 0x01, 0x1f, 0x01, 0x00, 0x29, 0x0b, 0x00, 0x48, 0xd5, 0x08, 0x00, 0x96,
+                0x10, 0x2b, 0x15, 0xa0, 0xa5, 0x6d, 0x00, 0xd9, 0x77, 0x2a, 0x92, 0x64, 0xa5, 0xfd,
+                0xfd, 0xdf, 0x2b, 0x6d, 0x55, 0xc5, 0xfe, 0xed, 0x7f, 0xd6, 0x26, 0x00, 0x23, 0x00,
+                0x23, 0x00, 0x87, 0x50, 0xe2, 0x33, 0x35, 0xe6, 0xa4, 0x81, 0x3d, 0x65, 0xd6, 0xc4,
+                0x60, 0x07, 0x72, 0x01, 0xe2, 0x74, 0x5b, 0xa9, 0xcc, 0x5b, 0x6e, 0xe1, 0xe9, 0x74,
+                0x2b, 0x18, 0xea, 0x33, 0x9a, 0xc4, 0x03, 0xd1, 0x1c, 0xe8, 0xc6, 0x20, 0x37, 0x2c,
+                0xe4, 0x2d, 0x17, 0x9e, 0x16, 0x9e, 0xbe, 0x61, 0xa1, 0x2d, 0xf7, 0xa9, 0xdf, 0xb0,
+                0xd0, 0xa9, 0xfb, 0xd4, 0x91, 0x22, 0x84, 0x84, 0xd1, 0x25, 0x83, 0x7a, 0xbc, 0xe5,
+                0x36, 0x8a, 0xbb, 0x87, 0x02, 0x68, 0x06, 0x7d, 0xea, 0x96, 0xf6, 0x1e, 0x88, 0xe6,
+                0x38, 0x78, 0x80, 0xb7, 0xfc, 0x4c, 0x8d, 0x09, 0xcf, 0xe2, 0xac, 0xb0, 0xa5, 0x7d,
+                0xde, 0xa8, 0x3c, 0x75, 0xe7, 0x8d, 0x4a, 0x9f, 0xd1, 0x24, 0x5b, 0xee, 0x46, 0x71,
+                0xf7, 0xd0, 0x21, 0x94, 0x58, 0xda, 0x9f, 0xd1, 0x24, 0xc5, 0x59, 0xe1, 0x53, 0xc7,
+                0x03, 0x9c, 0x37, 0x2a, 0x07, 0xa2, 0x39, 0xf2, 0x46, 0x65, 0x71, 0x56, 0xf8, 0x86,
+                0x85, 0x4c, 0x8d, 0x91, 0x43, 0x01, 0x2a, 0x20, 0x10, 0x14, 0xe3, 0x30, 0xbb, 0x01,
+                0xe8, 0x05, 0x20, 0xb4, 0x10, 0x81, 0x64, 0x80, 0x4d, 0xf4, 0x80, 0x61, 0x60, 0x12,
+                0x64, 0x15, 0xb0, 0xde, 0x76, 0x60, 0xa9, 0x16, 0x97, 0xa6, 0x04, 0x68, 0x10, 0x99,
+                0x43, 0x78, 0x7e, 0x84, 0xd6, 0x40, 0x63, 0x69, 0xa4, 0x05, 0xc9, 0x0e, 0xa2, 0xef,
+                0x63, 0x8d, 0x18, 0x16, 0x69, 0x88, 0x24, 0xa0, 0x6a, 0x34, 0xc8, 0x26, 0xe6, 0x70,
+                0x10, 0xe5, 0x40, 0xe4, 0x7d, 0x8c, 0x9b, 0x56, 0xf2, 0xf8, 0x2f, 0x20, 0x58, 0x80,
+                0x85, 0xe6, 0xba, 0xfa, 0xfe, 0x6b, 0x64, 0xf1, 0xe7, 0xc0, 0x86, 0x00, 0xc6, 0x79,
+                0xdc, 0x22, 0x74, 0x1f, 0x23, 0xa5, 0x80, 0xb0, 0x68, 0x4b, 0x61, 0x9d, 0x71, 0x50,
+                0x33,
+            ],
+        ),
+        // Level 19, with a checksum.
+        (
+            &[
+                0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e,
+                0x65, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x7a,
+                0x66, 0x73, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73,
+                0x73, 0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74,
+                0x65, 0x72, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72, 0x20, 0x7a, 0x66, 0x73, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x61,
+                0x70, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64,
+                0x7a, 0x20, 0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63,
+                0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63,
+                0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x73, 0x6e, 0x61,
+                0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x6c, 0x7a,
+                0x34, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6f, 0x62, 0x6a, 0x73, 0x65, 0x74, 0x20, 0x7a, 0x66, 0x73,
+                0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x63, 0x68, 0x65,
+                0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x6c, 0x61,
+                0x62, 0x65, 0x6c, 0x20, 0x7a, 0x61, 0x70, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20,
+                0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73,
+                0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f,
+                0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73,
+                0x75, 0x6d, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x73,
+                0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x7a, 0x34, 0x20, 0x6d, 0x69, 0x72, 0x72, 0x6f, 0x72,
+                0x20, 0x64, 0x6e, 0x6f, 0x64, 0x65, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f,
+                0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x64, 0x61, 0x74,
+                0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69,
+                0x6f, 0x6e, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20, 0x6d, 0x69,
+                0x72, 0x72, 0x6f, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x7a, 0x73, 0x74,
+                0x64, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75, 0x6d, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64,
+                0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73,
+                0x69, 0x6f, 0x6e, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65, 0x63, 0x74, 0x20, 0x64,
+                0x6e, 0x6f, 0x64, 0x65, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x72, 0x61, 0x69,
+                0x64, 0x7a, 0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x72, 0x61, 0x69, 0x64, 0x7a, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65,
+                0x73, 0x73, 0x69, 0x6f, 0x6e, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x70, 0x6f,
+                0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x75, 0x62,
+                0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20, 0x69, 0x6e, 0x64, 0x69, 0x72, 0x65,
+                0x63, 0x74, 0x20, 0x6c, 0x61, 0x62, 0x65, 0x6c, 0x20, 0x67, 0x7a, 0x69, 0x70, 0x20,
+                0x64, 0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68,
+                0x6f, 0x74, 0x20, 0x70, 0x6f, 0x69, 0x6e, 0x74, 0x65, 0x72, 0x20, 0x7a, 0x61, 0x70,
+                0x20, 0x76, 0x64, 0x65, 0x76, 0x20, 0x75, 0x62, 0x65, 0x72, 0x62, 0x6c, 0x6f, 0x63,
+                0x6b, 0x20, 0x63, 0x6c, 0x6f, 0x6e, 0x65, 0x20, 0x62, 0x6c, 0x6f, 0x63, 0x6b, 0x20,
+                0x7a, 0x61, 0x70, 0x20, 0x7a, 0x66, 0x73, 0x20, 0x7a, 0x73, 0x74, 0x64, 0x20, 0x64,
+                0x61, 0x74, 0x61, 0x73, 0x65, 0x74, 0x20, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x73, 0x75,
+                0x6d, 0x20, 0x63, 0x6f, 0x6d, 0x70, 0x72, 0x65, 0x73, 0x73, 0x69, 0x6f, 0x6e,
+            ],
+            &[
+                0x00, 0x00, 0x00, 0xe8, 0x13, 0x00, 0x29, 0x0b, 0x04, 0x68, 0xfd, 0x06, 0x00, 0x82,
+                0x47, 0x15, 0x11, 0xb0, 0x3b, 0x38, 0x89, 0x32, 0x55, 0x5d, 0xab, 0x6a, 0xad, 0x18,
+                0x94, 0xa4, 0x95, 0x92, 0x01, 0x15, 0x62, 0x9d, 0x55, 0xf1, 0xe2, 0x47, 0xc0, 0x73,
+                0xab, 0x0e, 0x95, 0x3d, 0xda, 0x90, 0xe8, 0xc7, 0x53, 0x4e, 0x5f, 0xf9, 0xbc, 0xdd,
+                0x39, 0xa1, 0x2f, 0x53, 0xdc, 0x4b, 0x35, 0xff, 0xaf, 0xaf, 0xd4, 0x02, 0x57, 0x5b,
+                0x29, 0xb2, 0x2e, 0xbf, 0x52, 0x3d, 0xa6, 0xf6, 0x47, 0x6c, 0x11, 0xc9, 0x52, 0xf1,
+                0xc7, 0x43, 0xf0, 0x10, 0xb2, 0x7d, 0x13, 0x1a, 0x57, 0x35, 0x12, 0x78, 0x44, 0xa3,
+                0xe0, 0x1b, 0x5a, 0x40, 0xa8, 0x61, 0x87, 0x91, 0x16, 0xa4, 0xce, 0x9e, 0x01, 0x10,
+                0x10, 0x62, 0x0c, 0x83, 0xe8, 0x01, 0x21, 0x08, 0x99, 0x10, 0x4f, 0x84, 0xc4, 0x26,
+                0x50, 0x8b, 0xc5, 0x97, 0x29, 0x45, 0x24, 0x38, 0x20, 0x20, 0xdf, 0xe7, 0x9f, 0xc6,
+                0xb4, 0x12, 0x00, 0x1b, 0x32, 0x26, 0xd0, 0xd2, 0x4a, 0x0b, 0xa3, 0x99, 0xc5, 0x65,
+                0xeb, 0xd6, 0x4b, 0x0b, 0xcd, 0x85, 0xc4, 0xfb, 0x2f, 0xe0, 0x94, 0x53, 0x72, 0x4a,
+                0x3b, 0xeb, 0xe9, 0x9d, 0x6c, 0xfb, 0xfa, 0x13, 0x62, 0xdc, 0x82, 0xa2, 0xd5, 0xda,
+                0xa2, 0x95, 0x41, 0x4c, 0xdd, 0x98, 0x3a, 0xea, 0x06, 0x63, 0x57, 0xbd, 0xe9, 0xdc,
+                0x1c, 0x00, 0x74, 0xd9, 0x1f, 0x7d, 0xdb, 0x88, 0x35, 0x61, 0x57, 0xf4, 0x07, 0x2c,
+                0xba, 0x51, 0xbf, 0x03, 0x99, 0x57, 0x70, 0xc0, 0xd6, 0x25, 0xa5, 0x08, 0x24, 0x60,
+                0xb8, 0xca, 0xa7, 0x37, 0xfa, 0xdf, 0xd4, 0x43, 0x6d, 0xd6, 0xa9, 0x06, 0x8a, 0xd3,
+                0xf1, 0xe6,
+            ],
+        ),
+        // Raw block.
+        (
+            &[0x68, 0x65, 0x6c, 0x6c, 0x6f],
+            &[
+                0x00, 0x00, 0x00, 0x0a, 0x03, 0x00, 0x29, 0x0b, 0x00, 0x58, 0x29, 0x00, 0x00, 0x68,
+                0x65, 0x6c, 0x6c, 0x6f,
+            ],
+        ),
+    ];
+
+    const TEST_VECTORS_DECOMPRESS_ERRORS: &[(usize, &[u8])] = &[
+        // Raw block, reserved frame header bit.
+        (
+            5,
+            &[
+                0x00, 0x00, 0x00, 0x0e, 0x03, 0x00, 0x29, 0x0b, 0x0c, 0x58, 0x29, 0x00, 0x00, 0x68,
+                0x65, 0x6c, 0x6c, 0x6f, 0xa3, 0x6d, 0x9f, 0x88,
+            ],
+        ),
+        // Raw block, reserved block type.
+        (
+            5,
+            &[
+                0x00, 0x00, 0x00, 0x0e, 0x03, 0x00, 0x29, 0x0b, 0x04, 0x58, 0x2f, 0x00, 0x00, 0x68,
+                0x65, 0x6c, 0x6c, 0x6f, 0xa3, 0x6d, 0x9f, 0x88,
+            ],
+        ),
+        // Raw block, invalid checksum.
+        (
+            5,
+            &[
+                0x00, 0x00, 0x00, 0x0e, 0x03, 0x00, 0x29, 0x0b, 0x04, 0x58, 0x29, 0x00, 0x00, 0x68,
+                0x65, 0x6c, 0x6c, 0x6f, 0xa3, 0x6d, 0x9f, 0x89,
+            ],
+        ),
+        // Raw block, compressed size is too large.
+        (
+            5,
+            &[
+                0x00, 0x00, 0x00, 0x0f, 0x03, 0x00, 0x29, 0x0b, 0x04, 0x58, 0x29, 0x00, 0x00, 0x68,
+                0x65, 0x6c, 0x6c, 0x6f, 0xa3, 0x6d, 0x9f, 0x88,
+            ],
+        ),
+    ];
+
+    /** Test vectors that decompress to all zeroes.
+     *
+     * Each is a tuple of the number of zeroes, and the compressed data.
+     */
+    const TEST_VECTORS_ZEROS: &[(usize, &[u8])] = &[
+        (
+            512,
+            &[
+                0x00, 0x00, 0x00, 0x0e, 0x03, 0x00, 0x29, 0x0b, 0x00, 0x58, 0x4d, 0x00, 0x00, 0x10,
+                0x00, 0x00, 0x01, 0x00, 0xfb, 0x2a, 0xc0, 0x02,
+            ],
+        ),
+        (
+            4096,
+            &[
+                0x00, 0x00, 0x00, 0x0e, 0x03, 0x00, 0x29, 0x0b, 0x00, 0x58, 0x4d, 0x00, 0x00, 0x10,
+                0x00, 0x00, 0x01, 0x00, 0xfb, 0xf7, 0x01, 0x16,
+            ],
+        ),
+        (
+            131072,
+            &[
+                0x00, 0x00, 0x00, 0x0f, 0x03, 0x00, 0x29, 0x0b, 0x00, 0x58, 0x55, 0x00, 0x00, 0x10,
+                0x00, 0x00, 0x01, 0x00, 0xfb, 0xff, 0x39, 0xc0, 0x02,
+            ],
+        ),
+    ];
+    #[test]
+    fn header() {
+        let header = ZstdHeader::from_bytes(TEST_VECTORS[1].1).unwrap();
+        assert_eq!(
+            header,
+            ZstdHeader {
+                compressed_size: (TEST_VECTORS[1].1.len() - ZstdHeader::SIZE) as u32,
+                level: 19,
+                version: 10507,
+            }
+        );
+
+        // Truncated header.
+        for test_size in 0..ZstdHeader::SIZE {
+            assert!(ZstdHeader::from_bytes(&TEST_VECTORS[1].1[0..test_size]).is_err());
+        }
+    }
+
+    #[test]
+    fn decompress() {
+        let mut zstd = ZstdDecoder::new();
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            // Create output.
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Decompress the data.
+            zstd.decompress(&mut output, compressed_data, 0).unwrap();
+            assert_eq!(output, *decompressed_data);
+
+            // Now test with padding at the end, as if read from a sector.
+            let mut padded = compressed_data.to_vec();
+            padded.resize(compressed_data.len() + 512, 0xaa);
+
+            output.fill(0);
+            zstd.decompress(&mut output, &padded, 0).unwrap();
+            assert_eq!(output, *decompressed_data);
+        }
+    }
+
+    #[test]
+    fn decompress_error() {
+        let mut zstd = ZstdDecoder::new();
+
+        // Loop over test vectors.
+        for (decompressed_data, compressed_data) in TEST_VECTORS {
+            let mut output = vec![0; decompressed_data.len()];
+
+            // Truncated source.
+            for test_size in 0..compressed_data.len() {
+                assert!(zstd
+                    .decompress(&mut output, &compressed_data[0..test_size], 0)
+                    .is_err());
+            }
+
+            // Output is too small.
+            assert!(zstd
+                .decompress(&mut output[1..], compressed_data, 0)
+                .is_err());
+
+            // Output is too large.
+            let mut output = vec![0; decompressed_data.len() + 1];
+            assert!(zstd.decompress(&mut output, compressed_data, 0).is_err());
+        }
+
+        // Loop over invalid vectors.
+        for (size, compressed_data) in TEST_VECTORS_DECOMPRESS_ERRORS {
+            let mut output = vec![0; *size];
+            assert!(zstd.decompress(&mut output, compressed_data, 0).is_err());
+        }
+    }
+
+    #[test]
+    fn decompress_zeros() {
+        let mut zstd = ZstdDecoder::new();
+
+        // Loop over test vectors.
+        for (size, compressed_data) in TEST_VECTORS_ZEROS {
+            // Set the initial values to non-zero to check that data is
+            // actually written out.
+            let mut output = vec![33; *size];
+            zstd.decompress(&mut output, compressed_data, 0).unwrap();
+
+            // Should be all zeroes.
+            assert!(output.iter().all(|&b| b == 0));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+pub(crate) mod block_reader;
+pub use block_reader::{BadCopy, BadCopyKind, BlockReadError, BlockReader};
+
+pub(crate) mod draid;
+pub use draid::{
+    DRaidError, DRaidLayout, DRaidMap, DRAID_CHILDREN_MAX, DRAID_MAPS, DRAID_ROW_HEIGHT,
+};
+
+pub(crate) mod indirect;
+pub use indirect::{IndirectError, IndirectMapping, INDIRECT_LEVELS_MAX};
+
+pub(crate) mod raidz;
+pub use raidz::{RaidZColumn, RaidZError, RaidZMap, RAIDZ_ALLOCATE_SHIFT_MAX, RAIDZ_PARITY_MAX};
+
+pub(crate) mod vdev;
+pub use vdev::{
+    Vdev, VdevDRaid, VdevDecodeError, VdevDevice, VdevIndirect, VdevLeaf, VdevMirror, VdevRaidZ,
+    VdevRaidZRead, VdevReadError,
+};
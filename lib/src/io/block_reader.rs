@@ -0,0 +1,2401 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Block pointer reader.
+ *
+ * - A [`BlockPointer`] is read from the first [`Dva`] that can be read, has a
+ *   valid checksum, and decompresses. The other [`Dva`] are only read if the
+ *   previous one fails.
+ * - A [`Dva`] is read from the [`Vdev`] at index `vdev`. If the [`Vdev`]
+ *   stores more than one copy of the data, such as a mirror, each copy is
+ *   read until the checksum verifies. Copies that fail are recorded as a
+ *   [`BadCopy`].
+ * - A RAID-Z or dRAID [`Vdev`] reconstructs children from parity until the checksum
+ *   verifies. Children that fail are recorded as a [`BadCopy`].
+ * - A gang [`Dva`] points to a [`GangHeader`], and the physical bytes are the
+ *   concatenation of the physical bytes of its [`BlockPointer`], which may
+ *   themselves be gang blocks.
+ * - A [`BlockPointerEmbedded`] has no [`Dva`], and its payload is the
+ *   physical bytes.
+ * - A [`Dva`] on an indirect [`Vdev`] is remapped through the
+ *   [`IndirectMapping`] of the vdev, loaded from the MOS, to one [`Dva`] per
+ *   mapped segment. The physical bytes are the concatenation of the
+ *   segments.
+ * - [`Dnode`] blocks are read by walking the indirect blocks from the top
+ *   level [`BlockPointer`] of the [`Dnode`].
+ */
+use core::fmt;
+use std::error;
+
+use crate::checksum::{
+    embedded_verify, gang_header_verifier, verify_block, BlockVerifyError, ChecksumRegistry,
+    EmbeddedVerifyError,
+};
+use crate::compression::{DecompressionError, DecompressionRegistry};
+use crate::io::{
+    IndirectError, IndirectMapping, Vdev, VdevIndirect, VdevLeaf, VdevRaidZRead, VdevReadError,
+    INDIRECT_LEVELS_MAX,
+};
+use crate::phys::{
+    BigLittleEndianDecoder, BinaryDecodeError, BinaryDecoder, BlockPointer,
+    BlockPointerDecodeError, BlockPointerEmbedded, BlockPointerEmbeddedType, BlockPointerRegular,
+    ChecksumType, CompressionType, Dnode, DnodeDecodeError, Dva, EndianOrder, FeatureSet,
+    GangHeader, GangHeaderDecodeError, IndirectBirthEntry, IndirectBirthsDecodeError,
+    IndirectBirthsPhys, IndirectMappingDecodeError, IndirectMappingEntry, IndirectMappingPhys,
+    SECTOR_SHIFT,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Maximum number of [`Dnode`] levels.
+const DNODE_LEVELS_MAX: usize = 8;
+
+/// Maximum number of nested [`GangHeader`] levels.
+const GANG_LEVELS_MAX: usize = 8;
+
+/// Reason a copy of a [`Dva`] is bad.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BadCopyKind {
+    /// Checksum of the data does not match.
+    Checksum,
+
+    /// Data could not be read.
+    Read,
+}
+
+/** A copy of a [`Dva`] that returned bad data, or could not be read.
+ *
+ * A bad copy of a block, for which another copy verifies, is silent
+ * corruption of the leaf [`Vdev`].
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BadCopy {
+    /// Top level [`Vdev`] index.
+    pub vdev: u32,
+
+    /// Copy index, such as the mirror, RAID-Z, or dRAID child.
+    pub copy: usize,
+
+    /// Guid of the leaf [`Vdev`] that stores the copy.
+    pub guid: Option<u64>,
+
+    /// Sector offset of the [`Dva`].
+    pub offset: u64,
+
+    /// Number of sectors read.
+    pub sectors: u32,
+
+    /// Reason the copy is bad.
+    pub kind: BadCopyKind,
+}
+
+/** Reads [`BlockPointer`] data from a set of virtual devices.
+ *
+ * - `vdevs` are indexed by the `vdev` of each [`Dva`].
+ */
+pub struct BlockReader<'a> {
+    /// Top level virtual devices.
+    vdevs: &'a [Vdev<'a>],
+
+    /// Decompressors, reused for every block.
+    decompressors: DecompressionRegistry,
+
+    /// Checksums, with the per pool checksum salt, reused for every block.
+    checksums: ChecksumRegistry,
+
+    /// Bad copies found while reading.
+    bad_copies: Vec<BadCopy>,
+
+    /// Indirect mappings, indexed like `vdevs`.
+    indirect_mappings: Vec<Option<IndirectMapping>>,
+}
+
+impl<'a> BlockReader<'a> {
+    /// Create a new block reader, for the top level `vdevs`.
+    pub fn new(vdevs: &'a [Vdev<'a>]) -> BlockReader<'a> {
+        BlockReader {
+            vdevs,
+            decompressors: DecompressionRegistry::new(),
+            checksums: ChecksumRegistry::new(),
+            bad_copies: Vec::new(),
+            indirect_mappings: vdevs.iter().map(|_| None).collect(),
+        }
+    }
+
+    /// Takes the bad copies found while reading, since the last call.
+    pub fn take_bad_copies(&mut self) -> Vec<BadCopy> {
+        core::mem::take(&mut self.bad_copies)
+    }
+
+    /// Set the per pool checksum salt, stored in the MOS.
+    pub fn with_salt(mut self, salt: [u8; 32]) -> Self {
+        self.checksums = self.checksums.with_salt(salt);
+        self
+    }
+
+    /// Set the checksums, such as a [`ChecksumRegistry`] with specific
+    /// implementations. The salt of `checksums` replaces any previous salt.
+    pub fn with_checksums(mut self, checksums: ChecksumRegistry) -> Self {
+        self.checksums = checksums;
+        self
+    }
+
+    /** Loads the [`IndirectMapping`] of each indirect [`Vdev`] from the
+     * `mos` meta [`Dnode`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn load_indirect_mappings(&mut self, mos: &Dnode) -> Result<(), BlockReadError> {
+        let vdevs = self.vdevs;
+
+        for (idx, vdev) in vdevs.iter().enumerate() {
+            if let Vdev::Indirect(indirect) = vdev {
+                self.indirect_mappings[idx] = Some(self.read_indirect_mapping(mos, indirect)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    /** Sets the [`IndirectMapping`] of the indirect [`Vdev`] at index `vdev`.
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] if `vdev` is not an indirect [`Vdev`].
+     */
+    pub fn set_indirect_mapping(
+        &mut self,
+        vdev: u32,
+        mapping: IndirectMapping,
+    ) -> Result<(), BlockReadError> {
+        match usize::try_from(vdev).ok().and_then(|idx| {
+            matches!(self.vdevs.get(idx), Some(Vdev::Indirect(_)))
+                .then(|| self.indirect_mappings.get_mut(idx))
+                .flatten()
+        }) {
+            Some(v) => {
+                *v = Some(mapping);
+                Ok(())
+            }
+            None => Err(BlockReadError::InvalidVdev { vdev }),
+        }
+    }
+
+    /// Gets the [`IndirectMapping`] of the indirect [`Vdev`] at index `vdev`,
+    /// or [`None`] if it is not loaded.
+    pub fn indirect_mapping(&self, vdev: u32) -> Option<&IndirectMapping> {
+        usize::try_from(vdev)
+            .ok()
+            .and_then(|idx| self.indirect_mappings.get(idx))
+            .and_then(|mapping| mapping.as_ref())
+    }
+
+    /** Reads `copy` of `sectors` from a [`Dva`].
+     *
+     * The `is_gang` flag is ignored, so for a gang [`Dva`] the sectors of the
+     * [`GangHeader`] are read.
+     *
+     * A [`Dva`] on an indirect [`Vdev`] is remapped, and each segment is read
+     * from the first copy that can be read.
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn read_dva(
+        &self,
+        dva: &Dva,
+        sectors: u32,
+        copy: usize,
+    ) -> Result<Vec<u8>, BlockReadError> {
+        if sectors > dva.allocated {
+            return Err(BlockReadError::InvalidSectors {
+                sectors,
+                allocated: dva.allocated,
+            });
+        }
+
+        let vdev = self.vdev(dva)?;
+
+        let size = sectors_to_bytes(sectors)?;
+        let mut data = vec![0; size];
+
+        if !matches!(vdev, Vdev::Indirect(_)) {
+            vdev.read(&mut data, dva.offset, copy)?;
+            return Ok(data);
+        }
+
+        if copy != 0 {
+            return Err(VdevReadError::InvalidCopy { copy }.into());
+        }
+
+        let mut start = 0;
+
+        for segment in self.remap(dva, sectors)? {
+            let vdev = self.vdev(&segment)?;
+            let end = start + sectors_to_bytes(segment.allocated)?;
+
+            let mut result = Ok(());
+            for copy in 0..vdev.copies() {
+                result = vdev.read(&mut data[start..end], segment.offset, copy);
+                if result.is_ok() {
+                    break;
+                }
+            }
+            result?;
+
+            start = end;
+        }
+
+        Ok(data)
+    }
+
+    /** Reads the physical bytes of a [`BlockPointerRegular`], and verifies
+     * the checksum.
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] if no [`Dva`] can be read and verified. The
+     * error is from the last [`Dva`].
+     */
+    pub fn read_physical(&mut self, ptr: &BlockPointerRegular) -> Result<Vec<u8>, BlockReadError> {
+        self.read_physical_gang(ptr, 0)
+    }
+
+    /// Reads the physical bytes of a [`BlockPointerRegular`], that is `level`
+    /// gang blocks deep.
+    fn read_physical_gang(
+        &mut self,
+        ptr: &BlockPointerRegular,
+        level: usize,
+    ) -> Result<Vec<u8>, BlockReadError> {
+        let mut result = Err(BlockReadError::NoDva {});
+
+        for dva in ptr.dvas.iter().flatten() {
+            result = self.read_dva_verified(ptr, dva, level);
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /** Decompresses the physical bytes of a [`BlockPointerRegular`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn decompress(
+        &mut self,
+        ptr: &BlockPointerRegular,
+        physical: Vec<u8>,
+    ) -> Result<Vec<u8>, BlockReadError> {
+        if ptr.compression == CompressionType::Off {
+            if ptr.logical_sectors != ptr.physical_sectors {
+                return Err(BlockReadError::InvalidLogicalSectors {
+                    logical: ptr.logical_sectors,
+                    physical: ptr.physical_sectors,
+                });
+            }
+            return Ok(physical);
+        }
+
+        let mut logical = vec![0; sectors_to_bytes(ptr.logical_sectors)?];
+
+        // Block pointers store the resolved compression, so features are not
+        // needed to resolve On.
+        self.decompressors
+            .decompressor_for(ptr.compression, &FeatureSet::default())?
+            .decompress(&mut logical, &physical, 0)?;
+
+        Ok(logical)
+    }
+
+    /** Reads the logical bytes of a [`BlockPointerRegular`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] if no [`Dva`] can be read, verified, and
+     * decompressed. The error is from the last [`Dva`].
+     */
+    pub fn read_regular(&mut self, ptr: &BlockPointerRegular) -> Result<Vec<u8>, BlockReadError> {
+        let mut result = Err(BlockReadError::NoDva {});
+
+        for dva in ptr.dvas.iter().flatten() {
+            result = match self.read_dva_verified(ptr, dva, 0) {
+                Ok(physical) => self.decompress(ptr, physical),
+                Err(err) => Err(err),
+            };
+            if result.is_ok() {
+                break;
+            }
+        }
+
+        result
+    }
+
+    /** Reads the logical bytes of a [`BlockPointerEmbedded`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] if the [`BlockPointerEmbedded`] is not
+     * [`BlockPointerEmbeddedType::Data`], or in case of error.
+     */
+    pub fn read_embedded(&mut self, ptr: &BlockPointerEmbedded) -> Result<Vec<u8>, BlockReadError> {
+        match ptr.embedded_type {
+            BlockPointerEmbeddedType::Data => (),
+            BlockPointerEmbeddedType::Redacted => return Err(BlockReadError::EmbeddedRedacted {}),
+            embedded_type => return Err(BlockReadError::EmbeddedType { embedded_type }),
+        }
+
+        let physical = match ptr.payload.get(0..ptr.physical_size) {
+            Some(v) => v,
+            None => {
+                return Err(BlockReadError::InvalidEmbeddedSize {
+                    logical: ptr.logical_size,
+                    physical: ptr.physical_size,
+                })
+            }
+        };
+
+        if ptr.compression == CompressionType::Off {
+            if ptr.logical_size != ptr.physical_size {
+                return Err(BlockReadError::InvalidEmbeddedSize {
+                    logical: ptr.logical_size,
+                    physical: ptr.physical_size,
+                });
+            }
+            return Ok(physical.to_vec());
+        }
+
+        let mut logical = vec![0; ptr.logical_size];
+
+        self.decompressors
+            .decompressor_for(ptr.compression, &FeatureSet::default())?
+            .decompress(&mut logical, physical, 0)?;
+
+        Ok(logical)
+    }
+
+    /** Reads the logical bytes of a [`BlockPointer`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn read(&mut self, ptr: &BlockPointer) -> Result<Vec<u8>, BlockReadError> {
+        match ptr {
+            BlockPointer::Embedded(ptr) => self.read_embedded(ptr),
+            BlockPointer::Encrypted(_) => Err(BlockReadError::Encrypted {}),
+            BlockPointer::Regular(ptr) => self.read_regular(ptr),
+        }
+    }
+
+    /** Reads block `block_id` of a [`Dnode`].
+     *
+     * Returns the [`EndianOrder`] and logical bytes of the block, or [`None`]
+     * if the block is not allocated.
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn read_dnode_block(
+        &mut self,
+        dnode: &Dnode,
+        block_id: u64,
+    ) -> Result<Option<(EndianOrder, Vec<u8>)>, BlockReadError> {
+        // Return None if the block id is not allocated.
+        if block_id > dnode.max_block_id {
+            return Ok(None);
+        }
+
+        let levels = usize::from(dnode.levels);
+        if levels == 0 || levels > DNODE_LEVELS_MAX {
+            return Err(BlockReadError::InvalidLevels {
+                levels: dnode.levels,
+            });
+        }
+
+        // Number of block pointers per indirect block.
+        let block_pointers_per_block = match 1u64.checked_shl(dnode.indirect_block_shift.into()) {
+            Some(v) if v >= BlockPointer::SIZE as u64 => v / (BlockPointer::SIZE as u64),
+            _ => {
+                return Err(BlockReadError::InvalidIndirectBlockShift {
+                    shift: dnode.indirect_block_shift,
+                })
+            }
+        };
+
+        let mut block_ids = [0; DNODE_LEVELS_MAX];
+        let mut block_pointer_idxs = [0; DNODE_LEVELS_MAX];
+
+        // Level 0 block is the requested block id.
+        block_ids[0] = block_id;
+
+        // Compute the block ids and block pointers for the intermediate levels.
+        for level in 1..levels {
+            let block_id = block_ids[level - 1];
+            block_ids[level] = block_id / block_pointers_per_block;
+            block_pointer_idxs[level] =
+                ((block_id % block_pointers_per_block) as usize) * BlockPointer::SIZE;
+        }
+
+        // Read the top most block pointer. A block id that is too large for
+        // the dnode pointers, or an empty pointer, is not allocated.
+        let ptr = match usize::try_from(block_ids[levels - 1])
+            .ok()
+            .and_then(|idx| dnode.pointers().get(idx))
+        {
+            Some(Some(ptr)) => ptr,
+            _ => return Ok(None),
+        };
+
+        // Block order and bytes.
+        let mut order = ptr.order();
+        let mut block_bytes = self.read(ptr)?;
+
+        // Read intermediate block pointers.
+        for level in (1..levels).rev() {
+            // Decode the block as an intermediate block.
+            let mut bl_decoder = BigLittleEndianDecoder::from_bytes(&block_bytes, order);
+            let decoder = bl_decoder.decoder_as_mut();
+
+            // Seek to index of block pointer.
+            decoder.seek(block_pointer_idxs[level])?;
+
+            // Decode block pointer.
+            let ptr = match BlockPointer::from_decoder(decoder)? {
+                Some(ptr) => ptr,
+                None => return Ok(None),
+            };
+
+            // Read data for next block.
+            order = ptr.order();
+            block_bytes = self.read(&ptr)?;
+        }
+
+        Ok(Some((order, block_bytes)))
+    }
+
+    /** Reads object `object_id` of `object_size` bytes from a [`Dnode`].
+     *
+     * Returns the [`EndianOrder`] and bytes of the object, or [`None`] if the
+     * block of the object is not allocated.
+     *
+     * # Errors
+     *
+     * Returns [`BlockReadError`] in case of error.
+     */
+    pub fn read_dnode_object(
+        &mut self,
+        dnode: &Dnode,
+        object_id: u64,
+        object_size: usize,
+    ) -> Result<Option<(EndianOrder, Vec<u8>)>, BlockReadError> {
+        // Compute the block size.
+        let block_size = sectors_to_bytes(dnode.data_block_size_sectors.into())?;
+
+        // Check the block size is a multiple of the object size.
+        if object_size == 0 || block_size % object_size != 0 {
+            return Err(BlockReadError::InvalidObjectSize {
+                block_size,
+                object_size,
+            });
+        }
+
+        // Compute the number of objects per block.
+        let objects_per_block = (block_size / object_size) as u64;
+
+        // Compute the block id, and the object index in the block.
+        let block_id = object_id / objects_per_block;
+        let idx_in_block = (object_id % objects_per_block) as usize;
+
+        // Read the block.
+        match self.read_dnode_block(dnode, block_id)? {
+            Some((order, block)) => {
+                let start = idx_in_block * object_size;
+                let end = start + object_size;
+
+                // Block may be smaller than the data block size.
+                match block.get(start..end) {
+                    Some(data) => Ok(Some((order, data.to_vec()))),
+                    None => Err(BlockReadError::InvalidObjectSize {
+                        block_size: block.len(),
+                        object_size,
+                    }),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /** Remaps `sectors` of a [`Dva`] on an indirect [`Vdev`], through chained
+     * indirect [`Vdev`], to one [`Dva`] per segment on other [`Vdev`].
+     */
+    fn remap(&self, dva: &Dva, sectors: u32) -> Result<Vec<Dva>, BlockReadError> {
+        let mut segments = vec![Dva {
+            allocated: sectors,
+            offset: dva.offset,
+            is_gang: false,
+            vdev: dva.vdev,
+        }];
+
+        for _ in 0..=INDIRECT_LEVELS_MAX {
+            let mut remapped = Vec::with_capacity(segments.len());
+            let mut is_indirect = false;
+
+            for segment in segments {
+                if !matches!(self.vdev(&segment)?, Vdev::Indirect(_)) {
+                    remapped.push(segment);
+                    continue;
+                }
+
+                let mapping = match self.indirect_mapping(segment.vdev) {
+                    Some(v) => v,
+                    None => {
+                        return Err(BlockReadError::MissingIndirectMapping { vdev: segment.vdev })
+                    }
+                };
+
+                remapped.extend(mapping.remap(segment.offset, segment.allocated)?);
+                is_indirect = true;
+            }
+
+            if !is_indirect {
+                return Ok(remapped);
+            }
+
+            segments = remapped;
+        }
+
+        Err(BlockReadError::IndirectLevels {
+            levels: INDIRECT_LEVELS_MAX + 1,
+        })
+    }
+
+    /// Reads and decodes the [`IndirectMapping`] of an indirect [`Vdev`].
+    fn read_indirect_mapping(
+        &mut self,
+        mos: &Dnode,
+        indirect: &VdevIndirect,
+    ) -> Result<IndirectMapping, BlockReadError> {
+        ////////////////////////////////
+        // Read the mapping entries.
+        let (order, dnode) = self.read_mos_dnode(mos, indirect.mapping_object)?;
+
+        let mut bl_decoder = BigLittleEndianDecoder::from_bytes(dnode.bonus_used(), order);
+        let phys = IndirectMappingPhys::from_decoder(bl_decoder.decoder_as_mut())?;
+
+        let entries = self.read_dnode_array(
+            &dnode,
+            order,
+            phys.entries,
+            IndirectMappingEntry::SIZE,
+            &mut |decoder| Ok(IndirectMappingEntry::from_decoder(decoder)?),
+        )?;
+
+        ////////////////////////////////
+        // Read the births.
+        let (order, dnode) = self.read_mos_dnode(mos, indirect.births_object)?;
+
+        let mut bl_decoder = BigLittleEndianDecoder::from_bytes(dnode.bonus_used(), order);
+        let phys = IndirectBirthsPhys::from_decoder(bl_decoder.decoder_as_mut())?;
+
+        let births = self.read_dnode_array(
+            &dnode,
+            order,
+            phys.entries,
+            IndirectBirthEntry::SIZE,
+            &mut |decoder| Ok(IndirectBirthEntry::from_decoder(decoder)?),
+        )?;
+
+        Ok(IndirectMapping::new(entries, births)?)
+    }
+
+    /// Reads the [`Dnode`] of `object` from the `mos` meta [`Dnode`].
+    fn read_mos_dnode(
+        &mut self,
+        mos: &Dnode,
+        object: u64,
+    ) -> Result<(EndianOrder, Dnode), BlockReadError> {
+        if let Some((order, bytes)) = self.read_dnode_object(mos, object, Dnode::SIZE)? {
+            let mut bl_decoder = BigLittleEndianDecoder::from_bytes(&bytes, order);
+            if let Some(dnode) = Dnode::from_decoder(bl_decoder.decoder_as_mut())? {
+                return Ok((order, dnode));
+            }
+        }
+
+        Err(BlockReadError::MissingObject { object })
+    }
+
+    /// Reads an array of `count` entries of `size` bytes from the data of a
+    /// [`Dnode`], and decodes each entry with `decode`.
+    fn read_dnode_array<T>(
+        &mut self,
+        dnode: &Dnode,
+        order: EndianOrder,
+        count: u64,
+        size: usize,
+        decode: &mut dyn FnMut(&mut dyn BinaryDecoder<'_>) -> Result<T, BlockReadError>,
+    ) -> Result<Vec<T>, BlockReadError> {
+        let total = match usize::try_from(count)
+            .ok()
+            .and_then(|count| count.checked_mul(size))
+        {
+            Some(v) => v,
+            None => return Err(BlockReadError::InvalidArraySize { count, size: 0 }),
+        };
+
+        // Entries may span blocks, so the blocks are concatenated. Holes are
+        // zeros.
+        let block_size = sectors_to_bytes(dnode.data_block_size_sectors.into())?;
+        let mut bytes = Vec::new();
+        let mut block_id = 0;
+
+        while bytes.len() < total && block_id <= dnode.max_block_id {
+            match self.read_dnode_block(dnode, block_id)? {
+                Some((_, block)) => bytes.extend(block),
+                None => bytes.resize(bytes.len() + block_size, 0),
+            }
+            block_id += 1;
+        }
+
+        if bytes.len() < total {
+            return Err(BlockReadError::InvalidArraySize {
+                count,
+                size: bytes.len(),
+            });
+        }
+
+        let mut bl_decoder = BigLittleEndianDecoder::from_bytes(&bytes[0..total], order);
+        let decoder = bl_decoder.decoder_as_mut();
+
+        let mut entries = Vec::new();
+        while !decoder.is_empty() {
+            entries.push(decode(decoder)?);
+        }
+
+        Ok(entries)
+    }
+
+    /// Gets the [`Vdev`] of a [`Dva`].
+    fn vdev(&self, dva: &Dva) -> Result<&'a Vdev<'a>, BlockReadError> {
+        match usize::try_from(dva.vdev)
+            .ok()
+            .and_then(|vdev| self.vdevs.get(vdev))
+        {
+            Some(v) => Ok(v),
+            None => Err(BlockReadError::InvalidVdev { vdev: dva.vdev }),
+        }
+    }
+
+    /** Reads the physical bytes of one [`Dva`], and verifies the checksum.
+     *
+     * Each copy of the [`Dva`] is read until one verifies. Copies that fail
+     * are recorded as a [`BadCopy`]. The error is from the last copy.
+     */
+    fn read_dva_verified(
+        &mut self,
+        ptr: &BlockPointerRegular,
+        dva: &Dva,
+        level: usize,
+    ) -> Result<Vec<u8>, BlockReadError> {
+        let vdev = self.vdev(dva)?;
+        let mut result = Err(BlockReadError::NoCopy { vdev: dva.vdev });
+
+        if !dva.is_gang {
+            match vdev {
+                Vdev::RaidZ(raidz) => {
+                    return self.read_parity_verified(ptr, dva, &raidz.children, |data, verify| {
+                        raidz.read_verified(data, dva.offset, verify)
+                    });
+                }
+                Vdev::DRaid(draid) => {
+                    return self.read_parity_verified(ptr, dva, &draid.children, |data, verify| {
+                        draid.read_verified(data, dva.offset, verify)
+                    });
+                }
+                Vdev::Indirect(_) => {
+                    // A block in one segment keeps the verification of the
+                    // destination vdev, such as mirror copies and parity.
+                    let segments = self.remap(dva, ptr.physical_sectors)?;
+                    if let [segment] = segments.as_slice() {
+                        return self.read_dva_verified(ptr, segment, level);
+                    }
+                }
+                Vdev::Leaf(_) | Vdev::Mirror(_) => (),
+            }
+        }
+
+        for copy in 0..vdev.copies() {
+            let mut bad_copy = BadCopy {
+                vdev: dva.vdev,
+                copy,
+                guid: vdev.copy_guid(copy),
+                offset: dva.offset,
+                sectors: ptr.physical_sectors,
+                kind: BadCopyKind::Read,
+            };
+
+            let physical = if dva.is_gang {
+                bad_copy.sectors = (GangHeader::SIZE >> SECTOR_SHIFT) as u32;
+                self.read_gang(ptr, dva, level, copy)
+            } else {
+                self.read_dva(dva, ptr.physical_sectors, copy)
+            };
+
+            // Only errors of this copy are recorded, and not errors of gang
+            // members, which are recorded for their own copies.
+            let physical = match physical {
+                Ok(physical) => physical,
+                Err(err) => {
+                    match err {
+                        BlockReadError::Vdev { .. } => self.bad_copies.push(bad_copy),
+                        BlockReadError::GangHeaderVerify { .. } => {
+                            bad_copy.kind = BadCopyKind::Checksum;
+                            self.bad_copies.push(bad_copy);
+                        }
+                        _ => (),
+                    }
+                    result = Err(err);
+                    continue;
+                }
+            };
+
+            match verify_block(ptr, &physical, &mut self.checksums) {
+                Ok(()) => return Ok(physical),
+                Err(err) => {
+                    if !dva.is_gang {
+                        bad_copy.kind = BadCopyKind::Checksum;
+                        self.bad_copies.push(bad_copy);
+                    }
+                    result = Err(err.into());
+                }
+            }
+        }
+
+        result
+    }
+
+    /** Reads the physical bytes of a [`Dva`] on a RAID-Z or dRAID [`Vdev`]
+     * with `read`, and verifies the checksum.
+     *
+     * Children that can not be read, or are reconstructed for the checksum to
+     * verify, are recorded as a [`BadCopy`].
+     */
+    fn read_parity_verified<F>(
+        &mut self,
+        ptr: &BlockPointerRegular,
+        dva: &Dva,
+        children: &[VdevLeaf<'_>],
+        read: F,
+    ) -> Result<Vec<u8>, BlockReadError>
+    where
+        F: FnOnce(&mut [u8], &mut dyn FnMut(&[u8]) -> bool) -> Result<VdevRaidZRead, VdevReadError>,
+    {
+        let mut physical = vec![0; sectors_to_bytes(ptr.physical_sectors)?];
+        let checksums = &mut self.checksums;
+
+        let read = read(&mut physical, &mut |data| {
+            verify_block(ptr, data, checksums).is_ok()
+        })?;
+
+        let bad_children = read
+            .read_errors
+            .iter()
+            .map(|child| (child, BadCopyKind::Read))
+            .chain(
+                read.checksum_errors
+                    .iter()
+                    .map(|child| (child, BadCopyKind::Checksum)),
+            );
+
+        for (child, kind) in bad_children {
+            self.bad_copies.push(BadCopy {
+                vdev: dva.vdev,
+                copy: *child,
+                guid: children.get(*child).map(|child| child.guid),
+                offset: dva.offset,
+                sectors: ptr.physical_sectors,
+                kind,
+            });
+        }
+
+        if !read.verified {
+            verify_block(ptr, &physical, &mut self.checksums)?;
+        }
+
+        Ok(physical)
+    }
+
+    /** Reads `copy` of the physical bytes of a gang [`Dva`], that is `level`
+     * gang blocks deep.
+     *
+     * The [`GangHeader`] is verified, and the physical bytes of its
+     * [`BlockPointer`] are concatenated. The checksum of the concatenated
+     * bytes is not verified.
+     */
+    fn read_gang(
+        &mut self,
+        ptr: &BlockPointerRegular,
+        dva: &Dva,
+        level: usize,
+        copy: usize,
+    ) -> Result<Vec<u8>, BlockReadError> {
+        if level >= GANG_LEVELS_MAX {
+            return Err(BlockReadError::GangLevels { levels: level + 1 });
+        }
+
+        ////////////////////////////////
+        // Read and verify the gang header.
+        let header_bytes = self.read_dva(dva, (GangHeader::SIZE >> SECTOR_SHIFT) as u32, copy)?;
+
+        // The verifier is always for the first DVA, for all copies.
+        let verifier = match gang_header_verifier(ptr) {
+            Some(v) => v,
+            None => return Err(BlockReadError::NoDva {}),
+        };
+        let sha256 = self
+            .checksums
+            .checksum(ChecksumType::GangHeader)
+            .map_err(EmbeddedVerifyError::from)?;
+        embedded_verify(&header_bytes, ChecksumType::GangHeader, &verifier, sha256)?;
+
+        let mut gang_bytes = [0; GangHeader::SIZE];
+        gang_bytes.copy_from_slice(&header_bytes);
+        let header = GangHeader::from_bytes(&gang_bytes)?;
+
+        ////////////////////////////////
+        // Concatenate the physical bytes of the gang members.
+        let size = sectors_to_bytes(ptr.physical_sectors)?;
+        let mut physical = Vec::with_capacity(size);
+
+        for member in header.ptrs.iter().flatten() {
+            match member {
+                BlockPointer::Regular(member) => {
+                    physical.extend(self.read_physical_gang(member, level + 1)?);
+                }
+                _ => return Err(BlockReadError::GangBlockPointer {}),
+            }
+        }
+
+        if physical.len() != size {
+            return Err(BlockReadError::GangSize {
+                size: physical.len(),
+                expected: size,
+            });
+        }
+
+        Ok(physical)
+    }
+}
+
+/// Convert sectors to bytes.
+fn sectors_to_bytes(sectors: u32) -> Result<usize, BlockReadError> {
+    match usize::try_from(sectors)
+        .ok()
+        .and_then(|v| v.checked_mul(1 << SECTOR_SHIFT))
+    {
+        Some(v) => Ok(v),
+        None => Err(BlockReadError::SectorsTooLarge { sectors }),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`BlockReader`] error.
+#[derive(Debug)]
+pub enum BlockReadError {
+    /// [`crate::phys::BinaryDecoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// [`Dnode`] decode error.
+    Dnode {
+        /// Error.
+        err: DnodeDecodeError,
+    },
+
+    /// [`BlockPointer`] decode error.
+    BlockPointer {
+        /// Error.
+        err: BlockPointerDecodeError,
+    },
+
+    /// Block verify error.
+    BlockVerify {
+        /// Error.
+        err: BlockVerifyError,
+    },
+
+    /// Decompression error.
+    Decompression {
+        /// Error.
+        err: DecompressionError,
+    },
+
+    /// [`BlockPointerEmbedded`] is redacted, and has no data.
+    EmbeddedRedacted {},
+
+    /// [`BlockPointerEmbeddedType`] is not supported.
+    EmbeddedType {
+        /// Embedded type.
+        embedded_type: BlockPointerEmbeddedType,
+    },
+
+    /// Encrypted [`BlockPointer`] is not supported.
+    Encrypted {},
+
+    /// [`GangHeader`] has an embedded or encrypted [`BlockPointer`].
+    GangBlockPointer {},
+
+    /// [`GangHeader`] decode error.
+    GangHeader {
+        /// Error.
+        err: GangHeaderDecodeError,
+    },
+
+    /// [`GangHeader`] checksum error.
+    GangHeaderVerify {
+        /// Error.
+        err: EmbeddedVerifyError,
+    },
+
+    /// [`IndirectMapping`] error.
+    Indirect {
+        /// Error.
+        err: IndirectError,
+    },
+
+    /// [`IndirectBirthsPhys`] or [`IndirectBirthEntry`] decode error.
+    IndirectBirths {
+        /// Error.
+        err: IndirectBirthsDecodeError,
+    },
+
+    /// Too many chained indirect [`Vdev`] levels.
+    IndirectLevels {
+        /// Levels.
+        levels: usize,
+    },
+
+    /// [`IndirectMappingPhys`] or [`IndirectMappingEntry`] decode error.
+    IndirectMapping {
+        /// Error.
+        err: IndirectMappingDecodeError,
+    },
+
+    /// Too many nested [`GangHeader`] levels.
+    GangLevels {
+        /// Levels.
+        levels: usize,
+    },
+
+    /// Size of gang block members does not match physical size.
+    GangSize {
+        /// Size of gang block members in bytes.
+        size: usize,
+        /// Physical size in bytes.
+        expected: usize,
+    },
+
+    /// [`Dnode`] data is too small for an array.
+    InvalidArraySize {
+        /// Number of entries.
+        count: u64,
+        /// Size of the data in bytes.
+        size: usize,
+    },
+
+    /// Invalid [`BlockPointerEmbedded`] sizes.
+    InvalidEmbeddedSize {
+        /// Logical size in bytes.
+        logical: usize,
+        /// Physical size in bytes.
+        physical: usize,
+    },
+
+    /// Invalid indirect block shift.
+    InvalidIndirectBlockShift {
+        /// Shift.
+        shift: u8,
+    },
+
+    /// Invalid number of [`Dnode`] levels.
+    InvalidLevels {
+        /// Levels.
+        levels: u8,
+    },
+
+    /// Logical sectors do not match physical sectors of uncompressed block.
+    InvalidLogicalSectors {
+        /// Logical sectors.
+        logical: u32,
+        /// Physical sectors.
+        physical: u32,
+    },
+
+    /// Block size is not a multiple of object size.
+    InvalidObjectSize {
+        /// Block size in bytes.
+        block_size: usize,
+        /// Object size in bytes.
+        object_size: usize,
+    },
+
+    /// Sectors to read are more than the allocated sectors.
+    InvalidSectors {
+        /// Sectors to read.
+        sectors: u32,
+        /// Allocated sectors.
+        allocated: u32,
+    },
+
+    /// Invalid virtual device.
+    InvalidVdev {
+        /// Virtual device.
+        vdev: u32,
+    },
+
+    /// Indirect [`Vdev`] has no [`IndirectMapping`].
+    MissingIndirectMapping {
+        /// Virtual device.
+        vdev: u32,
+    },
+
+    /// MOS object is not allocated.
+    MissingObject {
+        /// Object number.
+        object: u64,
+    },
+
+    /// [`Vdev`] has no copies of the data.
+    NoCopy {
+        /// Virtual device.
+        vdev: u32,
+    },
+
+    /// [`BlockPointerRegular`] has no [`Dva`].
+    NoDva {},
+
+    /// Sectors are too large to read.
+    SectorsTooLarge {
+        /// Sectors.
+        sectors: u32,
+    },
+
+    /// [`Vdev`] read error.
+    Vdev {
+        /// Error.
+        err: VdevReadError,
+    },
+}
+
+impl From<BinaryDecodeError> for BlockReadError {
+    fn from(value: BinaryDecodeError) -> Self {
+        BlockReadError::Binary { err: value }
+    }
+}
+
+impl From<BlockPointerDecodeError> for BlockReadError {
+    fn from(value: BlockPointerDecodeError) -> Self {
+        BlockReadError::BlockPointer { err: value }
+    }
+}
+
+impl From<BlockVerifyError> for BlockReadError {
+    fn from(value: BlockVerifyError) -> Self {
+        BlockReadError::BlockVerify { err: value }
+    }
+}
+
+impl From<DecompressionError> for BlockReadError {
+    fn from(value: DecompressionError) -> Self {
+        BlockReadError::Decompression { err: value }
+    }
+}
+
+impl From<EmbeddedVerifyError> for BlockReadError {
+    fn from(value: EmbeddedVerifyError) -> Self {
+        BlockReadError::GangHeaderVerify { err: value }
+    }
+}
+
+impl From<DnodeDecodeError> for BlockReadError {
+    fn from(value: DnodeDecodeError) -> Self {
+        BlockReadError::Dnode { err: value }
+    }
+}
+
+impl From<IndirectBirthsDecodeError> for BlockReadError {
+    fn from(value: IndirectBirthsDecodeError) -> Self {
+        BlockReadError::IndirectBirths { err: value }
+    }
+}
+
+impl From<IndirectError> for BlockReadError {
+    fn from(value: IndirectError) -> Self {
+        BlockReadError::Indirect { err: value }
+    }
+}
+
+impl From<IndirectMappingDecodeError> for BlockReadError {
+    fn from(value: IndirectMappingDecodeError) -> Self {
+        BlockReadError::IndirectMapping { err: value }
+    }
+}
+
+impl From<GangHeaderDecodeError> for BlockReadError {
+    fn from(value: GangHeaderDecodeError) -> Self {
+        BlockReadError::GangHeader { err: value }
+    }
+}
+
+impl From<VdevReadError> for BlockReadError {
+    fn from(value: VdevReadError) -> Self {
+        BlockReadError::Vdev { err: value }
+    }
+}
+
+impl fmt::Display for BlockReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockReadError::Binary { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::BlockPointer { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::BlockVerify { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::Decompression { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::Dnode { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::EmbeddedRedacted {} => {
+                write!(f, "Block read error, embedded block pointer is redacted")
+            }
+            BlockReadError::EmbeddedType { embedded_type } => {
+                write!(
+                    f,
+                    "Block read error, embedded block pointer type {embedded_type} is not supported"
+                )
+            }
+            BlockReadError::Encrypted {} => {
+                write!(
+                    f,
+                    "Block read error, encrypted block pointer is not supported"
+                )
+            }
+            BlockReadError::GangBlockPointer {} => {
+                write!(
+                    f,
+                    "Block read error, gang header has an embedded or encrypted block pointer"
+                )
+            }
+            BlockReadError::GangHeader { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::GangHeaderVerify { err } => {
+                write!(f, "Block read error, gang header | {err}")
+            }
+            BlockReadError::Indirect { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::IndirectBirths { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::IndirectLevels { levels } => {
+                write!(f, "Block read error, too many indirect levels {levels}")
+            }
+            BlockReadError::IndirectMapping { err } => {
+                write!(f, "Block read error | {err}")
+            }
+            BlockReadError::GangLevels { levels } => {
+                write!(f, "Block read error, too many gang levels {levels}")
+            }
+            BlockReadError::GangSize { size, expected } => {
+                write!(
+                    f,
+                    "Block read error, gang size {size} does not match physical size {expected}"
+                )
+            }
+            BlockReadError::InvalidArraySize { count, size } => {
+                write!(
+                    f,
+                    "Block read error, data size {size} is too small for {count} entries"
+                )
+            }
+            BlockReadError::InvalidEmbeddedSize { logical, physical } => {
+                write!(
+                    f,
+                    "Block read error, invalid embedded logical size {logical} and physical size {physical}"
+                )
+            }
+            BlockReadError::InvalidIndirectBlockShift { shift } => {
+                write!(f, "Block read error, invalid indirect block shift {shift}")
+            }
+            BlockReadError::InvalidLevels { levels } => {
+                write!(f, "Block read error, invalid dnode levels {levels}")
+            }
+            BlockReadError::InvalidLogicalSectors { logical, physical } => {
+                write!(
+                    f,
+                    "Block read error, uncompressed logical sectors {logical} do not match physical sectors {physical}"
+                )
+            }
+            BlockReadError::InvalidObjectSize {
+                block_size,
+                object_size,
+            } => {
+                write!(
+                    f,
+                    "Block read error, block size {block_size} is not a multiple of object size {object_size}"
+                )
+            }
+            BlockReadError::InvalidSectors { sectors, allocated } => {
+                write!(
+                    f,
+                    "Block read error, sectors {sectors} are more than allocated {allocated}"
+                )
+            }
+            BlockReadError::InvalidVdev { vdev } => {
+                write!(f, "Block read error, invalid vdev {vdev}")
+            }
+            BlockReadError::MissingIndirectMapping { vdev } => {
+                write!(f, "Block read error, indirect vdev {vdev} has no mapping")
+            }
+            BlockReadError::MissingObject { object } => {
+                write!(f, "Block read error, object {object} is not allocated")
+            }
+            BlockReadError::NoCopy { vdev } => {
+                write!(f, "Block read error, vdev {vdev} has no copies")
+            }
+            BlockReadError::NoDva {} => {
+                write!(f, "Block read error, block pointer has no DVA")
+            }
+            BlockReadError::SectorsTooLarge { sectors } => {
+                write!(f, "Block read error, sectors {sectors} are too large")
+            }
+            BlockReadError::Vdev { err } => {
+                write!(f, "Block read error | {err}")
+            }
+        }
+    }
+}
+
+impl error::Error for BlockReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BlockReadError::Binary { err } => Some(err),
+            BlockReadError::BlockPointer { err } => Some(err),
+            BlockReadError::BlockVerify { err } => Some(err),
+            BlockReadError::Decompression { err } => Some(err),
+            BlockReadError::Dnode { err } => Some(err),
+            BlockReadError::GangHeader { err } => Some(err),
+            BlockReadError::Indirect { err } => Some(err),
+            BlockReadError::IndirectBirths { err } => Some(err),
+            BlockReadError::IndirectMapping { err } => Some(err),
+            BlockReadError::GangHeaderVerify { err } => Some(err),
+            BlockReadError::Vdev { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::checksum::{
+        embedded_checksum, gang_header_verifier, Checksum, ChecksumRegistry, Fletcher4,
+        Fletcher4Implementation,
+    };
+    use crate::compression::{Compression, Lz4Encoder};
+    use crate::io::{
+        BadCopy, BadCopyKind, BlockReadError, BlockReader, DRaidLayout, DRaidMap, IndirectError,
+        RaidZMap, Vdev, VdevDRaid, VdevIndirect, VdevLeaf, VdevMirror, VdevRaidZ, VdevReadError,
+    };
+    use crate::phys::dnode::{DnodeTailOne, DnodeUsed};
+    use crate::phys::{
+        BigLittleEndianDecoder, BigLittleEndianEncoder, BlockPointer, BlockPointerEmbedded,
+        BlockPointerEmbeddedType, BlockPointerRegular, BootBlock, ChecksumTail, ChecksumType,
+        ChecksumValue, CompressionType, DmuType, Dnode, DnodeTail, Dva, EndianOrder, GangHeader,
+        IndirectBirthEntry, IndirectBirthsPhys, IndirectMappingEntry, IndirectMappingPhys,
+        SECTOR_SHIFT,
+    };
+    use crate::userspace::BlockDeviceMemory;
+
+    /// Sectors of data in test virtual devices.
+    const IMAGE_DATA_SECTORS: u64 = 64;
+
+    /// Creates a virtual device, with `data` written at sector `offset` of
+    /// the data area.
+    fn image(blocks: &[(u64, &[u8])]) -> BlockDeviceMemory {
+        let data_offset =
+            ((BootBlock::BLOCK_DEVICE_OFFSET + BootBlock::SECTORS) << SECTOR_SHIFT) as usize;
+        let mut bytes = vec![0; data_offset + ((IMAGE_DATA_SECTORS as usize) << SECTOR_SHIFT)];
+
+        for (offset, data) in blocks {
+            let start = data_offset + ((*offset as usize) << SECTOR_SHIFT);
+            bytes[start..start + data.len()].copy_from_slice(data);
+        }
+
+        BlockDeviceMemory::new(bytes).unwrap()
+    }
+
+    /// Creates leaf [`Vdev`] for `devices`, with guids starting at 1.
+    fn leaves(devices: &[BlockDeviceMemory]) -> Vec<Vdev<'_>> {
+        devices
+            .iter()
+            .enumerate()
+            .map(|(idx, device)| Vdev::leaf(idx as u64 + 1, device))
+            .collect()
+    }
+
+    /// Creates a [`BlockPointerRegular`] for the `physical` bytes.
+    fn block_pointer(
+        physical: &[u8],
+        logical_sectors: u32,
+        compression: CompressionType,
+        dvas: [Option<Dva>; 3],
+        level: u8,
+    ) -> BlockPointerRegular {
+        let mut fletcher4 = Fletcher4::new(Fletcher4Implementation::Generic).unwrap();
+        let words = fletcher4.hash(physical, EndianOrder::Little).unwrap();
+
+        BlockPointerRegular {
+            checksum_type: ChecksumType::Fletcher4,
+            checksum_value: ChecksumValue { words },
+            compression,
+            dedup: false,
+            dmu: DmuType::PlainFileContents,
+            dvas,
+            order: EndianOrder::Little,
+            fill_count: 1,
+            level,
+            logical_birth_txg: 1,
+            logical_sectors,
+            physical_birth_txg: 1,
+            physical_sectors: (physical.len() >> SECTOR_SHIFT) as u32,
+        }
+    }
+
+    /// Creates a [`Dva`] on `vdev` at sector `offset`.
+    fn dva(vdev: u32, offset: u64, allocated: u32) -> Option<Dva> {
+        Some(Dva {
+            allocated,
+            offset,
+            is_gang: false,
+            vdev,
+        })
+    }
+
+    /// Creates a gang [`Dva`] on `vdev` at sector `offset`.
+    fn gang_dva(vdev: u32, offset: u64, allocated: u32) -> Option<Dva> {
+        Some(Dva {
+            allocated,
+            offset,
+            is_gang: true,
+            vdev,
+        })
+    }
+
+    /// Creates a [`Dnode`] with one top level [`BlockPointer`].
+    fn dnode(ptr: BlockPointer, levels: u8, max_block_id: u64) -> Dnode {
+        Dnode {
+            bonus_len: 0,
+            bonus_type: DmuType::None,
+            checksum: ChecksumType::Fletcher4,
+            compression: CompressionType::Off,
+            data_block_size_sectors: 2,
+            extra_slots: 0,
+            dmu: DmuType::PlainFileContents,
+            indirect_block_shift: 10,
+            levels,
+            max_block_id,
+            tail: DnodeTail::One(DnodeTailOne {
+                ptrs: [Some(ptr)],
+                bonus: [0; DnodeTailOne::BONUS_SIZE],
+            }),
+            used: DnodeUsed::Bytes(0),
+            user_obj_used_accounted: false,
+            user_used_accounted: false,
+        }
+    }
+
+    /// Creates a [`GangHeader`] for the `members`, pointed to by `ptr`.
+    fn gang_header(ptr: &BlockPointerRegular, members: [Option<BlockPointer>; 3]) -> Vec<u8> {
+        let header = GangHeader {
+            order: EndianOrder::Little,
+            ptrs: members,
+            tail: ChecksumTail {
+                order: EndianOrder::Little,
+                value: ChecksumValue { words: [0; 4] },
+            },
+        };
+
+        let mut bytes = [0; GangHeader::SIZE];
+        header.to_bytes(&mut bytes).unwrap();
+        embedded_checksum(
+            &mut bytes,
+            ChecksumType::GangHeader,
+            &gang_header_verifier(ptr).unwrap(),
+            ChecksumRegistry::new()
+                .checksum_for(ChecksumType::GangHeader)
+                .unwrap()
+                .as_mut(),
+            EndianOrder::Little,
+        )
+        .unwrap();
+
+        bytes.to_vec()
+    }
+
+    /// Compresses the `logical` bytes with LZ4, padded to a sector.
+    fn lz4(logical: &[u8]) -> Vec<u8> {
+        let mut physical = vec![0; logical.len()];
+        let size = Lz4Encoder::new()
+            .compress(&mut physical, logical, 0)
+            .unwrap();
+        let size = size.next_multiple_of(1 << SECTOR_SHIFT);
+        physical.truncate(size);
+        physical
+    }
+
+    #[test]
+    fn read() {
+        let logical: Vec<u8> = (0..4096).map(|x| (x % 7) as u8).collect();
+        let compressed = lz4(&logical);
+        assert!(compressed.len() < logical.len());
+
+        let devices = [image(&[(0, &logical), (8, &compressed)])];
+        let vdevs = leaves(&devices);
+        let mut reader = BlockReader::new(&vdevs);
+
+        // Uncompressed.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 8), None, None],
+            0,
+        );
+        assert_eq!(reader.read_physical(&ptr).unwrap(), logical);
+        assert_eq!(reader.read(&BlockPointer::Regular(ptr)).unwrap(), logical);
+
+        // Compressed.
+        let sectors = (compressed.len() >> SECTOR_SHIFT) as u32;
+        let ptr = block_pointer(
+            &compressed,
+            8,
+            CompressionType::Lz4,
+            [dva(0, 8, sectors), None, None],
+            0,
+        );
+        assert_eq!(reader.read_physical(&ptr).unwrap(), compressed);
+        assert_eq!(reader.read(&BlockPointer::Regular(ptr)).unwrap(), logical);
+    }
+
+    #[test]
+    fn read_fallback() {
+        let logical: Vec<u8> = (0..4096).map(|x| (x * 13) as u8).collect();
+        let mut corrupted = logical.clone();
+        corrupted[100] ^= 1;
+
+        let devices = [image(&[(0, &corrupted)]), image(&[(16, &logical)])];
+        let vdevs = leaves(&devices);
+        let mut reader = BlockReader::new(&vdevs);
+
+        // First DVA is corrupted, second DVA is on an invalid vdev, third
+        // DVA is good.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 8), dva(2, 0, 8), dva(1, 16, 8)],
+            0,
+        );
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+
+        // Error is from the last DVA.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(2, 0, 8), dva(0, 0, 8), None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::BlockVerify { .. }
+        ));
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 8), dva(2, 0, 8), None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::InvalidVdev { vdev: 2 }
+        ));
+    }
+
+    #[test]
+    fn read_embedded() {
+        let logical: Vec<u8> = b"embedded ".iter().copied().cycle().take(300).collect();
+        let compressed = {
+            let mut compressed = vec![0; logical.len()];
+            let size = Lz4Encoder::new()
+                .compress(&mut compressed, &logical, 0)
+                .unwrap();
+            compressed.truncate(size);
+            compressed
+        };
+        assert!(compressed.len() <= BlockPointerEmbedded::PHYSICAL_SIZE_MAX);
+
+        let mut payload = [0; BlockPointerEmbedded::PHYSICAL_SIZE_MAX];
+        payload[0..compressed.len()].copy_from_slice(&compressed);
+
+        let ptr = BlockPointerEmbedded {
+            compression: CompressionType::Lz4,
+            dmu: DmuType::PlainFileContents,
+            embedded_type: BlockPointerEmbeddedType::Data,
+            order: EndianOrder::Little,
+            level: 0,
+            logical_birth_txg: 1,
+            logical_size: logical.len(),
+            physical_size: compressed.len(),
+            payload,
+        };
+
+        let vdevs = [];
+        let mut reader = BlockReader::new(&vdevs);
+        assert_eq!(reader.read_embedded(&ptr).unwrap(), logical);
+
+        // Encode and decode in both byte orders.
+        for order in [EndianOrder::Big, EndianOrder::Little] {
+            let mut bytes = [0; BlockPointer::SIZE];
+            let mut encoder = BigLittleEndianEncoder::to_bytes(&mut bytes, order);
+            BlockPointer::Embedded(BlockPointerEmbedded { order, ..ptr })
+                .to_encoder(encoder.encoder())
+                .unwrap();
+
+            // Payload words store the least significant byte first.
+            let word = &bytes[0..8];
+            match order {
+                EndianOrder::Big => assert!(word.iter().eq(payload[0..8].iter().rev())),
+                EndianOrder::Little => assert_eq!(word, &payload[0..8]),
+            }
+
+            let mut decoder = BigLittleEndianDecoder::from_bytes(&bytes, order);
+            let decoded = BlockPointer::from_decoder(decoder.decoder_as_mut())
+                .unwrap()
+                .unwrap();
+            assert_eq!(reader.read(&decoded).unwrap(), logical);
+        }
+
+        // Uncompressed.
+        let ptr = BlockPointerEmbedded {
+            compression: CompressionType::Off,
+            dmu: DmuType::PlainFileContents,
+            embedded_type: BlockPointerEmbeddedType::Data,
+            order: EndianOrder::Little,
+            level: 0,
+            logical_birth_txg: 1,
+            logical_size: BlockPointerEmbedded::PHYSICAL_SIZE_MAX,
+            physical_size: BlockPointerEmbedded::PHYSICAL_SIZE_MAX,
+            payload,
+        };
+        assert_eq!(reader.read_embedded(&ptr).unwrap(), payload);
+
+        // Uncompressed with different logical size.
+        let ptr = BlockPointerEmbedded {
+            logical_size: 300,
+            ..ptr
+        };
+        assert!(matches!(
+            reader.read_embedded(&ptr).unwrap_err(),
+            BlockReadError::InvalidEmbeddedSize {
+                logical: 300,
+                physical: 112
+            }
+        ));
+
+        // Redacted and reserved have no data.
+        let ptr = BlockPointerEmbedded {
+            embedded_type: BlockPointerEmbeddedType::Redacted,
+            ..ptr
+        };
+        assert!(matches!(
+            reader.read_embedded(&ptr).unwrap_err(),
+            BlockReadError::EmbeddedRedacted {}
+        ));
+        let ptr = BlockPointerEmbedded {
+            embedded_type: BlockPointerEmbeddedType::Reserved,
+            ..ptr
+        };
+        assert!(matches!(
+            reader.read_embedded(&ptr).unwrap_err(),
+            BlockReadError::EmbeddedType {
+                embedded_type: BlockPointerEmbeddedType::Reserved
+            }
+        ));
+    }
+
+    #[test]
+    fn read_gang() {
+        let logical: Vec<u8> = (0..3072).map(|x| (x * 31 % 251) as u8).collect();
+        let mut corrupted = logical.clone();
+        corrupted[0] ^= 1;
+
+        // Gang block of 6 sectors, with a nested gang block:
+        //
+        // - sector 0: gang header [sector 1, gang at sector 3]
+        // - sector 1: member 0, logical bytes 0..1024
+        // - sector 3: gang header [sector 4, none, sector 6]
+        // - sector 4: member 1, logical bytes 1024..2048
+        // - sector 6: member 2, logical bytes 2048..3072
+        let member = |data: &[u8], offset| {
+            block_pointer(
+                data,
+                2,
+                CompressionType::Off,
+                [dva(0, offset, 2), None, None],
+                0,
+            )
+        };
+        let nested = block_pointer(
+            &logical[1024..3072],
+            4,
+            CompressionType::Off,
+            [gang_dva(0, 3, 1), None, None],
+            0,
+        );
+        let nested_header = gang_header(
+            &nested,
+            [
+                Some(BlockPointer::Regular(member(&logical[1024..2048], 4))),
+                None,
+                Some(BlockPointer::Regular(member(&logical[2048..3072], 6))),
+            ],
+        );
+
+        let mut ptr = block_pointer(
+            &logical,
+            6,
+            CompressionType::Off,
+            [gang_dva(0, 0, 1), None, None],
+            0,
+        );
+        let header = gang_header(
+            &ptr,
+            [
+                Some(BlockPointer::Regular(member(&logical[0..1024], 1))),
+                Some(BlockPointer::Regular(nested)),
+                None,
+            ],
+        );
+
+        let devices = [
+            image(&[
+                (0, &header),
+                (1, &logical[0..1024]),
+                (3, &nested_header),
+                (4, &logical[1024..2048]),
+                (6, &logical[2048..3072]),
+            ]),
+            image(&[(0, &corrupted), (16, &logical)]),
+        ];
+        let vdevs = leaves(&devices);
+        let mut reader = BlockReader::new(&vdevs);
+
+        assert_eq!(reader.read_physical(&ptr).unwrap(), logical);
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+
+        // Gang header checksum uses the first DVA, so a copy of the gang header
+        // at another DVA fails for a different first DVA.
+        ptr.dvas[0] = dva(1, 0, 6);
+        ptr.dvas[1] = gang_dva(0, 0, 1);
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::GangHeaderVerify { .. }
+        ));
+
+        // Fallback to a non gang DVA.
+        ptr.dvas[2] = dva(1, 16, 6);
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+
+        // Size of members does not match.
+        let mut ptr = block_pointer(
+            &logical,
+            6,
+            CompressionType::Off,
+            [gang_dva(0, 0, 1), None, None],
+            0,
+        );
+        ptr.physical_sectors = 4;
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::GangSize {
+                size: 3072,
+                expected: 2048
+            }
+        ));
+    }
+
+    #[test]
+    fn read_mirror() {
+        let logical: Vec<u8> = (0..4096).map(|x| (x * 17) as u8).collect();
+        let mut corrupted = logical.clone();
+        corrupted[4000] ^= 0x80;
+
+        let devices = [
+            image(&[(0, &corrupted), (8, &logical)]),
+            image(&[(0, &logical), (8, &corrupted)]),
+        ];
+
+        // Mirror with a missing child, and a child that is not a mirror.
+        let vdevs = [
+            Vdev::Mirror(VdevMirror {
+                guid: 10,
+                children: vec![
+                    VdevLeaf {
+                        guid: 11,
+                        device: Some(&devices[0]),
+                    },
+                    VdevLeaf {
+                        guid: 12,
+                        device: None,
+                    },
+                    VdevLeaf {
+                        guid: 13,
+                        device: Some(&devices[1]),
+                    },
+                ],
+            }),
+            Vdev::leaf(20, &devices[0]),
+        ];
+        assert_eq!(vdevs[0].guid(), 10);
+        assert_eq!(vdevs[0].copies(), 3);
+        assert_eq!(vdevs[0].copy_guid(2), Some(13));
+        assert_eq!(vdevs[0].copy_guid(3), None);
+        assert_eq!(vdevs[1].copies(), 1);
+
+        let mut reader = BlockReader::new(&vdevs);
+
+        // Last child is good.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 8), None, None],
+            0,
+        );
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+        assert_eq!(
+            reader.take_bad_copies(),
+            [
+                BadCopy {
+                    vdev: 0,
+                    copy: 0,
+                    guid: Some(11),
+                    offset: 0,
+                    sectors: 8,
+                    kind: BadCopyKind::Checksum,
+                },
+                BadCopy {
+                    vdev: 0,
+                    copy: 1,
+                    guid: Some(12),
+                    offset: 0,
+                    sectors: 8,
+                    kind: BadCopyKind::Read,
+                },
+            ]
+        );
+        assert!(reader.take_bad_copies().is_empty());
+
+        // First child is good.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 8, 8), None, None],
+            0,
+        );
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+        assert!(reader.take_bad_copies().is_empty());
+
+        // No child is good, and then fall back to the next DVA.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 16, 8), dva(1, 8, 8), None],
+            0,
+        );
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+        let bad_copies = reader.take_bad_copies();
+        assert_eq!(bad_copies.len(), 3);
+        assert!(bad_copies
+            .iter()
+            .all(|bad_copy| bad_copy.vdev == 0 && bad_copy.offset == 16));
+
+        // Error is from the last child.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 16, 8), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::BlockVerify { .. }
+        ));
+
+        // Mirror with no children.
+        let vdevs = [Vdev::Mirror(VdevMirror {
+            guid: 10,
+            children: Vec::new(),
+        })];
+        let mut reader = BlockReader::new(&vdevs);
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::NoCopy { vdev: 0 }
+        ));
+    }
+
+    #[test]
+    fn read_raidz() {
+        let logical: Vec<u8> = (0..4096).map(|x| (x * 19) as u8).collect();
+
+        // Columns of the block on a double parity RAID-Z of 5 children, at
+        // sector `offset`, with the `corrupted` children.
+        let columns = |offset: u64, corrupted: &[usize]| {
+            let map = RaidZMap::new(offset << SECTOR_SHIFT, logical.len(), 5, 2, 9).unwrap();
+            let mut columns: Vec<Vec<u8>> = map.columns.iter().map(|c| vec![0; c.size]).collect();
+            map.data_to_columns(&logical, &mut columns);
+            map.generate_parity(&mut columns);
+
+            for (column, bytes) in map.columns.iter().zip(columns.iter_mut()) {
+                if corrupted.contains(&column.child) {
+                    bytes[0] ^= 0x80;
+                }
+            }
+
+            map.columns
+                .iter()
+                .map(|c| c.offset >> SECTOR_SHIFT)
+                .zip(columns)
+                .collect::<Vec<(u64, Vec<u8>)>>()
+        };
+
+        let blocks = [columns(0, &[]), columns(40, &[3]), columns(80, &[2, 3])];
+
+        let devices: Vec<BlockDeviceMemory> = (0..5)
+            .map(|child| {
+                let child_blocks: Vec<(u64, &[u8])> = blocks
+                    .iter()
+                    .flat_map(|block| block.iter())
+                    .enumerate()
+                    .filter(|(idx, _)| idx % 5 == child)
+                    .map(|(_, (offset, bytes))| (*offset, bytes.as_slice()))
+                    .collect();
+                image(&child_blocks)
+            })
+            .collect();
+
+        // RAID-Z with all children, and with child 1 missing.
+        let raidz = |missing: Option<usize>| {
+            Vdev::RaidZ(VdevRaidZ {
+                guid: 10,
+                allocate_shift: 9,
+                parity: 2,
+                children: devices
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, device)| VdevLeaf {
+                        guid: idx as u64 + 11,
+                        device: (missing != Some(idx)).then_some(device),
+                    })
+                    .collect(),
+            })
+        };
+        let vdevs = [raidz(None), raidz(Some(1))];
+        assert_eq!(vdevs[0].copies(), 1);
+        assert_eq!(vdevs[0].copy_guid(0), None);
+
+        let mut reader = BlockReader::new(&vdevs);
+        let bad_copy = |vdev: u32, child: usize, offset: u64, kind: BadCopyKind| BadCopy {
+            vdev,
+            copy: child,
+            guid: Some(child as u64 + 11),
+            offset,
+            sectors: 8,
+            kind,
+        };
+        let ptr = |vdev: u32, offset: u64| {
+            block_pointer(
+                &logical,
+                8,
+                CompressionType::Off,
+                [dva(vdev, offset, 12), None, None],
+                0,
+            )
+        };
+
+        // All children are good.
+        assert_eq!(reader.read_regular(&ptr(0, 0)).unwrap(), logical);
+        assert!(reader.take_bad_copies().is_empty());
+
+        // Missing child is reconstructed from parity.
+        assert_eq!(reader.read_regular(&ptr(1, 0)).unwrap(), logical);
+        assert_eq!(
+            reader.take_bad_copies(),
+            [bad_copy(1, 1, 0, BadCopyKind::Read)]
+        );
+
+        // Corrupted child is found by checksum.
+        assert_eq!(reader.read_regular(&ptr(0, 40)).unwrap(), logical);
+        assert_eq!(
+            reader.take_bad_copies(),
+            [bad_copy(0, 3, 40, BadCopyKind::Checksum)]
+        );
+
+        // Missing and corrupted children.
+        assert_eq!(reader.read_regular(&ptr(1, 40)).unwrap(), logical);
+        assert_eq!(
+            reader.take_bad_copies(),
+            [
+                bad_copy(1, 1, 40, BadCopyKind::Read),
+                bad_copy(1, 3, 40, BadCopyKind::Checksum),
+            ]
+        );
+
+        // Two corrupted children.
+        assert_eq!(reader.read_regular(&ptr(0, 80)).unwrap(), logical);
+        assert_eq!(
+            reader.take_bad_copies(),
+            [
+                bad_copy(0, 2, 80, BadCopyKind::Checksum),
+                bad_copy(0, 3, 80, BadCopyKind::Checksum),
+            ]
+        );
+
+        // More bad children than parity.
+        assert!(matches!(
+            reader.read_regular(&ptr(1, 80)).unwrap_err(),
+            BlockReadError::BlockVerify { .. }
+        ));
+        assert_eq!(
+            reader.take_bad_copies(),
+            [bad_copy(1, 1, 80, BadCopyKind::Read)]
+        );
+    }
+
+    #[test]
+    fn read_draid() {
+        let logical: Vec<u8> = (0..4096).map(|x| (x * 23) as u8).collect();
+
+        // Double parity dRAID of 5 children with 1 distributed spare.
+        let layout = || {
+            let map = DRaidMap {
+                children: 5,
+                permutations: 2,
+                seed: 0x0123_4567_89ab_cdef,
+                checksum: 0,
+            };
+            DRaidLayout::new(5, 2, 2, 1, 1, 9, map).unwrap()
+        };
+
+        let rows = layout().rows(0, logical.len()).unwrap();
+        assert_eq!(rows.len(), 1);
+        let map = &rows[0];
+
+        let mut columns: Vec<Vec<u8>> = map.columns.iter().map(|c| vec![0; c.size]).collect();
+        map.data_to_columns(&logical, &mut columns);
+        map.generate_parity(&mut columns);
+
+        // First data column is corrupted, and second data column is missing.
+        let corrupted = map.columns[2].child;
+        let missing = map.columns[3].child;
+        columns[2][100] ^= 0x01;
+
+        let devices: Vec<BlockDeviceMemory> = (0..5)
+            .map(|child| {
+                let blocks: Vec<(u64, &[u8])> = map
+                    .columns
+                    .iter()
+                    .zip(columns.iter())
+                    .filter(|(column, _)| column.child == child)
+                    .map(|(column, bytes)| (column.offset >> SECTOR_SHIFT, bytes.as_slice()))
+                    .collect();
+                image(&blocks)
+            })
+            .collect();
+
+        let vdevs = [Vdev::DRaid(VdevDRaid {
+            guid: 10,
+            layout: layout(),
+            children: devices
+                .iter()
+                .enumerate()
+                .map(|(idx, device)| VdevLeaf {
+                    guid: idx as u64 + 11,
+                    device: (idx != missing).then_some(device),
+                })
+                .collect(),
+        })];
+        assert_eq!(vdevs[0].guid(), 10);
+        assert_eq!(vdevs[0].copies(), 1);
+
+        let mut reader = BlockReader::new(&vdevs);
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 16), None, None],
+            0,
+        );
+        assert_eq!(reader.read_regular(&ptr).unwrap(), logical);
+
+        let bad_copy = |child: usize, kind: BadCopyKind| BadCopy {
+            vdev: 0,
+            copy: child,
+            guid: Some(child as u64 + 11),
+            offset: 0,
+            sectors: 8,
+            kind,
+        };
+        assert_eq!(
+            reader.take_bad_copies(),
+            [
+                bad_copy(missing, BadCopyKind::Read),
+                bad_copy(corrupted, BadCopyKind::Checksum),
+            ]
+        );
+    }
+
+    #[test]
+    fn read_indirect() {
+        let block_a: Vec<u8> = (0..4096).map(|x| (x % 13) as u8).collect();
+        let block_b = vec![9; 2048];
+
+        // Removed vdev 0 is mapped to vdev 1 and 2. Block A at sector 8 is
+        // split over both, and block B at sector 20 is in one segment.
+        let mapping_entry = |source: u64, vdev: u32, offset: u64| IndirectMappingEntry {
+            source,
+            mark: false,
+            destination: dva(vdev, offset, 4).unwrap(),
+        };
+        let entries = [
+            mapping_entry(8, 1, 16),
+            mapping_entry(12, 2, 0),
+            mapping_entry(20, 1, 40),
+        ];
+
+        let mut mapping_block = vec![0; 1024];
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut mapping_block, EndianOrder::Little);
+        for entry in &entries {
+            entry.to_encoder(encoder.encoder()).unwrap();
+        }
+
+        let mut births_block = vec![0; 1024];
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut births_block, EndianOrder::Little);
+        let birth = IndirectBirthEntry {
+            offset: 24 << SECTOR_SHIFT,
+            birth_txg: 3,
+        };
+        birth.to_encoder(encoder.encoder()).unwrap();
+
+        // Dnodes of the mapping and births, with the header in the bonus.
+        let with_bonus = |mut dnode: Dnode, bonus: &[u8]| {
+            if let DnodeTail::One(tail) = &mut dnode.tail {
+                tail.bonus[0..bonus.len()].copy_from_slice(bonus);
+            }
+            dnode.bonus_len = bonus.len();
+            dnode
+        };
+
+        let mut bonus = [0; IndirectMappingPhys::SIZE_V0];
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut bonus, EndianOrder::Little);
+        let phys = IndirectMappingPhys {
+            max_offset: 24 << SECTOR_SHIFT,
+            bytes_mapped: 12 << SECTOR_SHIFT,
+            entries: entries.len() as u64,
+            counts_object: None,
+        };
+        phys.to_encoder(encoder.encoder()).unwrap();
+        let ptr = block_pointer(
+            &mapping_block,
+            2,
+            CompressionType::Off,
+            [dva(1, 0, 2), None, None],
+            0,
+        );
+        let mapping_dnode = with_bonus(dnode(BlockPointer::Regular(ptr), 1, 0), &bonus);
+
+        let mut bonus = [0; IndirectBirthsPhys::SIZE];
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut bonus, EndianOrder::Little);
+        IndirectBirthsPhys { entries: 1 }
+            .to_encoder(encoder.encoder())
+            .unwrap();
+        let ptr = block_pointer(
+            &births_block,
+            2,
+            CompressionType::Off,
+            [dva(1, 2, 2), None, None],
+            0,
+        );
+        let births_dnode = with_bonus(dnode(BlockPointer::Regular(ptr), 1, 0), &bonus);
+
+        // MOS with the mapping at object 0, and the births at object 1.
+        let mut mos_block = vec![0; 1024];
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut mos_block, EndianOrder::Little);
+        mapping_dnode.to_encoder(encoder.encoder()).unwrap();
+        births_dnode.to_encoder(encoder.encoder()).unwrap();
+        let ptr = block_pointer(
+            &mos_block,
+            2,
+            CompressionType::Off,
+            [dva(1, 4, 2), None, None],
+            0,
+        );
+        let mos = dnode(BlockPointer::Regular(ptr), 1, 0);
+
+        let devices = [
+            image(&[
+                (0, &mapping_block),
+                (2, &births_block),
+                (4, &mos_block),
+                (16, &block_a[0..2048]),
+                (40, &block_b),
+            ]),
+            image(&[(0, &block_a[2048..])]),
+        ];
+        let vdevs = [
+            Vdev::Indirect(VdevIndirect {
+                guid: 10,
+                mapping_object: 0,
+                births_object: 1,
+            }),
+            Vdev::leaf(1, &devices[0]),
+            Vdev::leaf(2, &devices[1]),
+        ];
+        assert!(matches!(
+            vdevs[0].read(&mut [0; 512], 8, 0).unwrap_err(),
+            VdevReadError::Indirect { guid: 10 }
+        ));
+
+        let mut reader = BlockReader::new(&vdevs);
+        let ptr_a = block_pointer(
+            &block_a,
+            8,
+            CompressionType::Off,
+            [dva(0, 8, 8), None, None],
+            0,
+        );
+        let ptr_b = block_pointer(
+            &block_b,
+            4,
+            CompressionType::Off,
+            [dva(0, 20, 4), None, None],
+            0,
+        );
+
+        // Mapping is not loaded.
+        assert!(matches!(
+            reader.read_regular(&ptr_a).unwrap_err(),
+            BlockReadError::MissingIndirectMapping { vdev: 0 }
+        ));
+
+        reader.load_indirect_mappings(&mos).unwrap();
+        let mapping = reader.indirect_mapping(0).unwrap();
+        assert_eq!(mapping.entries.len(), 3);
+        assert_eq!(mapping.birth_txg(8), Some(3));
+        assert!(reader.indirect_mapping(1).is_none());
+
+        // Split, and single segment.
+        assert_eq!(reader.read_regular(&ptr_a).unwrap(), block_a);
+        assert_eq!(reader.read_regular(&ptr_b).unwrap(), block_b);
+        assert!(reader.take_bad_copies().is_empty());
+
+        // Not mapped.
+        let ptr = block_pointer(
+            &block_b,
+            4,
+            CompressionType::Off,
+            [dva(0, 0, 4), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::Indirect {
+                err: IndirectError::Unmapped { offset: 0 }
+            }
+        ));
+    }
+
+    #[test]
+    fn read_dnode() {
+        // Data blocks of 2 sectors each.
+        let blocks: Vec<Vec<u8>> = (0..3u8).map(|x| vec![x + 1; 1024]).collect();
+
+        // Indirect block of 1024 bytes, with block 1 not allocated.
+        let mut indirect = vec![0; 1024];
+        let mut ptrs = Vec::new();
+        for (idx, block) in blocks.iter().enumerate() {
+            let ptr = block_pointer(
+                block,
+                2,
+                CompressionType::Off,
+                [dva(0, 2 * idx as u64, 2), None, None],
+                0,
+            );
+            ptrs.push(if idx == 1 {
+                None
+            } else {
+                Some(BlockPointer::Regular(ptr))
+            });
+        }
+        let mut encoder = BigLittleEndianEncoder::to_bytes(&mut indirect, EndianOrder::Little);
+        for ptr in &ptrs {
+            BlockPointer::option_to_encoder(ptr, encoder.encoder()).unwrap();
+        }
+
+        let devices = [image(&[
+            (0, &blocks[0]),
+            (2, &blocks[1]),
+            (4, &blocks[2]),
+            (8, &indirect),
+        ])];
+        let vdevs = leaves(&devices);
+        let mut reader = BlockReader::new(&vdevs);
+
+        let ptr = block_pointer(
+            &indirect,
+            2,
+            CompressionType::Off,
+            [dva(0, 8, 2), None, None],
+            1,
+        );
+        let dnode = dnode(BlockPointer::Regular(ptr), 2, 2);
+
+        // Blocks.
+        let (order, data) = reader.read_dnode_block(&dnode, 0).unwrap().unwrap();
+        assert_eq!(order, EndianOrder::Little);
+        assert_eq!(data, blocks[0]);
+        assert!(reader.read_dnode_block(&dnode, 1).unwrap().is_none());
+        assert_eq!(
+            reader.read_dnode_block(&dnode, 2).unwrap().unwrap().1,
+            blocks[2]
+        );
+        assert!(reader.read_dnode_block(&dnode, 3).unwrap().is_none());
+
+        // Objects.
+        let (_, data) = reader.read_dnode_object(&dnode, 17, 128).unwrap().unwrap();
+        assert_eq!(data, vec![3; 128]);
+        assert!(reader.read_dnode_object(&dnode, 8, 128).unwrap().is_none());
+        assert!(matches!(
+            reader.read_dnode_object(&dnode, 0, 100).unwrap_err(),
+            BlockReadError::InvalidObjectSize { .. }
+        ));
+    }
+
+    #[test]
+    fn read_error() {
+        let logical = vec![5; 4096];
+        let devices = [image(&[(0, &logical)])];
+        let vdevs = leaves(&devices);
+        let mut reader = BlockReader::new(&vdevs);
+
+        // Invalid vdev.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(1, 0, 8), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::InvalidVdev { vdev: 1 }
+        ));
+
+        // Sectors more than allocated.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 4), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::InvalidSectors { .. }
+        ));
+
+        // Read past end of device.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, IMAGE_DATA_SECTORS - 4, 8), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::Vdev {
+                err: VdevReadError::BlockDevice { .. }
+            }
+        ));
+
+        // Uncompressed with different logical size.
+        let ptr = block_pointer(
+            &logical,
+            16,
+            CompressionType::Off,
+            [dva(0, 0, 8), None, None],
+            0,
+        );
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::InvalidLogicalSectors { .. }
+        ));
+
+        // No DVA.
+        let ptr = block_pointer(&logical, 8, CompressionType::Off, [None, None, None], 0);
+        assert!(matches!(
+            reader.read_regular(&ptr).unwrap_err(),
+            BlockReadError::NoDva {}
+        ));
+
+        // Invalid dnode levels.
+        let ptr = block_pointer(
+            &logical,
+            8,
+            CompressionType::Off,
+            [dva(0, 0, 8), None, None],
+            0,
+        );
+        let dnode = dnode(BlockPointer::Regular(ptr), 9, 0);
+        assert!(matches!(
+            reader.read_dnode_block(&dnode, 0).unwrap_err(),
+            BlockReadError::InvalidLevels { levels: 9 }
+        ));
+    }
+}
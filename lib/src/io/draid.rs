@@ -0,0 +1,673 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! dRAID geometry.
+ *
+ * - A dRAID [`crate::io::Vdev`] is made of redundancy groups of `data` and
+ *   `parity` columns. Each group is laid out like a RAID-Z [`RaidZMap`] row.
+ * - Groups are placed one after another on the `children - spares` disks,
+ *   in rows of [`DRAID_ROW_HEIGHT`] bytes on each disk. A group that does not
+ *   fit at the end of a row wraps onto the next row.
+ * - A slice of `groups` groups covers a whole number of rows, and each slice
+ *   uses the next permutation of the children.
+ * - The permutations are generated from a seed and a count, that are not
+ *   stored in the label. OpenZFS selects them from a built-in table, indexed
+ *   by the number of children, and verifies them against a checksum. That
+ *   table is [`DRAID_MAPS`].
+ * - A block may span the end of one group and the start of the next group,
+ *   and is then mapped to two rows.
+ */
+use core::fmt;
+use std::error;
+
+use crate::io::raidz::{RaidZColumn, RaidZError, RaidZMap, RAIDZ_PARITY_MAX};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Byte height of a row of a group on each child.
+pub const DRAID_ROW_HEIGHT: u64 = 1 << 24;
+
+/// Maximum number of children.
+pub const DRAID_CHILDREN_MAX: usize = 255;
+
+/// Constant half of the seed of the permutation generator.
+const DRAID_SEED: u64 = 0xd7a1d5eed;
+
+/// Permutation map of a dRAID [`crate::io::Vdev`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DRaidMap {
+    /// Number of children.
+    pub children: usize,
+
+    /// Number of permutations.
+    pub permutations: u64,
+
+    /// Seed of the permutation generator.
+    pub seed: u64,
+
+    /// Checksum of the permutations, or 0 to not verify them.
+    pub checksum: u64,
+}
+
+/** Built-in permutation maps, ordered by the number of children.
+ *
+ * This is `draid_maps[]` of `module/zfs/vdev_draid.c` in OpenZFS, with one
+ * map for each of 2 to [`DRAID_CHILDREN_MAX`] children.
+ */
+// TODO: Copy the entries of draid_maps[]. Each entry is checked against its
+//       checksum by the draid_maps test, and the draid_maps and
+//       io::vdev::tests::from_tree_draid tests are ignored until then.
+pub const DRAID_MAPS: &[DRaidMap] = &[];
+
+impl DRaidMap {
+    /// Finds the built-in map in [`DRAID_MAPS`] for `children`.
+    pub fn find(children: usize) -> Option<DRaidMap> {
+        DRAID_MAPS
+            .iter()
+            .find(|map| map.children == children)
+            .copied()
+    }
+}
+
+/** Layout of a dRAID [`crate::io::Vdev`].
+ *
+ * Maps the byte offset of a block onto rows of columns of the children.
+ */
+#[derive(Debug)]
+pub struct DRaidLayout {
+    /// Number of children.
+    pub children: usize,
+
+    /// Number of data columns in each group.
+    pub data: usize,
+
+    /// Number of parity columns in each group.
+    pub parity: usize,
+
+    /// Number of distributed spares.
+    pub spares: usize,
+
+    /// Number of groups in each slice.
+    pub groups: u64,
+
+    /// Allocation shift of the sectors of the children.
+    pub allocate_shift: u32,
+
+    /// Permutations of the children, `children` bytes each.
+    permutations: Vec<u8>,
+}
+
+impl DRaidLayout {
+    /** Creates a [`DRaidLayout`], and generates the permutations of `map`.
+     *
+     * # Errors
+     *
+     * Returns [`DRaidError`] if the geometry is invalid, or the permutations
+     * do not match the checksum of `map`.
+     */
+    pub fn new(
+        children: usize,
+        data: usize,
+        parity: usize,
+        spares: usize,
+        groups: u64,
+        allocate_shift: u32,
+        map: DRaidMap,
+    ) -> Result<DRaidLayout, DRaidError> {
+        let group_width = data + parity;
+        let disks = children.saturating_sub(spares);
+
+        // Each slice of groups must cover whole rows of the disks.
+        let whole_rows = groups
+            .checked_mul(group_width as u64)
+            .is_some_and(|width| disks > 0 && width.is_multiple_of(disks as u64));
+
+        if data == 0
+            || parity == 0
+            || parity > RAIDZ_PARITY_MAX
+            || groups == 0
+            || children > DRAID_CHILDREN_MAX
+            || group_width > disks
+            || !whole_rows
+        {
+            return Err(DRaidError::InvalidGeometry {
+                children,
+                data,
+                parity,
+                spares,
+                groups,
+            });
+        }
+
+        if map.children != children || map.seed == 0 || map.permutations == 0 {
+            return Err(DRaidError::InvalidMap { map });
+        }
+
+        // Validate the allocation shift, and the sizes derived from it.
+        RaidZMap::new(0, 1 << allocate_shift, group_width, parity, allocate_shift)?;
+
+        Ok(DRaidLayout {
+            children,
+            data,
+            parity,
+            spares,
+            groups,
+            allocate_shift,
+            permutations: draid_permutations(children, map)?,
+        })
+    }
+
+    /// Gets the number of columns in each group.
+    pub fn group_width(&self) -> usize {
+        self.data + self.parity
+    }
+
+    /// Gets the byte size of a group, across all of its columns.
+    pub fn group_size(&self) -> u64 {
+        self.group_width() as u64 * DRAID_ROW_HEIGHT
+    }
+
+    /// Gets the number of children that are not distributed spares.
+    pub fn disks(&self) -> usize {
+        self.children - self.spares
+    }
+
+    /** Maps `size` bytes at byte `offset` of the dRAID [`crate::io::Vdev`]
+     * onto one or two rows.
+     *
+     * `size` is rounded up to a multiple of `1 << allocate_shift`. The data
+     * of the rows is in order.
+     *
+     * # Errors
+     *
+     * Returns [`DRaidError`] if the size is invalid, or `offset` is not at
+     * the start of a row of a group.
+     */
+    pub fn rows(&self, offset: u64, size: usize) -> Result<Vec<RaidZMap>, DRaidError> {
+        let ashift = self.allocate_shift;
+        let size = (size as u64).div_ceil(1 << ashift) << ashift;
+        if size == 0 {
+            return Err(DRaidError::InvalidSize { size });
+        }
+
+        // Allocations are always whole group rows.
+        if !(offset >> ashift).is_multiple_of(self.group_width() as u64) {
+            return Err(DRaidError::InvalidOffset { offset });
+        }
+
+        let mut rows = Vec::with_capacity(2);
+        let mut offset = offset;
+        let mut remaining = size;
+
+        while remaining > 0 {
+            if rows.len() == 2 {
+                return Err(DRaidError::InvalidSize { size });
+            }
+
+            // Limit the row to the remaining space in the group.
+            let group = offset / self.group_size();
+            let group_end = (group + 1) * self.group_size();
+            let row_size = remaining.min(self.allocated_to_data(group_end - offset));
+
+            rows.push(self.row(offset, row_size));
+
+            offset = group_end;
+            remaining -= row_size;
+        }
+
+        Ok(rows)
+    }
+
+    /// Converts allocated bytes of whole group rows to data bytes.
+    fn allocated_to_data(&self, allocated: u64) -> u64 {
+        (allocated / self.group_width() as u64) * self.data as u64
+    }
+
+    /// Maps `size` bytes at byte `offset`, that fit in one group.
+    fn row(&self, offset: u64, size: u64) -> RaidZMap {
+        let ashift = self.allocate_shift;
+        let group_width = self.group_width() as u64;
+        let disks = self.disks() as u64;
+        let row_height_sectors = DRAID_ROW_HEIGHT >> ashift;
+
+        ////////////////////////////////
+        // Find the group, the permutation, and the physical offset.
+        let group = offset / self.group_size();
+        let group_start = (group * group_width) % disks;
+
+        let permutation = group / self.groups;
+        let row = permutation * ((group_width * self.groups) / disks)
+            + ((group % self.groups) * group_width) / disks;
+
+        let sector_in_group = (offset >> ashift) % (row_height_sectors * group_width);
+        let mut physical_offset =
+            (row_height_sectors * row + sector_in_group / group_width) << ashift;
+
+        // Group disk number that wraps onto the next row.
+        let wrap = if group_start + group_width > disks {
+            disks - group_start
+        } else {
+            group_width
+        };
+
+        ////////////////////////////////
+        // Compute the column sizes, like a RAID-Z row that spans all columns.
+        let sectors = size >> ashift;
+        let data = self.data as u64;
+        let parity = self.parity as u64;
+
+        let q = sectors / data;
+        let r = sectors - q * data;
+        let bc = if r == 0 { 0 } else { r + parity };
+
+        let children = self.children as u64;
+        let permutations = self.permutations.len() as u64 / children;
+        let permutation_index = permutation % (permutations * children);
+        let base = (permutation_index / children * children) as usize;
+        let iteration = permutation_index % children;
+
+        let mut columns = Vec::with_capacity(self.group_width());
+
+        for c in 0..group_width {
+            if c == wrap {
+                physical_offset += DRAID_ROW_HEIGHT;
+            }
+
+            let disk = ((group_start + c) % disks) as usize;
+            let child = (u64::from(self.permutations[base + disk]) + iteration) % children;
+
+            let column_sectors = if c < bc { q + 1 } else { q };
+
+            columns.push(RaidZColumn {
+                child: child as usize,
+                offset: physical_offset,
+                size: (column_sectors << ashift) as usize,
+            });
+        }
+
+        RaidZMap {
+            parity: self.parity,
+            columns,
+        }
+    }
+}
+
+/** Generates `map.permutations` permutations of `children`, each shuffled
+ * from the previous one.
+ *
+ * Like `vdev_draid_generate_perms` in OpenZFS, followed by the checksum check
+ * of `verify_perms`.
+ */
+fn draid_permutations(children: usize, map: DRaidMap) -> Result<Vec<u8>, DRaidError> {
+    let size = usize::try_from(map.permutations)
+        .ok()
+        .and_then(|permutations| permutations.checked_mul(children));
+    let size = match size {
+        Some(v) => v,
+        None => return Err(DRaidError::InvalidMap { map }),
+    };
+
+    let mut permutations = Vec::with_capacity(size);
+    let mut row: Vec<u8> = (0..children).map(|child| child as u8).collect();
+    let mut state = [DRAID_SEED, map.seed];
+
+    for _ in 0..map.permutations {
+        // Fisher-Yates shuffle of the previous row.
+        for j in (1..children).rev() {
+            let k = (draid_rand(&mut state) % (j as u64 + 1)) as usize;
+            row.swap(j, k);
+        }
+        permutations.extend_from_slice(&row);
+    }
+
+    if map.checksum != 0 {
+        let checksum = draid_checksum(&permutations);
+        if checksum != map.checksum {
+            return Err(DRaidError::MapChecksum { map, checksum });
+        }
+    }
+
+    Ok(permutations)
+}
+
+/** Checksum of the permutations.
+ *
+ * This is the first word of the Fletcher 4 checksum, of the whole 32 bit
+ * little endian words of the permutations.
+ */
+fn draid_checksum(permutations: &[u8]) -> u64 {
+    permutations.chunks_exact(4).fold(0, |sum: u64, x| {
+        sum.wrapping_add(u64::from(u32::from_le_bytes(x.try_into().unwrap())))
+    })
+}
+
+/// Xoroshiro128+ random number generator.
+fn draid_rand(state: &mut [u64; 2]) -> u64 {
+    let s0 = state[0];
+    let mut s1 = state[1];
+    let result = s0.wrapping_add(s1);
+
+    s1 ^= s0;
+    state[0] = s0.rotate_left(24) ^ s1 ^ (s1 << 16);
+    state[1] = s1.rotate_left(37);
+
+    result
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// dRAID error.
+#[derive(Debug)]
+pub enum DRaidError {
+    /// Invalid number of children, without a built-in [`DRaidMap`].
+    InvalidChildren {
+        /// Children.
+        children: usize,
+    },
+
+    /// Invalid geometry.
+    InvalidGeometry {
+        /// Children.
+        children: usize,
+        /// Data columns.
+        data: usize,
+        /// Parity columns.
+        parity: usize,
+        /// Distributed spares.
+        spares: usize,
+        /// Groups.
+        groups: u64,
+    },
+
+    /// Invalid permutation map.
+    InvalidMap {
+        /// Map.
+        map: DRaidMap,
+    },
+
+    /// Offset is not at the start of a row of a group.
+    InvalidOffset {
+        /// Byte offset.
+        offset: u64,
+    },
+
+    /// Invalid size.
+    InvalidSize {
+        /// Byte size.
+        size: u64,
+    },
+
+    /// Permutations do not match the checksum of the map.
+    MapChecksum {
+        /// Map.
+        map: DRaidMap,
+        /// Computed checksum.
+        checksum: u64,
+    },
+
+    /// RAID-Z error.
+    RaidZ {
+        /// Error.
+        err: RaidZError,
+    },
+}
+
+impl From<RaidZError> for DRaidError {
+    fn from(err: RaidZError) -> Self {
+        DRaidError::RaidZ { err }
+    }
+}
+
+impl fmt::Display for DRaidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DRaidError::InvalidChildren { children } => {
+                write!(f, "dRAID error, invalid children {children}")
+            }
+            DRaidError::InvalidGeometry {
+                children,
+                data,
+                parity,
+                spares,
+                groups,
+            } => {
+                write!(
+                    f,
+                    "dRAID error, invalid children {children} data {data} parity {parity} spares {spares} groups {groups}"
+                )
+            }
+            DRaidError::InvalidMap { map } => {
+                write!(
+                    f,
+                    "dRAID error, invalid map children {} seed {} permutations {}",
+                    map.children, map.seed, map.permutations
+                )
+            }
+            DRaidError::InvalidOffset { offset } => {
+                write!(f, "dRAID error, invalid offset {offset}")
+            }
+            DRaidError::InvalidSize { size } => {
+                write!(f, "dRAID error, invalid size {size}")
+            }
+            DRaidError::MapChecksum { map, checksum } => {
+                write!(
+                    f,
+                    "dRAID error, map checksum mismatch for {} children, expected {:#016x} computed {checksum:#016x}",
+                    map.children, map.checksum
+                )
+            }
+            DRaidError::RaidZ { err } => {
+                write!(f, "dRAID error | {err}")
+            }
+        }
+    }
+}
+
+impl error::Error for DRaidError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DRaidError::RaidZ { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::io::draid::{
+        draid_checksum, draid_permutations, draid_rand, DRaidError, DRaidLayout, DRaidMap,
+        DRAID_CHILDREN_MAX, DRAID_MAPS, DRAID_ROW_HEIGHT,
+    };
+    use crate::phys::SECTOR_SHIFT;
+
+    /// Creates an unverified [`DRaidMap`] of 4 permutations of `children`.
+    fn map(children: usize) -> DRaidMap {
+        DRaidMap {
+            children,
+            permutations: 4,
+            seed: 0x1234_5678_9abc_def0,
+            checksum: 0,
+        }
+    }
+
+    #[test]
+    fn rand() {
+        // Xoroshiro128+ with state (1, 2).
+        let mut state = [1, 2];
+        let values: Vec<u64> = (0..4).map(|_| draid_rand(&mut state)).collect();
+        assert_eq!(
+            values,
+            [0x3, 0x6001030003, 0x20c102c302000c03, 0x810180670d23ad61]
+        );
+    }
+
+    #[test]
+    #[ignore = "DRAID_MAPS does not have the entries of OpenZFS draid_maps[] yet"]
+    fn draid_maps() {
+        // One map for each of 2 to DRAID_CHILDREN_MAX children.
+        assert_eq!(DRAID_MAPS.len(), DRAID_CHILDREN_MAX - 1);
+
+        for (i, map) in DRAID_MAPS.iter().enumerate() {
+            // One map for each number of children, in order.
+            assert_eq!(map.children, i + 2);
+            assert!(map.children <= DRAID_CHILDREN_MAX);
+            assert_eq!(DRaidMap::find(map.children), Some(*map));
+
+            // Generated permutations match the checksum of OpenZFS.
+            assert_ne!(map.checksum, 0);
+            draid_permutations(map.children, *map).unwrap();
+        }
+
+        assert_eq!(DRaidMap::find(DRAID_CHILDREN_MAX + 1), None);
+    }
+
+    #[test]
+    fn checksum() {
+        let permutations = draid_permutations(7, map(7)).unwrap();
+        let checksum = draid_checksum(&permutations);
+
+        // Matching checksum.
+        let mut verified = map(7);
+        verified.checksum = checksum;
+        assert_eq!(draid_permutations(7, verified).unwrap(), permutations);
+
+        // Mismatched checksum.
+        verified.checksum = checksum + 1;
+        match draid_permutations(7, verified).unwrap_err() {
+            DRaidError::MapChecksum { map, checksum: c } => {
+                assert_eq!(map, verified);
+                assert_eq!(c, checksum);
+            }
+            err => panic!("unexpected error {err:?}"),
+        }
+
+        // Trailing bytes are not checksummed.
+        assert_eq!(draid_checksum(&[1, 0, 0, 0, 2, 0, 0]), 1);
+        assert_eq!(draid_checksum(&[0, 1, 0, 0, 0, 0, 0, 1]), 0x0100_0100);
+    }
+
+    #[test]
+    fn permutations() {
+        let layout = DRaidLayout::new(7, 4, 2, 1, 1, 9, map(7)).unwrap();
+        assert_eq!(layout.permutations.len(), 7 * 4);
+
+        // Each row is a permutation.
+        for row in layout.permutations.chunks(7) {
+            let mut sorted = row.to_vec();
+            sorted.sort();
+            assert_eq!(sorted, [0, 1, 2, 3, 4, 5, 6]);
+        }
+
+        // Generation is deterministic.
+        let other = DRaidLayout::new(7, 4, 2, 1, 1, 9, map(7)).unwrap();
+        assert_eq!(layout.permutations, other.permutations);
+    }
+
+    #[test]
+    fn rows() {
+        // 6 disks and 1 spare, 2 groups of 3 per slice.
+        let layout = DRaidLayout::new(7, 2, 1, 1, 2, 9, map(7)).unwrap();
+        assert_eq!(layout.group_width(), 3);
+        assert_eq!(layout.group_size(), 3 * DRAID_ROW_HEIGHT);
+
+        // 4 sectors in the first group.
+        let rows = layout.rows(0, 4 << SECTOR_SHIFT).unwrap();
+        assert_eq!(rows.len(), 1);
+        let row = &rows[0];
+        assert_eq!(row.parity, 1);
+        assert_eq!(row.columns.len(), 3);
+        assert_eq!(row.data_size(), 4 << SECTOR_SHIFT);
+        assert!(row.columns.iter().all(|c| c.offset == 0 && c.size == 1024));
+
+        // Columns are distinct children, and not the spare of the permutation.
+        let mut children: Vec<usize> = row.columns.iter().map(|c| c.child).collect();
+        children.sort();
+        children.dedup();
+        assert_eq!(children.len(), 3);
+
+        // Second group is on the other disks of the same row.
+        let rows = layout.rows(layout.group_size() + 3 * 512, 512).unwrap();
+        assert_eq!(rows.len(), 1);
+        let sizes: Vec<usize> = rows[0].columns.iter().map(|c| c.size).collect();
+        assert_eq!(sizes, [512, 512, 0]);
+        assert!(rows[0].columns.iter().all(|c| c.offset == 512));
+        let other: Vec<usize> = layout.rows(0, 512).unwrap()[0]
+            .columns
+            .iter()
+            .map(|c| c.child)
+            .collect();
+        assert!(rows[0].columns.iter().all(|c| !other.contains(&c.child)));
+
+        // Block at the end of a group spans into the next group.
+        let offset = layout.group_size() - 3 * 512;
+        let rows = layout.rows(offset, 4 << SECTOR_SHIFT).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].data_size(), 2 << SECTOR_SHIFT);
+        assert_eq!(rows[1].data_size(), 2 << SECTOR_SHIFT);
+        assert!(rows[1].columns.iter().all(|c| c.offset == 0));
+
+        // Errors.
+        assert!(matches!(
+            layout.rows(512, 512).unwrap_err(),
+            DRaidError::InvalidOffset { offset: 512 }
+        ));
+        assert!(matches!(
+            layout.rows(0, 0).unwrap_err(),
+            DRaidError::InvalidSize { size: 0 }
+        ));
+    }
+
+    #[test]
+    fn wrap() {
+        // 4 disks, groups of 3, so groups wrap onto the next row.
+        let layout = DRaidLayout::new(4, 2, 1, 0, 4, 9, map(4)).unwrap();
+
+        // Second group starts at disk 3, and wraps after one column.
+        let rows = layout.rows(layout.group_size(), 6 << SECTOR_SHIFT).unwrap();
+        let offsets: Vec<u64> = rows[0].columns.iter().map(|c| c.offset).collect();
+        assert_eq!(offsets, [0, DRAID_ROW_HEIGHT, DRAID_ROW_HEIGHT]);
+    }
+
+    #[test]
+    fn new_error() {
+        // Group wider than the disks.
+        assert!(matches!(
+            DRaidLayout::new(4, 3, 1, 1, 1, 9, map(4)).unwrap_err(),
+            DRaidError::InvalidGeometry { .. }
+        ));
+
+        // Groups do not cover whole rows.
+        assert!(matches!(
+            DRaidLayout::new(5, 2, 1, 0, 1, 9, map(5)).unwrap_err(),
+            DRaidError::InvalidGeometry { .. }
+        ));
+
+        // Invalid map.
+        assert!(matches!(
+            DRaidLayout::new(
+                4,
+                2,
+                1,
+                1,
+                1,
+                9,
+                DRaidMap {
+                    children: 4,
+                    permutations: 1,
+                    seed: 0,
+                    checksum: 0,
+                }
+            )
+            .unwrap_err(),
+            DRaidError::InvalidMap { .. }
+        ));
+
+        // Map for other number of children.
+        assert!(matches!(
+            DRaidLayout::new(4, 2, 1, 1, 1, 9, map(5)).unwrap_err(),
+            DRaidError::InvalidMap { .. }
+        ));
+    }
+}
@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Indirect mapping.
+ *
+ * - When a top level [`crate::io::Vdev`] is removed, its allocated segments
+ *   are copied to the other top level vdevs, and it becomes an indirect
+ *   [`crate::io::Vdev`].
+ * - The [`IndirectMapping`] maps each copied segment of the removed vdev to
+ *   its destination [`Dva`]. A [`Dva`] on an indirect vdev is remapped to
+ *   one [`Dva`] per segment it spans.
+ * - The destination of a segment may itself be on an indirect vdev, if that
+ *   vdev was removed later.
+ */
+use core::fmt;
+use std::error;
+
+use crate::phys::{Dva, IndirectBirthEntry, IndirectMappingEntry, SECTOR_SHIFT};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Maximum number of chained indirect [`crate::io::Vdev`] a [`Dva`] is
+/// remapped through.
+pub const INDIRECT_LEVELS_MAX: usize = 8;
+
+/// Indirect mapping of a removed [`crate::io::Vdev`].
+#[derive(Debug)]
+pub struct IndirectMapping {
+    /// Mapped segments, sorted by source offset.
+    pub entries: Vec<IndirectMappingEntry>,
+
+    /// Births of the mapped segments, sorted by offset.
+    pub births: Vec<IndirectBirthEntry>,
+}
+
+impl IndirectMapping {
+    /** Create an [`IndirectMapping`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectError`] if the `entries` are not sorted, or overlap.
+     */
+    pub fn new(
+        entries: Vec<IndirectMappingEntry>,
+        births: Vec<IndirectBirthEntry>,
+    ) -> Result<IndirectMapping, IndirectError> {
+        let mut end = 0;
+
+        for entry in &entries {
+            if entry.source < end {
+                return Err(IndirectError::Overlap {
+                    source: entry.source,
+                });
+            }
+            end = match entry.source.checked_add(entry.destination.allocated.into()) {
+                Some(v) => v,
+                None => {
+                    return Err(IndirectError::Overlap {
+                        source: entry.source,
+                    })
+                }
+            };
+        }
+
+        Ok(IndirectMapping { entries, births })
+    }
+
+    /** Remaps `sectors` at sector `offset` of the removed vdev.
+     *
+     * Returns a [`Dva`] for each mapped segment, in order.
+     *
+     * # Errors
+     *
+     * Returns [`IndirectError`] if part of the sectors is not mapped.
+     */
+    pub fn remap(&self, offset: u64, sectors: u32) -> Result<Vec<Dva>, IndirectError> {
+        let end = match offset.checked_add(sectors.into()) {
+            Some(v) => v,
+            None => return Err(IndirectError::Unmapped { offset }),
+        };
+
+        // Index of the last entry that starts at or before the offset.
+        let mut idx = match self.entries.partition_point(|entry| entry.source <= offset) {
+            0 => return Err(IndirectError::Unmapped { offset }),
+            v => v - 1,
+        };
+
+        let mut dvas = Vec::new();
+        let mut cursor = offset;
+
+        while cursor < end {
+            let entry = match self.entries.get(idx) {
+                Some(v) if v.source <= cursor => v,
+                _ => return Err(IndirectError::Unmapped { offset: cursor }),
+            };
+
+            let entry_end = entry.source + u64::from(entry.destination.allocated);
+            if cursor >= entry_end {
+                return Err(IndirectError::Unmapped { offset: cursor });
+            }
+
+            let segment_end = end.min(entry_end);
+
+            dvas.push(Dva {
+                allocated: (segment_end - cursor) as u32,
+                offset: entry.destination.offset + (cursor - entry.source),
+                is_gang: false,
+                vdev: entry.destination.vdev,
+            });
+
+            cursor = segment_end;
+            idx += 1;
+        }
+
+        Ok(dvas)
+    }
+
+    /// Gets the transaction group in which the segment at sector `offset` was
+    /// copied, or [`None`] if it is past the last birth.
+    pub fn birth_txg(&self, offset: u64) -> Option<u64> {
+        let offset = offset.checked_mul(1 << SECTOR_SHIFT)?;
+        let idx = self.births.partition_point(|birth| birth.offset <= offset);
+
+        self.births.get(idx).map(|birth| birth.birth_txg)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`IndirectMapping`] error.
+#[derive(Debug)]
+pub enum IndirectError {
+    /// Entries are not sorted, or overlap.
+    Overlap {
+        /// Source sector offset.
+        source: u64,
+    },
+
+    /// Sector offset is not mapped.
+    Unmapped {
+        /// Sector offset.
+        offset: u64,
+    },
+}
+
+impl fmt::Display for IndirectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectError::Overlap { source } => {
+                write!(f, "Indirect error, overlapping entry at sector {source}")
+            }
+            IndirectError::Unmapped { offset } => {
+                write!(f, "Indirect error, sector {offset} is not mapped")
+            }
+        }
+    }
+}
+
+impl error::Error for IndirectError {}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::io::{IndirectError, IndirectMapping};
+    use crate::phys::{Dva, IndirectBirthEntry, IndirectMappingEntry};
+
+    /// Creates an [`IndirectMappingEntry`] from `source` to `vdev` at
+    /// `offset`.
+    fn entry(source: u64, vdev: u32, offset: u64, allocated: u32) -> IndirectMappingEntry {
+        IndirectMappingEntry {
+            source,
+            mark: false,
+            destination: Dva {
+                allocated,
+                offset,
+                is_gang: false,
+                vdev,
+            },
+        }
+    }
+
+    #[test]
+    fn remap() {
+        let mapping = IndirectMapping::new(
+            vec![entry(8, 1, 100, 8), entry(16, 2, 40, 4), entry(32, 1, 0, 4)],
+            vec![
+                IndirectBirthEntry {
+                    offset: 20 << 9,
+                    birth_txg: 5,
+                },
+                IndirectBirthEntry {
+                    offset: 36 << 9,
+                    birth_txg: 7,
+                },
+            ],
+        )
+        .unwrap();
+
+        // Inside one segment.
+        let dvas = mapping.remap(10, 4).unwrap();
+        assert_eq!(dvas.len(), 1);
+        assert_eq!(
+            (dvas[0].vdev, dvas[0].offset, dvas[0].allocated),
+            (1, 102, 4)
+        );
+
+        // Split over two segments.
+        let dvas = mapping.remap(12, 6).unwrap();
+        assert_eq!(dvas.len(), 2);
+        assert_eq!(
+            (dvas[0].vdev, dvas[0].offset, dvas[0].allocated),
+            (1, 104, 4)
+        );
+        assert_eq!(
+            (dvas[1].vdev, dvas[1].offset, dvas[1].allocated),
+            (2, 40, 2)
+        );
+
+        // Not mapped.
+        assert!(matches!(
+            mapping.remap(4, 2).unwrap_err(),
+            IndirectError::Unmapped { offset: 4 }
+        ));
+        assert!(matches!(
+            mapping.remap(18, 4).unwrap_err(),
+            IndirectError::Unmapped { offset: 20 }
+        ));
+        assert!(matches!(
+            mapping.remap(34, 4).unwrap_err(),
+            IndirectError::Unmapped { offset: 36 }
+        ));
+
+        // Births.
+        assert_eq!(mapping.birth_txg(8), Some(5));
+        assert_eq!(mapping.birth_txg(32), Some(7));
+        assert_eq!(mapping.birth_txg(36), None);
+
+        // Overlap.
+        assert!(matches!(
+            IndirectMapping::new(vec![entry(8, 1, 0, 8), entry(12, 1, 20, 8)], Vec::new())
+                .unwrap_err(),
+            IndirectError::Overlap { source: 12 }
+        ));
+    }
+}
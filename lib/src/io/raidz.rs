@@ -0,0 +1,631 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! RAID-Z geometry and parity.
+ *
+ * - A block on a RAID-Z [`crate::io::Vdev`] is split into columns, one per
+ *   child. The first `parity` columns are parity, and the rest are data.
+ * - Data is laid out in the data columns in order. The first columns may be
+ *   one sector larger than the rest ("big columns").
+ * - Parity `P` is the XOR of the data columns. Parity `Q` and `R` are the
+ *   sums of the data columns multiplied by powers of `2` and `4`, in
+ *   GF(2^8) with the polynomial `x^8 + x^4 + x^3 + x^2 + 1`.
+ * - Columns shorter than the parity columns are treated as zero filled.
+ * - Any `parity` missing columns can be reconstructed.
+ */
+use core::fmt;
+use std::error;
+
+use crate::phys::SECTOR_SHIFT;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Maximum number of parity columns.
+pub const RAIDZ_PARITY_MAX: usize = 3;
+
+/// Maximum allocation shift.
+pub const RAIDZ_ALLOCATE_SHIFT_MAX: u32 = 16;
+
+/// Parity generator of each parity column (`P`, `Q`, `R`).
+const PARITY_GENERATORS: [u8; RAIDZ_PARITY_MAX] = [1, 2, 4];
+
+/// GF(2^8) polynomial, without the `x^8` term.
+const GF_POLYNOMIAL: u16 = 0x11d;
+
+/// GF(2^8) exponent table, for generator `2`, repeated to avoid a modulo.
+const GF_EXP: [u8; 512] = gf_exp_table();
+
+/// GF(2^8) logarithm table, for generator `2`.
+const GF_LOG: [u8; 256] = gf_log_table();
+
+/// Computes [`GF_EXP`].
+const fn gf_exp_table() -> [u8; 512] {
+    let mut table = [0; 512];
+    let mut value: u16 = 1;
+    let mut idx = 0;
+
+    while idx < 512 {
+        table[idx] = value as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= GF_POLYNOMIAL;
+        }
+        idx += 1;
+    }
+
+    table
+}
+
+/// Computes [`GF_LOG`].
+const fn gf_log_table() -> [u8; 256] {
+    let exp = gf_exp_table();
+    let mut table = [0; 256];
+    let mut idx = 0;
+
+    while idx < 255 {
+        table[exp[idx] as usize] = idx as u8;
+        idx += 1;
+    }
+
+    table
+}
+
+/// Multiplies in GF(2^8).
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+
+    GF_EXP[usize::from(GF_LOG[usize::from(a)]) + usize::from(GF_LOG[usize::from(b)])]
+}
+
+/// Inverts a non-zero value in GF(2^8).
+fn gf_inv(a: u8) -> u8 {
+    GF_EXP[255 - usize::from(GF_LOG[usize::from(a)])]
+}
+
+/// Raises `a` to the power `n` in GF(2^8).
+fn gf_pow(a: u8, n: usize) -> u8 {
+    if a == 0 {
+        return if n == 0 { 1 } else { 0 };
+    }
+
+    GF_EXP[(usize::from(GF_LOG[usize::from(a)]) * n) % 255]
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A column of a [`RaidZMap`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RaidZColumn {
+    /// Child index.
+    pub child: usize,
+
+    /// Byte offset in the allocatable space of the child.
+    pub offset: u64,
+
+    /// Byte size.
+    pub size: usize,
+}
+
+/** Mapping of a block onto the children of a RAID-Z [`crate::io::Vdev`].
+ *
+ * - `columns` are the columns that are read, parity first.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RaidZMap {
+    /// Number of parity columns.
+    pub parity: usize,
+
+    /// Columns.
+    pub columns: Vec<RaidZColumn>,
+}
+
+impl RaidZMap {
+    /** Maps `size` bytes at byte `offset` of a RAID-Z [`crate::io::Vdev`]
+     * with `children`, `parity`, and allocation shift `ashift`.
+     *
+     * `size` is rounded up to a multiple of `1 << ashift`.
+     *
+     * # Errors
+     *
+     * Returns [`RaidZError`] if the geometry is invalid.
+     */
+    pub fn new(
+        offset: u64,
+        size: usize,
+        children: usize,
+        parity: usize,
+        ashift: u32,
+    ) -> Result<RaidZMap, RaidZError> {
+        if parity == 0 || parity > RAIDZ_PARITY_MAX || children <= parity {
+            return Err(RaidZError::InvalidGeometry { children, parity });
+        }
+
+        if !(SECTOR_SHIFT..=RAIDZ_ALLOCATE_SHIFT_MAX).contains(&ashift) {
+            return Err(RaidZError::InvalidAllocateShift { ashift });
+        }
+
+        // Everything is in units of (1 << ashift) sectors, like ZFS.
+        let dcols = children as u64;
+        let nparity = parity as u64;
+
+        // Starting sector, and size in sectors.
+        let b = offset >> ashift;
+        let s = (size as u64).div_ceil(1 << ashift);
+        if s == 0 {
+            return Err(RaidZError::InvalidSize { size });
+        }
+
+        // First column, and starting byte offset on each child.
+        let f = b % dcols;
+        let o = (b / dcols) << ashift;
+
+        // Sectors on every data column, and remainder sectors.
+        let q = s / (dcols - nparity);
+        let r = s - q * (dcols - nparity);
+
+        // Number of columns with an extra remainder sector.
+        let bc = if r == 0 { 0 } else { r + nparity };
+
+        // Number of columns that are accessed.
+        let acols = if q == 0 { bc } else { dcols };
+
+        let mut columns = Vec::with_capacity(acols as usize);
+
+        for c in 0..acols {
+            let mut child = f + c;
+            let mut child_offset = o;
+            if child >= dcols {
+                child -= dcols;
+                child_offset += 1 << ashift;
+            }
+
+            let sectors = if c < bc { q + 1 } else { q };
+
+            columns.push(RaidZColumn {
+                child: child as usize,
+                offset: child_offset,
+                size: (sectors << ashift) as usize,
+            });
+        }
+
+        // Single parity switches the parity and first data column every 1 MiB.
+        if parity == 1 && (offset & (1 << 20)) != 0 {
+            let (child, child_offset) = (columns[0].child, columns[0].offset);
+            columns[0].child = columns[1].child;
+            columns[0].offset = columns[1].offset;
+            columns[1].child = child;
+            columns[1].offset = child_offset;
+        }
+
+        Ok(RaidZMap { parity, columns })
+    }
+
+    /// Gets the byte size of the data columns.
+    pub fn data_size(&self) -> usize {
+        self.columns[self.parity..].iter().map(|c| c.size).sum()
+    }
+
+    /** Generates the parity columns from the data columns.
+     *
+     * `columns` are sized to match [`RaidZMap::columns`].
+     */
+    pub fn generate_parity(&self, columns: &mut [Vec<u8>]) {
+        let (parity_columns, data_columns) = columns.split_at_mut(self.parity);
+        let data_count = data_columns.len();
+
+        for (p, parity_column) in parity_columns.iter_mut().enumerate() {
+            parity_column.fill(0);
+
+            for (i, data_column) in data_columns.iter().enumerate() {
+                let coefficient = gf_pow(PARITY_GENERATORS[p], data_count - 1 - i);
+                for (parity_byte, data_byte) in parity_column.iter_mut().zip(data_column.iter()) {
+                    *parity_byte ^= gf_mul(coefficient, *data_byte);
+                }
+            }
+        }
+    }
+
+    /** Reconstructs the `missing` column indices, from the other columns.
+     *
+     * `columns` are sized to match [`RaidZMap::columns`]. Missing parity
+     * columns are not reconstructed.
+     *
+     * # Errors
+     *
+     * Returns [`RaidZError`] if there are more missing columns than parity
+     * columns.
+     */
+    pub fn reconstruct(
+        &self,
+        columns: &mut [Vec<u8>],
+        missing: &[usize],
+    ) -> Result<(), RaidZError> {
+        if missing.len() > self.parity {
+            return Err(RaidZError::TooManyMissing {
+                missing: missing.len(),
+                parity: self.parity,
+            });
+        }
+
+        // Missing data columns, and available parity columns.
+        let missing_data: Vec<usize> = missing
+            .iter()
+            .copied()
+            .filter(|c| *c >= self.parity && *c < columns.len())
+            .collect();
+        let parity_rows: Vec<usize> = (0..self.parity)
+            .filter(|p| !missing.contains(p))
+            .take(missing_data.len())
+            .collect();
+
+        if missing_data.is_empty() {
+            return Ok(());
+        }
+
+        let data_count = columns.len() - self.parity;
+        let coefficient =
+            |p: usize, c: usize| gf_pow(PARITY_GENERATORS[p], data_count - 1 - (c - self.parity));
+
+        ////////////////////////////////
+        // Invert the coefficients of the missing data columns, for the
+        // available parity rows.
+        let n = missing_data.len();
+        let mut matrix = [[0u8; RAIDZ_PARITY_MAX]; RAIDZ_PARITY_MAX];
+        let mut inverse = [[0u8; RAIDZ_PARITY_MAX]; RAIDZ_PARITY_MAX];
+
+        for (row, p) in parity_rows.iter().enumerate() {
+            for (col, c) in missing_data.iter().enumerate() {
+                matrix[row][col] = coefficient(*p, *c);
+            }
+            inverse[row][row] = 1;
+        }
+
+        for col in 0..n {
+            // Find a pivot.
+            let pivot = match (col..n).find(|row| matrix[*row][col] != 0) {
+                Some(v) => v,
+                None => return Err(RaidZError::Singular {}),
+            };
+            matrix.swap(col, pivot);
+            inverse.swap(col, pivot);
+
+            // Normalize the pivot row.
+            let scale = gf_inv(matrix[col][col]);
+            for idx in 0..n {
+                matrix[col][idx] = gf_mul(matrix[col][idx], scale);
+                inverse[col][idx] = gf_mul(inverse[col][idx], scale);
+            }
+
+            // Eliminate the column from the other rows.
+            for row in 0..n {
+                let factor = matrix[row][col];
+                if row != col && factor != 0 {
+                    for idx in 0..n {
+                        matrix[row][idx] ^= gf_mul(factor, matrix[col][idx]);
+                        inverse[row][idx] ^= gf_mul(factor, inverse[col][idx]);
+                    }
+                }
+            }
+        }
+
+        ////////////////////////////////
+        // Compute the syndromes of the available parity rows, without the
+        // missing data columns.
+        let parity_size = columns[0].len();
+        let mut syndromes = Vec::with_capacity(n);
+
+        for p in &parity_rows {
+            let mut syndrome = columns[*p].clone();
+            for (c, column) in columns.iter().enumerate().skip(self.parity) {
+                if missing_data.contains(&c) {
+                    continue;
+                }
+                let k = coefficient(*p, c);
+                for (s, d) in syndrome.iter_mut().zip(column.iter()) {
+                    *s ^= gf_mul(k, *d);
+                }
+            }
+            syndromes.push(syndrome);
+        }
+
+        ////////////////////////////////
+        // Solve for the missing data columns.
+        for (row, c) in missing_data.iter().enumerate() {
+            let size = columns[*c].len().min(parity_size);
+            let column = &mut columns[*c];
+
+            for (idx, byte) in column.iter_mut().enumerate().take(size) {
+                let mut value = 0;
+                for (k, syndrome) in syndromes.iter().enumerate() {
+                    value ^= gf_mul(inverse[row][k], syndrome[idx]);
+                }
+                *byte = value;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copies the data columns into `data`.
+    pub fn data_from_columns(&self, data: &mut [u8], columns: &[Vec<u8>]) {
+        let mut offset = 0;
+        for column in &columns[self.parity..] {
+            let end = (offset + column.len()).min(data.len());
+            if offset >= end {
+                break;
+            }
+            data[offset..end].copy_from_slice(&column[0..end - offset]);
+            offset = end;
+        }
+    }
+
+    /// Copies `data` into the data columns.
+    pub fn data_to_columns(&self, data: &[u8], columns: &mut [Vec<u8>]) {
+        let mut offset = 0;
+        for column in &mut columns[self.parity..] {
+            column.fill(0);
+            let end = (offset + column.len()).min(data.len());
+            if offset >= end {
+                continue;
+            }
+            column[0..end - offset].copy_from_slice(&data[offset..end]);
+            offset = end;
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Calls `f` with each combination of `k` values from `0..n`, in order,
+/// until `f` returns `true`. Returns `true` if `f` returned `true`.
+pub(crate) fn combinations(n: usize, k: usize, f: &mut dyn FnMut(&[usize]) -> bool) -> bool {
+    if k > n {
+        return false;
+    }
+
+    let mut idxs: Vec<usize> = (0..k).collect();
+
+    loop {
+        if f(&idxs) {
+            return true;
+        }
+
+        // Find the last index that can be incremented.
+        let mut i = k;
+        loop {
+            if i == 0 {
+                return false;
+            }
+            i -= 1;
+            if idxs[i] < n - k + i {
+                break;
+            }
+        }
+
+        idxs[i] += 1;
+        for j in i + 1..k {
+            idxs[j] = idxs[j - 1] + 1;
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// RAID-Z error.
+#[derive(Debug)]
+pub enum RaidZError {
+    /// Invalid allocation shift.
+    InvalidAllocateShift {
+        /// Allocation shift.
+        ashift: u32,
+    },
+
+    /// Invalid number of children or parity.
+    InvalidGeometry {
+        /// Children.
+        children: usize,
+        /// Parity.
+        parity: usize,
+    },
+
+    /// Invalid size.
+    InvalidSize {
+        /// Size in bytes.
+        size: usize,
+    },
+
+    /// Parity coefficients can not be inverted.
+    Singular {},
+
+    /// More missing columns than parity.
+    TooManyMissing {
+        /// Missing columns.
+        missing: usize,
+        /// Parity.
+        parity: usize,
+    },
+}
+
+impl fmt::Display for RaidZError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RaidZError::InvalidAllocateShift { ashift } => {
+                write!(f, "RAID-Z error, invalid allocate shift {ashift}")
+            }
+            RaidZError::InvalidGeometry { children, parity } => {
+                write!(
+                    f,
+                    "RAID-Z error, invalid children {children} and parity {parity}"
+                )
+            }
+            RaidZError::InvalidSize { size } => {
+                write!(f, "RAID-Z error, invalid size {size}")
+            }
+            RaidZError::Singular {} => {
+                write!(f, "RAID-Z error, parity can not be inverted")
+            }
+            RaidZError::TooManyMissing { missing, parity } => {
+                write!(
+                    f,
+                    "RAID-Z error, missing columns {missing} are more than parity {parity}"
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for RaidZError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::io::raidz::{combinations, gf_inv, gf_mul, RaidZColumn, RaidZError, RaidZMap};
+
+    #[test]
+    fn gf() {
+        assert_eq!(gf_mul(2, 0x80), 0x1d);
+        assert_eq!(gf_mul(0, 7), 0);
+        for a in 1..=255 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+    }
+
+    #[test]
+    fn map() {
+        // 4 children, single parity, 3 sectors at sector 5.
+        let map = RaidZMap::new(5 << 9, 3 << 9, 4, 1, 9).unwrap();
+        assert_eq!(
+            map.columns,
+            [
+                RaidZColumn {
+                    child: 1,
+                    offset: 1 << 9,
+                    size: 512
+                },
+                RaidZColumn {
+                    child: 2,
+                    offset: 1 << 9,
+                    size: 512
+                },
+                RaidZColumn {
+                    child: 3,
+                    offset: 1 << 9,
+                    size: 512
+                },
+                RaidZColumn {
+                    child: 0,
+                    offset: 2 << 9,
+                    size: 512
+                },
+            ]
+        );
+
+        // 5 children, double parity, 4 sectors with 1 remainder sector.
+        let map = RaidZMap::new(0, 4 << 12, 5, 2, 12).unwrap();
+        let sizes: Vec<usize> = map.columns.iter().map(|c| c.size >> 12).collect();
+        assert_eq!(sizes, [2, 2, 2, 1, 1]);
+        assert_eq!(map.data_size(), 4 << 12);
+
+        // Small block that does not span all children.
+        let map = RaidZMap::new(0, 512, 6, 3, 9).unwrap();
+        assert_eq!(map.columns.len(), 4);
+
+        // Single parity switches the first two columns every 1 MiB.
+        let map = RaidZMap::new(1 << 20, 3 << 9, 4, 1, 9).unwrap();
+        assert_eq!(map.columns[0].child, 1);
+        assert_eq!(map.columns[1].child, 0);
+
+        // Errors.
+        assert!(matches!(
+            RaidZMap::new(0, 512, 2, 2, 9).unwrap_err(),
+            RaidZError::InvalidGeometry {
+                children: 2,
+                parity: 2
+            }
+        ));
+        assert!(matches!(
+            RaidZMap::new(0, 512, 4, 4, 9).unwrap_err(),
+            RaidZError::InvalidGeometry { .. }
+        ));
+        assert!(matches!(
+            RaidZMap::new(0, 0, 4, 1, 9).unwrap_err(),
+            RaidZError::InvalidSize { size: 0 }
+        ));
+        assert!(matches!(
+            RaidZMap::new(0, 512, 4, 1, 8).unwrap_err(),
+            RaidZError::InvalidAllocateShift { ashift: 8 }
+        ));
+    }
+
+    #[test]
+    fn reconstruct() {
+        for parity in 1..=3 {
+            for children in parity + 1..=parity + 5 {
+                for sectors in 1..=(2 * children) {
+                    let map = RaidZMap::new(0, sectors << 9, children, parity, 9).unwrap();
+                    let data: Vec<u8> = (0..map.data_size())
+                        .map(|x| (x * 7 + sectors) as u8)
+                        .collect();
+
+                    let mut expected: Vec<Vec<u8>> =
+                        map.columns.iter().map(|c| vec![0; c.size]).collect();
+                    map.data_to_columns(&data, &mut expected);
+                    map.generate_parity(&mut expected);
+
+                    // Every combination of missing columns.
+                    for count in 1..=parity {
+                        combinations(map.columns.len(), count, &mut |missing| {
+                            let mut columns = expected.clone();
+                            for c in missing {
+                                columns[*c].fill(0xa5);
+                            }
+                            map.reconstruct(&mut columns, missing).unwrap();
+
+                            let mut reconstructed = vec![0; data.len()];
+                            map.data_from_columns(&mut reconstructed, &columns);
+                            assert_eq!(reconstructed, data, "{parity} {children} {missing:?}");
+                            false
+                        });
+                    }
+
+                    // Too many missing.
+                    let missing: Vec<usize> = (0..=parity).collect();
+                    let mut columns = expected.clone();
+                    if missing.len() <= columns.len() {
+                        assert!(matches!(
+                            map.reconstruct(&mut columns, &missing).unwrap_err(),
+                            RaidZError::TooManyMissing { .. }
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn combinations_count() {
+        let mut count = 0;
+        assert!(!combinations(5, 3, &mut |idxs| {
+            assert!(idxs.windows(2).all(|w| w[0] < w[1]));
+            count += 1;
+            false
+        }));
+        assert_eq!(count, 10);
+
+        let mut seen = Vec::new();
+        assert!(combinations(4, 2, &mut |idxs| {
+            seen.push(idxs.to_vec());
+            idxs == [1, 3]
+        }));
+        assert_eq!(seen, [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3]]);
+
+        assert!(!combinations(2, 3, &mut |_| true));
+    }
+}
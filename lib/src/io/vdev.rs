@@ -0,0 +1,932 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Virtual devices.
+ *
+ * - A [`Vdev`] maps the sectors of a [`crate::phys::Dva`] onto block devices.
+ * - A [`Vdev`] may store more than one copy of the data. Each copy is read
+ *   separately, so that the caller can verify the checksum of each copy, and
+ *   fall back to the next copy.
+ * - A RAID-Z [`Vdev`] stores one copy, striped over its children with parity.
+ *   Unreadable children are reconstructed from parity. If the data does not
+ *   verify, combinations of children are reconstructed until it does.
+ * - A dRAID [`Vdev`] is read like a RAID-Z [`Vdev`], with the columns of each
+ *   redundancy group mapped onto the children by a [`DRaidLayout`].
+ * - An indirect [`Vdev`] is a removed vdev. Its data is read through an
+ *   [`crate::io::IndirectMapping`], by the [`crate::io::BlockReader`].
+ */
+use core::fmt;
+use std::error;
+
+use crate::io::draid::{DRaidError, DRaidLayout, DRaidMap};
+use crate::io::raidz::{
+    combinations, RaidZError, RaidZMap, RAIDZ_ALLOCATE_SHIFT_MAX, RAIDZ_PARITY_MAX,
+};
+use crate::phys::{
+    BootBlock, LabelVdevChild, LabelVdevTree, LabelVdevTreeDecodeError, LabelVdevTreeType,
+    NvDecodeError, NvList, VdevType, SECTOR_SHIFT,
+};
+use crate::userspace::{BlockDevice, BlockDeviceReadError};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A [`BlockDevice`] of a leaf [`Vdev`].
+#[derive(Clone, Copy, Debug)]
+pub struct VdevDevice<'a> {
+    /// Guid of the leaf [`Vdev`], from its label.
+    pub guid: u64,
+
+    /// Block device.
+    pub device: &'a dyn BlockDevice,
+}
+
+/// Leaf [`Vdev`], a disk or a file.
+#[derive(Debug)]
+pub struct VdevLeaf<'a> {
+    /// Guid.
+    pub guid: u64,
+
+    /// Block device, or [`None`] if the device is missing.
+    pub device: Option<&'a dyn BlockDevice>,
+}
+
+impl VdevLeaf<'_> {
+    /** Reads the `data` at `sector`, relative to the start of the allocatable
+     * sectors.
+     *
+     * # Errors
+     *
+     * Returns [`VdevReadError`] in case of error.
+     */
+    pub fn read(&self, data: &mut [u8], sector: u64) -> Result<(), VdevReadError> {
+        let device = match self.device {
+            Some(v) => v,
+            None => return Err(VdevReadError::Missing { guid: self.guid }),
+        };
+
+        let sector = match sector.checked_add(BootBlock::BLOCK_DEVICE_OFFSET + BootBlock::SECTORS) {
+            Some(v) => v,
+            None => return Err(VdevReadError::InvalidSector { sector }),
+        };
+
+        Ok(device.read(data, sector)?)
+    }
+}
+
+/// Mirror [`Vdev`]. Each child stores a copy of the data.
+#[derive(Debug)]
+pub struct VdevMirror<'a> {
+    /// Guid.
+    pub guid: u64,
+
+    /// Children.
+    pub children: Vec<VdevLeaf<'a>>,
+}
+
+/// RAID-Z [`Vdev`]. Data is striped over the children, with parity.
+#[derive(Debug)]
+pub struct VdevRaidZ<'a> {
+    /// Guid.
+    pub guid: u64,
+
+    /// Allocation shift of the sectors of the children.
+    pub allocate_shift: u32,
+
+    /// Number of parity children (1 to 3).
+    pub parity: usize,
+
+    /// Children.
+    pub children: Vec<VdevLeaf<'a>>,
+}
+
+/// Result of [`VdevRaidZ::read_verified`], or [`VdevDRaid::read_verified`].
+#[derive(Debug)]
+pub struct VdevRaidZRead {
+    /// `true` if the data verified.
+    pub verified: bool,
+
+    /// Indices of children that could not be read.
+    pub read_errors: Vec<usize>,
+
+    /// Indices of children that returned data that did not verify.
+    pub checksum_errors: Vec<usize>,
+}
+
+impl VdevRaidZ<'_> {
+    /** Reads the `data` at `sector`, relative to the start of the allocatable
+     * sectors, and checks it with `verify`.
+     *
+     * Children that can not be read are reconstructed from parity. If the
+     * data does not verify, each combination of the remaining parity number
+     * of children is reconstructed, until the data verifies.
+     *
+     * If the data does not verify, `data` is the data as read, with
+     * unreadable children reconstructed.
+     *
+     * # Errors
+     *
+     * Returns [`VdevReadError`] if more children than parity can not be read.
+     */
+    pub fn read_verified(
+        &self,
+        data: &mut [u8],
+        sector: u64,
+        verify: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<VdevRaidZRead, VdevReadError> {
+        let map = RaidZMap::new(
+            sector_to_offset(sector)?,
+            data.len(),
+            self.children.len(),
+            self.parity,
+            self.allocate_shift,
+        )?;
+
+        read_rows_verified(&self.children, &[map], data, verify)
+    }
+}
+
+/// dRAID [`Vdev`]. Data is striped over redundancy groups with parity, that
+/// are permuted over the children.
+#[derive(Debug)]
+pub struct VdevDRaid<'a> {
+    /// Guid.
+    pub guid: u64,
+
+    /// Layout.
+    pub layout: DRaidLayout,
+
+    /// Children.
+    pub children: Vec<VdevLeaf<'a>>,
+}
+
+impl VdevDRaid<'_> {
+    /** Reads the `data` at `sector`, relative to the start of the allocatable
+     * sectors, and checks it with `verify`.
+     *
+     * Children are reconstructed like [`VdevRaidZ::read_verified`].
+     *
+     * # Errors
+     *
+     * Returns [`VdevReadError`] if more children than parity can not be read
+     * in a group.
+     */
+    pub fn read_verified(
+        &self,
+        data: &mut [u8],
+        sector: u64,
+        verify: &mut dyn FnMut(&[u8]) -> bool,
+    ) -> Result<VdevRaidZRead, VdevReadError> {
+        let rows = self.layout.rows(sector_to_offset(sector)?, data.len())?;
+
+        read_rows_verified(&self.children, &rows, data, verify)
+    }
+}
+
+/// Indirect [`Vdev`], a removed vdev whose segments were copied to other
+/// vdevs.
+#[derive(Debug)]
+pub struct VdevIndirect {
+    /// Guid.
+    pub guid: u64,
+
+    /// MOS object number of the [`crate::phys::IndirectMappingPhys`].
+    pub mapping_object: u64,
+
+    /// MOS object number of the [`crate::phys::IndirectBirthsPhys`].
+    pub births_object: u64,
+}
+
+/// Converts a sector to a byte offset.
+fn sector_to_offset(sector: u64) -> Result<u64, VdevReadError> {
+    match sector.checked_mul(1 << SECTOR_SHIFT) {
+        Some(v) => Ok(v),
+        None => Err(VdevReadError::InvalidSector { sector }),
+    }
+}
+
+/** Reads the `data` of the `rows` of columns from `children`, and checks it
+ * with `verify`.
+ *
+ * The data of the rows is in order. Unreadable columns are reconstructed
+ * from parity. If the data does not verify, each combination of the other
+ * columns, up to the parity of each row, is reconstructed until it does.
+ */
+fn read_rows_verified(
+    children: &[VdevLeaf<'_>],
+    rows: &[RaidZMap],
+    data: &mut [u8],
+    verify: &mut dyn FnMut(&[u8]) -> bool,
+) -> Result<VdevRaidZRead, VdevReadError> {
+    ////////////////////////////////
+    // Read the columns.
+    let mut columns = Vec::with_capacity(rows.len());
+    let mut unreadable = Vec::with_capacity(rows.len());
+
+    for map in rows {
+        let mut row_columns = Vec::with_capacity(map.columns.len());
+        let mut row_unreadable = Vec::new();
+        let mut read_error = None;
+
+        for (idx, column) in map.columns.iter().enumerate() {
+            let mut bytes = vec![0; column.size];
+            let child = match children.get(column.child) {
+                Some(v) => v,
+                None => {
+                    return Err(VdevReadError::InvalidChild {
+                        child: column.child,
+                    })
+                }
+            };
+            if column.size > 0 {
+                if let Err(err) = child.read(&mut bytes, column.offset >> SECTOR_SHIFT) {
+                    row_unreadable.push(idx);
+                    read_error = Some(err);
+                }
+            }
+            row_columns.push(bytes);
+        }
+
+        if row_unreadable.len() > map.parity {
+            if let Some(err) = read_error {
+                return Err(err);
+            }
+        }
+
+        columns.push(row_columns);
+        unreadable.push(row_unreadable);
+    }
+
+    let mut result = VdevRaidZRead {
+        verified: false,
+        read_errors: rows
+            .iter()
+            .zip(unreadable.iter())
+            .flat_map(|(map, row)| row.iter().map(|c| map.columns[*c].child))
+            .collect(),
+        checksum_errors: Vec::new(),
+    };
+
+    ////////////////////////////////
+    // Reconstruct the unreadable columns.
+    let reconstruct = |data: &mut [u8], bad: &[(usize, usize)]| -> Result<(), RaidZError> {
+        let mut offset = 0;
+
+        for (r, map) in rows.iter().enumerate() {
+            let mut missing = unreadable[r].clone();
+            missing.extend(bad.iter().filter(|(row, _)| *row == r).map(|(_, c)| *c));
+
+            let mut row_columns = columns[r].clone();
+            map.reconstruct(&mut row_columns, &missing)?;
+
+            let start = offset.min(data.len());
+            let end = (offset + map.data_size()).min(data.len());
+            map.data_from_columns(&mut data[start..end], &row_columns);
+            offset += map.data_size();
+        }
+
+        Ok(())
+    };
+
+    reconstruct(data, &[])?;
+
+    if verify(data) {
+        result.verified = true;
+        return Ok(result);
+    }
+
+    ////////////////////////////////
+    // Reconstruct combinations of the other columns, that include at least
+    // one data column with data.
+    let candidates: Vec<(usize, usize)> = rows
+        .iter()
+        .enumerate()
+        .flat_map(|(r, map)| {
+            let unreadable = &unreadable[r];
+            map.columns
+                .iter()
+                .enumerate()
+                .filter(move |(c, column)| column.size > 0 && !unreadable.contains(c))
+                .map(move |(c, _)| (r, c))
+        })
+        .collect();
+
+    let spare_parity: usize = rows
+        .iter()
+        .zip(unreadable.iter())
+        .map(|(map, row)| map.parity - row.len())
+        .sum();
+
+    for count in 1..=spare_parity {
+        let mut bad = Vec::new();
+
+        let found = combinations(candidates.len(), count, &mut |idxs| {
+            let attempt: Vec<(usize, usize)> = idxs.iter().map(|idx| candidates[*idx]).collect();
+
+            if attempt.iter().all(|(r, c)| *c < rows[*r].parity) {
+                return false;
+            }
+
+            let fits = rows.iter().enumerate().all(|(r, map)| {
+                unreadable[r].len() + attempt.iter().filter(|(row, _)| *row == r).count()
+                    <= map.parity
+            });
+            if !fits || reconstruct(data, &attempt).is_err() {
+                return false;
+            }
+
+            if verify(data) {
+                bad = attempt
+                    .iter()
+                    .map(|(r, c)| rows[*r].columns[*c].child)
+                    .collect();
+                return true;
+            }
+
+            false
+        });
+
+        if found {
+            result.verified = true;
+            result.checksum_errors = bad;
+            return Ok(result);
+        }
+    }
+
+    // Restore the data as read.
+    reconstruct(data, &[])?;
+
+    Ok(result)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Virtual device.
+#[derive(Debug)]
+pub enum Vdev<'a> {
+    /// [`VdevType::Disk`] or [`VdevType::File`].
+    Leaf(VdevLeaf<'a>),
+
+    /// [`VdevType::Mirror`].
+    Mirror(VdevMirror<'a>),
+
+    /// [`VdevType::RaidZ`].
+    RaidZ(VdevRaidZ<'a>),
+
+    /// [`VdevType::DRaid`].
+    DRaid(VdevDRaid<'a>),
+
+    /// [`VdevType::Indirect`].
+    Indirect(VdevIndirect),
+}
+
+impl<'a> Vdev<'a> {
+    /// Create a leaf [`Vdev`] for a [`BlockDevice`].
+    pub fn leaf(guid: u64, device: &'a dyn BlockDevice) -> Vdev<'a> {
+        Vdev::Leaf(VdevLeaf {
+            guid,
+            device: Some(device),
+        })
+    }
+
+    /** Create a [`Vdev`] from a top level [`LabelVdevTree`].
+     *
+     * Leaf [`Vdev`] are matched to `devices` by guid. A leaf without a
+     * matching device is missing.
+     *
+     * A dRAID [`Vdev`] uses the built-in [`DRaidMap`] for its number of
+     * children.
+     *
+     * # Errors
+     *
+     * Returns [`VdevDecodeError`] in case of decoding error, or unsupported
+     * [`VdevType`].
+     */
+    pub fn from_tree(
+        tree: &LabelVdevTree<'_>,
+        devices: &[VdevDevice<'a>],
+    ) -> Result<Vdev<'a>, VdevDecodeError> {
+        match &tree.vdev_type {
+            LabelVdevTreeType::Disk(_) | LabelVdevTreeType::File(_) => {
+                Ok(Vdev::Leaf(Vdev::leaf_from_guid(tree.guid, devices)))
+            }
+            LabelVdevTreeType::Mirror(mirror) => {
+                let mut children = Vec::with_capacity(mirror.children.len());
+
+                for child in &mirror.children {
+                    children.push(Vdev::child_from_list(&child?, devices)?);
+                }
+
+                Ok(Vdev::Mirror(VdevMirror {
+                    guid: tree.guid,
+                    children,
+                }))
+            }
+            LabelVdevTreeType::RaidZ(raidz) => {
+                let mut children = Vec::with_capacity(raidz.children.len());
+
+                for child in &raidz.children {
+                    children.push(Vdev::child_from_list(&child?, devices)?);
+                }
+
+                let parity = raidz.parity.unwrap_or(1);
+                let parity = match usize::try_from(parity) {
+                    Ok(v) if v > 0 && v <= RAIDZ_PARITY_MAX && v < children.len() => v,
+                    _ => return Err(VdevDecodeError::InvalidParity { parity }),
+                };
+
+                let allocate_shift = match u32::try_from(tree.allocate_shift) {
+                    Ok(v) if (SECTOR_SHIFT..=RAIDZ_ALLOCATE_SHIFT_MAX).contains(&v) => v,
+                    _ => {
+                        return Err(VdevDecodeError::InvalidAllocateShift {
+                            allocate_shift: tree.allocate_shift,
+                        })
+                    }
+                };
+
+                Ok(Vdev::RaidZ(VdevRaidZ {
+                    guid: tree.guid,
+                    allocate_shift,
+                    parity,
+                    children,
+                }))
+            }
+            LabelVdevTreeType::DRaid(draid) => {
+                let mut children = Vec::with_capacity(draid.children.len());
+
+                for child in &draid.children {
+                    children.push(Vdev::child_from_list(&child?, devices)?);
+                }
+
+                let map = match DRaidMap::find(children.len()) {
+                    Some(v) => v,
+                    None => {
+                        return Err(VdevDecodeError::DRaid {
+                            err: DRaidError::InvalidChildren {
+                                children: children.len(),
+                            },
+                        })
+                    }
+                };
+
+                let to_usize = |value: u64| usize::try_from(value).unwrap_or(usize::MAX);
+                let allocate_shift = u32::try_from(tree.allocate_shift).unwrap_or(u32::MAX);
+
+                let layout = DRaidLayout::new(
+                    children.len(),
+                    to_usize(draid.data),
+                    to_usize(draid.parity),
+                    to_usize(draid.spares),
+                    draid.groups,
+                    allocate_shift,
+                    map,
+                )?;
+
+                Ok(Vdev::DRaid(VdevDRaid {
+                    guid: tree.guid,
+                    layout,
+                    children,
+                }))
+            }
+            LabelVdevTreeType::Indirect(indirect) => Ok(Vdev::Indirect(VdevIndirect {
+                guid: tree.guid,
+                mapping_object: indirect.indirect_object,
+                births_object: indirect.indirect_births,
+            })),
+        }
+    }
+
+    /// Gets the guid.
+    pub fn guid(&self) -> u64 {
+        match self {
+            Vdev::Leaf(leaf) => leaf.guid,
+            Vdev::Mirror(mirror) => mirror.guid,
+            Vdev::RaidZ(raidz) => raidz.guid,
+            Vdev::DRaid(draid) => draid.guid,
+            Vdev::Indirect(indirect) => indirect.guid,
+        }
+    }
+
+    /// Gets the number of copies of the data.
+    pub fn copies(&self) -> usize {
+        match self {
+            Vdev::Leaf(_) | Vdev::RaidZ(_) | Vdev::DRaid(_) | Vdev::Indirect(_) => 1,
+            Vdev::Mirror(mirror) => mirror.children.len(),
+        }
+    }
+
+    /// Gets the guid of the leaf [`Vdev`] that stores `copy`, or [`None`] if
+    /// the copy is not stored on a single leaf [`Vdev`].
+    pub fn copy_guid(&self, copy: usize) -> Option<u64> {
+        match self {
+            Vdev::Leaf(leaf) => (copy == 0).then_some(leaf.guid),
+            Vdev::Mirror(mirror) => mirror.children.get(copy).map(|child| child.guid),
+            Vdev::RaidZ(_) | Vdev::DRaid(_) | Vdev::Indirect(_) => None,
+        }
+    }
+
+    /** Reads `copy` of the `data` at `sector`, relative to the start of the
+     * allocatable sectors.
+     *
+     * # Errors
+     *
+     * Returns [`VdevReadError`] in case of error, or for an indirect
+     * [`Vdev`], that has to be remapped first.
+     */
+    pub fn read(&self, data: &mut [u8], sector: u64, copy: usize) -> Result<(), VdevReadError> {
+        let leaf = match self {
+            Vdev::Leaf(leaf) => (copy == 0).then_some(leaf),
+            Vdev::Mirror(mirror) => mirror.children.get(copy),
+            Vdev::RaidZ(raidz) => {
+                if copy != 0 {
+                    return Err(VdevReadError::InvalidCopy { copy });
+                }
+                raidz.read_verified(data, sector, &mut |_| true)?;
+                return Ok(());
+            }
+            Vdev::DRaid(draid) => {
+                if copy != 0 {
+                    return Err(VdevReadError::InvalidCopy { copy });
+                }
+                draid.read_verified(data, sector, &mut |_| true)?;
+                return Ok(());
+            }
+            Vdev::Indirect(indirect) => {
+                return Err(VdevReadError::Indirect {
+                    guid: indirect.guid,
+                })
+            }
+        };
+
+        match leaf {
+            Some(leaf) => leaf.read(data, sector),
+            None => Err(VdevReadError::InvalidCopy { copy }),
+        }
+    }
+
+    /// Decodes a leaf child of a mirror or RAID-Z [`Vdev`].
+    fn child_from_list(
+        list: &NvList<'_>,
+        devices: &[VdevDevice<'a>],
+    ) -> Result<VdevLeaf<'a>, VdevDecodeError> {
+        let child = LabelVdevChild::from_list(list)?;
+        match child.vdev_type {
+            VdevType::Disk | VdevType::File => Ok(Vdev::leaf_from_guid(child.guid, devices)),
+            vdev_type => Err(VdevDecodeError::UnsupportedVdevType { vdev_type }),
+        }
+    }
+
+    /// Finds the leaf for `guid` in `devices`.
+    fn leaf_from_guid(guid: u64, devices: &[VdevDevice<'a>]) -> VdevLeaf<'a> {
+        VdevLeaf {
+            guid,
+            device: devices
+                .iter()
+                .find(|device| device.guid == guid)
+                .map(|device| device.device),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`Vdev`] decode error.
+#[derive(Debug)]
+pub enum VdevDecodeError {
+    /// dRAID error.
+    DRaid {
+        /// Error.
+        err: DRaidError,
+    },
+
+    /// Invalid allocation shift.
+    InvalidAllocateShift {
+        /// Allocation shift.
+        allocate_shift: u64,
+    },
+
+    /// Invalid number of parity children.
+    InvalidParity {
+        /// Parity.
+        parity: u64,
+    },
+
+    /// [`LabelVdevTree`] decode error.
+    LabelVdevTree {
+        /// Error.
+        err: LabelVdevTreeDecodeError,
+    },
+
+    /// [`crate::phys::NvList`] decode error.
+    Nv {
+        /// Error.
+        err: NvDecodeError,
+    },
+
+    /// Unsupported [`VdevType`].
+    UnsupportedVdevType {
+        /// Vdev type.
+        vdev_type: VdevType,
+    },
+}
+
+impl From<DRaidError> for VdevDecodeError {
+    fn from(err: DRaidError) -> Self {
+        VdevDecodeError::DRaid { err }
+    }
+}
+
+impl From<LabelVdevTreeDecodeError> for VdevDecodeError {
+    fn from(err: LabelVdevTreeDecodeError) -> Self {
+        VdevDecodeError::LabelVdevTree { err }
+    }
+}
+
+impl From<NvDecodeError> for VdevDecodeError {
+    fn from(err: NvDecodeError) -> Self {
+        VdevDecodeError::Nv { err }
+    }
+}
+
+impl fmt::Display for VdevDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VdevDecodeError::DRaid { err } => {
+                write!(f, "Vdev decode error | {err}")
+            }
+            VdevDecodeError::InvalidAllocateShift { allocate_shift } => {
+                write!(
+                    f,
+                    "Vdev decode error, invalid allocate shift {allocate_shift}"
+                )
+            }
+            VdevDecodeError::InvalidParity { parity } => {
+                write!(f, "Vdev decode error, invalid parity {parity}")
+            }
+            VdevDecodeError::LabelVdevTree { err } => {
+                write!(f, "Vdev decode error | {err}")
+            }
+            VdevDecodeError::Nv { err } => {
+                write!(f, "Vdev decode error | {err}")
+            }
+            VdevDecodeError::UnsupportedVdevType { vdev_type } => {
+                write!(f, "Vdev decode error, unsupported vdev type {vdev_type}")
+            }
+        }
+    }
+}
+
+impl error::Error for VdevDecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VdevDecodeError::DRaid { err } => Some(err),
+            VdevDecodeError::LabelVdevTree { err } => Some(err),
+            VdevDecodeError::Nv { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`Vdev`] read error.
+#[derive(Debug)]
+pub enum VdevReadError {
+    /// [`BlockDevice`] error.
+    BlockDevice {
+        /// Error.
+        err: BlockDeviceReadError,
+    },
+
+    /// dRAID error.
+    DRaid {
+        /// Error.
+        err: DRaidError,
+    },
+
+    /// Indirect [`Vdev`] can not be read without remapping.
+    Indirect {
+        /// Guid.
+        guid: u64,
+    },
+
+    /// Invalid child.
+    InvalidChild {
+        /// Child index.
+        child: usize,
+    },
+
+    /// Invalid copy.
+    InvalidCopy {
+        /// Copy.
+        copy: usize,
+    },
+
+    /// Invalid sector.
+    InvalidSector {
+        /// Sector.
+        sector: u64,
+    },
+
+    /// Leaf [`Vdev`] is missing.
+    Missing {
+        /// Guid.
+        guid: u64,
+    },
+
+    /// RAID-Z error.
+    RaidZ {
+        /// Error.
+        err: RaidZError,
+    },
+}
+
+impl From<DRaidError> for VdevReadError {
+    fn from(err: DRaidError) -> Self {
+        VdevReadError::DRaid { err }
+    }
+}
+
+impl From<RaidZError> for VdevReadError {
+    fn from(err: RaidZError) -> Self {
+        VdevReadError::RaidZ { err }
+    }
+}
+
+impl From<BlockDeviceReadError> for VdevReadError {
+    fn from(err: BlockDeviceReadError) -> Self {
+        VdevReadError::BlockDevice { err }
+    }
+}
+
+impl fmt::Display for VdevReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VdevReadError::BlockDevice { err } => {
+                write!(f, "Vdev read error | {err}")
+            }
+            VdevReadError::DRaid { err } => {
+                write!(f, "Vdev read error | {err}")
+            }
+            VdevReadError::Indirect { guid } => {
+                write!(f, "Vdev read error, indirect vdev {guid} is not remapped")
+            }
+            VdevReadError::InvalidChild { child } => {
+                write!(f, "Vdev read error, invalid child {child}")
+            }
+            VdevReadError::InvalidCopy { copy } => {
+                write!(f, "Vdev read error, invalid copy {copy}")
+            }
+            VdevReadError::InvalidSector { sector } => {
+                write!(f, "Vdev read error, invalid sector {sector}")
+            }
+            VdevReadError::Missing { guid } => {
+                write!(f, "Vdev read error, missing vdev {guid}")
+            }
+            VdevReadError::RaidZ { err } => {
+                write!(f, "Vdev read error | {err}")
+            }
+        }
+    }
+}
+
+impl error::Error for VdevReadError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            VdevReadError::BlockDevice { err } => Some(err),
+            VdevReadError::DRaid { err } => Some(err),
+            VdevReadError::RaidZ { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::io::Vdev;
+    use crate::phys::{LabelVdevTree, LabelVdevTreeType, NvList};
+
+    /// Value of an encoded NV pair.
+    enum Value<'a> {
+        U64(u64),
+        Str(&'a str),
+        Lists(Vec<Vec<u8>>),
+    }
+
+    /// Appends an XDR string.
+    fn put_str(bytes: &mut Vec<u8>, value: &str) {
+        bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+    }
+
+    /// Encodes an XDR NV list, without the header.
+    fn nv_list(pairs: &[(&str, Value<'_>)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+
+        for (name, value) in pairs {
+            let mut pair = Vec::new();
+            put_str(&mut pair, name);
+
+            let (data_type, count, data): (u32, usize, Vec<u8>) = match value {
+                Value::U64(v) => (8, 1, v.to_be_bytes().to_vec()),
+                Value::Str(v) => {
+                    let mut data = Vec::new();
+                    put_str(&mut data, v);
+                    (9, 1, data)
+                }
+                Value::Lists(v) => (20, v.len(), v.concat()),
+            };
+            pair.extend_from_slice(&data_type.to_be_bytes());
+            pair.extend_from_slice(&(count as u32).to_be_bytes());
+            pair.extend_from_slice(&data);
+
+            let size = (pair.len() + 8) as u32;
+            bytes.extend_from_slice(&size.to_be_bytes());
+            bytes.extend_from_slice(&size.to_be_bytes());
+            bytes.extend_from_slice(&pair);
+        }
+
+        bytes.extend_from_slice(&[0; 8]);
+        bytes
+    }
+
+    /** Encodes the vdev tree of a `draid1:2d:7c:1s` vdev of files, with the
+     * fields of the label of a pool created by OpenZFS.
+     */
+    fn draid_tree() -> Vec<u8> {
+        let paths = ["/a", "/b", "/c", "/d", "/e", "/f", "/g"];
+        let children = paths
+            .iter()
+            .enumerate()
+            .map(|(id, path)| {
+                let id = id as u64;
+                nv_list(&[
+                    ("type", Value::Str("file")),
+                    ("id", Value::U64(id)),
+                    ("guid", Value::U64(100 + id)),
+                    ("path", Value::Str(path)),
+                    ("DTL", Value::U64(300 + id)),
+                    ("create_txg", Value::U64(4)),
+                    ("com.delphix:vdev_zap_leaf", Value::U64(200 + id)),
+                ])
+            })
+            .collect();
+
+        let mut bytes = vec![1, 1, 0, 0];
+        bytes.extend(nv_list(&[
+            ("type", Value::Str("draid")),
+            ("id", Value::U64(0)),
+            ("guid", Value::U64(10)),
+            ("nparity", Value::U64(1)),
+            ("draid_ndata", Value::U64(2)),
+            ("draid_nspares", Value::U64(1)),
+            ("draid_ngroups", Value::U64(2)),
+            ("metaslab_array", Value::U64(256)),
+            ("metaslab_shift", Value::U64(29)),
+            ("ashift", Value::U64(12)),
+            ("asize", Value::U64(6 << 30)),
+            ("is_log", Value::U64(0)),
+            ("create_txg", Value::U64(4)),
+            ("com.delphix:vdev_zap_top", Value::U64(129)),
+            ("children", Value::Lists(children)),
+        ]));
+        bytes
+    }
+
+    #[test]
+    #[ignore = "DRAID_MAPS does not have the entries of OpenZFS draid_maps[] yet"]
+    fn from_tree_draid() {
+        let bytes = draid_tree();
+        let list = NvList::from_bytes(&bytes).unwrap();
+        let tree = LabelVdevTree::from_list(&list).unwrap();
+        assert_eq!(tree.top_zap, Some(129));
+        assert!(matches!(tree.vdev_type, LabelVdevTreeType::DRaid(_)));
+
+        let draid = match Vdev::from_tree(&tree, &[]).unwrap() {
+            Vdev::DRaid(v) => v,
+            _ => panic!("expected dRAID"),
+        };
+        assert_eq!(draid.guid, 10);
+
+        let guids: Vec<u64> = draid.children.iter().map(|child| child.guid).collect();
+        assert_eq!(guids, [100, 101, 102, 103, 104, 105, 106]);
+        assert!(draid.children.iter().all(|child| child.device.is_none()));
+
+        let layout = &draid.layout;
+        assert_eq!(
+            (
+                layout.children,
+                layout.data,
+                layout.parity,
+                layout.spares,
+                layout.groups,
+                layout.allocate_shift,
+            ),
+            (7, 2, 1, 1, 2, 12)
+        );
+    }
+}
@@ -15,6 +15,10 @@ pub mod checksum;
 /// Compression.
 pub mod compression;
 
+/// Block reading from virtual devices.
+#[cfg(feature = "userspace")]
+pub mod io;
+
 /// On disk physical structure encoding and decoding.
 pub mod phys;
 
@@ -58,15 +58,25 @@ pub use feature::{
     FeatureSetDecodeError, FeatureSetIterator,
 };
 
+pub(crate) mod gang;
+pub use gang::{GangHeader, GangHeaderDecodeError, GangHeaderEncodeError};
+
+pub(crate) mod indirect;
+pub use indirect::{
+    IndirectBirthEntry, IndirectBirthsDecodeError, IndirectBirthsEncodeError, IndirectBirthsPhys,
+    IndirectMappingDecodeError, IndirectMappingEncodeError, IndirectMappingEntry,
+    IndirectMappingPhys,
+};
+
 pub(crate) mod label;
 pub use label::{
     BootBlock, BootBlockDecodeError, BootBlockEncodeError, Label, LabelBlank,
     LabelBlankDecodeError, LabelBlankEncodeError, LabelBootHeader, LabelBootHeaderDecodeError,
     LabelBootHeaderEncodeError, LabelConfig, LabelConfigDecodeError, LabelConfigL2Cache,
     LabelConfigSpare, LabelConfigStorage, LabelNvPairs, LabelNvPairsDecodeError,
-    LabelNvPairsEncodeError, LabelSectorsError, LabelVdevChild, LabelVdevTree,
-    LabelVdevTreeDecodeError, LabelVdevTreeDisk, LabelVdevTreeFile, LabelVdevTreeMirror,
-    LabelVdevTreeRaidZ, LabelVdevTreeType,
+    LabelNvPairsEncodeError, LabelSectorsError, LabelVdevChild, LabelVdevTree, LabelVdevTreeDRaid,
+    LabelVdevTreeDecodeError, LabelVdevTreeDisk, LabelVdevTreeFile, LabelVdevTreeIndirect,
+    LabelVdevTreeMirror, LabelVdevTreeRaidZ, LabelVdevTreeType,
 };
 
 pub(crate) mod nv;
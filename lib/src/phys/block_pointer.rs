@@ -224,9 +224,13 @@ impl BlockPointer {
  * e embedded   (1)
  *
  * etype    BlockPointerEmbeddedType
- * physical size in bytes
- * logical  size in bytes
+ * physical (size - 1) in bytes
+ * logical  (size - 1) in bytes
  * ```
+ *
+ * The payload is stored in 64 bit words, in the byte order of the block
+ * pointer. Each word holds 8 bytes of the payload, least significant byte
+ * first.
  */
 #[derive(Debug)]
 pub struct BlockPointerEmbedded {
@@ -260,7 +264,7 @@ pub struct BlockPointerEmbedded {
      */
     pub physical_size: usize,
 
-    /// Physical payload.
+    /// Physical payload. Only the first `physical_size` bytes are used.
     pub payload: [u8; BlockPointerEmbedded::PHYSICAL_SIZE_MAX],
 }
 
@@ -346,7 +350,7 @@ impl error::Error for BlockPointerEmbeddedTypeError {
 
 impl BlockPointerEmbedded {
     /// Maximum logical size in bytes of data embedded in pointer.
-    pub const LOGICAL_SIZE_MAX: usize = (BlockPointerEmbedded::LOGICAL_SIZE_MASK as usize);
+    pub const LOGICAL_SIZE_MAX: usize = (BlockPointerEmbedded::LOGICAL_SIZE_MASK as usize) + 1;
 
     /// Maximum payload length in bytes of data embedded in pointer.
     pub const PHYSICAL_SIZE_MAX: usize = 112;
@@ -360,6 +364,32 @@ impl BlockPointerEmbedded {
     /// Shift for [`BlockPointerEmbedded`] physical size.
     const PHYSICAL_SIZE_SHIFT: u64 = 25;
 
+    /// Decodes payload bytes from 64 bit words.
+    fn payload_from_decoder(
+        payload: &mut [u8],
+        decoder: &mut dyn BinaryDecoder<'_>,
+    ) -> Result<(), BinaryDecodeError> {
+        for word in payload.chunks_exact_mut(8) {
+            word.copy_from_slice(&decoder.get_u64()?.to_le_bytes());
+        }
+
+        Ok(())
+    }
+
+    /// Encodes payload bytes as 64 bit words.
+    fn payload_to_encoder(
+        payload: &[u8],
+        encoder: &mut dyn BinaryEncoder<'_>,
+    ) -> Result<(), BinaryEncodeError> {
+        for word in payload.chunks_exact(8) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(word);
+            encoder.put_u64(u64::from_le_bytes(bytes))?;
+        }
+
+        Ok(())
+    }
+
     /** Decodes a [`BlockPointer`].
      *
      * # Errors
@@ -374,8 +404,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Decode embedded payload (part 1).
-        let part_1 = &mut payload[0..48];
-        part_1.copy_from_slice(decoder.get_bytes_n(part_1.len())?);
+        BlockPointerEmbedded::payload_from_decoder(&mut payload[0..48], decoder)?;
 
         ////////////////////////////////
         // Decode flags.
@@ -383,8 +412,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Decode embedded payload (part 2).
-        let part_2 = &mut payload[48..72];
-        part_2.copy_from_slice(decoder.get_bytes_n(part_2.len())?);
+        BlockPointerEmbedded::payload_from_decoder(&mut payload[48..72], decoder)?;
 
         ////////////////////////////////
         // Decode logical birth transaction group.
@@ -392,8 +420,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Decode embedded payload (part 3).
-        let part_3 = &mut payload[72..112];
-        part_3.copy_from_slice(decoder.get_bytes_n(part_3.len())?);
+        BlockPointerEmbedded::payload_from_decoder(&mut payload[72..112], decoder)?;
 
         ////////////////////////////////
         // Decode encrypted and embedded.
@@ -447,7 +474,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Decode sizes. Already in bytes.
-        let logical_size = (flags & BlockPointerEmbedded::LOGICAL_SIZE_MASK) as u32;
+        let logical_size = ((flags & BlockPointerEmbedded::LOGICAL_SIZE_MASK) as u32) + 1;
         let logical_size = match usize::try_from(logical_size) {
             Ok(v) => v,
             Err(_) => return Err(BlockPointerDecodeError::LogicalSizeTooLarge { logical_size }),
@@ -456,7 +483,7 @@ impl BlockPointerEmbedded {
         let physical_size = usize::from(
             ((flags >> BlockPointerEmbedded::PHYSICAL_SIZE_SHIFT)
                 & BlockPointerEmbedded::PHYSICAL_SIZE_MASK_DOWN_SHIFTED) as u8,
-        );
+        ) + 1;
 
         ////////////////////////////////
         // Check that physical size is within embedded payload length.
@@ -493,7 +520,7 @@ impl BlockPointerEmbedded {
     ) -> Result<(), BlockPointerEncodeError> {
         ////////////////////////////////
         // Check physical size.
-        if self.physical_size > self.payload.len() {
+        if self.physical_size < 1 || self.physical_size > self.payload.len() {
             return Err(BlockPointerEncodeError::InvalidEmbeddedLength {
                 length: self.physical_size,
             });
@@ -501,7 +528,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Encode embedded payload (part 1).
-        encoder.put_bytes(&self.payload[0..48])?;
+        BlockPointerEmbedded::payload_to_encoder(&self.payload[0..48], encoder)?;
 
         ////////////////////////////////
         // Encode flags.
@@ -514,14 +541,14 @@ impl BlockPointerEmbedded {
         let embedded_type: u8 = self.embedded_type.into();
         let compression: u8 = self.compression.into();
 
-        if self.logical_size > (BlockPointerEmbedded::LOGICAL_SIZE_MASK as usize) {
+        if self.logical_size < 1 || self.logical_size > BlockPointerEmbedded::LOGICAL_SIZE_MAX {
             return Err(BlockPointerEncodeError::InvalidLogicalSize {
                 logical_size: self.logical_size,
             });
         }
 
-        let flags = (self.logical_size as u64)
-            | (self.physical_size as u64) << BlockPointerEmbedded::PHYSICAL_SIZE_SHIFT
+        let flags = (self.logical_size as u64 - 1)
+            | (self.physical_size as u64 - 1) << BlockPointerEmbedded::PHYSICAL_SIZE_SHIFT
             | u64::from(compression) << BlockPointer::COMPRESSION_SHIFT
             | BlockPointer::EMBEDDED_BIT_FLAG
             | u64::from(embedded_type) << BlockPointer::CHECKSUM_SHIFT
@@ -536,7 +563,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Encode embedded payload (part 2).
-        encoder.put_bytes(&self.payload[48..72])?;
+        BlockPointerEmbedded::payload_to_encoder(&self.payload[48..72], encoder)?;
 
         ////////////////////////////////
         // Encode logical birth transaction group.
@@ -544,7 +571,7 @@ impl BlockPointerEmbedded {
 
         ////////////////////////////////
         // Encode embedded payload (part 3).
-        encoder.put_bytes(&self.payload[72..112])?;
+        BlockPointerEmbedded::payload_to_encoder(&self.payload[72..112], encoder)?;
 
         ////////////////////////////////
         // Success.
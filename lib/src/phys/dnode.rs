@@ -601,7 +601,7 @@ impl Dnode {
                 None => BlockPointer::empty_to_encoder(encoder)?,
             }
         }
-        encoder.put_bytes(self.bonus_capacity())?;
+        encoder.put_bytes_n(self.bonus_capacity())?;
 
         if let DnodeTail::Spill(tail) = &self.tail {
             match &tail.spill {
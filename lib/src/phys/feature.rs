@@ -932,11 +932,9 @@ mod tests {
 
         ////////////////////////////////
         // IntoIterator.
-        let mut idx = 0;
         let expected = ["a", "", " b ", "c", ""];
-        for entry in compatibility {
+        for (idx, entry) in compatibility.into_iter().enumerate() {
             assert_eq!(entry, expected[idx]);
-            idx += 1;
         }
     }
 
@@ -1104,12 +1102,12 @@ mod tests {
             // Check FeatureSet does not contain Feature.
             assert_eq!(feature_set.is_empty(), index == 0);
             assert_eq!(feature_set.len(), index);
-            assert_eq!(feature_set.contains(*feature), false);
+            assert!(!feature_set.contains(*feature));
 
             // Insert Feature.
-            assert_eq!(feature_set.insert(*feature), true);
+            assert!(feature_set.insert(*feature));
             assert_eq!(feature_set.len(), index + 1);
-            assert_eq!(feature_set.is_empty(), false);
+            assert!(!feature_set.is_empty());
 
             // Check FeatureSet contains expected Feature.
             for (index_2, feature_2) in Feature::all().iter().enumerate() {
@@ -1126,7 +1124,7 @@ mod tests {
             assert_eq!(feature_set_2.len(), feature_set.len());
 
             // Adding Feature again does nothing.
-            assert_eq!(feature_set.insert(*feature), false);
+            assert!(!feature_set.insert(*feature));
             for (index_2, feature_2) in Feature::all().iter().enumerate() {
                 assert_eq!(feature_set.contains(*feature_2), index_2 <= index);
             }
@@ -1136,12 +1134,12 @@ mod tests {
         // Remove all features.
         for (index, feature) in Feature::all().iter().enumerate() {
             // Check FeatureSet contains Feature.
-            assert_eq!(feature_set.is_empty(), false);
+            assert!(!feature_set.is_empty());
             assert_eq!(feature_set.len(), total_features - index);
-            assert_eq!(feature_set.contains(*feature), true);
+            assert!(feature_set.contains(*feature));
 
             // Remove Feature.
-            assert_eq!(feature_set.remove(*feature), true);
+            assert!(feature_set.remove(*feature));
             assert_eq!(feature_set.len(), total_features - index - 1);
             assert_eq!(feature_set.is_empty(), index == total_features - 1);
 
@@ -1155,12 +1153,12 @@ mod tests {
             let mut feature_set_2 = FeatureSet::default();
             for feature_2 in feature_set {
                 assert!(feature_set.contains(feature_2));
-                assert_eq!(feature_set_2.insert(feature_2), true);
+                assert!(feature_set_2.insert(feature_2));
             }
             assert_eq!(feature_set_2.len(), feature_set.len());
 
             // Removing Feature again does nothing.
-            assert_eq!(feature_set.remove(*feature), false);
+            assert!(!feature_set.remove(*feature));
             for (index_2, feature_2) in Feature::all().iter().enumerate() {
                 assert_eq!(feature_set.contains(*feature_2), index_2 > index);
             }
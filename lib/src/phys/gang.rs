@@ -0,0 +1,274 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::phys::{
+    BigLittleEndianDecoder, BigLittleEndianEncoder, BinaryDecodeError, BinaryEncodeError,
+    BlockPointer, BlockPointerDecodeError, BlockPointerEncodeError, ChecksumTail,
+    ChecksumTailDecodeError, ChecksumTailEncodeError, EndianOrder,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Gang block header.
+ *
+ * A gang block is a block that is split into up to three smaller blocks,
+ * when a contiguous allocation of the full size is not possible. The
+ * [`crate::phys::Dva`] of a gang block has `is_gang` set, and points to a
+ * [`GangHeader`]. The data of the gang block is the concatenation of the
+ * data of the [`GangHeader`] block pointers.
+ *
+ * ### Byte layout.
+ *
+ * - Bytes: 512
+ *
+ * ```text
+ * +---------+------+
+ * | Field   | Size |
+ * +---------+------+
+ * | ptrs    |  384 |
+ * | padding |   88 |
+ * | tail    |   40 |
+ * +---------+------+
+ * ```
+ *
+ * ### tail
+ *
+ * The [`ChecksumTail`] is a [`crate::phys::ChecksumType::GangHeader`]
+ * checksum, and its magic determines the [`EndianOrder`] of `ptrs`.
+ */
+#[derive(Debug)]
+pub struct GangHeader {
+    /// Endian order.
+    pub order: EndianOrder,
+
+    /// Block pointers to the gang block data.
+    pub ptrs: [Option<BlockPointer>; GangHeader::BLOCK_POINTERS],
+
+    /// Checksum tail.
+    pub tail: ChecksumTail,
+}
+
+impl GangHeader {
+    /// Byte size of an encoded [`GangHeader`] (512).
+    pub const SIZE: usize = 512;
+
+    /// Number of block pointers in a [`GangHeader`].
+    pub const BLOCK_POINTERS: usize = 3;
+
+    /// Padding byte size.
+    const PADDING_SIZE: usize =
+        GangHeader::SIZE - GangHeader::BLOCK_POINTERS * BlockPointer::SIZE - ChecksumTail::SIZE;
+
+    /// Byte offset of [`ChecksumTail`].
+    const TAIL_OFFSET: usize = GangHeader::SIZE - ChecksumTail::SIZE;
+
+    /** Decodes a [`GangHeader`].
+     *
+     * # Errors
+     *
+     * Returns [`GangHeaderDecodeError`] in case of decoding error.
+     */
+    pub fn from_bytes(bytes: &[u8; GangHeader::SIZE]) -> Result<GangHeader, GangHeaderDecodeError> {
+        ////////////////////////////////
+        // Decode checksum tail.
+        let mut tail_bytes = [0; ChecksumTail::SIZE];
+        tail_bytes.copy_from_slice(&bytes[GangHeader::TAIL_OFFSET..]);
+        let tail = ChecksumTail::from_bytes(&tail_bytes)?;
+
+        ////////////////////////////////
+        // Decode block pointers, in the order of the tail.
+        let mut bl_decoder =
+            BigLittleEndianDecoder::from_bytes(&bytes[0..GangHeader::TAIL_OFFSET], tail.order);
+        let decoder = bl_decoder.decoder_as_mut();
+
+        let ptrs = [
+            BlockPointer::from_decoder(decoder)?,
+            BlockPointer::from_decoder(decoder)?,
+            BlockPointer::from_decoder(decoder)?,
+        ];
+
+        decoder.skip_zeros(GangHeader::PADDING_SIZE)?;
+
+        ////////////////////////////////
+        // Success.
+        Ok(GangHeader {
+            order: tail.order,
+            ptrs,
+            tail,
+        })
+    }
+
+    /** Encodes a [`GangHeader`].
+     *
+     * # Errors
+     *
+     * Returns [`GangHeaderEncodeError`] in case of encoding error.
+     */
+    pub fn to_bytes(
+        &self,
+        bytes: &mut [u8; GangHeader::SIZE],
+    ) -> Result<(), GangHeaderEncodeError> {
+        let (head, tail) = bytes.split_at_mut(GangHeader::TAIL_OFFSET);
+
+        ////////////////////////////////
+        // Encode block pointers.
+        let mut bl_encoder = BigLittleEndianEncoder::to_bytes(head, self.order);
+        let encoder = bl_encoder.encoder();
+
+        for ptr in &self.ptrs {
+            BlockPointer::option_to_encoder(ptr, encoder)?;
+        }
+
+        encoder.put_zeros(GangHeader::PADDING_SIZE)?;
+
+        ////////////////////////////////
+        // Encode checksum tail.
+        let mut tail_bytes = [0; ChecksumTail::SIZE];
+        self.tail.to_bytes(&mut tail_bytes)?;
+        tail.copy_from_slice(&tail_bytes);
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`GangHeader`] decode error.
+#[derive(Debug)]
+pub enum GangHeaderDecodeError {
+    /// [`crate::phys::BinaryDecoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// [`BlockPointer`] decode error.
+    BlockPointer {
+        /// Error.
+        err: BlockPointerDecodeError,
+    },
+
+    /// [`ChecksumTail`] decode error.
+    ChecksumTail {
+        /// Error.
+        err: ChecksumTailDecodeError,
+    },
+}
+
+impl From<BinaryDecodeError> for GangHeaderDecodeError {
+    fn from(err: BinaryDecodeError) -> Self {
+        GangHeaderDecodeError::Binary { err }
+    }
+}
+
+impl From<BlockPointerDecodeError> for GangHeaderDecodeError {
+    fn from(err: BlockPointerDecodeError) -> Self {
+        GangHeaderDecodeError::BlockPointer { err }
+    }
+}
+
+impl From<ChecksumTailDecodeError> for GangHeaderDecodeError {
+    fn from(err: ChecksumTailDecodeError) -> Self {
+        GangHeaderDecodeError::ChecksumTail { err }
+    }
+}
+
+impl fmt::Display for GangHeaderDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GangHeaderDecodeError::Binary { err } => {
+                write!(f, "GangHeader decode error | {err}")
+            }
+            GangHeaderDecodeError::BlockPointer { err } => {
+                write!(f, "GangHeader decode error | {err}")
+            }
+            GangHeaderDecodeError::ChecksumTail { err } => {
+                write!(f, "GangHeader decode error | {err}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for GangHeaderDecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            GangHeaderDecodeError::Binary { err } => Some(err),
+            GangHeaderDecodeError::BlockPointer { err } => Some(err),
+            GangHeaderDecodeError::ChecksumTail { err } => Some(err),
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`GangHeader`] encode error.
+#[derive(Debug)]
+pub enum GangHeaderEncodeError {
+    /// [`crate::phys::BinaryEncoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryEncodeError,
+    },
+
+    /// [`BlockPointer`] encode error.
+    BlockPointer {
+        /// Error.
+        err: BlockPointerEncodeError,
+    },
+
+    /// [`ChecksumTail`] encode error.
+    ChecksumTail {
+        /// Error.
+        err: ChecksumTailEncodeError,
+    },
+}
+
+impl From<BinaryEncodeError> for GangHeaderEncodeError {
+    fn from(err: BinaryEncodeError) -> Self {
+        GangHeaderEncodeError::Binary { err }
+    }
+}
+
+impl From<BlockPointerEncodeError> for GangHeaderEncodeError {
+    fn from(err: BlockPointerEncodeError) -> Self {
+        GangHeaderEncodeError::BlockPointer { err }
+    }
+}
+
+impl From<ChecksumTailEncodeError> for GangHeaderEncodeError {
+    fn from(err: ChecksumTailEncodeError) -> Self {
+        GangHeaderEncodeError::ChecksumTail { err }
+    }
+}
+
+impl fmt::Display for GangHeaderEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GangHeaderEncodeError::Binary { err } => {
+                write!(f, "GangHeader encode error | {err}")
+            }
+            GangHeaderEncodeError::BlockPointer { err } => {
+                write!(f, "GangHeader encode error | {err}")
+            }
+            GangHeaderEncodeError::ChecksumTail { err } => {
+                write!(f, "GangHeader encode error | {err}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for GangHeaderEncodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            GangHeaderEncodeError::Binary { err } => Some(err),
+            GangHeaderEncodeError::BlockPointer { err } => Some(err),
+            GangHeaderEncodeError::ChecksumTail { err } => Some(err),
+        }
+    }
+}
@@ -0,0 +1,534 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::error;
+
+use crate::phys::{
+    BinaryDecodeError, BinaryDecoder, BinaryEncodeError, BinaryEncoder, Dva, DvaDecodeError,
+    DvaEncodeError,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Indirect mapping header, in the bonus of the indirect mapping object of a
+ * [`crate::phys::VdevType::Indirect`] virtual device.
+ *
+ * When a virtual device is removed, its allocated segments are copied to
+ * the other virtual devices, and it becomes an indirect virtual device. The
+ * data of the indirect mapping object is an array of [`IndirectMappingEntry`],
+ * sorted by source offset.
+ *
+ * ### Byte layout.
+ *
+ * - Bytes: 24, or 32
+ *
+ * ```text
+ * +---------------+------+-------------+-----------------------------+
+ * | Field         | Size | SPA Version | Feature                     |
+ * +---------------+------+-------------+-----------------------------+
+ * | max offset    |    8 |        5000 | com.delphix:device_removal  |
+ * | bytes mapped  |    8 |        5000 | com.delphix:device_removal  |
+ * | entries       |    8 |        5000 | com.delphix:device_removal  |
+ * | counts object |    8 |        5000 | com.delphix:obsolete_counts |
+ * +---------------+------+-------------+-----------------------------+
+ * ```
+ */
+#[derive(Debug)]
+pub struct IndirectMappingPhys {
+    /// Byte offset of the end of the last mapped segment.
+    pub max_offset: u64,
+
+    /// Number of bytes mapped.
+    pub bytes_mapped: u64,
+
+    /// Number of [`IndirectMappingEntry`].
+    pub entries: u64,
+
+    /// Object number of the obsolete counts of each [`IndirectMappingEntry`].
+    pub counts_object: Option<u64>,
+}
+
+impl IndirectMappingPhys {
+    /// Byte size of an encoded [`IndirectMappingPhys`] without counts object.
+    pub const SIZE_V0: usize = 24;
+
+    /// Byte size of an encoded [`IndirectMappingPhys`].
+    pub const SIZE: usize = 32;
+
+    /** Decodes an [`IndirectMappingPhys`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectMappingDecodeError`] in case of decoding error.
+     */
+    pub fn from_decoder(
+        decoder: &mut dyn BinaryDecoder<'_>,
+    ) -> Result<IndirectMappingPhys, IndirectMappingDecodeError> {
+        ////////////////////////////////
+        // Decode values.
+        let max_offset = decoder.get_u64()?;
+        let bytes_mapped = decoder.get_u64()?;
+        let entries = decoder.get_u64()?;
+
+        let counts_object = if decoder.is_empty() {
+            None
+        } else {
+            Some(decoder.get_u64()?)
+        };
+
+        ////////////////////////////////
+        // Success.
+        Ok(IndirectMappingPhys {
+            max_offset,
+            bytes_mapped,
+            entries,
+            counts_object,
+        })
+    }
+
+    /** Encodes an [`IndirectMappingPhys`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectMappingEncodeError`] in case of encoding error.
+     */
+    pub fn to_encoder(
+        &self,
+        encoder: &mut dyn BinaryEncoder<'_>,
+    ) -> Result<(), IndirectMappingEncodeError> {
+        encoder.put_u64(self.max_offset)?;
+        encoder.put_u64(self.bytes_mapped)?;
+        encoder.put_u64(self.entries)?;
+
+        if let Some(counts_object) = self.counts_object {
+            encoder.put_u64(counts_object)?;
+        }
+
+        Ok(())
+    }
+}
+
+/** Indirect mapping entry.
+ *
+ * Maps a segment of a removed virtual device to a [`Dva`] on another
+ * virtual device. The size of the segment is the allocated size of the
+ * destination [`Dva`].
+ *
+ * ### Byte layout.
+ *
+ * - Bytes: 24
+ *
+ * ```text
+ * +-------------+------+
+ * | Field       | Size |
+ * +-------------+------+
+ * | source      |    8 |
+ * | destination |   16 |
+ * +-------------+------+
+ * ```
+ *
+ * ### source
+ *
+ * ```text
+ *        6                   5                   4                   3                   2                   1                   0
+ *  3 2 1 0 9 8 7 6 5 4 3 2 1 0 9 8 7 6 5 4 3 2 1 0 9 8 7 6 5 4 3 2 1 0 9 8 7 6 5 4 3 2 1 0 9 8 7 6 5 4 3 2 1 0 9 8 7 6 5 4 3 2 1 0
+ * +-------------------------------------------------------------------------------------------------------------------------------+
+ * |m|                                                     source offset (63)                                                      |
+ * +-------------------------------------------------------------------------------------------------------------------------------+
+ *
+ * m  mark, used for garbage collection by zdb
+ * ```
+ */
+#[derive(Debug)]
+pub struct IndirectMappingEntry {
+    /// Source offset in sectors (512 bytes) of the removed virtual device.
+    pub source: u64,
+
+    /// Mark, used for garbage collection by zdb.
+    pub mark: bool,
+
+    /// Destination of the segment.
+    pub destination: Dva,
+}
+
+impl IndirectMappingEntry {
+    /// Byte size of an encoded [`IndirectMappingEntry`].
+    pub const SIZE: usize = 24;
+
+    /// Mask for mark bit.
+    const MARK_MASK_BIT_FLAG: u64 = 0x8000000000000000;
+
+    /// Mask for source offset.
+    const SOURCE_MASK: u64 = (1 << 63) - 1;
+
+    /** Decodes an [`IndirectMappingEntry`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectMappingDecodeError`] in case of decoding error.
+     */
+    pub fn from_decoder(
+        decoder: &mut dyn BinaryDecoder<'_>,
+    ) -> Result<IndirectMappingEntry, IndirectMappingDecodeError> {
+        ////////////////////////////////
+        // Decode values.
+        let source = decoder.get_u64()?;
+
+        let destination = match Dva::from_decoder(decoder)? {
+            Some(v) => v,
+            None => return Err(IndirectMappingDecodeError::EmptyDestination {}),
+        };
+
+        ////////////////////////////////
+        // Success.
+        Ok(IndirectMappingEntry {
+            source: source & IndirectMappingEntry::SOURCE_MASK,
+            mark: (source & IndirectMappingEntry::MARK_MASK_BIT_FLAG) != 0,
+            destination,
+        })
+    }
+
+    /** Encodes an [`IndirectMappingEntry`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectMappingEncodeError`] in case of encoding error.
+     */
+    pub fn to_encoder(
+        &self,
+        encoder: &mut dyn BinaryEncoder<'_>,
+    ) -> Result<(), IndirectMappingEncodeError> {
+        if self.source > IndirectMappingEntry::SOURCE_MASK {
+            return Err(IndirectMappingEncodeError::InvalidSource {
+                source: self.source,
+            });
+        }
+
+        let mark = if self.mark {
+            IndirectMappingEntry::MARK_MASK_BIT_FLAG
+        } else {
+            0
+        };
+
+        encoder.put_u64(self.source | mark)?;
+        self.destination.to_encoder(encoder)?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`IndirectMappingPhys`] and [`IndirectMappingEntry`] decode error.
+#[derive(Debug)]
+pub enum IndirectMappingDecodeError {
+    /// [`BinaryDecoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// [`Dva`] decode error.
+    Dva {
+        /// Error.
+        err: DvaDecodeError,
+    },
+
+    /// Destination [`Dva`] is empty.
+    EmptyDestination {},
+}
+
+impl From<BinaryDecodeError> for IndirectMappingDecodeError {
+    fn from(err: BinaryDecodeError) -> Self {
+        IndirectMappingDecodeError::Binary { err }
+    }
+}
+
+impl From<DvaDecodeError> for IndirectMappingDecodeError {
+    fn from(err: DvaDecodeError) -> Self {
+        IndirectMappingDecodeError::Dva { err }
+    }
+}
+
+impl fmt::Display for IndirectMappingDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectMappingDecodeError::Binary { err } => {
+                write!(f, "IndirectMapping decode error | {err}")
+            }
+            IndirectMappingDecodeError::Dva { err } => {
+                write!(f, "IndirectMapping decode error | {err}")
+            }
+            IndirectMappingDecodeError::EmptyDestination {} => {
+                write!(f, "IndirectMapping decode error, empty destination")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for IndirectMappingDecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IndirectMappingDecodeError::Binary { err } => Some(err),
+            IndirectMappingDecodeError::Dva { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// [`IndirectMappingPhys`] and [`IndirectMappingEntry`] encode error.
+#[derive(Debug)]
+pub enum IndirectMappingEncodeError {
+    /// [`BinaryEncoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryEncodeError,
+    },
+
+    /// [`Dva`] encode error.
+    Dva {
+        /// Error.
+        err: DvaEncodeError,
+    },
+
+    /// Invalid source offset.
+    InvalidSource {
+        /// Source offset.
+        source: u64,
+    },
+}
+
+impl From<BinaryEncodeError> for IndirectMappingEncodeError {
+    fn from(err: BinaryEncodeError) -> Self {
+        IndirectMappingEncodeError::Binary { err }
+    }
+}
+
+impl From<DvaEncodeError> for IndirectMappingEncodeError {
+    fn from(err: DvaEncodeError) -> Self {
+        IndirectMappingEncodeError::Dva { err }
+    }
+}
+
+impl fmt::Display for IndirectMappingEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectMappingEncodeError::Binary { err } => {
+                write!(f, "IndirectMapping encode error | {err}")
+            }
+            IndirectMappingEncodeError::Dva { err } => {
+                write!(f, "IndirectMapping encode error | {err}")
+            }
+            IndirectMappingEncodeError::InvalidSource { source } => {
+                write!(f, "IndirectMapping encode error, invalid source {source}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for IndirectMappingEncodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IndirectMappingEncodeError::Binary { err } => Some(err),
+            IndirectMappingEncodeError::Dva { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/** Indirect births header, in the bonus of the indirect births object of a
+ * [`crate::phys::VdevType::Indirect`] virtual device.
+ *
+ * The data of the indirect births object is an array of
+ * [`IndirectBirthEntry`], one for each txg in which segments were copied.
+ *
+ * ### Byte layout.
+ *
+ * - Bytes: 8
+ *
+ * ```text
+ * +---------+------+
+ * | Field   | Size |
+ * +---------+------+
+ * | entries |    8 |
+ * +---------+------+
+ * ```
+ */
+#[derive(Debug)]
+pub struct IndirectBirthsPhys {
+    /// Number of [`IndirectBirthEntry`].
+    pub entries: u64,
+}
+
+impl IndirectBirthsPhys {
+    /// Byte size of an encoded [`IndirectBirthsPhys`].
+    pub const SIZE: usize = 8;
+
+    /** Decodes an [`IndirectBirthsPhys`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectBirthsDecodeError`] in case of decoding error.
+     */
+    pub fn from_decoder(
+        decoder: &mut dyn BinaryDecoder<'_>,
+    ) -> Result<IndirectBirthsPhys, IndirectBirthsDecodeError> {
+        Ok(IndirectBirthsPhys {
+            entries: decoder.get_u64()?,
+        })
+    }
+
+    /** Encodes an [`IndirectBirthsPhys`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectBirthsEncodeError`] in case of encoding error.
+     */
+    pub fn to_encoder(
+        &self,
+        encoder: &mut dyn BinaryEncoder<'_>,
+    ) -> Result<(), IndirectBirthsEncodeError> {
+        Ok(encoder.put_u64(self.entries)?)
+    }
+}
+
+/** Indirect birth entry.
+ *
+ * Segments of the removed virtual device below `offset` were copied in
+ * txg `birth_txg` or earlier.
+ *
+ * ### Byte layout.
+ *
+ * - Bytes: 16
+ *
+ * ```text
+ * +-----------+------+
+ * | Field     | Size |
+ * +-----------+------+
+ * | offset    |    8 |
+ * | birth txg |    8 |
+ * +-----------+------+
+ * ```
+ */
+#[derive(Debug)]
+pub struct IndirectBirthEntry {
+    /// Byte offset of the removed virtual device.
+    pub offset: u64,
+
+    /// Physical birth txg of the copies.
+    pub birth_txg: u64,
+}
+
+impl IndirectBirthEntry {
+    /// Byte size of an encoded [`IndirectBirthEntry`].
+    pub const SIZE: usize = 16;
+
+    /** Decodes an [`IndirectBirthEntry`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectBirthsDecodeError`] in case of decoding error.
+     */
+    pub fn from_decoder(
+        decoder: &mut dyn BinaryDecoder<'_>,
+    ) -> Result<IndirectBirthEntry, IndirectBirthsDecodeError> {
+        Ok(IndirectBirthEntry {
+            offset: decoder.get_u64()?,
+            birth_txg: decoder.get_u64()?,
+        })
+    }
+
+    /** Encodes an [`IndirectBirthEntry`].
+     *
+     * # Errors
+     *
+     * Returns [`IndirectBirthsEncodeError`] in case of encoding error.
+     */
+    pub fn to_encoder(
+        &self,
+        encoder: &mut dyn BinaryEncoder<'_>,
+    ) -> Result<(), IndirectBirthsEncodeError> {
+        encoder.put_u64(self.offset)?;
+        encoder.put_u64(self.birth_txg)?;
+
+        Ok(())
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`IndirectBirthsPhys`] and [`IndirectBirthEntry`] decode error.
+#[derive(Debug)]
+pub enum IndirectBirthsDecodeError {
+    /// [`BinaryDecoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+}
+
+impl From<BinaryDecodeError> for IndirectBirthsDecodeError {
+    fn from(err: BinaryDecodeError) -> Self {
+        IndirectBirthsDecodeError::Binary { err }
+    }
+}
+
+impl fmt::Display for IndirectBirthsDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectBirthsDecodeError::Binary { err } => {
+                write!(f, "IndirectBirths decode error | {err}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for IndirectBirthsDecodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IndirectBirthsDecodeError::Binary { err } => Some(err),
+        }
+    }
+}
+
+/// [`IndirectBirthsPhys`] and [`IndirectBirthEntry`] encode error.
+#[derive(Debug)]
+pub enum IndirectBirthsEncodeError {
+    /// [`BinaryEncoder`] error.
+    Binary {
+        /// Error.
+        err: BinaryEncodeError,
+    },
+}
+
+impl From<BinaryEncodeError> for IndirectBirthsEncodeError {
+    fn from(err: BinaryEncodeError) -> Self {
+        IndirectBirthsEncodeError::Binary { err }
+    }
+}
+
+impl fmt::Display for IndirectBirthsEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndirectBirthsEncodeError::Binary { err } => {
+                write!(f, "IndirectBirths encode error | {err}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for IndirectBirthsEncodeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IndirectBirthsEncodeError::Binary { err } => Some(err),
+        }
+    }
+}
@@ -1535,15 +1535,24 @@ pub enum LabelVdevTreeType<'a> {
 
     /// [`VdevType::RaidZ`].
     RaidZ(LabelVdevTreeRaidZ<'a>),
+
+    /// [`VdevType::DRaid`].
+    DRaid(LabelVdevTreeDRaid<'a>),
+
+    /// [`VdevType::Indirect`].
+    Indirect(LabelVdevTreeIndirect),
 }
 
 impl<'a> LabelVdevTreeType<'a> {
     /// Gets the children from the vdev tree.
     pub fn children(&self) -> Option<NvArray<'a, NvList<'a>>> {
         match self {
-            LabelVdevTreeType::Disk(_) | LabelVdevTreeType::File(_) => None,
+            LabelVdevTreeType::Disk(_)
+            | LabelVdevTreeType::File(_)
+            | LabelVdevTreeType::Indirect(_) => None,
             LabelVdevTreeType::Mirror(mirror) => Some(mirror.children),
             LabelVdevTreeType::RaidZ(raidz) => Some(raidz.children),
+            LabelVdevTreeType::DRaid(draid) => Some(draid.children),
         }
     }
 }
@@ -1563,10 +1572,12 @@ pub struct LabelVdevTree<'a> {
     /// [`VdevTreeKey::Id`].
     pub id: u64,
 
-    /// [`VdevTreeKey::MetaSlabArray`].
+    /// [`VdevTreeKey::MetaSlabArray`], or 0 for a [`VdevType::Indirect`]
+    /// without metaslabs.
     pub metaslab_array: u64,
 
-    /// [`VdevTreeKey::MetaSlabShift`].
+    /// [`VdevTreeKey::MetaSlabShift`], or 0 for a [`VdevType::Indirect`]
+    /// without metaslabs.
     pub metaslab_shift: u64,
 
     /// [`VdevTreeKey::VdevType`].
@@ -1577,19 +1588,31 @@ pub struct LabelVdevTree<'a> {
 
     /// [`VdevTreeKey::IsLog`].
     pub is_log: Option<bool>,
+
+    /// [`VdevTreeKey::Dtl`].
+    pub dtl: Option<u64>,
+
+    /// [`VdevTreeKey::LeafZap`].
+    pub leaf_zap: Option<u64>,
+
+    /// [`VdevTreeKey::TopZap`].
+    pub top_zap: Option<u64>,
 }
 
 impl LabelVdevTree<'_> {
     /// Expected [`VdevTreeKey`] values in [`VdevTreeKey`].
-    const EXPECTED: [VdevTreeKey; 9] = [
+    const EXPECTED: [VdevTreeKey; 12] = [
         VdevTreeKey::AllocateShift,
         VdevTreeKey::AllocateSize,
         VdevTreeKey::CreateTxg,
+        VdevTreeKey::Dtl,
         VdevTreeKey::Guid,
         VdevTreeKey::Id,
         VdevTreeKey::IsLog,
+        VdevTreeKey::LeafZap,
         VdevTreeKey::MetaSlabArray,
         VdevTreeKey::MetaSlabShift,
+        VdevTreeKey::TopZap,
         VdevTreeKey::VdevType,
     ];
 
@@ -1634,32 +1657,16 @@ impl LabelVdevTree<'_> {
             None => return Err(LabelVdevTreeDecodeError::Missing { name: id_str }),
         };
 
-        let metaslab_array_str = VdevTreeKey::MetaSlabArray.into();
-        let metaslab_array = match list.get_u64(metaslab_array_str)? {
-            Some(v) => v,
-            None => {
-                return Err(LabelVdevTreeDecodeError::Missing {
-                    name: metaslab_array_str,
-                })
-            }
-        };
-
-        let metaslab_shift_str = VdevTreeKey::MetaSlabShift.into();
-        let metaslab_shift = match list.get_u64(metaslab_shift_str)? {
-            Some(v) => v,
-            None => {
-                return Err(LabelVdevTreeDecodeError::Missing {
-                    name: metaslab_shift_str,
-                })
-            }
-        };
-
         ////////////////////////////////
         // Decode optional values.
         let create_txg = list.get_u64(VdevTreeKey::CreateTxg.into())?;
 
         let is_log = list.get_u64(VdevTreeKey::IsLog.into())?.map(|v| v != 0);
 
+        let dtl = list.get_u64(VdevTreeKey::Dtl.into())?;
+        let leaf_zap = list.get_u64(VdevTreeKey::LeafZap.into())?;
+        let top_zap = list.get_u64(VdevTreeKey::TopZap.into())?;
+
         ////////////////////////////////
         // Decode type.
         let vdev_type_str = VdevTreeKey::VdevType.into();
@@ -1698,9 +1705,44 @@ impl LabelVdevTree<'_> {
                 vdev_type_expected_fields = &LabelVdevTreeRaidZ::EXPECTED;
                 LabelVdevTreeType::RaidZ(LabelVdevTreeRaidZ::from_list(list)?)
             }
+            VdevType::DRaid => {
+                vdev_type_expected_fields = &LabelVdevTreeDRaid::EXPECTED;
+                LabelVdevTreeType::DRaid(LabelVdevTreeDRaid::from_list(list)?)
+            }
+            VdevType::Indirect => {
+                vdev_type_expected_fields = &LabelVdevTreeIndirect::EXPECTED;
+                LabelVdevTreeType::Indirect(LabelVdevTreeIndirect::from_list(list)?)
+            }
             _ => return Err(LabelVdevTreeDecodeError::UnsupportedVdevType { vdev_type }),
         };
 
+        ////////////////////////////////
+        // Decode metaslab values, that are removed from an indirect vdev when
+        // its metaslabs are freed.
+        let metaslabs_optional = matches!(vdev_type, LabelVdevTreeType::Indirect(_));
+
+        let metaslab_array_str = VdevTreeKey::MetaSlabArray.into();
+        let metaslab_array = match list.get_u64(metaslab_array_str)? {
+            Some(v) => v,
+            None if metaslabs_optional => 0,
+            None => {
+                return Err(LabelVdevTreeDecodeError::Missing {
+                    name: metaslab_array_str,
+                })
+            }
+        };
+
+        let metaslab_shift_str = VdevTreeKey::MetaSlabShift.into();
+        let metaslab_shift = match list.get_u64(metaslab_shift_str)? {
+            Some(v) => v,
+            None if metaslabs_optional => 0,
+            None => {
+                return Err(LabelVdevTreeDecodeError::Missing {
+                    name: metaslab_shift_str,
+                })
+            }
+        };
+
         ////////////////////////////////
         // Check for unknown values.
         for pair_res in list {
@@ -1736,6 +1778,9 @@ impl LabelVdevTree<'_> {
             vdev_type,
             create_txg,
             is_log,
+            dtl,
+            leaf_zap,
+            top_zap,
         })
     }
 }
@@ -1936,7 +1981,169 @@ impl LabelVdevTreeRaidZ<'_> {
 
 ////////////////////////////////////////////////////////////////////////////////
 
-/// A child entry from the `children` array of a a [`VdevType::Mirror`] or [`VdevType::RaidZ`].
+/// [`VdevType::DRaid`] fields of a [`LabelVdevTree`].
+pub struct LabelVdevTreeDRaid<'a> {
+    /// [`VdevTreeKey::Children`].
+    pub children: NvArray<'a, NvList<'a>>,
+
+    /// [`VdevTreeKey::DRaidNData`].
+    pub data: u64,
+
+    /// [`VdevTreeKey::DRaidNGroups`].
+    pub groups: u64,
+
+    /// [`VdevTreeKey::NParity`].
+    pub parity: u64,
+
+    /// [`VdevTreeKey::DRaidNSpares`].
+    pub spares: u64,
+}
+
+impl fmt::Debug for LabelVdevTreeDRaid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Change debug printing to print length instead of raw data.
+        f.debug_struct("LabelVdevTreeDRaid")
+            .field("count", &self.children.len())
+            .field("data", &self.data)
+            .field("groups", &self.groups)
+            .field("parity", &self.parity)
+            .field("spares", &self.spares)
+            .finish()
+    }
+}
+
+impl LabelVdevTreeDRaid<'_> {
+    /// Expected [`VdevTreeKey`] values in [`LabelVdevTreeDRaid`].
+    const EXPECTED: [VdevTreeKey; 5] = [
+        VdevTreeKey::Children,
+        VdevTreeKey::DRaidNData,
+        VdevTreeKey::DRaidNGroups,
+        VdevTreeKey::DRaidNSpares,
+        VdevTreeKey::NParity,
+    ];
+
+    /** Decodes a [`LabelVdevTreeDRaid`].
+     *
+     * # Errors
+     *
+     * Returns [`LabelVdevTreeDecodeError`] in case of decoding error.
+     */
+    pub fn from_list<'a>(
+        list: &NvList<'a>,
+    ) -> Result<LabelVdevTreeDRaid<'a>, LabelVdevTreeDecodeError> {
+        ////////////////////////////////
+        // Decode required values.
+        let children_str = VdevTreeKey::Children.into();
+        let children = match list.get_nv_list_array(children_str)? {
+            Some(v) => v,
+            None => return Err(LabelVdevTreeDecodeError::Missing { name: children_str }),
+        };
+
+        let data_str = VdevTreeKey::DRaidNData.into();
+        let data = match list.get_u64(data_str)? {
+            Some(v) => v,
+            None => return Err(LabelVdevTreeDecodeError::Missing { name: data_str }),
+        };
+
+        let groups_str = VdevTreeKey::DRaidNGroups.into();
+        let groups = match list.get_u64(groups_str)? {
+            Some(v) => v,
+            None => return Err(LabelVdevTreeDecodeError::Missing { name: groups_str }),
+        };
+
+        let parity_str = VdevTreeKey::NParity.into();
+        let parity = match list.get_u64(parity_str)? {
+            Some(v) => v,
+            None => return Err(LabelVdevTreeDecodeError::Missing { name: parity_str }),
+        };
+
+        let spares_str = VdevTreeKey::DRaidNSpares.into();
+        let spares = match list.get_u64(spares_str)? {
+            Some(v) => v,
+            None => return Err(LabelVdevTreeDecodeError::Missing { name: spares_str }),
+        };
+
+        ////////////////////////////////
+        // Success.
+        Ok(LabelVdevTreeDRaid {
+            children,
+            data,
+            groups,
+            parity,
+            spares,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`VdevType::Indirect`] fields of a [`LabelVdevTree`].
+#[derive(Debug)]
+pub struct LabelVdevTreeIndirect {
+    /// [`VdevTreeKey::IndirectBirths`].
+    pub indirect_births: u64,
+
+    /// [`VdevTreeKey::IndirectObject`].
+    pub indirect_object: u64,
+
+    /// [`VdevTreeKey::PrevIndirectVdev`].
+    pub prev_indirect_vdev: Option<u64>,
+}
+
+impl LabelVdevTreeIndirect {
+    /// Expected [`VdevTreeKey`] values in [`LabelVdevTreeIndirect`].
+    const EXPECTED: [VdevTreeKey; 3] = [
+        VdevTreeKey::IndirectBirths,
+        VdevTreeKey::IndirectObject,
+        VdevTreeKey::PrevIndirectVdev,
+    ];
+
+    /** Decodes a [`LabelVdevTreeIndirect`].
+     *
+     * # Errors
+     *
+     * Returns [`LabelVdevTreeDecodeError`] in case of decoding error.
+     */
+    pub fn from_list(list: &NvList<'_>) -> Result<LabelVdevTreeIndirect, LabelVdevTreeDecodeError> {
+        ////////////////////////////////
+        // Decode required values.
+        let indirect_births_str = VdevTreeKey::IndirectBirths.into();
+        let indirect_births = match list.get_u64(indirect_births_str)? {
+            Some(v) => v,
+            None => {
+                return Err(LabelVdevTreeDecodeError::Missing {
+                    name: indirect_births_str,
+                })
+            }
+        };
+
+        let indirect_object_str = VdevTreeKey::IndirectObject.into();
+        let indirect_object = match list.get_u64(indirect_object_str)? {
+            Some(v) => v,
+            None => {
+                return Err(LabelVdevTreeDecodeError::Missing {
+                    name: indirect_object_str,
+                })
+            }
+        };
+
+        ////////////////////////////////
+        // Decode optional values.
+        let prev_indirect_vdev = list.get_u64(VdevTreeKey::PrevIndirectVdev.into())?;
+
+        ////////////////////////////////
+        // Success.
+        Ok(LabelVdevTreeIndirect {
+            indirect_births,
+            indirect_object,
+            prev_indirect_vdev,
+        })
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A child entry from the `children` array of a a [`VdevType::Mirror`], [`VdevType::RaidZ`], or [`VdevType::DRaid`].
 #[derive(Debug)]
 pub struct LabelVdevChild<'a> {
     /// [`PoolConfigKey::Guid`]
@@ -1953,16 +2160,36 @@ pub struct LabelVdevChild<'a> {
 
     /// [`VdevTreeKey::CreateTxg`].
     pub create_txg: Option<u64>,
+
+    /// [`VdevTreeKey::DevId`].
+    pub dev_id: Option<&'a str>,
+
+    /// [`VdevTreeKey::Dtl`].
+    pub dtl: Option<u64>,
+
+    /// [`VdevTreeKey::LeafZap`].
+    pub leaf_zap: Option<u64>,
+
+    /// [`VdevTreeKey::PhysPath`].
+    pub phys_path: Option<&'a str>,
+
+    /// [`VdevTreeKey::WholeDisk`].
+    pub whole_disk: Option<bool>,
 }
 
 impl LabelVdevChild<'_> {
     /// Expected [`VdevTreeKey`] values in [`LabelVdevChild`].
-    const EXPECTED: [VdevTreeKey; 5] = [
+    const EXPECTED: [VdevTreeKey; 10] = [
         VdevTreeKey::CreateTxg,
+        VdevTreeKey::DevId,
+        VdevTreeKey::Dtl,
         VdevTreeKey::Guid,
         VdevTreeKey::Id,
+        VdevTreeKey::LeafZap,
         VdevTreeKey::Path,
+        VdevTreeKey::PhysPath,
         VdevTreeKey::VdevType,
+        VdevTreeKey::WholeDisk,
     ];
 
     /** Decodes a [`LabelVdevChild`].
@@ -2014,6 +2241,12 @@ impl LabelVdevChild<'_> {
         ////////////////////////////////
         // Decode optional values.
         let create_txg = list.get_u64(VdevTreeKey::CreateTxg.into())?;
+        let dev_id = list.get_str(VdevTreeKey::DevId.into())?;
+        let dtl = list.get_u64(VdevTreeKey::Dtl.into())?;
+        let leaf_zap = list.get_u64(VdevTreeKey::LeafZap.into())?;
+        let phys_path = list.get_str(VdevTreeKey::PhysPath.into())?;
+
+        let whole_disk = list.get_u64(VdevTreeKey::WholeDisk.into())?.map(|v| v != 0);
 
         ////////////////////////////////
         // Check for unknown values.
@@ -2044,6 +2277,11 @@ impl LabelVdevChild<'_> {
             path,
             vdev_type,
             create_txg,
+            dev_id,
+            dtl,
+            leaf_zap,
+            phys_path,
+            whole_disk,
         })
     }
 }
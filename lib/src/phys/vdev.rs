@@ -218,7 +218,8 @@ pub enum VdevTreeKey {
      */
     AllocateSize,
 
-    /** Array of children for a [`VdevType::Mirror`], or [`VdevType::RaidZ`].
+    /** Array of children for a [`VdevType::Mirror`], [`VdevType::RaidZ`], or
+     * [`VdevType::DRaid`].
      *
      * - [`crate::phys::NvPair`] name: `children`
      * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::NvListArray`]
@@ -239,6 +240,34 @@ pub enum VdevTreeKey {
      */
     DevId,
 
+    /** Number of data children in each redundancy group of a [`VdevType::DRaid`].
+     *
+     * - [`crate::phys::NvPair`] name: `draid_ndata`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    DRaidNData,
+
+    /** Number of redundancy groups in each slice of a [`VdevType::DRaid`].
+     *
+     * - [`crate::phys::NvPair`] name: `draid_ngroups`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    DRaidNGroups,
+
+    /** Number of distributed spares of a [`VdevType::DRaid`].
+     *
+     * - [`crate::phys::NvPair`] name: `draid_nspares`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    DRaidNSpares,
+
+    /** Object number of the dirty time log of a leaf vdev.
+     *
+     * - [`crate::phys::NvPair`] name: `DTL`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    Dtl,
+
     /** Device GUID.
      *
      * - [`crate::phys::NvPair`] name: `guid`
@@ -262,6 +291,26 @@ pub enum VdevTreeKey {
      */
     Id,
 
+    /** Object number of the births of an indirect mapping.
+     *
+     * - [`crate::phys::NvPair`] name: `com.delphix:indirect_births`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     *
+     * - MOS object with [`crate::phys::IndirectBirthsPhys`] bonus.
+     * - Only set for [`VdevType::Indirect`].
+     */
+    IndirectBirths,
+
+    /** Object number of an indirect mapping.
+     *
+     * - [`crate::phys::NvPair`] name: `com.delphix:indirect_object`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     *
+     * - MOS object with [`crate::phys::IndirectMappingPhys`] bonus.
+     * - Only set for [`VdevType::Indirect`].
+     */
+    IndirectObject,
+
     /** Is this device a ZFS log.
      *
      * - [`crate::phys::NvPair`] name: `is_log`
@@ -272,6 +321,13 @@ pub enum VdevTreeKey {
      */
     IsLog,
 
+    /** Object number of the ZAP of a leaf vdev.
+     *
+     * - [`crate::phys::NvPair`] name: `com.delphix:vdev_zap_leaf`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    LeafZap,
+
     /** MOS object number of [`crate::phys::DmuType::ObjectArray`] metaslab array.
      *
      * - [`crate::phys::NvPair`] name: `metaslab_array`
@@ -286,7 +342,7 @@ pub enum VdevTreeKey {
      */
     MetaSlabShift,
 
-    /** Parity for [`VdevType::RaidZ`].
+    /** Parity for [`VdevType::RaidZ`], or [`VdevType::DRaid`].
      *
      * - [`crate::phys::NvPair`] name: `nparity`
      * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
@@ -298,6 +354,13 @@ pub enum VdevTreeKey {
      */
     NParity,
 
+    /** Id of the previously removed [`VdevType::Indirect`].
+     *
+     * - [`crate::phys::NvPair`] name: `com.delphix:prev_indirect_vdev`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    PrevIndirectVdev,
+
     /** Path to [`VdevType::Disk`], [`VdevType::File`].
      *
      * - [`crate::phys::NvPair`] name: `path`
@@ -312,6 +375,13 @@ pub enum VdevTreeKey {
      */
     PhysPath,
 
+    /** Object number of the ZAP of a top level vdev.
+     *
+     * - [`crate::phys::NvPair`] name: `com.delphix:vdev_zap_top`
+     * - [`crate::phys::NvPair`] value: [`crate::phys::NvDataType::Uint64`]
+     */
+    TopZap,
+
     /** [`VdevType`].
      *
      * - [`crate::phys::NvPair`] name: `type`
@@ -331,19 +401,28 @@ pub enum VdevTreeKey {
 
 impl VdevTreeKey {
     /// All known NV names
-    const ALL: [VdevTreeKey; 14] = [
+    const ALL: [VdevTreeKey; 23] = [
         VdevTreeKey::AllocateShift,
         VdevTreeKey::AllocateSize,
         VdevTreeKey::Children,
         VdevTreeKey::CreateTxg,
         VdevTreeKey::DevId,
+        VdevTreeKey::DRaidNData,
+        VdevTreeKey::DRaidNGroups,
+        VdevTreeKey::DRaidNSpares,
+        VdevTreeKey::Dtl,
         VdevTreeKey::Guid,
         VdevTreeKey::Id,
+        VdevTreeKey::IndirectBirths,
+        VdevTreeKey::IndirectObject,
         VdevTreeKey::IsLog,
+        VdevTreeKey::LeafZap,
         VdevTreeKey::MetaSlabArray,
         VdevTreeKey::MetaSlabShift,
         VdevTreeKey::Path,
         VdevTreeKey::PhysPath,
+        VdevTreeKey::PrevIndirectVdev,
+        VdevTreeKey::TopZap,
         VdevTreeKey::VdevType,
         VdevTreeKey::WholeDisk,
     ];
@@ -369,14 +448,23 @@ impl From<VdevTreeKey> for &'static str {
             VdevTreeKey::Children => "children",
             VdevTreeKey::CreateTxg => "create_txg",
             VdevTreeKey::DevId => "devid",
+            VdevTreeKey::DRaidNData => "draid_ndata",
+            VdevTreeKey::DRaidNGroups => "draid_ngroups",
+            VdevTreeKey::DRaidNSpares => "draid_nspares",
+            VdevTreeKey::Dtl => "DTL",
             VdevTreeKey::Guid => "guid",
             VdevTreeKey::Id => "id",
+            VdevTreeKey::IndirectBirths => "com.delphix:indirect_births",
+            VdevTreeKey::IndirectObject => "com.delphix:indirect_object",
             VdevTreeKey::IsLog => "is_log",
+            VdevTreeKey::LeafZap => "com.delphix:vdev_zap_leaf",
             VdevTreeKey::MetaSlabArray => "metaslab_array",
             VdevTreeKey::MetaSlabShift => "metaslab_shift",
             VdevTreeKey::NParity => "nparity",
             VdevTreeKey::Path => "path",
             VdevTreeKey::PhysPath => "phys_path",
+            VdevTreeKey::PrevIndirectVdev => "com.delphix:prev_indirect_vdev",
+            VdevTreeKey::TopZap => "com.delphix:vdev_zap_top",
             VdevTreeKey::VdevType => "type",
             VdevTreeKey::WholeDisk => "whole_disk",
         }
@@ -399,14 +487,23 @@ impl TryFrom<&str> for VdevTreeKey {
             "create_txg" => Ok(VdevTreeKey::CreateTxg),
             "children" => Ok(VdevTreeKey::Children),
             "devid" => Ok(VdevTreeKey::DevId),
+            "draid_ndata" => Ok(VdevTreeKey::DRaidNData),
+            "draid_ngroups" => Ok(VdevTreeKey::DRaidNGroups),
+            "draid_nspares" => Ok(VdevTreeKey::DRaidNSpares),
+            "DTL" => Ok(VdevTreeKey::Dtl),
             "guid" => Ok(VdevTreeKey::Guid),
             "id" => Ok(VdevTreeKey::Id),
+            "com.delphix:indirect_births" => Ok(VdevTreeKey::IndirectBirths),
+            "com.delphix:indirect_object" => Ok(VdevTreeKey::IndirectObject),
             "is_log" => Ok(VdevTreeKey::IsLog),
+            "com.delphix:vdev_zap_leaf" => Ok(VdevTreeKey::LeafZap),
             "metaslab_array" => Ok(VdevTreeKey::MetaSlabArray),
             "metaslab_shift" => Ok(VdevTreeKey::MetaSlabShift),
             "nparity" => Ok(VdevTreeKey::NParity),
             "path" => Ok(VdevTreeKey::Path),
             "phys_path" => Ok(VdevTreeKey::PhysPath),
+            "com.delphix:prev_indirect_vdev" => Ok(VdevTreeKey::PrevIndirectVdev),
+            "com.delphix:vdev_zap_top" => Ok(VdevTreeKey::TopZap),
             "type" => Ok(VdevTreeKey::VdevType),
             "whole_disk" => Ok(VdevTreeKey::WholeDisk),
             _ => Err(VdevTreeKeyDecodeError::Unknown {}),
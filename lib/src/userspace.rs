@@ -1,4 +1,17 @@
 // SPDX-License-Identifier: GPL-2.0 OR MIT
 
 pub(crate) mod block_device;
-pub use block_device::{BlockDevice, BlockDeviceOpenError, BlockDeviceReadError};
+pub use block_device::{
+    BlockDevice, BlockDeviceFile, BlockDeviceMemory, BlockDeviceOffset, BlockDeviceOpenError,
+    BlockDeviceReadError, BlockDeviceWriteError,
+};
+
+pub(crate) mod partition;
+pub use partition::{
+    Partition, PartitionError, PartitionGuid, PartitionTable, PartitionTableType, PartitionType,
+    PARTITION_GUID_FREEBSD_ZFS, PARTITION_GUID_SOLARIS_RESERVED, PARTITION_GUID_ZFS,
+    PARTITION_MBR_GPT_PROTECTIVE, PARTITION_MBR_SOLARIS,
+};
+
+pub(crate) mod scan;
+pub use scan::{PoolScanner, ScanDevice, ScanDeviceState, ScanError, ScanPool, ScanVdev};
@@ -1,101 +1,209 @@
 // SPDX-License-Identifier: GPL-2.0 OR MIT
 
+/*! Block devices.
+ *
+ * - A [`BlockDevice`] reads, and optionally writes, whole sectors.
+ * - [`BlockDeviceFile`] is a regular file or block device.
+ * - [`BlockDeviceMemory`] is an in-memory buffer, for tests and fuzzing.
+ * - [`BlockDeviceOffset`] is a window of another [`BlockDevice`], such as a
+ *   partition.
+ */
 use core::fmt;
 use std::error;
 
 use std::fs;
 use std::io;
+use std::io::Seek;
 use std::os::unix::fs::FileExt;
+use std::sync::RwLock;
 
 use crate::phys::{is_multiple_of_sector_size, SECTOR_SHIFT};
 
-/// A block device interface to a file.
+////////////////////////////////////////////////////////////////////////////////
+
+/// A block device interface.
+pub trait BlockDevice: fmt::Debug {
+    /// Gets the size in sectors.
+    fn sectors(&self) -> u64;
+
+    /** Read the data starting at sector.
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceReadError`] in case of error.
+     */
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError>;
+
+    /** Write the data starting at sector.
+     *
+     * The default implementation is read only.
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceWriteError`] in case of error.
+     */
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        Err(BlockDeviceWriteError::ReadOnly {
+            sector,
+            size: data.len(),
+        })
+    }
+}
+
+impl<D: BlockDevice + ?Sized> BlockDevice for &D {
+    fn sectors(&self) -> u64 {
+        (**self).sectors()
+    }
+
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
+        (**self).read(data, sector)
+    }
+
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        (**self).write(data, sector)
+    }
+}
+
+impl<D: BlockDevice + ?Sized> BlockDevice for Box<D> {
+    fn sectors(&self) -> u64 {
+        (**self).sectors()
+    }
+
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
+        (**self).read(data, sector)
+    }
+
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        (**self).write(data, sector)
+    }
+}
+
+/** Computes the byte offset of `size` bytes at `sector`, for a device of
+ * `sectors`.
+ *
+ * Returns [`None`] if the size is not a multiple of the sector size, or the
+ * range is out of bounds.
+ */
+fn byte_offset(size: usize, sector: u64, sectors: u64) -> Option<u64> {
+    ////////////////////////////////
+    // Check data is a multiple of sector.
+    if !is_multiple_of_sector_size(size) {
+        return None;
+    }
+
+    ////////////////////////////////
+    // Compute number of sectors.
+    let sector_count = u64::try_from(size >> SECTOR_SHIFT).ok()?;
+
+    ////////////////////////////////
+    // Check bounds.
+    if sector > sectors || sectors - sector < sector_count {
+        return None;
+    }
+
+    ////////////////////////////////
+    // Compute offset in bytes.
+    sector.checked_shl(SECTOR_SHIFT)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// A [`BlockDevice`] of a file.
 #[derive(Debug)]
-pub struct BlockDevice {
+pub struct BlockDeviceFile {
     /// Block device file.
     pub file: fs::File,
 
     /// Size of file in sectors.
     pub sectors: u64,
+
+    /// Is the file opened for writing.
+    writable: bool,
 }
 
-impl BlockDevice {
-    /** Open the path as a block device.
+impl BlockDeviceFile {
+    /** Open the path as a read only block device.
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceOpenError`] in case of error.
+     */
+    pub fn open(path: &str) -> Result<BlockDeviceFile, BlockDeviceOpenError> {
+        BlockDeviceFile::open_options(path, false)
+    }
+
+    /** Open the path as a read write block device.
      *
      * # Errors
      *
      * Returns [`BlockDeviceOpenError`] in case of error.
      */
-    pub fn open(path: &str) -> Result<BlockDevice, BlockDeviceOpenError> {
+    pub fn open_writable(path: &str) -> Result<BlockDeviceFile, BlockDeviceOpenError> {
+        BlockDeviceFile::open_options(path, true)
+    }
+
+    /// Open the path as a block device.
+    fn open_options(path: &str, writable: bool) -> Result<BlockDeviceFile, BlockDeviceOpenError> {
         ////////////////////////////////////
         // Open file.
-        let file = match fs::OpenOptions::new().read(true).open(path) {
+        let file = match fs::OpenOptions::new().read(true).write(writable).open(path) {
             Ok(v) => v,
             Err(err) => return Err(BlockDeviceOpenError::OpenError { err }),
         };
 
         ////////////////////////////////////
-        // Get file size.
+        // Get file size. The size of a block device is its end offset.
         let metadata = match file.metadata() {
             Ok(v) => v,
             Err(err) => return Err(BlockDeviceOpenError::MetadataError { err }),
         };
 
-        let size = metadata.len();
+        let size = if metadata.is_file() {
+            metadata.len()
+        } else {
+            match (&file).seek(io::SeekFrom::End(0)) {
+                Ok(v) => v,
+                Err(err) => return Err(BlockDeviceOpenError::MetadataError { err }),
+            }
+        };
+
         if !is_multiple_of_sector_size(size) {
             return Err(BlockDeviceOpenError::InvalidSize { size });
         }
 
         ////////////////////////////////////
         // Success.
-        Ok(BlockDevice {
+        Ok(BlockDeviceFile {
             file,
             sectors: size >> SECTOR_SHIFT,
+            writable,
         })
     }
+}
 
-    /** Read the data starting at sector.
-     *
-     * # Errors
-     *
-     * Returns [`BlockDeviceReadError`] in case of error.
-     */
-    pub fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
-        let size = data.len();
-
-        ////////////////////////////////
-        // Check destination data is a multiple of sector.
-        if !is_multiple_of_sector_size(size) {
-            return Err(BlockDeviceReadError::InvalidRead { sector, size });
-        }
-
-        ////////////////////////////////
-        // Compute number of sectors.
-        let sector_count = match u64::try_from(size >> SECTOR_SHIFT) {
-            Ok(v) => v,
-            Err(_) => return Err(BlockDeviceReadError::InvalidRead { sector, size }),
-        };
+impl BlockDevice for BlockDeviceFile {
+    fn sectors(&self) -> u64 {
+        self.sectors
+    }
 
-        ////////////////////////////////
-        // Check bounds.
-        if sector > self.sectors || self.sectors - sector < sector_count {
-            return Err(BlockDeviceReadError::InvalidRead { sector, size });
-        }
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
+        let size = data.len();
 
-        ////////////////////////////////
-        // Compute offset in bytes for read_at.
-        let offset = match sector.checked_shl(SECTOR_SHIFT) {
+        let mut offset = match byte_offset(size, sector, self.sectors) {
             Some(v) => v,
             None => return Err(BlockDeviceReadError::InvalidRead { sector, size }),
         };
 
         ////////////////////////////////
         // Read bytes, while handling short reads.
-        let mut offset = offset;
         let mut data: &mut [u8] = data;
 
         while !data.is_empty() {
             let read = match self.file.read_at(data, offset) {
+                Ok(0) => {
+                    let err = io::Error::from(io::ErrorKind::UnexpectedEof);
+                    return Err(BlockDeviceReadError::IoError { err, sector, size });
+                }
                 Ok(v) => v,
                 Err(err) => return Err(BlockDeviceReadError::IoError { err, sector, size }),
             };
@@ -109,8 +217,175 @@ impl BlockDevice {
         // Success.
         Ok(())
     }
+
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        let size = data.len();
+
+        if !self.writable {
+            return Err(BlockDeviceWriteError::ReadOnly { sector, size });
+        }
+
+        let offset = match byte_offset(size, sector, self.sectors) {
+            Some(v) => v,
+            None => return Err(BlockDeviceWriteError::InvalidWrite { sector, size }),
+        };
+
+        match self.file.write_all_at(data, offset) {
+            Ok(()) => Ok(()),
+            Err(err) => Err(BlockDeviceWriteError::IoError { err, sector, size }),
+        }
+    }
+}
+
+/// An in-memory [`BlockDevice`].
+#[derive(Debug)]
+pub struct BlockDeviceMemory {
+    /// Bytes.
+    bytes: RwLock<Vec<u8>>,
+
+    /// Size in sectors.
+    sectors: u64,
+}
+
+impl BlockDeviceMemory {
+    /** Create a [`BlockDeviceMemory`] of the `bytes`.
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceOpenError`] if the size is not a multiple of the
+     * sector size.
+     */
+    pub fn new(bytes: Vec<u8>) -> Result<BlockDeviceMemory, BlockDeviceOpenError> {
+        let size = bytes.len() as u64;
+        if !is_multiple_of_sector_size(size) {
+            return Err(BlockDeviceOpenError::InvalidSize { size });
+        }
+
+        Ok(BlockDeviceMemory {
+            bytes: RwLock::new(bytes),
+            sectors: size >> SECTOR_SHIFT,
+        })
+    }
+
+    /// Takes the bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl BlockDevice for BlockDeviceMemory {
+    fn sectors(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
+        let size = data.len();
+
+        let start = match byte_offset(size, sector, self.sectors) {
+            Some(v) => v as usize,
+            None => return Err(BlockDeviceReadError::InvalidRead { sector, size }),
+        };
+
+        let bytes = self.bytes.read().unwrap_or_else(|err| err.into_inner());
+        data.copy_from_slice(&bytes[start..start + size]);
+
+        Ok(())
+    }
+
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        let size = data.len();
+
+        let start = match byte_offset(size, sector, self.sectors) {
+            Some(v) => v as usize,
+            None => return Err(BlockDeviceWriteError::InvalidWrite { sector, size }),
+        };
+
+        let mut bytes = self.bytes.write().unwrap_or_else(|err| err.into_inner());
+        bytes[start..start + size].copy_from_slice(data);
+
+        Ok(())
+    }
+}
+
+/// A window of another [`BlockDevice`], starting at a byte offset.
+#[derive(Debug)]
+pub struct BlockDeviceOffset<D: BlockDevice> {
+    /// Block device.
+    pub device: D,
+
+    /// Offset of the window in sectors.
+    offset: u64,
+
+    /// Size of the window in sectors.
+    sectors: u64,
+}
+
+impl<D: BlockDevice> BlockDeviceOffset<D> {
+    /** Create a window of `size` bytes at byte `offset` of `device`.
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceOpenError`] if the offset or size is not a
+     * multiple of the sector size, or the window is out of bounds.
+     */
+    pub fn new(
+        device: D,
+        offset: u64,
+        size: u64,
+    ) -> Result<BlockDeviceOffset<D>, BlockDeviceOpenError> {
+        if !is_multiple_of_sector_size(offset) || !is_multiple_of_sector_size(size) {
+            return Err(BlockDeviceOpenError::InvalidWindow { offset, size });
+        }
+
+        let (offset_sectors, sectors) = (offset >> SECTOR_SHIFT, size >> SECTOR_SHIFT);
+        match offset_sectors.checked_add(sectors) {
+            Some(end) if end <= device.sectors() => (),
+            _ => return Err(BlockDeviceOpenError::InvalidWindow { offset, size }),
+        }
+
+        Ok(BlockDeviceOffset {
+            device,
+            offset: offset_sectors,
+            sectors,
+        })
+    }
+
+    /// Gets the byte offset of the window.
+    pub fn offset(&self) -> u64 {
+        self.offset << SECTOR_SHIFT
+    }
 }
 
+impl<D: BlockDevice> BlockDevice for BlockDeviceOffset<D> {
+    fn sectors(&self) -> u64 {
+        self.sectors
+    }
+
+    fn read(&self, data: &mut [u8], sector: u64) -> Result<(), BlockDeviceReadError> {
+        let size = data.len();
+
+        if byte_offset(size, sector, self.sectors).is_none() {
+            return Err(BlockDeviceReadError::InvalidRead { sector, size });
+        }
+
+        self.device.read(data, self.offset + sector)
+    }
+
+    fn write(&self, data: &[u8], sector: u64) -> Result<(), BlockDeviceWriteError> {
+        let size = data.len();
+
+        if byte_offset(size, sector, self.sectors).is_none() {
+            return Err(BlockDeviceWriteError::InvalidWrite { sector, size });
+        }
+
+        self.device.write(data, self.offset + sector)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
 /// [`BlockDevice`] open error.
 #[derive(Debug)]
 pub enum BlockDeviceOpenError {
@@ -120,6 +395,14 @@ pub enum BlockDeviceOpenError {
         size: u64,
     },
 
+    /// Block device invalid window.
+    InvalidWindow {
+        /// Byte offset.
+        offset: u64,
+        /// Size in bytes.
+        size: u64,
+    },
+
     /// Block device open error.
     OpenError {
         /// Error.
@@ -139,6 +422,12 @@ impl fmt::Display for BlockDeviceOpenError {
             BlockDeviceOpenError::InvalidSize { size } => {
                 write!(f, "Block Device invalid size:0x{size:016x}")
             }
+            BlockDeviceOpenError::InvalidWindow { offset, size } => {
+                write!(
+                    f,
+                    "Block Device invalid window offset:0x{offset:016x} size:0x{size:016x}"
+                )
+            }
             BlockDeviceOpenError::OpenError { err } => {
                 write!(f, "Block Device open error: [{err}]")
             }
@@ -214,3 +503,181 @@ impl error::Error for BlockDeviceReadError {
         }
     }
 }
+
+/// [`BlockDevice`] write error.
+#[derive(Debug)]
+pub enum BlockDeviceWriteError {
+    /// Invalid write bytes offset and / or size.
+    InvalidWrite {
+        /// Sector.
+        sector: u64,
+        /// Size in bytes.
+        size: usize,
+    },
+
+    /// I/O error.
+    IoError {
+        /// Error.
+        err: io::Error,
+        /// Sector.
+        sector: u64,
+        /// Size.
+        size: usize,
+    },
+
+    /// Block device is read only.
+    ReadOnly {
+        /// Sector.
+        sector: u64,
+        /// Size in bytes.
+        size: usize,
+    },
+}
+
+impl fmt::Display for BlockDeviceWriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlockDeviceWriteError::InvalidWrite { sector, size } => {
+                write!(
+                    f,
+                    "Block Device invalid write sector:0x{sector:016x} size:0x{size:016x}"
+                )
+            }
+            BlockDeviceWriteError::IoError { err, sector, size } => {
+                write!(
+                    f,
+                    "Block Device write IO error at sector:0x{sector:016x} size:0x{size:016x}: [{err}]"
+                )
+            }
+            BlockDeviceWriteError::ReadOnly { sector, size } => {
+                write!(
+                    f,
+                    "Block Device is read only, write sector:0x{sector:016x} size:0x{size:016x}"
+                )
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for BlockDeviceWriteError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            BlockDeviceWriteError::IoError {
+                err,
+                sector: _,
+                size: _,
+            } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use std::env;
+    use std::fs;
+
+    use crate::userspace::{
+        BlockDevice, BlockDeviceFile, BlockDeviceMemory, BlockDeviceOffset, BlockDeviceOpenError,
+        BlockDeviceReadError, BlockDeviceWriteError,
+    };
+
+    #[test]
+    fn memory() {
+        let bytes: Vec<u8> = (0..2048).map(|x| (x / 512) as u8).collect();
+        let device = BlockDeviceMemory::new(bytes).unwrap();
+        assert_eq!(device.sectors(), 4);
+
+        let mut data = [0; 1024];
+        device.read(&mut data, 2).unwrap();
+        assert_eq!(data[0..512], [2; 512]);
+        assert_eq!(data[512..], [3; 512]);
+
+        device.write(&[7; 512], 1).unwrap();
+        device.read(&mut data, 1).unwrap();
+        assert_eq!(data[0..512], [7; 512]);
+
+        // Out of bounds, and partial sectors.
+        assert!(matches!(
+            device.read(&mut data, 3).unwrap_err(),
+            BlockDeviceReadError::InvalidRead { sector: 3, .. }
+        ));
+        assert!(matches!(
+            device.write(&[0; 100], 0).unwrap_err(),
+            BlockDeviceWriteError::InvalidWrite { sector: 0, .. }
+        ));
+        assert!(matches!(
+            BlockDeviceMemory::new(vec![0; 100]).unwrap_err(),
+            BlockDeviceOpenError::InvalidSize { size: 100 }
+        ));
+
+        assert_eq!(device.into_bytes()[512..1024], [7; 512]);
+    }
+
+    #[test]
+    fn offset() {
+        let bytes: Vec<u8> = (0..4096).map(|x| (x / 512) as u8).collect();
+        let device = BlockDeviceMemory::new(bytes).unwrap();
+
+        let window = BlockDeviceOffset::new(&device, 1024, 2048).unwrap();
+        assert_eq!(window.sectors(), 4);
+        assert_eq!(window.offset(), 1024);
+
+        let mut data = [0; 512];
+        window.read(&mut data, 0).unwrap();
+        assert_eq!(data, [2; 512]);
+
+        window.write(&[9; 512], 3).unwrap();
+        device.read(&mut data, 5).unwrap();
+        assert_eq!(data, [9; 512]);
+
+        // Reads past the end of the window.
+        assert!(matches!(
+            window.read(&mut data, 4).unwrap_err(),
+            BlockDeviceReadError::InvalidRead { sector: 4, .. }
+        ));
+
+        // Invalid windows.
+        assert!(matches!(
+            BlockDeviceOffset::new(&device, 100, 512).unwrap_err(),
+            BlockDeviceOpenError::InvalidWindow { .. }
+        ));
+        assert!(matches!(
+            BlockDeviceOffset::new(&device, 2048, 4096).unwrap_err(),
+            BlockDeviceOpenError::InvalidWindow { .. }
+        ));
+
+        // Windows nest, and own their device.
+        let inner = BlockDeviceOffset::new(Box::new(window), 512, 512).unwrap();
+        inner.read(&mut data, 0).unwrap();
+        assert_eq!(data, [3; 512]);
+    }
+
+    #[test]
+    fn file() {
+        let path = env::temp_dir().join(format!("rzfs-block-device-{}.img", std::process::id()));
+        fs::write(&path, [1; 1024]).unwrap();
+        let path_str = path.to_str().unwrap();
+
+        let device = BlockDeviceFile::open(path_str).unwrap();
+        assert_eq!(device.sectors(), 2);
+        assert!(matches!(
+            device.write(&[0; 512], 0).unwrap_err(),
+            BlockDeviceWriteError::ReadOnly { .. }
+        ));
+
+        let device = BlockDeviceFile::open_writable(path_str).unwrap();
+        device.write(&[2; 512], 1).unwrap();
+
+        let mut data = [0; 1024];
+        device.read(&mut data, 0).unwrap();
+        assert_eq!(data[0..512], [1; 512]);
+        assert_eq!(data[512..], [2; 512]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
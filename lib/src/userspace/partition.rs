@@ -0,0 +1,694 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Partition tables.
+ *
+ * - A whole disk vdev is usually in the first partition of a GPT, with the
+ *   [`PARTITION_GUID_ZFS`] type. The labels of the vdev are relative to the
+ *   start of the partition.
+ * - A GPT is read from the primary header at LBA 1, or the backup header at
+ *   the last LBA, with 512 or 4096 byte logical blocks.
+ * - An MBR is read if there is no GPT. Only primary partitions are listed.
+ * - A [`Partition`] is opened as a [`BlockDeviceOffset`] of the disk.
+ */
+use core::fmt;
+use std::error;
+
+use crate::phys::{BinaryDecodeError, BinaryDecoder, LittleEndianDecoder, SECTOR_SHIFT};
+use crate::userspace::{
+    BlockDevice, BlockDeviceOffset, BlockDeviceOpenError, BlockDeviceReadError,
+};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// GPT partition type or unique GUID, in its mixed endian encoding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PartitionGuid {
+    /// Encoded bytes.
+    pub bytes: [u8; 16],
+}
+
+impl PartitionGuid {
+    /// Create a [`PartitionGuid`] from the fields of its text form
+    /// `aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee`.
+    pub const fn new(a: u32, b: u16, c: u16, d: u16, e: u64) -> PartitionGuid {
+        let a = a.to_le_bytes();
+        let b = b.to_le_bytes();
+        let c = c.to_le_bytes();
+        let d = d.to_be_bytes();
+        let e = e.to_be_bytes();
+
+        PartitionGuid {
+            bytes: [
+                a[0], a[1], a[2], a[3], b[0], b[1], c[0], c[1], d[0], d[1], e[2], e[3], e[4], e[5],
+                e[6], e[7],
+            ],
+        }
+    }
+
+    /// Is the GUID all zeros, for an unused GPT entry.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.iter().all(|b| *b == 0)
+    }
+}
+
+impl fmt::Display for PartitionGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let b = &self.bytes;
+        write!(
+            f,
+            "{:02X}{:02X}{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-",
+            b[3], b[2], b[1], b[0], b[5], b[4], b[7], b[6], b[8], b[9]
+        )?;
+        for byte in &b[10..16] {
+            write!(f, "{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Solaris `/usr` and Apple ZFS GPT partition type, used by OpenZFS for
+/// whole disk vdevs.
+pub const PARTITION_GUID_ZFS: PartitionGuid =
+    PartitionGuid::new(0x6a898cc3, 0x1dd2, 0x11b2, 0x99a6, 0x080020736631);
+
+/// FreeBSD ZFS GPT partition type.
+pub const PARTITION_GUID_FREEBSD_ZFS: PartitionGuid =
+    PartitionGuid::new(0x516e7cba, 0x6ecf, 0x11d6, 0x8ff8, 0x00022d09712b);
+
+/// Solaris reserved GPT partition type, created after the ZFS partition of a
+/// whole disk vdev.
+pub const PARTITION_GUID_SOLARIS_RESERVED: PartitionGuid =
+    PartitionGuid::new(0x6a945a3b, 0x1dd2, 0x11b2, 0x99a6, 0x080020736631);
+
+/// Solaris MBR partition type, used for ZFS.
+pub const PARTITION_MBR_SOLARIS: u8 = 0xbf;
+
+/// Protective MBR partition type of a GPT disk.
+pub const PARTITION_MBR_GPT_PROTECTIVE: u8 = 0xee;
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Type of a [`Partition`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PartitionType {
+    /// GPT partition type GUID.
+    Gpt(PartitionGuid),
+
+    /// MBR partition type.
+    Mbr(u8),
+}
+
+impl PartitionType {
+    /// Is the partition type used for ZFS.
+    pub fn is_zfs(&self) -> bool {
+        match self {
+            PartitionType::Gpt(guid) => {
+                *guid == PARTITION_GUID_ZFS || *guid == PARTITION_GUID_FREEBSD_ZFS
+            }
+            PartitionType::Mbr(mbr) => *mbr == PARTITION_MBR_SOLARIS,
+        }
+    }
+}
+
+/// A partition of a [`PartitionTable`].
+#[derive(Debug)]
+pub struct Partition {
+    /// Partition number, starting at 1.
+    pub number: u32,
+
+    /// Partition type.
+    pub partition_type: PartitionType,
+
+    /// Byte offset from the start of the disk.
+    pub offset: u64,
+
+    /// Size in bytes.
+    pub size: u64,
+
+    /// GPT partition name, or empty for MBR.
+    pub name: String,
+}
+
+impl Partition {
+    /// Is the partition used for ZFS.
+    pub fn is_zfs(&self) -> bool {
+        self.partition_type.is_zfs()
+    }
+
+    /** Open the partition of the disk `device`, as a [`BlockDeviceOffset`].
+     *
+     * # Errors
+     *
+     * Returns [`BlockDeviceOpenError`] if the partition is out of bounds.
+     */
+    pub fn open<D: BlockDevice>(
+        &self,
+        device: D,
+    ) -> Result<BlockDeviceOffset<D>, BlockDeviceOpenError> {
+        BlockDeviceOffset::new(device, self.offset, self.size)
+    }
+}
+
+/// Kind of [`PartitionTable`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PartitionTableType {
+    /// GUID Partition Table.
+    Gpt,
+
+    /// Master Boot Record.
+    Mbr,
+}
+
+/// Partition table of a disk.
+#[derive(Debug)]
+pub struct PartitionTable {
+    /// Kind of partition table.
+    pub table_type: PartitionTableType,
+
+    /// Partitions, in order of partition number.
+    pub partitions: Vec<Partition>,
+}
+
+impl PartitionTable {
+    /// GPT header signature.
+    const GPT_SIGNATURE: &'static [u8; 8] = b"EFI PART";
+
+    /// Minimum size of a GPT header.
+    const GPT_HEADER_SIZE_MIN: usize = 92;
+
+    /// Minimum size of a GPT entry.
+    const GPT_ENTRY_SIZE_MIN: usize = 128;
+
+    /// Maximum size of the GPT entries.
+    const GPT_ENTRIES_SIZE_MAX: usize = 1 << 20;
+
+    /// Byte size of the name of a GPT entry.
+    const GPT_NAME_SIZE: usize = 72;
+
+    /// GPT logical block sizes.
+    const GPT_BLOCK_SIZES: [u64; 2] = [512, 4096];
+
+    /// Byte offset of the MBR partition entries.
+    const MBR_ENTRIES_OFFSET: usize = 446;
+
+    /// Number of MBR partition entries.
+    const MBR_ENTRIES: usize = 4;
+
+    /// Byte size of an MBR partition entry.
+    const MBR_ENTRY_SIZE: usize = 16;
+
+    /// MBR boot signature, at the end of the first sector.
+    const MBR_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+
+    /** Reads the partition table of the disk `device`.
+     *
+     * Returns [`None`] if the disk has no GPT or MBR.
+     *
+     * # Errors
+     *
+     * Returns [`PartitionError`] if the partition table can not be read, or
+     * is invalid.
+     */
+    pub fn read(device: &dyn BlockDevice) -> Result<Option<PartitionTable>, PartitionError> {
+        if let Some(partitions) = PartitionTable::read_gpt(device)? {
+            return Ok(Some(PartitionTable {
+                table_type: PartitionTableType::Gpt,
+                partitions,
+            }));
+        }
+
+        Ok(
+            PartitionTable::read_mbr(device)?.map(|partitions| PartitionTable {
+                table_type: PartitionTableType::Mbr,
+                partitions,
+            }),
+        )
+    }
+
+    /// Gets the ZFS partitions.
+    pub fn zfs(&self) -> impl Iterator<Item = &Partition> {
+        self.partitions
+            .iter()
+            .filter(|partition| partition.is_zfs())
+    }
+
+    /// Reads the GPT from the primary or backup header, for each logical
+    /// block size. The error is from the first invalid header.
+    fn read_gpt(device: &dyn BlockDevice) -> Result<Option<Vec<Partition>>, PartitionError> {
+        let mut first_err = None;
+
+        for block_size in PartitionTable::GPT_BLOCK_SIZES {
+            let blocks = (device.sectors() << SECTOR_SHIFT) / block_size;
+            if blocks < 3 {
+                continue;
+            }
+
+            for lba in [1, blocks - 1] {
+                match PartitionTable::read_gpt_at(device, block_size, blocks, lba) {
+                    Ok(Some(v)) => return Ok(Some(v)),
+                    Ok(None) => (),
+                    Err(err) => {
+                        first_err.get_or_insert(err);
+                    }
+                }
+            }
+        }
+
+        match first_err {
+            Some(err) => Err(err),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the GPT header at `lba`, and its entries. Returns [`None`] if
+    /// there is no header signature.
+    fn read_gpt_at(
+        device: &dyn BlockDevice,
+        block_size: u64,
+        blocks: u64,
+        lba: u64,
+    ) -> Result<Option<Vec<Partition>>, PartitionError> {
+        let sectors_per_block = block_size >> SECTOR_SHIFT;
+
+        ////////////////////////////////
+        // Read the header.
+        let mut header = vec![0; block_size as usize];
+        device.read(&mut header, lba * sectors_per_block)?;
+
+        if header[0..8] != *PartitionTable::GPT_SIGNATURE {
+            return Ok(None);
+        }
+
+        let mut decoder = LittleEndianDecoder::from_bytes(&header);
+        decoder.skip(12)?;
+        let header_size = decoder.get_usize_32()?;
+        let header_checksum = decoder.get_u32()?;
+        decoder.skip(4)?;
+        let my_lba = decoder.get_u64()?;
+        decoder.skip(40)?;
+        let entries_lba = decoder.get_u64()?;
+        let entries_count = decoder.get_usize_32()?;
+        let entry_size = decoder.get_usize_32()?;
+        let entries_checksum = decoder.get_u32()?;
+
+        if header_size < PartitionTable::GPT_HEADER_SIZE_MIN
+            || header_size > header.len()
+            || my_lba != lba
+        {
+            return Err(PartitionError::InvalidGptHeader { lba });
+        }
+
+        // The checksum is computed with the checksum field zeroed.
+        header[16..20].fill(0);
+        if crc32(&header[0..header_size]) != header_checksum {
+            return Err(PartitionError::GptHeaderChecksum { lba });
+        }
+
+        ////////////////////////////////
+        // Read the entries.
+        let entries_size = match entries_count.checked_mul(entry_size) {
+            Some(v)
+                if v <= PartitionTable::GPT_ENTRIES_SIZE_MAX
+                    && entry_size >= PartitionTable::GPT_ENTRY_SIZE_MIN
+                    && entry_size.is_multiple_of(8) =>
+            {
+                v
+            }
+            _ => {
+                return Err(PartitionError::InvalidGptEntries {
+                    count: entries_count,
+                    size: entry_size,
+                })
+            }
+        };
+
+        let entries_blocks = (entries_size as u64).div_ceil(block_size);
+        match entries_lba.checked_add(entries_blocks) {
+            Some(end) if entries_lba > 0 && end <= blocks => (),
+            _ => return Err(PartitionError::InvalidGptHeader { lba }),
+        }
+
+        let mut entries = vec![0; (entries_blocks * block_size) as usize];
+        device.read(&mut entries, entries_lba * sectors_per_block)?;
+
+        if crc32(&entries[0..entries_size]) != entries_checksum {
+            return Err(PartitionError::GptEntriesChecksum { lba });
+        }
+
+        ////////////////////////////////
+        // Decode the entries.
+        let mut partitions = Vec::new();
+
+        for (idx, entry) in entries[0..entries_size].chunks(entry_size).enumerate() {
+            let number = (idx + 1) as u32;
+
+            let mut decoder = LittleEndianDecoder::from_bytes(entry);
+            let mut type_guid = PartitionGuid { bytes: [0; 16] };
+            type_guid.bytes.copy_from_slice(decoder.get_bytes_n(16)?);
+            if type_guid.is_empty() {
+                continue;
+            }
+            decoder.skip(16)?;
+            let first_lba = decoder.get_u64()?;
+            let last_lba = decoder.get_u64()?;
+            decoder.skip(8)?;
+            let name = decoder.get_bytes_n(PartitionTable::GPT_NAME_SIZE)?;
+
+            if first_lba > last_lba || last_lba >= blocks {
+                return Err(PartitionError::InvalidPartition { number });
+            }
+
+            let name: Vec<u16> = name
+                .chunks(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .take_while(|c| *c != 0)
+                .collect();
+
+            partitions.push(Partition {
+                number,
+                partition_type: PartitionType::Gpt(type_guid),
+                offset: first_lba * block_size,
+                size: (last_lba - first_lba + 1) * block_size,
+                name: String::from_utf16_lossy(&name),
+            });
+        }
+
+        Ok(Some(partitions))
+    }
+
+    /// Reads the primary partitions of the MBR. Returns [`None`] if there is
+    /// no boot signature.
+    fn read_mbr(device: &dyn BlockDevice) -> Result<Option<Vec<Partition>>, PartitionError> {
+        if device.sectors() == 0 {
+            return Ok(None);
+        }
+
+        let mut sector = [0; 1 << SECTOR_SHIFT];
+        device.read(&mut sector, 0)?;
+
+        if sector[510..512] != PartitionTable::MBR_SIGNATURE {
+            return Ok(None);
+        }
+
+        let mut partitions = Vec::new();
+
+        for idx in 0..PartitionTable::MBR_ENTRIES {
+            let number = (idx + 1) as u32;
+
+            let start = PartitionTable::MBR_ENTRIES_OFFSET + idx * PartitionTable::MBR_ENTRY_SIZE;
+            let mut decoder = LittleEndianDecoder::from_bytes(
+                &sector[start..start + PartitionTable::MBR_ENTRY_SIZE],
+            );
+            decoder.skip(4)?;
+            let partition_type = decoder.get_u8()?;
+            decoder.skip(3)?;
+            let first_sector = u64::from(decoder.get_u32()?);
+            let sectors = u64::from(decoder.get_u32()?);
+
+            if partition_type == 0 || sectors == 0 {
+                continue;
+            }
+
+            if first_sector == 0 || first_sector + sectors > device.sectors() {
+                return Err(PartitionError::InvalidPartition { number });
+            }
+
+            partitions.push(Partition {
+                number,
+                partition_type: PartitionType::Mbr(partition_type),
+                offset: first_sector << SECTOR_SHIFT,
+                size: sectors << SECTOR_SHIFT,
+                name: String::new(),
+            });
+        }
+
+        Ok(Some(partitions))
+    }
+}
+
+/// Computes the CRC-32 (IEEE 802.3) of the `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+
+    !crc
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`PartitionTable`] error.
+#[derive(Debug)]
+pub enum PartitionError {
+    /// Binary decode error.
+    Binary {
+        /// Error.
+        err: BinaryDecodeError,
+    },
+
+    /// Block device read error.
+    BlockDevice {
+        /// Error.
+        err: BlockDeviceReadError,
+    },
+
+    /// GPT entries checksum mismatch.
+    GptEntriesChecksum {
+        /// LBA of the header.
+        lba: u64,
+    },
+
+    /// GPT header checksum mismatch.
+    GptHeaderChecksum {
+        /// LBA of the header.
+        lba: u64,
+    },
+
+    /// Invalid GPT entries count or size.
+    InvalidGptEntries {
+        /// Number of entries.
+        count: usize,
+        /// Size of an entry.
+        size: usize,
+    },
+
+    /// Invalid GPT header.
+    InvalidGptHeader {
+        /// LBA of the header.
+        lba: u64,
+    },
+
+    /// Partition is out of bounds.
+    InvalidPartition {
+        /// Partition number.
+        number: u32,
+    },
+}
+
+impl From<BinaryDecodeError> for PartitionError {
+    fn from(err: BinaryDecodeError) -> Self {
+        PartitionError::Binary { err }
+    }
+}
+
+impl From<BlockDeviceReadError> for PartitionError {
+    fn from(err: BlockDeviceReadError) -> Self {
+        PartitionError::BlockDevice { err }
+    }
+}
+
+impl fmt::Display for PartitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PartitionError::Binary { err } => {
+                write!(f, "Partition table decode error: [{err}]")
+            }
+            PartitionError::BlockDevice { err } => {
+                write!(f, "Partition table read error: [{err}]")
+            }
+            PartitionError::GptEntriesChecksum { lba } => {
+                write!(
+                    f,
+                    "Partition table GPT entries checksum mismatch, header lba:0x{lba:016x}"
+                )
+            }
+            PartitionError::GptHeaderChecksum { lba } => {
+                write!(
+                    f,
+                    "Partition table GPT header checksum mismatch, lba:0x{lba:016x}"
+                )
+            }
+            PartitionError::InvalidGptEntries { count, size } => {
+                write!(
+                    f,
+                    "Partition table invalid GPT entries count:{count} size:{size}"
+                )
+            }
+            PartitionError::InvalidGptHeader { lba } => {
+                write!(f, "Partition table invalid GPT header lba:0x{lba:016x}")
+            }
+            PartitionError::InvalidPartition { number } => {
+                write!(f, "Partition table invalid partition {number}")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for PartitionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            PartitionError::Binary { err } => Some(err),
+            PartitionError::BlockDevice { err } => Some(err),
+            _ => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::userspace::partition::crc32;
+    use crate::userspace::{
+        BlockDevice, BlockDeviceMemory, PartitionError, PartitionGuid, PartitionTable,
+        PartitionTableType, PartitionType, PARTITION_GUID_SOLARIS_RESERVED, PARTITION_GUID_ZFS,
+        PARTITION_MBR_GPT_PROTECTIVE, PARTITION_MBR_SOLARIS,
+    };
+
+    /// Sectors of test disks.
+    const DISK_SECTORS: usize = 128;
+
+    /// Writes an MBR entry.
+    fn mbr_entry(disk: &mut [u8], idx: usize, partition_type: u8, first: u32, sectors: u32) {
+        let start = 446 + idx * 16;
+        disk[start + 4] = partition_type;
+        disk[start + 8..start + 12].copy_from_slice(&first.to_le_bytes());
+        disk[start + 12..start + 16].copy_from_slice(&sectors.to_le_bytes());
+        disk[510..512].copy_from_slice(&[0x55, 0xaa]);
+    }
+
+    /// Writes a GPT header at `lba`, for 4 entries at LBA 2.
+    fn gpt_header(disk: &mut [u8], lba: u64) {
+        let entries_checksum = crc32(&disk[1024..1536]);
+
+        let start = (lba as usize) * 512;
+        let header = &mut disk[start..start + 92];
+        header[0..8].copy_from_slice(b"EFI PART");
+        header[8..12].copy_from_slice(&0x00010000u32.to_le_bytes());
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[24..32].copy_from_slice(&lba.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        header[88..92].copy_from_slice(&entries_checksum.to_le_bytes());
+
+        let header_checksum = crc32(header);
+        header[16..20].copy_from_slice(&header_checksum.to_le_bytes());
+    }
+
+    /// Writes a GPT entry.
+    fn gpt_entry(disk: &mut [u8], idx: usize, guid: PartitionGuid, first: u64, last: u64) {
+        let start = 1024 + idx * 128;
+        let entry = &mut disk[start..start + 128];
+        entry[0..16].copy_from_slice(&guid.bytes);
+        entry[32..40].copy_from_slice(&first.to_le_bytes());
+        entry[40..48].copy_from_slice(&last.to_le_bytes());
+        for (idx, c) in "zfs".encode_utf16().enumerate() {
+            entry[56 + idx * 2..58 + idx * 2].copy_from_slice(&c.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn guid() {
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(
+            format!("{PARTITION_GUID_ZFS}"),
+            "6A898CC3-1DD2-11B2-99A6-080020736631"
+        );
+        assert_eq!(PARTITION_GUID_ZFS.bytes[0..4], [0xc3, 0x8c, 0x89, 0x6a]);
+    }
+
+    #[test]
+    fn gpt() {
+        let mut disk = vec![0; DISK_SECTORS * 512];
+        mbr_entry(&mut disk, 0, PARTITION_MBR_GPT_PROTECTIVE, 1, 127);
+        gpt_entry(&mut disk, 0, PARTITION_GUID_ZFS, 8, 99);
+        gpt_entry(&mut disk, 2, PARTITION_GUID_SOLARIS_RESERVED, 100, 115);
+        gpt_header(&mut disk, 1);
+        gpt_header(&mut disk, 127);
+        disk[8 * 512..9 * 512].fill(0xab);
+
+        let device = BlockDeviceMemory::new(disk.clone()).unwrap();
+        let table = PartitionTable::read(&device).unwrap().unwrap();
+        assert_eq!(table.table_type, PartitionTableType::Gpt);
+        assert_eq!(table.partitions.len(), 2);
+
+        let partition = &table.partitions[0];
+        assert_eq!(partition.number, 1);
+        assert_eq!(partition.offset, 8 * 512);
+        assert_eq!(partition.size, 92 * 512);
+        assert_eq!(partition.name, "zfs");
+        assert_eq!(table.partitions[1].number, 3);
+        assert!(!table.partitions[1].is_zfs());
+
+        // Open the ZFS partition.
+        let zfs: Vec<_> = table.zfs().collect();
+        assert_eq!(zfs.len(), 1);
+        let window = zfs[0].open(&device).unwrap();
+        assert_eq!(window.sectors(), 92);
+        let mut data = [0; 512];
+        window.read(&mut data, 0).unwrap();
+        assert_eq!(data, [0xab; 512]);
+
+        // Backup header, if the primary header is corrupted.
+        disk[512 + 40] ^= 1;
+        let device = BlockDeviceMemory::new(disk.clone()).unwrap();
+        let table = PartitionTable::read(&device).unwrap().unwrap();
+        assert_eq!(table.partitions.len(), 2);
+
+        // Error from the primary header, if both are corrupted.
+        disk[127 * 512 + 40] ^= 1;
+        let device = BlockDeviceMemory::new(disk).unwrap();
+        assert!(matches!(
+            PartitionTable::read(&device).unwrap_err(),
+            PartitionError::GptHeaderChecksum { lba: 1 }
+        ));
+    }
+
+    #[test]
+    fn mbr() {
+        let mut disk = vec![0; DISK_SECTORS * 512];
+        mbr_entry(&mut disk, 0, 0x83, 1, 7);
+        mbr_entry(&mut disk, 1, PARTITION_MBR_SOLARIS, 8, 16);
+
+        let device = BlockDeviceMemory::new(disk.clone()).unwrap();
+        let table = PartitionTable::read(&device).unwrap().unwrap();
+        assert_eq!(table.table_type, PartitionTableType::Mbr);
+        assert_eq!(table.partitions.len(), 2);
+        assert_eq!(table.partitions[0].partition_type, PartitionType::Mbr(0x83));
+
+        let zfs: Vec<_> = table.zfs().collect();
+        assert_eq!(zfs.len(), 1);
+        assert_eq!((zfs[0].number, zfs[0].offset, zfs[0].size), (2, 4096, 8192));
+
+        // Out of bounds.
+        mbr_entry(&mut disk, 2, 0x83, 120, 16);
+        let device = BlockDeviceMemory::new(disk).unwrap();
+        assert!(matches!(
+            PartitionTable::read(&device).unwrap_err(),
+            PartitionError::InvalidPartition { number: 3 }
+        ));
+
+        // No partition table.
+        let device = BlockDeviceMemory::new(vec![0; DISK_SECTORS * 512]).unwrap();
+        assert!(PartitionTable::read(&device).unwrap().is_none());
+    }
+}
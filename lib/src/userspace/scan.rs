@@ -0,0 +1,1011 @@
+// SPDX-License-Identifier: GPL-2.0 OR MIT
+
+/*! Pool discovery.
+ *
+ * - A [`PoolScanner`] reads the four labels of each device, like
+ *   `zpool import` without arguments. A device without a valid label is
+ *   searched for partitions, and each partition is scanned.
+ * - The config of a device is from its valid label with the highest `txg`.
+ * - Devices are grouped by pool guid into a [`ScanPool`]. Each top level vdev
+ *   is reconstructed from the config with the highest `txg` of the devices in
+ *   it.
+ * - A leaf vdev of the reconstructed tree is [`ScanDeviceState::Present`] if
+ *   a device has its guid and the `txg` of its top level vdev,
+ *   [`ScanDeviceState::Stale`] if the device has an older `txg`, and
+ *   [`ScanDeviceState::Missing`] if no device has its guid. A child vdev
+ *   whose config can not be decoded is kept as [`ScanDeviceState::Unknown`].
+ */
+use core::fmt;
+use std::error;
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::FileTypeExt;
+
+use crate::checksum::{ChecksumError, Sha256, Sha256Implementation};
+use crate::phys::{
+    Label, LabelConfig, LabelConfigStorage, LabelNvPairs, LabelVdevTree, LabelVdevTreeType,
+    NvArray, NvList, PoolState, VdevTreeKey, VdevType,
+};
+use crate::userspace::{BlockDevice, BlockDeviceFile, BlockDeviceOpenError, PartitionTable};
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// State of a leaf vdev of a [`ScanPool`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ScanDeviceState {
+    /// Device is present, with the latest config.
+    Present,
+
+    /// Device is present, with an older config.
+    Stale,
+
+    /// Device is missing.
+    Missing,
+
+    /// Vdev config can not be decoded.
+    Unknown,
+}
+
+impl fmt::Display for ScanDeviceState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanDeviceState::Present => write!(f, "present"),
+            ScanDeviceState::Stale => write!(f, "stale"),
+            ScanDeviceState::Missing => write!(f, "missing"),
+            ScanDeviceState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+/// A vdev of a [`ScanPool`], from a label config.
+#[derive(Clone, Debug)]
+pub struct ScanVdev {
+    /// Guid.
+    pub guid: u64,
+
+    /// Id, in its parent.
+    pub id: u64,
+
+    /// Vdev type.
+    pub vdev_type: VdevType,
+
+    /// Path of a leaf vdev.
+    pub path: Option<String>,
+
+    /// State of a leaf vdev, or [`None`] for a vdev with children.
+    pub state: Option<ScanDeviceState>,
+
+    /// Children.
+    pub children: Vec<ScanVdev>,
+}
+
+impl ScanVdev {
+    /// Create a top level [`ScanVdev`] from a [`LabelVdevTree`].
+    fn from_tree(tree: &LabelVdevTree<'_>) -> ScanVdev {
+        let (vdev_type, path) = match &tree.vdev_type {
+            LabelVdevTreeType::Disk(disk) => (VdevType::Disk, Some(disk.path)),
+            LabelVdevTreeType::File(file) => (VdevType::File, Some(file.path)),
+            LabelVdevTreeType::Mirror(_) => (VdevType::Mirror, None),
+            LabelVdevTreeType::RaidZ(_) => (VdevType::RaidZ, None),
+            LabelVdevTreeType::DRaid(_) => (VdevType::DRaid, None),
+            LabelVdevTreeType::Indirect(_) => (VdevType::Indirect, None),
+        };
+
+        ScanVdev {
+            guid: tree.guid,
+            id: tree.id,
+            vdev_type,
+            path: path.map(String::from),
+            state: None,
+            children: match tree.vdev_type.children() {
+                Some(children) => ScanVdev::from_children(&children),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Create the children of a vdev. A child that can not be decoded is
+    /// kept as a [`ScanDeviceState::Unknown`] leaf, with its index as id.
+    fn from_children(children: &NvArray<'_, NvList<'_>>) -> Vec<ScanVdev> {
+        children
+            .into_iter()
+            .enumerate()
+            .map(|(idx, child)| {
+                let id = idx as u64;
+                match child.ok().and_then(|list| ScanVdev::from_list(&list, id)) {
+                    Some(v) => v,
+                    None => ScanVdev {
+                        guid: 0,
+                        id,
+                        vdev_type: VdevType::Missing,
+                        path: None,
+                        state: Some(ScanDeviceState::Unknown),
+                        children: Vec::new(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /** Create a child [`ScanVdev`] from its config, and its nested children,
+     * such as the children of a `replacing` or `spare` vdev.
+     *
+     * Returns [`None`] if the guid or vdev type can not be decoded.
+     */
+    fn from_list(list: &NvList<'_>, idx: u64) -> Option<ScanVdev> {
+        let guid = list.get_u64(VdevTreeKey::Guid.into()).ok()??;
+        let vdev_type = list.get_str(VdevTreeKey::VdevType.into()).ok()??;
+        let vdev_type = VdevType::try_from(vdev_type).ok()?;
+
+        let id = list.get_u64(VdevTreeKey::Id.into()).ok().flatten();
+        let path = list.get_str(VdevTreeKey::Path.into()).ok().flatten();
+        let children = match list.get_nv_list_array(VdevTreeKey::Children.into()) {
+            Ok(Some(children)) => ScanVdev::from_children(&children),
+            Ok(None) => Vec::new(),
+            Err(_) => return None,
+        };
+
+        Some(ScanVdev {
+            guid,
+            id: id.unwrap_or(idx),
+            vdev_type,
+            path: path.map(String::from),
+            state: None,
+            children,
+        })
+    }
+
+    /// Gets the leaf vdevs.
+    pub fn leaves(&self) -> Vec<&ScanVdev> {
+        if self.children.is_empty() {
+            return vec![self];
+        }
+
+        self.children
+            .iter()
+            .flat_map(|child| child.leaves())
+            .collect()
+    }
+
+    /// Sets the state of the leaf vdevs from the `devices`, for a top level
+    /// `txg`.
+    fn set_states(&mut self, devices: &[ScanDevice], txg: u64) {
+        if !self.children.is_empty() {
+            for child in &mut self.children {
+                child.set_states(devices, txg);
+            }
+            return;
+        }
+
+        if self.state == Some(ScanDeviceState::Unknown) {
+            return;
+        }
+
+        let device_txg = devices
+            .iter()
+            .filter(|device| device.guid == self.guid)
+            .map(|device| device.txg)
+            .max();
+
+        self.state = Some(match device_txg {
+            Some(v) if v >= txg => ScanDeviceState::Present,
+            Some(_) => ScanDeviceState::Stale,
+            None => ScanDeviceState::Missing,
+        });
+    }
+}
+
+/// A device with a valid label.
+#[derive(Debug)]
+pub struct ScanDevice {
+    /// Path of the scanned device.
+    pub path: String,
+
+    /// Partition number, if the labels are in a partition of the device.
+    pub partition: Option<u32>,
+
+    /// Valid labels.
+    pub labels: [bool; 4],
+
+    /// Guid of the leaf vdev.
+    pub guid: u64,
+
+    /// Pool guid.
+    pub pool_guid: u64,
+
+    /// Pool name.
+    pub pool_name: String,
+
+    /// Pool state.
+    pub pool_state: PoolState,
+
+    /// Transaction group of the config.
+    pub txg: u64,
+
+    /// Guid of the top level vdev.
+    pub top_guid: u64,
+
+    /// Number of top level vdevs of the pool.
+    pub vdev_children: Option<u64>,
+
+    /// Top level vdev of the config.
+    pub vdev_tree: ScanVdev,
+}
+
+impl ScanDevice {
+    /// Create a [`ScanDevice`] from a label config.
+    fn from_config(
+        path: &str,
+        partition: Option<u32>,
+        config: &LabelConfigStorage<'_>,
+    ) -> ScanDevice {
+        ScanDevice {
+            path: path.into(),
+            partition,
+            labels: [false; 4],
+            guid: config.guid,
+            pool_guid: config.pool_guid,
+            pool_name: config.name.into(),
+            pool_state: config.state,
+            txg: config.txg,
+            top_guid: config.top_guid,
+            vdev_children: config.vdev_children,
+            vdev_tree: ScanVdev::from_tree(&config.vdev_tree),
+        }
+    }
+}
+
+/// A pool reconstructed from the labels of its devices.
+#[derive(Debug)]
+pub struct ScanPool {
+    /// Pool guid.
+    pub guid: u64,
+
+    /// Pool name, from the config with the highest `txg`.
+    pub name: String,
+
+    /// Pool state, from the config with the highest `txg`.
+    pub state: PoolState,
+
+    /// Highest `txg` of the configs.
+    pub txg: u64,
+
+    /// Top level vdevs, indexed by id, or [`None`] if no device of a top level
+    /// vdev was found.
+    pub vdevs: Vec<Option<ScanVdev>>,
+
+    /// Devices of the pool.
+    pub devices: Vec<ScanDevice>,
+}
+
+impl ScanPool {
+    /// Gets the devices that are not a leaf vdev of the reconstructed tree.
+    pub fn unused_devices(&self) -> Vec<&ScanDevice> {
+        let leaves: Vec<u64> = self
+            .vdevs
+            .iter()
+            .flatten()
+            .flat_map(|vdev| vdev.leaves())
+            .map(|leaf| leaf.guid)
+            .collect();
+
+        self.devices
+            .iter()
+            .filter(|device| !leaves.contains(&device.guid))
+            .collect()
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// Scans devices for pools.
+pub struct PoolScanner {
+    /// Label checksum.
+    sha256: Sha256,
+
+    /// Devices with a valid label.
+    devices: Vec<ScanDevice>,
+}
+
+impl PoolScanner {
+    /** Create a new [`PoolScanner`].
+     *
+     * # Errors
+     *
+     * Returns [`ScanError`] if the label checksum is not available.
+     */
+    pub fn new() -> Result<PoolScanner, ScanError> {
+        Ok(PoolScanner {
+            sha256: Sha256::new(Sha256Implementation::Generic)?,
+            devices: Vec::new(),
+        })
+    }
+
+    /// Gets the devices with a valid label.
+    pub fn devices(&self) -> &[ScanDevice] {
+        &self.devices
+    }
+
+    /** Scans a device path, or the entries of a directory.
+     *
+     * Only block devices and regular files are opened. Other entries, such
+     * as character devices, are skipped, because opening them can have side
+     * effects. Entries of a directory are not scanned recursively. Returns
+     * the number of devices found.
+     *
+     * # Errors
+     *
+     * Returns [`ScanError`] if the path can not be read, or is not a block
+     * device, regular file, or directory.
+     */
+    pub fn scan_path(&mut self, path: &str) -> Result<usize, ScanError> {
+        let metadata = match fs::metadata(path) {
+            Ok(v) => v,
+            Err(err) => return Err(ScanError::Io { err }),
+        };
+
+        if !metadata.is_dir() {
+            if !PoolScanner::is_device(&metadata) {
+                return Err(ScanError::UnsupportedFileType { path: path.into() });
+            }
+
+            let device = BlockDeviceFile::open(path)?;
+            return Ok(self.scan_device(path, &device));
+        }
+
+        let entries = match fs::read_dir(path) {
+            Ok(v) => v,
+            Err(err) => return Err(ScanError::Io { err }),
+        };
+
+        // Symbolic links, such as in `/dev/disk/by-id`, are followed, and
+        // each device is only scanned once.
+        let mut paths = Vec::new();
+        for entry in entries {
+            let entry = match entry {
+                Ok(v) => v,
+                Err(err) => return Err(ScanError::Io { err }),
+            };
+
+            match fs::metadata(entry.path()) {
+                Ok(v) if PoolScanner::is_device(&v) => (),
+                _ => continue,
+            };
+
+            let target = match fs::canonicalize(entry.path()) {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            if let Some(path) = entry.path().to_str() {
+                paths.push((target, String::from(path)));
+            }
+        }
+        paths.sort();
+        paths.dedup_by(|a, b| a.0 == b.0);
+
+        let mut found = 0;
+        for (_, path) in &paths {
+            if let Ok(device) = BlockDeviceFile::open(path) {
+                found += self.scan_device(path, &device);
+            }
+        }
+
+        Ok(found)
+    }
+
+    /// Is the file a block device or a regular file.
+    fn is_device(metadata: &fs::Metadata) -> bool {
+        let file_type = metadata.file_type();
+        file_type.is_block_device() || file_type.is_file()
+    }
+
+    /// Scans a device at `path`. If the device has no valid label, its
+    /// partitions are scanned. Returns the number of devices found.
+    pub fn scan_device(&mut self, path: &str, device: &dyn BlockDevice) -> usize {
+        if self.scan_labels(path, None, device) {
+            return 1;
+        }
+
+        let table = match PartitionTable::read(device) {
+            Ok(Some(v)) => v,
+            _ => return 0,
+        };
+
+        let mut found = 0;
+        for partition in &table.partitions {
+            if let Ok(window) = partition.open(device) {
+                if self.scan_labels(path, Some(partition.number), &window) {
+                    found += 1;
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Reads the labels of a device, and adds it if a label is valid.
+    fn scan_labels(
+        &mut self,
+        path: &str,
+        partition: Option<u32>,
+        device: &dyn BlockDevice,
+    ) -> bool {
+        let label_sectors = match Label::offsets(device.sectors()) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+
+        let mut labels = [false; 4];
+        let mut best: Option<ScanDevice> = None;
+        let mut bytes = vec![0; LabelNvPairs::SIZE];
+
+        for (idx, label_sector) in label_sectors.into_iter().enumerate() {
+            let offset = label_sector + LabelNvPairs::LABEL_OFFSET;
+            if device.read(&mut bytes, offset).is_err() {
+                continue;
+            }
+
+            let config = match PoolScanner::decode_label(&bytes, offset, &mut self.sha256) {
+                Some(v) => v,
+                None => continue,
+            };
+            let config = match &config {
+                LabelConfig::Storage(v) => v,
+                _ => continue,
+            };
+
+            labels[idx] = true;
+
+            if best.as_ref().is_none_or(|best| config.txg > best.txg) {
+                best = Some(ScanDevice::from_config(path, partition, config));
+            }
+        }
+
+        match best {
+            Some(mut device) => {
+                device.labels = labels;
+                self.devices.push(device);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Decodes the config of a label.
+    fn decode_label<'a>(
+        bytes: &'a [u8],
+        offset: u64,
+        sha256: &mut Sha256,
+    ) -> Option<LabelConfig<'a>> {
+        let nv_pairs = LabelNvPairs::from_bytes(bytes, offset, sha256).ok()?;
+        let list = NvList::from_bytes(nv_pairs.payload).ok()?;
+        LabelConfig::from_list(&list).ok()
+    }
+
+    /// Groups the scanned devices into pools, ordered by pool name and guid.
+    pub fn pools(self) -> Vec<ScanPool> {
+        let mut pools: Vec<ScanPool> = Vec::new();
+
+        ////////////////////////////////
+        // Group devices by pool guid.
+        for device in self.devices {
+            let idx = match pools.iter().position(|pool| pool.guid == device.pool_guid) {
+                Some(v) => v,
+                None => {
+                    pools.push(ScanPool {
+                        guid: device.pool_guid,
+                        name: device.pool_name.clone(),
+                        state: device.pool_state,
+                        txg: device.txg,
+                        vdevs: Vec::new(),
+                        devices: Vec::new(),
+                    });
+                    pools.len() - 1
+                }
+            };
+
+            let pool = &mut pools[idx];
+            if device.txg > pool.txg {
+                pool.name.clone_from(&device.pool_name);
+                pool.state = device.pool_state;
+                pool.txg = device.txg;
+            }
+            pool.devices.push(device);
+        }
+
+        ////////////////////////////////
+        // Reconstruct the top level vdevs from the latest config of each.
+        // The number of top level vdevs is from the configs, and without it,
+        // from the ids, bounded by the number of devices. A device with an id
+        // out of range is not in the tree.
+        for pool in &mut pools {
+            let children = pool
+                .devices
+                .iter()
+                .filter_map(|device| device.vdev_children)
+                .max();
+            let count = match children {
+                Some(v) => v,
+                None => pool
+                    .devices
+                    .iter()
+                    .map(|device| device.vdev_tree.id.saturating_add(1))
+                    .max()
+                    .unwrap_or(0)
+                    .min(pool.devices.len() as u64),
+            };
+
+            for id in 0..count {
+                let latest = pool
+                    .devices
+                    .iter()
+                    .filter(|device| device.vdev_tree.id == id)
+                    .max_by_key(|device| device.txg);
+
+                pool.vdevs.push(latest.map(|device| {
+                    let mut vdev = device.vdev_tree.clone();
+                    vdev.set_states(&pool.devices, device.txg);
+                    vdev
+                }));
+            }
+        }
+
+        pools.sort_by(|a, b| (&a.name, a.guid).cmp(&(&b.name, b.guid)));
+        pools
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+/// [`PoolScanner`] error.
+#[derive(Debug)]
+pub enum ScanError {
+    /// Block device open error.
+    BlockDeviceOpen {
+        /// Error.
+        err: BlockDeviceOpenError,
+    },
+
+    /// Checksum error.
+    Checksum {
+        /// Error.
+        err: ChecksumError,
+    },
+
+    /// I/O error.
+    Io {
+        /// Error.
+        err: io::Error,
+    },
+
+    /// Path is not a block device, regular file, or directory.
+    UnsupportedFileType {
+        /// Path.
+        path: String,
+    },
+}
+
+impl From<BlockDeviceOpenError> for ScanError {
+    fn from(err: BlockDeviceOpenError) -> Self {
+        ScanError::BlockDeviceOpen { err }
+    }
+}
+
+impl From<ChecksumError> for ScanError {
+    fn from(err: ChecksumError) -> Self {
+        ScanError::Checksum { err }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::BlockDeviceOpen { err } => write!(f, "Scan error: [{err}]"),
+            ScanError::Checksum { err } => write!(f, "Scan error: [{err}]"),
+            ScanError::Io { err } => write!(f, "Scan IO error: [{err}]"),
+            ScanError::UnsupportedFileType { path } => {
+                write!(f, "Scan error, {path} is not a block device or file")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for ScanError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ScanError::BlockDeviceOpen { err } => Some(err),
+            ScanError::Checksum { err } => Some(err),
+            ScanError::Io { err } => Some(err),
+            ScanError::UnsupportedFileType { path: _ } => None,
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+
+    use crate::checksum::{Sha256, Sha256Implementation};
+    use crate::phys::{EndianOrder, Label, LabelNvPairs, PoolState, VdevType};
+    use crate::userspace::{BlockDeviceMemory, PoolScanner, ScanDeviceState, ScanError};
+    use std::{env, fs, os};
+
+    /// Sectors of test disks.
+    const DISK_SECTORS: usize = 4096;
+
+    /// Value of an encoded NV pair.
+    enum Value<'a> {
+        U64(u64),
+        Str(&'a str),
+        List(Vec<u8>),
+        Lists(Vec<Vec<u8>>),
+    }
+
+    /// Appends an XDR string.
+    fn put_str(bytes: &mut Vec<u8>, value: &str) {
+        bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+    }
+
+    /// Encodes an XDR NV list, without the header.
+    fn nv_list(pairs: &[(&str, Value<'_>)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&0u32.to_be_bytes());
+        bytes.extend_from_slice(&1u32.to_be_bytes());
+
+        for (name, value) in pairs {
+            let mut pair = Vec::new();
+            put_str(&mut pair, name);
+
+            let (data_type, count, data): (u32, usize, Vec<u8>) = match value {
+                Value::U64(v) => (8, 1, v.to_be_bytes().to_vec()),
+                Value::Str(v) => {
+                    let mut data = Vec::new();
+                    put_str(&mut data, v);
+                    (9, 1, data)
+                }
+                Value::List(v) => (19, 1, v.clone()),
+                Value::Lists(v) => (20, v.len(), v.concat()),
+            };
+            pair.extend_from_slice(&data_type.to_be_bytes());
+            pair.extend_from_slice(&(count as u32).to_be_bytes());
+            pair.extend_from_slice(&data);
+
+            let size = (pair.len() + 8) as u32;
+            bytes.extend_from_slice(&size.to_be_bytes());
+            bytes.extend_from_slice(&size.to_be_bytes());
+            bytes.extend_from_slice(&pair);
+        }
+
+        bytes.extend_from_slice(&[0; 8]);
+        bytes
+    }
+
+    /// Encodes a leaf vdev.
+    fn leaf(guid: u64, id: u64, path: &str) -> Vec<u8> {
+        nv_list(&[
+            ("type", Value::Str("disk")),
+            ("id", Value::U64(id)),
+            ("guid", Value::U64(guid)),
+            ("path", Value::Str(path)),
+        ])
+    }
+
+    /// Encodes the vdev tree fields of a top level vdev.
+    fn top<'a>(guid: u64, id: u64, vdev: Vec<(&'a str, Value<'a>)>) -> Vec<u8> {
+        let mut pairs = vec![
+            ("id", Value::U64(id)),
+            ("guid", Value::U64(guid)),
+            ("metaslab_array", Value::U64(0)),
+            ("metaslab_shift", Value::U64(24)),
+            ("ashift", Value::U64(9)),
+            ("asize", Value::U64(1 << 20)),
+        ];
+        pairs.extend(vdev);
+        nv_list(&pairs)
+    }
+
+    /// Creates a disk, with a label config in the labels that are `valid`.
+    fn disk(
+        pool: (u64, &str),
+        guid: u64,
+        txg: u64,
+        vdev_tree: Vec<u8>,
+        top_guid: u64,
+        valid: [bool; 4],
+    ) -> Vec<u8> {
+        let mut config = vec![1, 1, 0, 0];
+        config.extend(nv_list(&[
+            ("version", Value::U64(5000)),
+            ("name", Value::Str(pool.1)),
+            ("state", Value::U64(0)),
+            ("txg", Value::U64(txg)),
+            ("pool_guid", Value::U64(pool.0)),
+            ("top_guid", Value::U64(top_guid)),
+            ("guid", Value::U64(guid)),
+            ("vdev_children", Value::U64(3)),
+            ("vdev_tree", Value::List(vdev_tree)),
+        ]));
+        config.resize(LabelNvPairs::PAYLOAD_SIZE, 0);
+
+        let mut sha256 = Sha256::new(Sha256Implementation::Generic).unwrap();
+        let mut disk = vec![0; DISK_SECTORS * 512];
+        let label_sectors = Label::offsets(DISK_SECTORS as u64).unwrap();
+
+        for (label_sector, valid) in label_sectors.into_iter().zip(valid) {
+            let offset = label_sector + LabelNvPairs::LABEL_OFFSET;
+            let start = (offset as usize) * 512;
+            let bytes = &mut disk[start..start + LabelNvPairs::SIZE];
+
+            LabelNvPairs { payload: &config }
+                .to_bytes(bytes, offset, &mut sha256, EndianOrder::Little)
+                .unwrap();
+
+            if !valid {
+                bytes[0] ^= 0xff;
+            }
+        }
+
+        disk
+    }
+
+    /// Encodes a mirror with id 0, of leaves 11, 12, and 13.
+    fn mirror() -> Vec<u8> {
+        top(
+            10,
+            0,
+            vec![
+                ("type", Value::Str("mirror")),
+                (
+                    "children",
+                    Value::Lists(vec![
+                        leaf(11, 0, "/dev/a"),
+                        leaf(12, 1, "/dev/b"),
+                        leaf(13, 2, "/dev/c"),
+                    ]),
+                ),
+            ],
+        )
+    }
+
+    /// Encodes a disk with id 1, and guid 20.
+    fn single() -> Vec<u8> {
+        top(
+            20,
+            1,
+            vec![("type", Value::Str("disk")), ("path", Value::Str("/dev/d"))],
+        )
+    }
+
+    #[test]
+    fn scan() {
+        let mut scanner = PoolScanner::new().unwrap();
+        let all = [true; 4];
+
+        // Leaf 12 has an older config than leaf 11, and leaf 13 is missing.
+        let a = disk(
+            (100, "tank"),
+            11,
+            50,
+            mirror(),
+            10,
+            [true, false, true, true],
+        );
+        let b = disk((100, "tank"), 12, 40, mirror(), 10, all);
+        let d = disk((100, "tank"), 20, 45, single(), 20, all);
+
+        // Other pool, in an MBR partition.
+        let mut e = vec![0; 2 * DISK_SECTORS * 512];
+        e[446 + 4] = 0xbf;
+        e[446 + 8..446 + 12].copy_from_slice(&2048u32.to_le_bytes());
+        e[446 + 12..446 + 16].copy_from_slice(&(DISK_SECTORS as u32).to_le_bytes());
+        e[510..512].copy_from_slice(&[0x55, 0xaa]);
+        let other = top(
+            31,
+            0,
+            vec![("type", Value::Str("file")), ("path", Value::Str("/f"))],
+        );
+        let start = 2048 * 512;
+        e[start..start + DISK_SECTORS * 512].copy_from_slice(&disk(
+            (200, "other"),
+            31,
+            7,
+            other,
+            31,
+            all,
+        ));
+
+        // No labels.
+        let f = vec![0; DISK_SECTORS * 512];
+
+        for (path, bytes) in [("a", a), ("b", b), ("d", d), ("e", e), ("f", f)] {
+            let device = BlockDeviceMemory::new(bytes).unwrap();
+            scanner.scan_device(path, &device);
+        }
+
+        ////////////////////////////////
+        // Devices.
+        let devices = scanner.devices();
+        assert_eq!(devices.len(), 4);
+        assert_eq!(devices[0].labels, [true, false, true, true]);
+        assert_eq!(devices[0].pool_state, PoolState::Active);
+        assert_eq!(devices[3].path, "e");
+        assert_eq!(devices[3].partition, Some(1));
+
+        ////////////////////////////////
+        // Pools.
+        let pools = scanner.pools();
+        assert_eq!(pools.len(), 2);
+
+        let other = &pools[0];
+        assert_eq!(
+            (other.guid, other.name.as_str(), other.txg),
+            (200, "other", 7)
+        );
+        assert_eq!(other.vdevs.len(), 3);
+        let file = other.vdevs[0].as_ref().unwrap();
+        assert_eq!(file.vdev_type, VdevType::File);
+        assert_eq!(file.state, Some(ScanDeviceState::Present));
+        assert!(other.vdevs[1].is_none());
+
+        let tank = &pools[1];
+        assert_eq!((tank.guid, tank.name.as_str(), tank.txg), (100, "tank", 50));
+        assert_eq!(tank.devices.len(), 3);
+        assert!(tank.unused_devices().is_empty());
+
+        // Mirror, from the config of leaf 11.
+        let mirror = tank.vdevs[0].as_ref().unwrap();
+        assert_eq!((mirror.guid, mirror.vdev_type), (10, VdevType::Mirror));
+        assert_eq!(mirror.state, None);
+        let states: Vec<_> = mirror
+            .leaves()
+            .iter()
+            .map(|leaf| (leaf.guid, leaf.path.as_deref(), leaf.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (11, Some("/dev/a"), Some(ScanDeviceState::Present)),
+                (12, Some("/dev/b"), Some(ScanDeviceState::Stale)),
+                (13, Some("/dev/c"), Some(ScanDeviceState::Missing)),
+            ]
+        );
+
+        // A top level vdev is compared to its own latest txg.
+        let single = tank.vdevs[1].as_ref().unwrap();
+        assert_eq!(single.state, Some(ScanDeviceState::Present));
+
+        // No device of the third top level vdev.
+        assert!(tank.vdevs[2].is_none());
+    }
+
+    /// Encodes a mirror with id 0, of leaf 11, replacing vdev 14 of leaves 12
+    /// and 15, and a child without a guid.
+    fn mirror_replacing() -> Vec<u8> {
+        let replacing = nv_list(&[
+            ("type", Value::Str("replacing")),
+            ("id", Value::U64(1)),
+            ("guid", Value::U64(14)),
+            (
+                "children",
+                Value::Lists(vec![leaf(12, 0, "/dev/b"), leaf(15, 1, "/dev/e")]),
+            ),
+        ]);
+        let invalid = nv_list(&[("type", Value::Str("disk")), ("id", Value::U64(2))]);
+
+        top(
+            10,
+            0,
+            vec![
+                ("type", Value::Str("mirror")),
+                (
+                    "children",
+                    Value::Lists(vec![leaf(11, 0, "/dev/a"), replacing, invalid]),
+                ),
+            ],
+        )
+    }
+
+    #[test]
+    fn scan_nested() {
+        let mut scanner = PoolScanner::new().unwrap();
+        let all = [true; 4];
+
+        let a = disk((100, "tank"), 11, 50, mirror_replacing(), 10, all);
+        let e = disk((100, "tank"), 15, 50, mirror_replacing(), 10, all);
+
+        for (path, bytes) in [("a", a), ("e", e)] {
+            let device = BlockDeviceMemory::new(bytes).unwrap();
+            scanner.scan_device(path, &device);
+        }
+        assert_eq!(scanner.devices().len(), 2);
+
+        let pools = scanner.pools();
+        let tank = &pools[0];
+        assert!(tank.unused_devices().is_empty());
+
+        let mirror = tank.vdevs[0].as_ref().unwrap();
+        let replacing = &mirror.children[1];
+        assert_eq!(
+            (
+                replacing.guid,
+                replacing.id,
+                replacing.vdev_type,
+                replacing.state
+            ),
+            (14, 1, VdevType::Replacing, None)
+        );
+
+        let states: Vec<_> = mirror
+            .leaves()
+            .iter()
+            .map(|leaf| (leaf.guid, leaf.id, leaf.path.as_deref(), leaf.state))
+            .collect();
+        assert_eq!(
+            states,
+            [
+                (11, 0, Some("/dev/a"), Some(ScanDeviceState::Present)),
+                (12, 0, Some("/dev/b"), Some(ScanDeviceState::Missing)),
+                (15, 1, Some("/dev/e"), Some(ScanDeviceState::Present)),
+                (0, 2, None, Some(ScanDeviceState::Unknown)),
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_id() {
+        let mut scanner = PoolScanner::new().unwrap();
+        let all = [true; 4];
+
+        // Top level vdev id is past the number of top level vdevs.
+        let invalid = top(
+            30,
+            u64::MAX,
+            vec![("type", Value::Str("disk")), ("path", Value::Str("/dev/x"))],
+        );
+        let d = disk((100, "tank"), 20, 45, single(), 20, all);
+        let x = disk((100, "tank"), 30, 45, invalid, 30, all);
+
+        for (path, bytes) in [("d", d), ("x", x)] {
+            let device = BlockDeviceMemory::new(bytes).unwrap();
+            scanner.scan_device(path, &device);
+        }
+
+        let pools = scanner.pools();
+        let tank = &pools[0];
+        assert_eq!(tank.vdevs.len(), 3);
+        assert_eq!(tank.vdevs[1].as_ref().unwrap().guid, 20);
+
+        let unused: Vec<_> = tank.unused_devices().iter().map(|d| d.guid).collect();
+        assert_eq!(unused, [30]);
+    }
+
+    #[test]
+    fn scan_path() {
+        let dir = env::temp_dir().join(format!("rzfs-scan-{}", std::process::id()));
+        fs::create_dir(&dir).unwrap();
+
+        // A disk image, a link to it, and a link to a character device.
+        let image = dir.join("image");
+        fs::write(&image, disk((100, "tank"), 20, 45, single(), 20, [true; 4])).unwrap();
+        os::unix::fs::symlink(&image, dir.join("link")).unwrap();
+        os::unix::fs::symlink("/dev/null", dir.join("null")).unwrap();
+
+        let mut scanner = PoolScanner::new().unwrap();
+        assert_eq!(scanner.scan_path(dir.to_str().unwrap()).unwrap(), 1);
+        assert_eq!(scanner.devices().len(), 1);
+
+        // Character devices are not opened.
+        assert!(matches!(
+            scanner.scan_path("/dev/null").unwrap_err(),
+            ScanError::UnsupportedFileType { path: _ }
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
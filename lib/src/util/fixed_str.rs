@@ -149,12 +149,12 @@ mod tests {
 
             // The string will not be trimmed, because there is enough space,
             // and from bytes does not check for utf8 validity.
-            assert_eq!(f.is_trimmed(), false);
+            assert!(!f.is_trimmed());
 
             // Bytes must match.
             assert_eq!(f.as_bytes(), bytes);
             assert_eq!(f.len(), bytes.len());
-            assert_eq!(f.is_empty(), bytes.len() == 0);
+            assert_eq!(f.is_empty(), bytes.is_empty());
 
             if is_ok {
                 // String will only be Some if it decodes successfully.
@@ -186,7 +186,7 @@ mod tests {
             // Bytes must match.
             assert_eq!(f.as_bytes(), exp_bytes);
             assert_eq!(f.len(), exp_bytes.len());
-            assert_eq!(f.is_empty(), exp_bytes.len() == 0);
+            assert_eq!(f.is_empty(), exp_bytes.is_empty());
 
             if is_ok {
                 // String will only be Some if it decodes successfully.
@@ -234,10 +234,10 @@ mod tests {
         let abc = "a÷⟰";
         let abcd = "a÷⟰🂡";
 
-        assert_eq!(a.as_bytes().len(), 1);
-        assert_eq!(ab.as_bytes().len(), 3);
-        assert_eq!(abc.as_bytes().len(), 6);
-        assert_eq!(abcd.as_bytes().len(), 10);
+        assert_eq!(a.len(), 1);
+        assert_eq!(ab.len(), 3);
+        assert_eq!(abc.len(), 6);
+        assert_eq!(abcd.len(), 10);
 
         // Empty.
         for s in [&a, &ab, &abc, &abcd] {